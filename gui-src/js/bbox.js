@@ -1,4 +1,4 @@
-var map, rsidebar, lsidebar, drawControl, drawnItems = null;
+var map, rsidebar, lsidebar, drawControl, drawnItems, cachedItems = null;
 
 // Where we keep the big list of proj defs from the server
 var proj4defs = null;
@@ -502,6 +502,10 @@ $(document).ready(function () {
     drawnItems = new L.FeatureGroup();
     map.addLayer(drawnItems);
 
+    // Areas already available in the download cache, shown below the selection
+    cachedItems = new L.FeatureGroup();
+    map.addLayer(cachedItems);
+
     // Initialize the draw control and pass it the FeatureGroup of editable layers
     drawControl = new L.Control.Draw({
         edit: {
@@ -657,3 +661,37 @@ function notifyBboxUpdate() {
     const bboxText = document.getElementById('boxbounds').textContent;
     window.parent.postMessage({ bboxText: bboxText }, '*');
 }
+
+// Show the cached areas reported by the main window, given as [min_lng, min_lat, max_lng, max_lat]
+window.addEventListener('message', function (event) {
+    const cachedAreas = event.data.cachedAreas;
+    if (!cachedAreas || !cachedItems) {
+        return;
+    }
+
+    cachedItems.clearLayers();
+    cachedAreas.forEach(function (area) {
+        L.rectangle([[area[1], area[0]], [area[3], area[2]]], {
+            color: '#7bd864',
+            weight: 1,
+            fillOpacity: 0.15,
+            interactive: false
+        }).addTo(cachedItems);
+    });
+});
+
+// Select the area of the last session restored by the main window, given as "lat lng lat lng"
+window.addEventListener('message', function (event) {
+    const selectedBBox = event.data.selectedBBox;
+    if (!selectedBBox || !map) {
+        return;
+    }
+
+    var coords = selectedBBox.split(' ').map(Number);
+    var restored = new L.LatLngBounds([coords[0], coords[1]], [coords[2], coords[3]]);
+    map.fire('draw:created', {
+        layer: new L.Rectangle(restored),
+        layerType: "polygon",
+    });
+    map.fitBounds(restored);
+});
@@ -7,8 +7,12 @@ window.addEventListener("DOMContentLoaded", async () => {
   registerMessageEvent();
   window.selectWorld = selectWorld;
   window.startGeneration = startGeneration;
+  window.cancelGeneration = cancelGeneration;
+  window.previewData = previewData;
   setupProgressListener();
   initSettings();
+  await initPresets();
+  await loadSettings();
   initWorldPicker();
   handleBboxInput();
 });
@@ -63,36 +67,119 @@ function registerMessageEvent() {
   });
 }
 
+// 各生成阶段的显示文本，键与后端的阶段标识一致
+const STAGE_LABELS = {
+  fetching: "正在获取数据...",
+  retrying: "正在重试获取数据...",
+  parsing: "正在解析数据...",
+  processing: "正在处理数据...",
+  ground: "生成地面层...",
+  saving: "保存世界...",
+  complete: "完成！世界生成完成。",
+  cancelled: "已取消生成。",
+  error: "错误！",
+};
+
 // 设置进度条监听器
 function setupProgressListener() {
   const progressBar = document.getElementById("progress-bar");
   const progressMessage = document.getElementById("progress-message");
   const progressDetail = document.getElementById("progress-detail");
+  const progressWarnings = document.getElementById("progress-warnings");
 
   window.__TAURI__.event.listen("progress-update", (event) => {
-    const { progress, message } = event.payload;
+    const { stage, progress, current, total, elapsed, remaining, warnings, error } = event.payload;
 
     if (progress != -1) {
       progressBar.style.width = `${progress}%`;
       progressDetail.textContent = `${Math.round(progress)}%`;
     }
 
-    if (message != "") {
-      progressMessage.textContent = message;
-
-      if (message.startsWith("错误！")) {
-        progressMessage.style.color = "#fa7878";
-        generationButtonEnabled = true;
-      } else if (message.startsWith("完毕！")) {
-        progressMessage.style.color = "#7bd864";
-        generationButtonEnabled = true;
-      } else {
-        progressMessage.style.color = "";
+    let message = STAGE_LABELS[stage] ?? "";
+    if (stage === "error" && error) {
+      message += ` ${error}`;
+    } else if (current != null && total != null) {
+      message += ` (${current}/${total})`;
+    }
+    if (stage !== "error" && stage !== "complete" && stage !== "cancelled" && elapsed != null) {
+      message += ` 已用 ${formatDuration(elapsed)}`;
+      if (remaining != null) {
+        message += `，剩余约 ${formatDuration(remaining)}`;
       }
     }
+    progressMessage.textContent = message;
+
+    if (stage === "error" || stage === "cancelled") {
+      progressMessage.style.color = "#fa7878";
+      generationButtonEnabled = true;
+      document.getElementById("cancel-button").style.display = "none";
+    } else if (stage === "complete") {
+      progressMessage.style.color = "#7bd864";
+      generationButtonEnabled = true;
+      document.getElementById("cancel-button").style.display = "none";
+    } else {
+      progressMessage.style.color = "";
+    }
+
+    for (const warning of warnings ?? []) {
+      const item = document.createElement("li");
+      item.textContent = warning;
+      progressWarnings.appendChild(item);
+    }
+  });
+
+  // 生成完成后显示摘要、播放提示音并提供打开世界文件夹的按钮
+  window.__TAURI__.event.listen("generation-complete", (event) => {
+    const { summary, worldPath } = event.payload;
+    const openFolderButton = document.getElementById("open-folder-button");
+
+    progressDetail.title = summary;
+    openFolderButton.style.display = "inline-block";
+    openFolderButton.onclick = async () => {
+      try {
+        await invoke('gui_open_world_folder', { worldPath: worldPath });
+      } catch (error) {
+        console.error("打开世界文件夹失败: ", error);
+      }
+    };
+
+    playCompletionSound();
   });
 }
 
+// 将秒数格式化为 "1:05" 或 "1:02:05"
+function formatDuration(seconds) {
+  const total = Math.round(seconds);
+  const hours = Math.floor(total / 3600);
+  const minutes = Math.floor((total % 3600) / 60);
+  const secs = String(total % 60).padStart(2, "0");
+  return hours > 0
+    ? `${hours}:${String(minutes).padStart(2, "0")}:${secs}`
+    : `${minutes}:${secs}`;
+}
+
+// 播放简短的上行和弦作为完成提示音
+function playCompletionSound() {
+  try {
+    const audioContext = new AudioContext();
+    [523.25, 659.25, 783.99].forEach((frequency, index) => {
+      const oscillator = audioContext.createOscillator();
+      const gain = audioContext.createGain();
+      const start = audioContext.currentTime + index * 0.12;
+
+      oscillator.type = "sine";
+      oscillator.frequency.value = frequency;
+      gain.gain.setValueAtTime(0.2, start);
+      gain.gain.exponentialRampToValueAtTime(0.001, start + 0.4);
+      oscillator.connect(gain).connect(audioContext.destination);
+      oscillator.start(start);
+      oscillator.stop(start + 0.4);
+    });
+  } catch (error) {
+    console.error("无法播放提示音: ", error);
+  }
+}
+
 function initSettings() {
   // 设置
   const settingsModal = document.getElementById("settings-modal");
@@ -109,6 +196,7 @@ function initSettings() {
   // 关闭设置模态框
   function closeSettings() {
     settingsModal.style.display = "none";
+    saveSettings();
   }
   
   window.openSettings = openSettings;
@@ -120,6 +208,99 @@ function initSettings() {
   });
 }
 
+let presets = [];
+
+// 加载内置和用户保存的预设，选择预设时将其选项填入设置
+async function initPresets() {
+  const presetSelect = document.getElementById("preset-select");
+  window.savePreset = savePreset;
+
+  presetSelect.addEventListener("change", () => {
+    const preset = presets.find((preset) => preset.name === presetSelect.value);
+    const presetDescription = document.getElementById("preset-description");
+    presetDescription.textContent = preset ? preset.description : "";
+    presetDescription.style.color = "";
+    if (preset) {
+      applyPresetOptions(preset.options);
+    }
+  });
+
+  await loadPresets();
+}
+
+async function loadPresets(selected = document.getElementById("preset-select").value) {
+  const presetSelect = document.getElementById("preset-select");
+  try {
+    presets = await invoke('gui_list_presets');
+  } catch (error) {
+    console.error("加载预设失败: ", error);
+    presets = [];
+  }
+
+  presetSelect.replaceChildren(new Option("无", ""));
+  for (const preset of presets) {
+    presetSelect.appendChild(new Option(preset.builtin ? preset.name : `${preset.name}（自定义）`, preset.name));
+  }
+  presetSelect.value = presets.some((preset) => preset.name === selected) ? selected : "";
+}
+
+// 将预设中设置对话框显示的选项填入对应的控件，其余选项在生成时由后端应用
+function applyPresetOptions(options) {
+  const slider = document.getElementById("scale-value-slider");
+  if (options.scale != null) {
+    slider.value = options.scale;
+    document.getElementById("slider-value").textContent = parseFloat(slider.value).toFixed(2);
+  }
+  if (options.ground_level != null) {
+    document.getElementById("ground-level").value = options.ground_level;
+  }
+  if (options.blocks_per_level != null) {
+    document.getElementById("blocks-per-level").value = options.blocks_per_level;
+  }
+  if (options.timeout != null) {
+    document.getElementById("floodfill-timeout").value = options.timeout;
+  }
+  if (options.palette != null) {
+    document.getElementById("color-palette").value = options.palette;
+  }
+  document.getElementById("winter-toggle").checked = options.winter === true;
+  document.getElementById("heatmap-toggle").checked = options.heatmap === true;
+}
+
+// 将当前设置连同所选预设的其他选项保存为用户预设
+async function savePreset() {
+  const presetSelect = document.getElementById("preset-select");
+  const presetDescription = document.getElementById("preset-description");
+  const name = document.getElementById("preset-name").value.trim();
+  const base = presets.find((preset) => preset.name === presetSelect.value);
+
+  const options = {
+    ...(base ? base.options : {}),
+    scale: parseFloat(document.getElementById("scale-value-slider").value),
+    ground_level: parseInt(document.getElementById("ground-level").value, 10),
+    blocks_per_level: parseInt(document.getElementById("blocks-per-level").value, 10),
+    timeout: parseInt(document.getElementById("floodfill-timeout").value, 10),
+    winter: document.getElementById("winter-toggle").checked,
+    heatmap: document.getElementById("heatmap-toggle").checked,
+    palette: document.getElementById("color-palette").value,
+  };
+
+  try {
+    await invoke('gui_save_preset', {
+      name: name,
+      description: base ? `基于 ${base.name}` : "",
+      options: options,
+    });
+    await loadPresets(name);
+    presetDescription.textContent = "预设已保存。";
+    presetDescription.style.color = "#7bd864";
+  } catch (error) {
+    console.error("保存预设失败: ", error);
+    presetDescription.textContent = `${error}`;
+    presetDescription.style.color = "#fa7878";
+  }
+}
+
 function initWorldPicker() {
   // 世界选择器
   const worldPickerModal = document.getElementById("world-modal");
@@ -240,6 +421,8 @@ function displayBboxInfoText(bboxText) {
   // 如果边界框为 0,0,0,0，则重置信息文本
   if (lng1 === 0 && lat1 === 0 && lng2 === 0 && lat2 === 0) {
     bboxInfo.textContent = "";
+    document.getElementById("cache-info").textContent = "";
+    document.getElementById("scale-hint").textContent = "";
     selectedBBox = "";
     return;
   }
@@ -257,6 +440,229 @@ function displayBboxInfoText(bboxText) {
     bboxInfo.textContent = "选择已确认！";
     bboxInfo.style.color = "#7bd864";
   }
+
+  // 之前区域的预览不再适用
+  document.getElementById("preview-info").textContent = "";
+  document.getElementById("preview-canvas").style.display = "none";
+
+  displayCacheCoverage(selectedBBox);
+  displayScaleSuggestion(selectedBBox);
+}
+
+// 根据所选区域的大小显示建议的世界比例和地面高度
+async function displayScaleSuggestion(bboxText) {
+  const scaleHint = document.getElementById("scale-hint");
+  try {
+    const suggestion = await invoke('gui_suggest_scale', { bboxText: bboxText });
+    if (bboxText !== selectedBBox) {
+      return;
+    }
+
+    scaleHint.textContent = `建议比例 ${suggestion.scale.toFixed(2)}，地面高度 ${suggestion.ground_level}` +
+      `（${suggestion.width_blocks} × ${suggestion.length_blocks} 方块）`;
+    if (suggestion.warnings.length > 0) {
+      scaleHint.textContent += `。${suggestion.warnings.join("；")}`;
+      scaleHint.style.color = "#fecc44";
+    } else {
+      scaleHint.style.color = "";
+    }
+  } catch (error) {
+    console.error("获取比例建议失败: ", error);
+    scaleHint.textContent = "";
+  }
+}
+
+// 显示所选区域的缓存情况，并在地图上标出已缓存的区域
+async function displayCacheCoverage(bboxText) {
+  const cacheInfo = document.getElementById("cache-info");
+  try {
+    const coverage = await invoke('gui_get_cache_coverage', { bboxText: bboxText });
+    if (bboxText !== selectedBBox) {
+      return;
+    }
+
+    if (coverage.exact) {
+      cacheInfo.textContent = "数据已缓存，无需联网下载。";
+      cacheInfo.style.color = "#7bd864";
+    } else {
+      const covered = Math.round(coverage.covered_fraction * 100);
+      const size = coverage.estimated_download_bytes === null
+        ? "大小未知"
+        : `约 ${(coverage.estimated_download_bytes / 1048576).toFixed(1)} MB`;
+      cacheInfo.textContent = `需要联网下载（${size}），已缓存区域覆盖 ${covered}%。`;
+      cacheInfo.style.color = "#fecc44";
+    }
+
+    document.querySelector('iframe').contentWindow.postMessage({ cachedAreas: coverage.cached_areas }, '*');
+  } catch (error) {
+    console.error("获取缓存信息失败: ", error);
+    cacheInfo.textContent = "";
+  }
+}
+
+// 预览中各图层的颜色，按后端返回的图层名称
+const PREVIEW_COLORS = {
+  water: "#4a90d9",
+  roads: "#d8d8d8",
+  railways: "#9a7b5a",
+  buildings: "#fecc44",
+};
+
+// 获取并解析所选区域的数据，在生成前将道路、建筑和水域绘制在画布上
+async function previewData() {
+  const previewInfo = document.getElementById("preview-info");
+  const canvas = document.getElementById("preview-canvas");
+
+  if (!selectedBBox) {
+    previewInfo.textContent = "请先选择一个位置！";
+    previewInfo.style.color = "#fa7878";
+    return;
+  }
+
+  const bboxText = selectedBBox;
+  const scale = parseFloat(document.getElementById("scale-value-slider").value);
+  previewInfo.textContent = "正在获取预览数据...";
+  previewInfo.style.color = "";
+
+  try {
+    const preview = await invoke('gui_preview_data', { bboxText: bboxText, worldScale: scale });
+    if (bboxText !== selectedBBox) {
+      return;
+    }
+
+    drawPreview(canvas, preview);
+    canvas.style.display = "block";
+    previewInfo.textContent = `${preview.width} × ${preview.length} 方块，${preview.shapes.length} 个要素`;
+    previewInfo.style.color = "#7bd864";
+  } catch (error) {
+    console.error("获取预览数据失败: ", error);
+    previewInfo.textContent = `预览失败：${error}`;
+    previewInfo.style.color = "#fa7878";
+    canvas.style.display = "none";
+  }
+}
+
+// 将预览要素按区域大小缩放后绘制，闭合的要素填充，其余的绘制为线
+function drawPreview(canvas, preview) {
+  const context = canvas.getContext("2d");
+  const scale = Math.min(canvas.width / preview.width, canvas.height / preview.length);
+  const offsetX = (canvas.width - preview.width * scale) / 2;
+  const offsetY = (canvas.height - preview.length * scale) / 2;
+
+  context.fillStyle = "#3a3a3a";
+  context.fillRect(0, 0, canvas.width, canvas.height);
+  context.fillStyle = "#4f6b3a";
+  context.fillRect(offsetX, offsetY, preview.width * scale, preview.length * scale);
+
+  for (const shape of preview.shapes) {
+    if (shape.points.length < 2) {
+      continue;
+    }
+    const color = PREVIEW_COLORS[shape.layer] ?? "#ffffff";
+    context.beginPath();
+    shape.points.forEach(([x, z], index) => {
+      const px = offsetX + x * scale;
+      const py = offsetY + z * scale;
+      if (index === 0) {
+        context.moveTo(px, py);
+      } else {
+        context.lineTo(px, py);
+      }
+    });
+    if (shape.closed && shape.layer !== "roads") {
+      context.fillStyle = color;
+      context.fill();
+    } else {
+      context.strokeStyle = color;
+      context.lineWidth = Math.max(1, scale * 2);
+      context.stroke();
+    }
+  }
+}
+
+// 恢复上次会话的区域、世界和设置
+async function loadSettings() {
+  try {
+    const settings = await invoke('gui_load_settings');
+
+    const slider = document.getElementById("scale-value-slider");
+    if (settings.scale != null) {
+      slider.value = settings.scale;
+      document.getElementById("slider-value").textContent = parseFloat(slider.value).toFixed(2);
+    }
+    if (settings.ground_level != null) {
+      document.getElementById("ground-level").value = settings.ground_level;
+    }
+    if (settings.blocks_per_level != null) {
+      document.getElementById("blocks-per-level").value = settings.blocks_per_level;
+    }
+    if (settings.floodfill_timeout != null) {
+      document.getElementById("floodfill-timeout").value = settings.floodfill_timeout;
+    }
+    if (settings.palette != null) {
+      document.getElementById("color-palette").value = settings.palette;
+    }
+    document.getElementById("winter-toggle").checked = settings.winter;
+    document.getElementById("heatmap-toggle").checked = settings.heatmap;
+
+    const preset = presets.find((preset) => preset.name === settings.preset);
+    if (preset) {
+      document.getElementById("preset-select").value = preset.name;
+      document.getElementById("preset-description").textContent = preset.description;
+    }
+
+    if (settings.world_path) {
+      worldPath = settings.world_path;
+      document.getElementById('selected-world').textContent = worldPath.split(/[\\/]/).pop();
+      document.getElementById('selected-world').style.color = "#fecc44";
+      await checkWorld(worldPath);
+    }
+
+    if (settings.bbox) {
+      displayBboxInfoText(settings.bbox);
+      sendBBoxToMap(settings.bbox);
+    }
+  } catch (error) {
+    console.error("加载设置失败: ", error);
+  }
+}
+
+// 保存当前的区域、世界和设置，供下次启动时恢复
+async function saveSettings() {
+  const scale = parseFloat(document.getElementById("scale-value-slider").value);
+  const groundLevel = parseInt(document.getElementById("ground-level").value, 10);
+  const blocksPerLevel = parseInt(document.getElementById("blocks-per-level").value, 10);
+  const floodfillTimeout = parseInt(document.getElementById("floodfill-timeout").value, 10);
+
+  try {
+    await invoke('gui_save_settings', {
+      settings: {
+        bbox: selectedBBox || null,
+        world_path: worldPath || null,
+        scale: isNaN(scale) ? null : scale,
+        ground_level: isNaN(groundLevel) ? null : groundLevel,
+        blocks_per_level: isNaN(blocksPerLevel) || blocksPerLevel < 2 ? null : blocksPerLevel,
+        floodfill_timeout: isNaN(floodfillTimeout) || floodfillTimeout < 0 ? null : floodfillTimeout,
+        winter: document.getElementById("winter-toggle").checked,
+        heatmap: document.getElementById("heatmap-toggle").checked,
+        palette: document.getElementById("color-palette").value,
+        preset: document.getElementById("preset-select").value || null,
+      },
+    });
+  } catch (error) {
+    console.error("保存设置失败: ", error);
+  }
+}
+
+// 在地图加载完成后选中恢复的区域
+function sendBBoxToMap(bboxText) {
+  const iframe = document.querySelector('iframe');
+  const send = () => iframe.contentWindow.postMessage({ selectedBBox: bboxText }, '*');
+  if (iframe.contentDocument && iframe.contentDocument.readyState === "complete") {
+    send();
+  } else {
+    iframe.addEventListener("load", send, { once: true });
+  }
 }
 
 let worldPath = "";
@@ -268,16 +674,59 @@ async function selectWorld(generate_new_world) {
       const lastSegment = worldName.split(/[\\/]/).pop();
       document.getElementById('selected-world').textContent = lastSegment;
       document.getElementById('selected-world').style.color = "#fecc44";
+      if (generate_new_world) {
+        document.getElementById('world-health').textContent = "";
+      } else {
+        await checkWorld(worldName);
+      }
+      saveSettings();
     }
   } catch (error) {
     console.error(error);
     document.getElementById('selected-world').textContent = error;
     document.getElementById('selected-world').style.color = "#fa7878";
+    document.getElementById('world-health').textContent = "";
   }
 
   closeWorldPicker();
 }
 
+// Shows how an existing world was generated and whether it can be updated in place
+async function checkWorld(path) {
+  const worldHealth = document.getElementById('world-health');
+  try {
+    const health = await invoke('gui_check_world', { worldPath: path });
+    const lines = [];
+    if (health.saved_at !== null) {
+      lines.push(`上次生成：${new Date(health.saved_at * 1000).toLocaleString()}`);
+    }
+    if (health.settings) {
+      const settings = health.settings;
+      lines.push(`比例 ${settings.scale}，地面高度 ${settings.ground_level}，风格 ${settings.style}` +
+        (settings.winter ? "，冬季" : "") + (settings.night ? "，夜间" : "") +
+        (settings.seed != null ? `，种子 ${settings.seed}` : ""));
+    }
+    if (health.corrupted_files.length > 0) {
+      lines.push(`损坏的文件：${health.corrupted_files.join("、")}`);
+    }
+    lines.push(health.recommendation);
+
+    worldHealth.textContent = "";
+    lines.forEach((line, index) => {
+      if (index > 0) {
+        worldHealth.appendChild(document.createElement('br'));
+      }
+      worldHealth.appendChild(document.createTextNode(line));
+    });
+    worldHealth.style.color = health.corrupted_files.length > 0
+      ? "#fa7878"
+      : health.can_update_in_place || !health.generated_by_arnis ? "#7bd864" : "#fecc44";
+  } catch (error) {
+    console.error("检查世界失败: ", error);
+    worldHealth.textContent = "";
+  }
+}
+
 let generationButtonEnabled = true;
 async function startGeneration() {
   try {
@@ -307,10 +756,16 @@ async function startGeneration() {
     var scale = parseFloat(document.getElementById("scale-value-slider").value);
     var floodfill_timeout = parseInt(document.getElementById("floodfill-timeout").value, 10);
     var ground_level = parseInt(document.getElementById("ground-level").value, 10);
+    var blocks_per_level = parseInt(document.getElementById("blocks-per-level").value, 10);
+    var heatmap = document.getElementById("heatmap-toggle").checked;
+    var color_palette = document.getElementById("color-palette").value;
 
-    // 验证 floodfill_timeout 和 ground_level
+    // 验证 floodfill_timeout、ground_level 和 blocks_per_level
     floodfill_timeout = isNaN(floodfill_timeout) || floodfill_timeout < 0 ? 20 : floodfill_timeout;
     ground_level = isNaN(ground_level) || ground_level < -62 ? 20 : ground_level;
+    blocks_per_level = isNaN(blocks_per_level) || blocks_per_level < 2 ? 4 : blocks_per_level;
+
+    document.getElementById("progress-warnings").replaceChildren();
 
     // 将边界框和所选世界传递给 Rust 后端
     await invoke("gui_start_generation", {
@@ -318,10 +773,17 @@ async function startGeneration() {
         selectedWorld: worldPath,
         worldScale: scale,
         groundLevel: ground_level,
+        blocksPerLevel: blocks_per_level,
         winterMode: winter_mode,
         floodfillTimeout: floodfill_timeout,
+        heatmap: heatmap,
+        colorPalette: color_palette,
+        preset: document.getElementById("preset-select").value || null,
     });
 
+    saveSettings();
+    document.getElementById("open-folder-button").style.display = "none";
+    document.getElementById("cancel-button").style.display = "inline-block";
     console.log("生成过程已开始。");
     generationButtonEnabled = false;
   } catch (error) {
@@ -329,3 +791,14 @@ async function startGeneration() {
     generationButtonEnabled = true;
   }
 }
+
+// 请求后端在下一个检查点停止生成
+async function cancelGeneration() {
+  try {
+    document.getElementById("cancel-button").style.display = "none";
+    document.getElementById("progress-message").textContent = "正在取消...";
+    await invoke("gui_cancel_generation");
+  } catch (error) {
+    console.error("取消生成时出错:", error);
+  }
+}
@@ -0,0 +1,191 @@
+use serde_json::Value;
+
+/// Polygon of the area to generate given with --polygon, instead of the rectangle of a
+/// bounding box. Only the outer ring is used, holes are generated like the rest of the area.
+#[derive(Debug, Clone)]
+pub struct AreaPolygon {
+    /// Positions of the ring as (lng, lat)
+    points: Vec<(f64, f64)>,
+}
+
+impl AreaPolygon {
+    /// Parses the first polygon of a GeoJSON object or a WKT POLYGON or MULTIPOLYGON
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text: &str = text.trim();
+        if text.starts_with('{') {
+            let geojson: Value = serde_json::from_str(text)
+                .map_err(|e: serde_json::Error| format!("无法解析 GeoJSON：{}", e))?;
+            return geojson_polygons(&geojson)?
+                .into_iter()
+                .next()
+                .ok_or_else(|| "GeoJSON 中没有多边形".to_string());
+        }
+
+        let upper: String = text.to_uppercase();
+        if !upper.starts_with("POLYGON") && !upper.starts_with("MULTIPOLYGON") {
+            return Err("多边形必须是 GeoJSON 或 WKT POLYGON".to_string());
+        }
+        // The outer ring of the first polygon is within the innermost leading parentheses
+        let end: usize = text.find(')').ok_or_else(|| "WKT 多边形无效".to_string())?;
+        let start: usize = text[..end]
+            .rfind('(')
+            .ok_or_else(|| "WKT 多边形无效".to_string())?;
+        let points: Vec<(f64, f64)> = text[start + 1..end]
+            .split(',')
+            .map(|position: &str| {
+                let coordinates: Vec<f64> = position
+                    .split_whitespace()
+                    .map(|value: &str| value.parse::<f64>())
+                    .collect::<Result<Vec<f64>, _>>()
+                    .map_err(|_| "WKT 多边形的坐标无效".to_string())?;
+                match coordinates[..] {
+                    [lng, lat, ..] => Ok((lng, lat)),
+                    _ => Err("WKT 多边形的坐标无效".to_string()),
+                }
+            })
+            .collect::<Result<Vec<(f64, f64)>, String>>()?;
+        Self::new(points)
+    }
+
+    fn new(points: Vec<(f64, f64)>) -> Result<Self, String> {
+        let valid: bool = points.len() >= 3
+            && points
+                .iter()
+                .all(|(lng, lat)| (-180.0..=180.0).contains(lng) && (-90.0..=90.0).contains(lat));
+        let polygon: Self = Self { points };
+        let bbox: (f64, f64, f64, f64) = polygon.bbox();
+        if !valid || bbox.0 >= bbox.2 || bbox.1 >= bbox.3 {
+            return Err("多边形无效".to_string());
+        }
+        Ok(polygon)
+    }
+
+    /// Returns the positions of the ring as (lng, lat)
+    pub fn points(&self) -> &[(f64, f64)] {
+        &self.points
+    }
+
+    /// Returns the bounding box (min_lng, min_lat, max_lng, max_lat) of the polygon
+    pub fn bbox(&self) -> (f64, f64, f64, f64) {
+        self.points.iter().fold(
+            (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+            |bbox: (f64, f64, f64, f64), (lng, lat)| {
+                (
+                    bbox.0.min(*lng),
+                    bbox.1.min(*lat),
+                    bbox.2.max(*lng),
+                    bbox.3.max(*lat),
+                )
+            },
+        )
+    }
+
+    /// Returns the Overpass filter selecting the elements within the polygon
+    pub fn overpass_filter(&self) -> String {
+        let positions: Vec<String> = self
+            .points
+            .iter()
+            .map(|(lng, lat)| format!("{} {}", lat, lng))
+            .collect();
+        format!("(poly:\"{}\")", positions.join(" "))
+    }
+}
+
+/// Returns the outer rings of all polygons of a GeoJSON object, including those of
+/// multipolygons, features and collections
+pub fn geojson_polygons(geojson: &Value) -> Result<Vec<AreaPolygon>, String> {
+    let mut polygons: Vec<AreaPolygon> = vec![];
+    collect_polygons(geojson, &mut polygons)?;
+    Ok(polygons)
+}
+
+fn collect_polygons(geojson: &Value, polygons: &mut Vec<AreaPolygon>) -> Result<(), String> {
+    let children =
+        |key: &str| -> Vec<Value> { geojson[key].as_array().cloned().unwrap_or_default() };
+    match geojson["type"].as_str() {
+        Some("FeatureCollection") => {
+            for feature in children("features") {
+                collect_polygons(&feature, polygons)?;
+            }
+        }
+        Some("GeometryCollection") => {
+            for geometry in children("geometries") {
+                collect_polygons(&geometry, polygons)?;
+            }
+        }
+        Some("Feature") => collect_polygons(&geojson["geometry"], polygons)?,
+        Some("Polygon") => polygons.push(geojson_ring(&geojson["coordinates"][0])?),
+        Some("MultiPolygon") => {
+            for polygon in children("coordinates") {
+                polygons.push(geojson_ring(&polygon[0])?);
+            }
+        }
+        // Points and lines do not enclose an area
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Reads a GeoJSON ring of [lng, lat] positions
+fn geojson_ring(ring: &Value) -> Result<AreaPolygon, String> {
+    let points: Vec<(f64, f64)> = ring
+        .as_array()
+        .ok_or_else(|| "GeoJSON 多边形缺少坐标".to_string())?
+        .iter()
+        .map(
+            |position: &Value| match (position[0].as_f64(), position[1].as_f64()) {
+                (Some(lng), Some(lat)) => Ok((lng, lat)),
+                _ => Err("GeoJSON 多边形的坐标无效".to_string()),
+            },
+        )
+        .collect::<Result<Vec<(f64, f64)>, String>>()?;
+    AreaPolygon::new(points)
+}
+
+/// Blocks of the area within the polygon, as spans of x coordinates for every row of z
+pub struct AreaMask {
+    rows: Vec<Vec<(i32, i32)>>,
+}
+
+impl AreaMask {
+    /// Rasterizes the polygon given in block coordinates of the area of the given size
+    pub fn new(points: &[(i32, i32)], size: (i32, i32)) -> Self {
+        let rows: Vec<Vec<(i32, i32)>> = (0..=size.1)
+            .map(|z: i32| {
+                let row: f64 = f64::from(z);
+                let mut crossings: Vec<f64> = vec![];
+                for (index, &(x1, z1)) in points.iter().enumerate() {
+                    let (x2, z2) = points[(index + 1) % points.len()];
+                    let (z1, z2) = (f64::from(z1), f64::from(z2));
+                    // Each vertex counts for one of its edges only
+                    if (z1 <= row && row < z2) || (z2 <= row && row < z1) {
+                        let t: f64 = (row - z1) / (z2 - z1);
+                        crossings.push(f64::from(x1) + t * f64::from(x2 - x1));
+                    }
+                }
+                crossings.sort_by(f64::total_cmp);
+                crossings
+                    .chunks_exact(2)
+                    .map(|span: &[f64]| {
+                        (
+                            (span[0].ceil() as i32).max(0),
+                            (span[1].floor() as i32).min(size.0),
+                        )
+                    })
+                    .filter(|(from, to)| from <= to)
+                    .collect()
+            })
+            .collect();
+        Self { rows }
+    }
+
+    /// Returns whether a block of the area lies within the polygon
+    pub fn contains(&self, x: i32, z: i32) -> bool {
+        usize::try_from(z)
+            .ok()
+            .and_then(|z: usize| self.rows.get(z))
+            .is_some_and(|spans: &Vec<(i32, i32)>| {
+                spans.iter().any(|(from, to)| (*from..=*to).contains(&x))
+            })
+    }
+}
@@ -1,3 +1,13 @@
+use crate::area_polygon::AreaPolygon;
+use crate::batch::read_geojson_bboxes;
+use crate::building_styles::BuildingStyle;
+use crate::colors::ColorPalette;
+use crate::floodfill::{FillMethod, FillOptions};
+use crate::layers::Layer;
+use crate::mc_version::McVersion;
+use crate::output::OutputFormat;
+use crate::retrieve_data::CacheMode;
+use crate::transliteration::SignScript;
 use clap::{ArgGroup, Parser};
 use colored::Colorize;
 use std::path::Path;
@@ -5,19 +15,27 @@ use std::process::exit;
 use std::time::Duration;
 
 /// Command-line arguments parser
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(author, version, about)]
 #[command(group(
     ArgGroup::new("location")
         .required(true)
-        .args(&["bbox", "file"])
+        .args(&["bbox", "file", "bbox_file", "polygon"])
 ))]
 pub struct Args {
-    /// Bounding box of the area (min_lng,min_lat,max_lng,max_lat) (required)
+    /// Bounding box of the area (min_lng,min_lat,max_lng,max_lat), repeat it to generate several areas into one world (required)
     #[arg(long, allow_hyphen_values = true)]
-    pub bbox: Option<String>,
+    pub bbox: Vec<String>,
+
+    /// GeoJSON file of polygons, each generated as the area of its bounding box into one world (optional)
+    #[arg(long)]
+    pub bbox_file: Option<String>,
+
+    /// Polygon of the area instead of a rectangle, as GeoJSON or WKT text or a file containing it. Nothing is placed outside the polygon (optional)
+    #[arg(long, value_parser = parse_polygon, conflicts_with_all = ["bbox", "bbox_file"])]
+    pub polygon: Option<AreaPolygon>,
 
-    /// JSON file containing OSM data (optional)
+    /// JSON or .osm.pbf file containing OSM data (optional)
     #[arg(long)]
     pub file: Option<String>,
 
@@ -25,6 +43,10 @@ pub struct Args {
     #[arg(long, required = true)]
     pub path: String,
 
+    /// Named set of options: fast-draft, detailed-city, terrain-only or a preset saved in the GUI. Options given on the command line take precedence (optional)
+    #[arg(long)]
+    pub preset: Option<String>,
+
     /// Downloader method (requests/curl/wget) (optional)
     #[arg(long, default_value = "requests")]
     pub downloader: String,
@@ -37,6 +59,88 @@ pub struct Args {
     #[arg(long, default_value_t = -62)]
     pub ground_level: i32,
 
+    /// Block X coordinate the west edge of the area is generated at, to place several areas side by side in one world (default: 0)
+    #[arg(long, default_value_t = 0, allow_negative_numbers = true)]
+    pub x_offset: i32,
+
+    /// Block Z coordinate the north edge of the area is generated at (default: 0)
+    #[arg(long, default_value_t = 0, allow_negative_numbers = true)]
+    pub z_offset: i32,
+
+    /// Rotate the area clockwise by this many degrees, e.g. 90 to put north at the east (default: 0)
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    pub rotation: f64,
+
+    /// Number of blocks per building level, used for building:levels tags
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(i32).range(2..))]
+    pub blocks_per_level: i32,
+
+    /// Regional building style used for buildings without detailed tags
+    #[arg(long, value_enum, default_value_t = BuildingStyle::Default)]
+    pub style: BuildingStyle,
+
+    /// Generate building interiors with storeys, stairwells and rooms (default: false)
+    #[arg(long, default_value_t = false)]
+    pub interior: bool,
+
+    /// Spawn named villagers at the entrances of shops and amenities and residents in front of
+    /// some houses (default: false)
+    #[arg(long, default_value_t = false)]
+    pub villagers: bool,
+
+    /// Spawn farm animals on fenced pastures, small animals in parks and boats in marinas
+    /// (default: false)
+    #[arg(long, default_value_t = false)]
+    pub mobs: bool,
+
+    /// Add a datapack showing the real-world coordinates on the action bar (default: false)
+    #[arg(long, default_value_t = false)]
+    pub coordinate_hud: bool,
+
+    /// Add small details like parked cars (default: false)
+    #[arg(long, default_value_t = false)]
+    pub details: bool,
+
+    /// Sprinkle trees, grass and flowers over unmapped green space, following nearby features and the climate (default: false)
+    #[arg(long, default_value_t = false)]
+    pub foliage: bool,
+
+    /// Light up windows, shop fronts and lit streets, so the city looks alive at night (default: false)
+    #[arg(long, default_value_t = false)]
+    pub night: bool,
+
+    /// Export per-chunk heatmaps of placed blocks and OSM elements (default: false)
+    #[arg(long, default_value_t = false)]
+    pub heatmap: bool,
+
+    /// Color palette of the heatmaps, including a color-blind safe set
+    #[arg(long, value_enum, default_value_t = ColorPalette::Default)]
+    pub palette: ColorPalette,
+
+    /// Do not read or write the download cache (default: false)
+    #[arg(long, default_value_t = false, conflicts_with = "refresh")]
+    pub no_cache: bool,
+
+    /// Download the data again even if it is cached (default: false)
+    #[arg(long, default_value_t = false)]
+    pub refresh: bool,
+
+    /// Generate and save the area around this point first (lat,lng) (default: center of the bbox)
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_focus)]
+    pub focus: Option<(f64, f64)>,
+
+    /// TOML or JSON file mapping element categories to custom Minecraft block IDs (optional)
+    #[arg(long)]
+    pub block_config: Option<String>,
+
+    /// Folder of tree structure NBT files, in subfolders named by species, genus or tree type (optional)
+    #[arg(long)]
+    pub tree_templates: Option<String>,
+
+    /// Script of street and shop names on signs and name tags, e.g. latin to transliterate them
+    #[arg(long, value_enum, default_value_t = SignScript::Original)]
+    pub sign_script: SignScript,
+
     /// Enable winter mode (default: false)
     #[arg(long, default_value_t = false)]
     pub winter: bool,
@@ -45,16 +149,162 @@ pub struct Args {
     #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
     pub debug: bool,
 
-    /// Set floodfill timeout (seconds) (optional)
+    /// Largest area in blocks filled for a single element, larger ones keep only their outline
+    #[arg(long, default_value_t = 4_000_000)]
+    pub max_fill_area: u64,
+
+    /// Algorithm filling the areas: scanline, or triangles for polygons with gaps in their
+    /// outline after scaling down (default: scanline)
+    #[arg(long, value_enum, default_value_t = FillMethod::default())]
+    pub fill_method: FillMethod,
+
+    /// Write only these layers into the existing world, e.g. terrain,buildings (default: all)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub only: Vec<Layer>,
+
+    /// Generate only these layers, e.g. terrain,roads for a base map (default: all)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub include: Vec<Layer>,
+
+    /// Skip these layers, e.g. buildings,railways (default: none)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub exclude: Vec<Layer>,
+
+    /// Set the world border around the generated area (default: false)
+    #[arg(long, default_value_t = false)]
+    pub world_border: bool,
+
+    /// Minecraft version the world is written for, e.g. 1.16.5 or 1.20.4 (default: 1.21.4)
+    #[arg(long, value_enum, default_value_t = McVersion::default())]
+    pub mc_version: McVersion,
+
+    /// Leave computing the light to Minecraft instead of writing lit chunks (default: false)
+    #[arg(long, default_value_t = false)]
+    pub no_lighting: bool,
+
+    /// Seed of the random choices, the same bbox and seed always give the same world (default: random)
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Only generate elements added or changed since the last run with the same bbox, keeping the rest of the world (default: false)
+    #[arg(long, default_value_t = false)]
+    pub incremental: bool,
+
+    /// Replace existing region files instead of merging the generated blocks into the chunks already in them (default: false)
+    #[arg(long, default_value_t = false, conflicts_with_all = ["only", "incremental"])]
+    pub overwrite: bool,
+
+    /// Keep a checkpoint once the elements are processed and resume an interrupted run of the same area from it (default: false)
+    #[arg(long, default_value_t = false)]
+    pub resume: bool,
+
+    /// Simplify areas farther than this from the center of the bbox (meters): no interiors, flat roofs and merged forest canopies (optional)
+    #[arg(long)]
+    pub lod_radius: Option<f64>,
+
+    /// Tree density of forests and woods, 1.0 is a dense forest and 0 leaves only undergrowth
+    #[arg(long, default_value_t = 1.0)]
+    pub forest_density: f64,
+
+    /// Set floodfill timeout per element (seconds) (optional)
     #[arg(long, value_parser = parse_duration)]
     pub timeout: Option<Duration>,
+
+    /// Output to write: Java world regions, a Bedrock world, a schematic file, a server via RCON
+    /// or a preview image
+    #[arg(long, value_enum, default_value_t = OutputFormat::Anvil)]
+    pub format: OutputFormat,
+
+    /// Address of the server's remote console, used with --format rcon
+    #[arg(long, default_value = "127.0.0.1:25575")]
+    pub rcon_address: String,
+
+    /// Password of the server's remote console, used with --format rcon (optional)
+    #[arg(long)]
+    pub rcon_password: Option<String>,
 }
 
 impl Args {
+    /// Returns the limits and the algorithm of the fills
+    pub fn fill_options(&self) -> FillOptions {
+        FillOptions {
+            max_area: Some(self.max_fill_area),
+            method: self.fill_method,
+            timeout: self.timeout,
+        }
+    }
+
+    /// Returns how the downloaded OSM data should be cached
+    pub fn cache_mode(&self) -> CacheMode {
+        if self.no_cache {
+            CacheMode::Disabled
+        } else if self.refresh {
+            CacheMode::Refresh
+        } else {
+            CacheMode::Enabled
+        }
+    }
+
+    /// Returns the bounding boxes of the areas to generate, (min_lng, min_lat, max_lng,
+    /// max_lat) each, from --bbox and --bbox-file
+    pub fn bboxes(&self) -> Result<Vec<(f64, f64, f64, f64)>, String> {
+        let mut bboxes: Vec<(f64, f64, f64, f64)> = self
+            .bbox
+            .iter()
+            .map(|bbox: &String| {
+                let parts: Vec<f64> = bbox
+                    .split(',')
+                    .map(|s: &str| s.parse::<f64>().map_err(|_| "边界框坐标无效".to_string()))
+                    .collect::<Result<Vec<f64>, String>>()?;
+                match parts[..] {
+                    [min_lng, min_lat, max_lng, max_lat] => {
+                        Ok((min_lng, min_lat, max_lng, max_lat))
+                    }
+                    _ => Err("边界框坐标无效".to_string()),
+                }
+            })
+            .collect::<Result<Vec<(f64, f64, f64, f64)>, String>>()?;
+        if let Some(polygon) = &self.polygon {
+            bboxes.push(polygon.bbox());
+        }
+        if let Some(bbox_file) = &self.bbox_file {
+            bboxes.extend(read_geojson_bboxes(bbox_file)?);
+        }
+        if bboxes.is_empty() {
+            return Err("需要边界框".to_string());
+        }
+        Ok(bboxes)
+    }
+
+    /// Returns whether the generated blocks are written into the existing world instead of
+    /// replacing its regions, which is the case with --only and --incremental
+    pub fn merges_into_world(&self) -> bool {
+        !self.only.is_empty() || self.incremental
+    }
+
+    /// Returns whether a layer is generated, which is every layer without --only, --include
+    /// and --exclude
+    pub fn includes_layer(&self, layer: Layer) -> bool {
+        (self.only.is_empty() || self.only.contains(&layer))
+            && (self.include.is_empty() || self.include.contains(&layer))
+            && !self.exclude.contains(&layer)
+    }
+
+    /// Returns the directory for additional output files like heatmaps. Schematics and
+    /// previews are written to a file path, so its directory is used for them.
+    pub fn output_dir(&self) -> &Path {
+        let path: &Path = Path::new(&self.path);
+        if path.is_dir() {
+            path
+        } else {
+            path.parent().unwrap_or(Path::new("."))
+        }
+    }
+
     pub fn run(&self) {
         // Validating the world path
         let mc_world_path: &Path = Path::new(&self.path);
-        if !mc_world_path.join("region").exists() {
+        if self.format == OutputFormat::Anvil && !mc_world_path.join("region").exists() {
             eprintln!(
                 "{}",
                 "错误！在指定路径中未找到 Minecraft 世界"
@@ -65,17 +315,147 @@ impl Args {
         }
 
         // Validating bbox if provided
-        if let Some(bbox) = &self.bbox {
+        for bbox in &self.bbox {
             if !validate_bounding_box(bbox) {
                 eprintln!("{}", "错误！输入的边界框无效".red().bold());
                 exit(1);
             }
         }
+
+        // Several areas are generated one after another into the same world
+        let area_count: usize = match self.bboxes() {
+            Ok(bboxes) => bboxes.len(),
+            Err(e) => {
+                eprintln!("{}", format!("错误！{}", e).red().bold());
+                exit(1);
+            }
+        };
+        if area_count > 1 {
+            if let Some(error) = self.batch_error() {
+                eprintln!("{}", format!("错误！{}", error).red().bold());
+                exit(1);
+            }
+        }
+    }
+
+    /// Returns why several areas can not be generated with these options, if they can't
+    fn batch_error(&self) -> Option<&'static str> {
+        let writes_world: bool = matches!(self.format, OutputFormat::Anvil | OutputFormat::Rcon);
+        if !writes_world {
+            Some("多个区域只能写入世界或服务器")
+        } else if self.rotation != 0.0 {
+            Some("多个区域不支持 --rotation")
+        } else if self.incremental {
+            Some("多个区域不支持 --incremental")
+        } else if self.format == OutputFormat::Anvil && self.mc_version.has_legacy_chunks() {
+            Some("此 Minecraft 版本的世界不支持多个区域")
+        } else {
+            None
+        }
     }
 }
 
+/// Arguments of the `arnis locate` command
+#[derive(Parser, Debug)]
+#[command(
+    name = "arnis locate",
+    author,
+    version,
+    about = "Converts between Minecraft and geographic coordinates"
+)]
+pub struct LocateArgs {
+    /// Path to a Minecraft world generated by Arnis
+    pub world: String,
+
+    /// Block X coordinate, or latitude with --geo
+    #[arg(allow_negative_numbers = true)]
+    pub first: f64,
+
+    /// Block Z coordinate, or longitude with --geo
+    #[arg(allow_negative_numbers = true)]
+    pub second: f64,
+
+    /// Convert latitude and longitude into block coordinates instead (default: false)
+    #[arg(long, default_value_t = false)]
+    pub geo: bool,
+}
+
+/// Arguments of the `arnis suggest` command
+#[derive(Parser, Debug)]
+#[command(
+    name = "arnis suggest",
+    author,
+    version,
+    about = "Suggests a world scale and ground level for a bounding box"
+)]
+pub struct SuggestArgs {
+    /// Bounding box of the area (min_lng,min_lat,max_lng,max_lat)
+    #[arg(long, allow_hyphen_values = true)]
+    pub bbox: String,
+
+    /// Check this scale against the limits instead of suggesting one (optional)
+    #[arg(long)]
+    pub scale: Option<f64>,
+
+    /// Check this ground level against the limits instead of suggesting one (optional)
+    #[arg(long, allow_hyphen_values = true)]
+    pub ground_level: Option<i32>,
+}
+
+/// Arguments of the `arnis debug-query` command
+#[derive(Parser, Debug)]
+#[command(
+    name = "arnis debug-query",
+    author,
+    version,
+    about = "Searches the debug dump of the parsed elements written with --debug"
+)]
+pub struct DebugQueryArgs {
+    /// Directory of the debug dump
+    #[arg(default_value = "arnis_debug")]
+    pub dir: String,
+
+    /// Only elements with this OSM ID (optional)
+    #[arg(long)]
+    pub id: Option<u64>,
+
+    /// Only elements of this type: node, way or relation (optional)
+    #[arg(long)]
+    pub kind: Option<String>,
+
+    /// Only elements with this tag, given as key or key=value (optional)
+    #[arg(long)]
+    pub tag: Option<String>,
+
+    /// Only elements with nodes in this chunk, given as x,z in chunk coordinates (optional)
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_chunk)]
+    pub chunk: Option<(i32, i32)>,
+}
+
+/// Arguments of the HTTP server started with `arnis --serve`
+#[derive(Parser, Debug)]
+#[command(
+    name = "arnis --serve",
+    author,
+    version,
+    about = "Runs an HTTP server generating worlds for web frontends and bots"
+)]
+pub struct ServeArgs {
+    /// Run the HTTP server (required)
+    #[arg(long)]
+    pub serve: bool,
+
+    /// Address and port to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    pub listen: String,
+
+    /// Directory the worlds of the jobs are generated in
+    #[arg(long, default_value = "arnis_jobs")]
+    pub jobs_dir: String,
+}
+
 /// Validates the bounding box string
-fn validate_bounding_box(bbox: &str) -> bool {
+pub fn validate_bounding_box(bbox: &str) -> bool {
     let parts: Vec<&str> = bbox.split(',').collect();
     if parts.len() != 4 {
         return false;
@@ -97,6 +477,46 @@ fn validate_bounding_box(bbox: &str) -> bool {
     min_lng < max_lng && min_lat < max_lat
 }
 
+/// Parses chunk coordinates given as x,z
+fn parse_chunk(arg: &str) -> Result<(i32, i32), String> {
+    let (x, z) = arg
+        .split_once(',')
+        .ok_or_else(|| "expected x,z".to_string())?;
+    let parse = |part: &str| -> Result<i32, String> {
+        part.trim()
+            .parse::<i32>()
+            .map_err(|e: std::num::ParseIntError| e.to_string())
+    };
+    Ok((parse(x)?, parse(z)?))
+}
+
+/// Parses a point of interest given as latitude and longitude
+fn parse_focus(arg: &str) -> Result<(f64, f64), String> {
+    let parts: Vec<f64> = arg
+        .split(',')
+        .map(|part: &str| part.trim().parse::<f64>())
+        .collect::<Result<Vec<f64>, _>>()
+        .map_err(|e: std::num::ParseFloatError| e.to_string())?;
+    match parts.as_slice() {
+        [lat, lng] if (-90.0..=90.0).contains(lat) && (-180.0..=180.0).contains(lng) => {
+            Ok((*lat, *lng))
+        }
+        _ => Err("expected latitude,longitude".to_string()),
+    }
+}
+
+/// Parses the polygon of the area, given directly or as the path of a file containing it
+fn parse_polygon(arg: &str) -> Result<AreaPolygon, String> {
+    let path: &Path = Path::new(arg);
+    if path.is_file() {
+        let text: String = std::fs::read_to_string(path)
+            .map_err(|e: std::io::Error| format!("无法读取多边形文件：{}", e))?;
+        AreaPolygon::parse(&text)
+    } else {
+        AreaPolygon::parse(arg)
+    }
+}
+
 fn parse_duration(arg: &str) -> Result<std::time::Duration, std::num::ParseIntError> {
     let seconds = arg.parse()?;
     Ok(std::time::Duration::from_secs(seconds))
@@ -0,0 +1,111 @@
+use crate::area_polygon::{geojson_polygons, AreaPolygon};
+use crate::args::Args;
+use crate::context::GenerationContext;
+use crate::level_dat::set_world_border;
+use crate::osm_parser::{area_size, geo_distance};
+use crate::output::{create_backend, OutputBackend, OutputFormat};
+use colored::Colorize;
+use serde_json::Value;
+use std::fs;
+
+/// Reads the areas of a GeoJSON file given with --bbox-file. Every polygon, also those of
+/// multipolygons, features and collections, becomes the bounding box of its outer ring.
+pub fn read_geojson_bboxes(path: &str) -> Result<Vec<(f64, f64, f64, f64)>, String> {
+    let content: String = fs::read_to_string(path)
+        .map_err(|e: std::io::Error| format!("无法读取 GeoJSON 文件 {}：{}", path, e))?;
+    let geojson: Value = serde_json::from_str(&content)
+        .map_err(|e: serde_json::Error| format!("无法解析 GeoJSON 文件 {}：{}", path, e))?;
+
+    let bboxes: Vec<(f64, f64, f64, f64)> = geojson_polygons(&geojson)?
+        .iter()
+        .map(AreaPolygon::bbox)
+        .collect();
+    if bboxes.is_empty() {
+        return Err(format!("GeoJSON 文件 {} 中没有多边形", path));
+    }
+    Ok(bboxes)
+}
+
+/// Returns the block offsets of the areas, placing them as they lie to each other on the
+/// map. The west and north edges of all areas together are at the --x-offset and --z-offset.
+pub fn area_offsets(bboxes: &[(f64, f64, f64, f64)], args: &Args) -> Vec<(i32, i32)> {
+    let min_lng: f64 = bboxes
+        .iter()
+        .map(|bbox: &(f64, f64, f64, f64)| bbox.0)
+        .fold(f64::MAX, f64::min);
+    let max_lat: f64 = bboxes
+        .iter()
+        .map(|bbox: &(f64, f64, f64, f64)| bbox.3)
+        .fold(f64::MIN, f64::max);
+
+    bboxes
+        .iter()
+        .map(|bbox: &(f64, f64, f64, f64)| {
+            // Distances east are measured at the latitude of the area, like its own width
+            let latitude: f64 = (bbox.1 + bbox.3) / 2.0;
+            let (_, east) = geo_distance(latitude, latitude, min_lng, bbox.0);
+            let (south, _) = geo_distance(bbox.3, max_lat, bbox.0, bbox.0);
+            (
+                args.x_offset + (east * args.scale).round() as i32,
+                args.z_offset + (south * args.scale).round() as i32,
+            )
+        })
+        .collect()
+}
+
+/// Generates several areas into one world, one after another at the offsets of their
+/// places on the map. Every area is fetched and generated like a single --bbox, later areas
+/// are merged into the regions written by the earlier ones.
+pub fn generate_batch(
+    bboxes: &[(f64, f64, f64, f64)],
+    args: &Args,
+    context: &GenerationContext,
+) -> Result<(), String> {
+    let offsets: Vec<(i32, i32)> = area_offsets(bboxes, args);
+
+    for (index, (bbox, offset)) in bboxes.iter().zip(&offsets).enumerate() {
+        println!(
+            "{}",
+            format!(
+                "正在生成区域 {}/{}（{},{},{},{}）",
+                index + 1,
+                bboxes.len(),
+                bbox.0,
+                bbox.1,
+                bbox.2,
+                bbox.3
+            )
+            .bold()
+        );
+
+        // Each area is generated like a run with only its --bbox
+        let mut area_args: Args = args.clone();
+        area_args.bbox = vec![format!("{},{},{},{}", bbox.0, bbox.1, bbox.2, bbox.3)];
+        area_args.bbox_file = None;
+        area_args.x_offset = offset.0;
+        area_args.z_offset = offset.1;
+        // The border is set around all areas once they are generated
+        area_args.world_border = false;
+        // Regions replaced with --overwrite would lose the areas generated before
+        area_args.overwrite = args.overwrite && index == 0;
+
+        let mut output: Box<dyn OutputBackend> = create_backend(&area_args)?;
+        crate::generate_world(*bbox, &area_args, output.as_mut(), context)?;
+    }
+
+    if args.world_border && args.format == OutputFormat::Anvil {
+        let (mut max_x, mut max_z) = (args.x_offset, args.z_offset);
+        for (bbox, offset) in bboxes.iter().zip(&offsets) {
+            let (size_x, size_z) = area_size(*bbox, args.scale);
+            max_x = max_x.max(offset.0 + size_x as i32 + 1);
+            max_z = max_z.max(offset.1 + size_z as i32 + 1);
+        }
+        let corner: (i32, i32) = (args.x_offset, args.z_offset);
+        let (size_x, size_z) = (max_x - corner.0, max_z - corner.1);
+        if let Err(e) = set_world_border(&args.path, corner, size_x, size_z) {
+            eprintln!("{}", format!("警告：无法设置世界边界：{}", e).yellow());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+#![allow(unused)]
+
+#[derive(Copy, Clone, PartialEq, Eq, Ord, PartialOrd, Hash, Debug)]
+pub struct Biome {
+    id: u8,
+}
+
+impl Biome {
+    const fn new(id: u8) -> Self {
+        Self { id }
+    }
+
+    /// Returns the biome with the given ID, e.g. read back from a checkpoint
+    pub fn from_id(id: u8) -> Self {
+        Self::new(id)
+    }
+
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        match self.id {
+            0 => "plains",
+            1 => "forest",
+            2 => "river",
+            3 => "beach",
+            4 => "desert",
+            5 => "swamp",
+            6 => "snowy_plains",
+            7 => "frozen_river",
+            8 => "snowy_taiga",
+            9 => "snowy_beach",
+            10 => "meadow",
+            _ => panic!("无效生物群系 ID"),
+        }
+    }
+
+    /// Returns the snowy counterpart of the biome, used in winter mode.
+    pub fn winter_variant(&self) -> Biome {
+        match *self {
+            PLAINS | MEADOW => SNOWY_PLAINS,
+            FOREST | SWAMP => SNOWY_TAIGA,
+            RIVER => FROZEN_RIVER,
+            BEACH => SNOWY_BEACH,
+            other => other,
+        }
+    }
+}
+
+pub const PLAINS: Biome = Biome::new(0);
+pub const FOREST: Biome = Biome::new(1);
+pub const RIVER: Biome = Biome::new(2);
+pub const BEACH: Biome = Biome::new(3);
+pub const DESERT: Biome = Biome::new(4);
+pub const SWAMP: Biome = Biome::new(5);
+pub const SNOWY_PLAINS: Biome = Biome::new(6);
+pub const FROZEN_RIVER: Biome = Biome::new(7);
+pub const SNOWY_TAIGA: Biome = Biome::new(8);
+pub const SNOWY_BEACH: Biome = Biome::new(9);
+pub const MEADOW: Biome = Biome::new(10);
@@ -4,6 +4,7 @@ use fastnbt::Value;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::RwLock;
 
 use crate::colors::RGBTuple;
 
@@ -17,6 +18,11 @@ impl Block {
         Self { id }
     }
 
+    /// Returns the block with the given ID, e.g. read back from a checkpoint
+    pub fn from_id(id: u8) -> Self {
+        Self::new(id)
+    }
+
     pub fn id(&self) -> u8 {
         self.id
     }
@@ -141,6 +147,34 @@ impl Block {
             111 => "snow_block",
             112 => "snow",
             113 => "oak_sign",
+            114..=117 => "stone_brick_stairs",
+            118..=121 => "brick_stairs",
+            122..=125 => "dark_oak_stairs",
+            126 => "brick_slab",
+            127 => "dark_oak_slab",
+            128 => "lantern",
+            129..=133 => "rail",
+            134 => "cave_air",
+            135..=138 => "oak_sign",
+            139 => "jungle_log",
+            140 => "jungle_leaves",
+            141 => "spruce_leaves",
+            142 => "fern",
+            143 => "coarse_dirt",
+            144 => "pumpkin",
+            145 => "melon",
+            146 => "beetroots",
+            147 => "daylight_detector",
+            148 => "light",
+            149 => "stone_brick_slab",
+            150 => "water",
+            151 => "chest",
+            152 => "furnace",
+            153 => "lectern",
+            154 => "white_banner",
+            155 => "flower_pot",
+            156 => "potted_poppy",
+            FIRST_CUSTOM_ID..=u8::MAX => custom_block_definition(self.id).0,
             _ => panic!("无效 ID"),
         }
     }
@@ -177,9 +211,31 @@ impl Block {
                 map
             })),
 
-            113 => Some(Value::Compound({
+            113 => Some(sign_properties(6)),
+
+            146 => Some(Value::Compound({
+                let mut map: HashMap<String, Value> = HashMap::new();
+                map.insert("age".to_string(), Value::String("3".to_string()));
+                map
+            })),
+
+            149 => Some(Value::Compound({
                 let mut map: HashMap<String, Value> = HashMap::new();
-                map.insert("rotation".to_string(), Value::String("6".to_string()));
+                map.insert("type".to_string(), Value::String("bottom".to_string()));
+                map.insert("waterlogged".to_string(), Value::String("true".to_string()));
+                map
+            })),
+
+            150 => Some(Value::Compound({
+                let mut map: HashMap<String, Value> = HashMap::new();
+                map.insert("level".to_string(), Value::String("8".to_string()));
+                map
+            })),
+
+            151 => Some(Value::Compound({
+                let mut map: HashMap<String, Value> = HashMap::new();
+                map.insert("facing".to_string(), Value::String("north".to_string()));
+                map.insert("type".to_string(), Value::String("single".to_string()));
                 map.insert(
                     "waterlogged".to_string(),
                     Value::String("false".to_string()),
@@ -187,11 +243,159 @@ impl Block {
                 map
             })),
 
+            152 => Some(Value::Compound({
+                let mut map: HashMap<String, Value> = HashMap::new();
+                map.insert("facing".to_string(), Value::String("north".to_string()));
+                map.insert("lit".to_string(), Value::String("false".to_string()));
+                map
+            })),
+
+            153 => Some(Value::Compound({
+                let mut map: HashMap<String, Value> = HashMap::new();
+                map.insert("facing".to_string(), Value::String("north".to_string()));
+                map.insert("has_book".to_string(), Value::String("true".to_string()));
+                map.insert("powered".to_string(), Value::String("false".to_string()));
+                map
+            })),
+
+            154 => Some(Value::Compound({
+                let mut map: HashMap<String, Value> = HashMap::new();
+                map.insert("rotation".to_string(), Value::String("0".to_string()));
+                map
+            })),
+
+            114 | 118 | 122 => Some(stairs_properties("north")),
+            115 | 119 | 123 => Some(stairs_properties("east")),
+            116 | 120 | 124 => Some(stairs_properties("south")),
+            117 | 121 | 125 => Some(stairs_properties("west")),
+
+            129 => Some(rail_properties("east_west")),
+            130 => Some(rail_properties("north_east")),
+            131 => Some(rail_properties("north_west")),
+            132 => Some(rail_properties("south_east")),
+            133 => Some(rail_properties("south_west")),
+
+            135 => Some(sign_properties(0)),
+            136 => Some(sign_properties(4)),
+            137 => Some(sign_properties(8)),
+            138 => Some(sign_properties(12)),
+
+            FIRST_CUSTOM_ID..=u8::MAX => custom_block_definition(self.id).1,
+
             _ => None,
         }
     }
 }
 
+/// First id of the blocks registered at runtime, e.g. from a custom block palette
+const FIRST_CUSTOM_ID: u8 = 200;
+
+/// Name and properties of a block registered at runtime
+type CustomBlock = (&'static str, Option<Value>);
+
+/// Blocks registered at runtime, indexed from FIRST_CUSTOM_ID
+static CUSTOM_BLOCKS: Lazy<RwLock<Vec<CustomBlock>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+fn custom_block_definition(id: u8) -> CustomBlock {
+    CUSTOM_BLOCKS
+        .read()
+        .unwrap()
+        .get(usize::from(id - FIRST_CUSTOM_ID))
+        .cloned()
+        .expect("无效 ID")
+}
+
+/// Registers a block that is not part of the predefined blocks, given as a Minecraft block
+/// ID with optional block states, e.g. "minecraft:oak_stairs[facing=north]".
+/// Registering the same block twice returns the same block.
+pub fn register_custom_block(block_id: &str) -> Result<Block, String> {
+    let block_id: &str = block_id.trim();
+    let (name, states): (&str, Option<&str>) = match block_id.split_once('[') {
+        Some((name, states)) => match states.strip_suffix(']') {
+            Some(states) => (name, Some(states)),
+            None => return Err(format!("方块 ID 无效：{}", block_id)),
+        },
+        None => (block_id, None),
+    };
+
+    let is_valid_name: bool = !name.is_empty()
+        && name.split(':').count() <= 2
+        && name
+            .chars()
+            .all(|c: char| matches!(c, 'a'..='z' | '0'..='9' | '_' | '-' | '.' | '/' | ':'));
+    if !is_valid_name {
+        return Err(format!("方块 ID 无效：{}", block_id));
+    }
+
+    let properties: Option<Value> = match states {
+        Some(states) => {
+            let mut map: HashMap<String, Value> = HashMap::new();
+            for state in states.split(',').filter(|state: &&str| !state.is_empty()) {
+                let Some((key, value)) = state.split_once('=') else {
+                    return Err(format!("方块状态无效：{}", block_id));
+                };
+                map.insert(
+                    key.trim().to_string(),
+                    Value::String(value.trim().to_string()),
+                );
+            }
+            Some(Value::Compound(map))
+        }
+        None => None,
+    };
+
+    let mut custom_blocks = CUSTOM_BLOCKS.write().unwrap();
+    let index: usize = match custom_blocks
+        .iter()
+        .position(|(existing, existing_properties)| {
+            *existing == name && *existing_properties == properties
+        }) {
+        Some(index) => index,
+        None => {
+            if custom_blocks.len() > usize::from(u8::MAX - FIRST_CUSTOM_ID) {
+                return Err("自定义方块过多".to_string());
+            }
+            // Custom blocks live for the whole run, so their names can be leaked
+            let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+            custom_blocks.push((name, properties));
+            custom_blocks.len() - 1
+        }
+    };
+    Ok(Block::new(FIRST_CUSTOM_ID + index as u8))
+}
+
+fn sign_properties(rotation: u8) -> Value {
+    let mut map: HashMap<String, Value> = HashMap::new();
+    map.insert("rotation".to_string(), Value::String(rotation.to_string()));
+    map.insert(
+        "waterlogged".to_string(),
+        Value::String("false".to_string()),
+    );
+    Value::Compound(map)
+}
+
+fn rail_properties(shape: &str) -> Value {
+    let mut map: HashMap<String, Value> = HashMap::new();
+    map.insert("shape".to_string(), Value::String(shape.to_string()));
+    map.insert(
+        "waterlogged".to_string(),
+        Value::String("false".to_string()),
+    );
+    Value::Compound(map)
+}
+
+fn stairs_properties(facing: &str) -> Value {
+    let mut map: HashMap<String, Value> = HashMap::new();
+    map.insert("facing".to_string(), Value::String(facing.to_string()));
+    map.insert("half".to_string(), Value::String("bottom".to_string()));
+    map.insert("shape".to_string(), Value::String("straight".to_string()));
+    map.insert(
+        "waterlogged".to_string(),
+        Value::String("false".to_string()),
+    );
+    Value::Compound(map)
+}
+
 // Lazy static blocks
 pub const ACACIA_PLANKS: Block = Block::new(0);
 pub const AIR: Block = Block::new(1);
@@ -310,6 +514,53 @@ pub const WHEAT: Block = Block::new(109);
 
 pub const BEDROCK: Block = Block::new(110);
 
+// Stairs, one block per facing direction
+pub const STONE_BRICK_STAIRS_NORTH: Block = Block::new(114);
+pub const STONE_BRICK_STAIRS_EAST: Block = Block::new(115);
+pub const STONE_BRICK_STAIRS_SOUTH: Block = Block::new(116);
+pub const STONE_BRICK_STAIRS_WEST: Block = Block::new(117);
+pub const BRICK_STAIRS_NORTH: Block = Block::new(118);
+pub const BRICK_STAIRS_EAST: Block = Block::new(119);
+pub const BRICK_STAIRS_SOUTH: Block = Block::new(120);
+pub const BRICK_STAIRS_WEST: Block = Block::new(121);
+pub const DARK_OAK_STAIRS_NORTH: Block = Block::new(122);
+pub const DARK_OAK_STAIRS_EAST: Block = Block::new(123);
+pub const DARK_OAK_STAIRS_SOUTH: Block = Block::new(124);
+pub const DARK_OAK_STAIRS_WEST: Block = Block::new(125);
+pub const BRICK_SLAB: Block = Block::new(126);
+pub const DARK_OAK_SLAB: Block = Block::new(127);
+pub const LANTERN: Block = Block::new(128);
+pub const RAIL_EAST_WEST: Block = Block::new(129);
+pub const RAIL_NORTH_EAST: Block = Block::new(130);
+pub const RAIL_NORTH_WEST: Block = Block::new(131);
+pub const RAIL_SOUTH_EAST: Block = Block::new(132);
+pub const RAIL_SOUTH_WEST: Block = Block::new(133);
+// Unlike AIR, cave air is stored as a block, so carved spaces are not filled by later elements
+pub const CAVE_AIR: Block = Block::new(134);
+// Standing signs, named after the direction their text faces
+pub const SIGN_SOUTH: Block = Block::new(135);
+pub const SIGN_WEST: Block = Block::new(136);
+pub const SIGN_NORTH: Block = Block::new(137);
+pub const SIGN_EAST: Block = Block::new(138);
+pub const JUNGLE_LOG: Block = Block::new(139);
+pub const JUNGLE_LEAVES: Block = Block::new(140);
+pub const SPRUCE_LEAVES: Block = Block::new(141);
+pub const FERN: Block = Block::new(142);
+pub const COARSE_DIRT: Block = Block::new(143);
+pub const PUMPKIN: Block = Block::new(144);
+pub const MELON: Block = Block::new(145);
+pub const BEETROOTS: Block = Block::new(146);
+pub const DAYLIGHT_DETECTOR: Block = Block::new(147);
+pub const LIGHT: Block = Block::new(148);
+pub const WATERLOGGED_STONE_BRICK_SLAB: Block = Block::new(149);
+pub const FALLING_WATER: Block = Block::new(150);
+pub const CHEST: Block = Block::new(151);
+pub const FURNACE: Block = Block::new(152);
+pub const LECTERN: Block = Block::new(153);
+pub const WHITE_BANNER: Block = Block::new(154);
+pub const FLOWER_POT: Block = Block::new(155);
+pub const POTTED_POPPY: Block = Block::new(156);
+
 // Variations for building corners
 pub fn building_corner_variations() -> Vec<Block> {
     vec![
@@ -368,6 +619,21 @@ pub fn building_wall_color_map() -> Vec<(RGBTuple, Block)> {
         ((255, 255, 255), WHITE_CONCRETE),
         ((209, 177, 161), WHITE_TERRACOTTA),
         ((191, 147, 42), YELLOW_TERRACOTTA),
+        ((8, 10, 15), BLACK_CONCRETE),
+        ((54, 57, 61), GRAY_CONCRETE),
+        ((125, 125, 115), LIGHT_GRAY_CONCRETE),
+        ((35, 137, 198), LIGHT_BLUE_CONCRETE),
+        ((44, 46, 143), BLUE_CONCRETE),
+        ((240, 175, 21), YELLOW_CONCRETE),
+        ((94, 168, 24), LIME_CONCRETE),
+        ((142, 32, 32), RED_CONCRETE),
+        ((152, 94, 67), TERRACOTTA),
+        ((74, 59, 91), BLUE_TERRACOTTA),
+        ((162, 130, 78), OAK_PLANKS),
+        ((114, 84, 48), SPRUCE_PLANKS),
+        ((66, 43, 20), DARK_OAK_PLANKS),
+        ((168, 90, 50), ACACIA_PLANKS),
+        ((235, 229, 222), QUARTZ_BRICKS),
     ]
 }
 
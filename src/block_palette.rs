@@ -0,0 +1,86 @@
+use crate::block_definitions::{register_custom_block, Block};
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// Blocks chosen by the user for element categories, replacing the built-in blocks
+static BLOCK_PALETTE: OnceCell<BlockPalette> = OnceCell::new();
+
+/// Element categories whose blocks can be replaced with a custom block palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockCategory {
+    RoadSurface,
+    BuildingWall,
+    Roof,
+    Water,
+    Grass,
+}
+
+/// Block palette file, mapping element categories to Minecraft block IDs
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct BlockPaletteConfig {
+    road_surface: Option<String>,
+    building_wall: Option<String>,
+    roof: Option<String>,
+    water: Option<String>,
+    grass: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct BlockPalette {
+    road_surface: Option<Block>,
+    building_wall: Option<Block>,
+    roof: Option<Block>,
+    water: Option<Block>,
+    grass: Option<Block>,
+}
+
+/// Loads a block palette from a TOML or JSON file. Categories missing in the file keep
+/// their built-in blocks.
+pub fn load_block_palette(path: &str) -> Result<(), String> {
+    let content: String =
+        fs::read_to_string(path).map_err(|e: std::io::Error| format!("无法读取方块配置：{}", e))?;
+    let is_json: bool = Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    let config: BlockPaletteConfig = if is_json {
+        serde_json::from_str(&content).map_err(|e: serde_json::Error| e.to_string())
+    } else {
+        toml::from_str(&content).map_err(|e: toml::de::Error| e.to_string())
+    }
+    .map_err(|e: String| format!("方块配置无效：{}", e))?;
+
+    let register = |block_id: Option<String>| -> Result<Option<Block>, String> {
+        block_id
+            .map(|block_id: String| register_custom_block(&block_id))
+            .transpose()
+    };
+    let palette: BlockPalette = BlockPalette {
+        road_surface: register(config.road_surface)?,
+        building_wall: register(config.building_wall)?,
+        roof: register(config.roof)?,
+        water: register(config.water)?,
+        grass: register(config.grass)?,
+    };
+
+    BLOCK_PALETTE
+        .set(palette)
+        .map_err(|_| "方块配置已加载".to_string())
+}
+
+/// Returns the block of the custom palette for the category, or the given built-in block
+pub fn palette_block(category: BlockCategory, default: Block) -> Block {
+    let Some(palette) = BLOCK_PALETTE.get() else {
+        return default;
+    };
+    match category {
+        BlockCategory::RoadSurface => palette.road_surface,
+        BlockCategory::BuildingWall => palette.building_wall,
+        BlockCategory::Roof => palette.roof,
+        BlockCategory::Water => palette.water,
+        BlockCategory::Grass => palette.grass,
+    }
+    .unwrap_or(default)
+}
@@ -1,3 +1,11 @@
+use fnv::FnvHashMap;
+
+/// Largest change of direction at a node, in degrees, that is smoothed into a curve. Sharper
+/// turns, e.g. at junctions, are kept as corners.
+const MAX_SMOOTHED_TURN: f64 = 60.0;
+/// Distance in blocks between the points sampled along a curve
+const CURVE_STEP: f64 = 2.0;
+
 /// Generates the coordinates for a line between two points using the Bresenham algorithm.
 /// The result is a vector of 3D coordinates (x, y, z).
 pub fn bresenham_line(
@@ -81,3 +89,180 @@ pub fn bresenham_line(
     points.push((x2, y2, z2));
     points
 }
+
+/// Smooths a polyline into curves through its points, using cubic Hermite splines with
+/// Catmull-Rom tangents. Returns the original points together with points sampled along the
+/// curves in between, to be connected with straight lines. Sharp corners are left as they are.
+pub fn smooth_polyline(points: &[(i32, i32)]) -> Vec<(i32, i32)> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    let as_f64 = |(x, z): (i32, i32)| -> (f64, f64) { (f64::from(x), f64::from(z)) };
+    // Whether the curve may bend at an inner point
+    let is_smooth = |i: usize| -> bool {
+        let (x0, z0) = as_f64(points[i - 1]);
+        let (x1, z1) = as_f64(points[i]);
+        let (x2, z2) = as_f64(points[i + 1]);
+        let (ux, uz, vx, vz) = (x1 - x0, z1 - z0, x2 - x1, z2 - z1);
+        let lengths: f64 = ux.hypot(uz) * vx.hypot(vz);
+        lengths > 0.0
+            && ((ux * vx + uz * vz) / lengths)
+                .clamp(-1.0, 1.0)
+                .acos()
+                .to_degrees()
+                <= MAX_SMOOTHED_TURN
+    };
+
+    let mut smoothed: Vec<(i32, i32)> = vec![points[0]];
+    for i in 0..points.len() - 1 {
+        let (ax, az) = as_f64(points[i]);
+        let (bx, bz) = as_f64(points[i + 1]);
+        let length: f64 = (bx - ax).hypot(bz - az);
+        if length < CURVE_STEP * 2.0 {
+            smoothed.push(points[i + 1]);
+            continue;
+        }
+
+        // Tangents as long as the segment, along the neighboring points at smooth inner
+        // points and along the segment otherwise
+        let tangent = |j: usize| -> (f64, f64) {
+            let (dx, dz) = if j > 0 && j < points.len() - 1 && is_smooth(j) {
+                let (px, pz) = as_f64(points[j - 1]);
+                let (nx, nz) = as_f64(points[j + 1]);
+                (nx - px, nz - pz)
+            } else {
+                (bx - ax, bz - az)
+            };
+            let tangent_length: f64 = dx.hypot(dz);
+            (dx / tangent_length * length, dz / tangent_length * length)
+        };
+        let (m0x, m0z) = tangent(i);
+        let (m1x, m1z) = tangent(i + 1);
+
+        let steps: usize = (length / CURVE_STEP).ceil() as usize;
+        for step in 1..steps {
+            let t: f64 = step as f64 / steps as f64;
+            let (t2, t3) = (t * t, t * t * t);
+            let h00: f64 = 2.0 * t3 - 3.0 * t2 + 1.0;
+            let h10: f64 = t3 - 2.0 * t2 + t;
+            let h01: f64 = -2.0 * t3 + 3.0 * t2;
+            let h11: f64 = t3 - t2;
+            let point: (i32, i32) = (
+                (h00 * ax + h10 * m0x + h01 * bx + h11 * m1x).round() as i32,
+                (h00 * az + h10 * m0z + h01 * bz + h11 * m1z).round() as i32,
+            );
+            if smoothed.last() != Some(&point) {
+                smoothed.push(point);
+            }
+        }
+        if smoothed.last() != Some(&points[i + 1]) {
+            smoothed.push(points[i + 1]);
+        }
+    }
+    smoothed
+}
+
+/// Returns the blocks whose centers lie within the radius of a line between two points, i.e.
+/// inside a capsule swept along the line, with their distance from the line.
+pub fn thick_line(x1: i32, z1: i32, x2: i32, z2: i32, radius: f64) -> Vec<(i32, i32, f64)> {
+    thick_polyline(&[(x1, z1), (x2, z2)], radius)
+}
+
+/// Returns the blocks whose centers lie within the radius of a polyline with their distance
+/// from the nearest segment. Unlike widening every point of the line, sharp corners are
+/// covered without gaps and diagonal lines keep their width. The blocks are sorted by
+/// position, so the result does not depend on hashing.
+pub fn thick_polyline(points: &[(i32, i32)], radius: f64) -> Vec<(i32, i32, f64)> {
+    let mut distances: FnvHashMap<(i32, i32), f64> = FnvHashMap::default();
+    let reach: i32 = radius.ceil() as i32;
+    let segments: Vec<((i32, i32), (i32, i32))> = if points.len() == 1 {
+        vec![(points[0], points[0])]
+    } else {
+        points.windows(2).map(|span| (span[0], span[1])).collect()
+    };
+
+    for ((x1, z1), (x2, z2)) in segments {
+        for x in (x1.min(x2) - reach)..=(x1.max(x2) + reach) {
+            for z in (z1.min(z2) - reach)..=(z1.max(z2) + reach) {
+                let distance: f64 = segment_distance((x, z), (x1, z1), (x2, z2));
+                if distance > radius {
+                    continue;
+                }
+                let entry: &mut f64 = distances.entry((x, z)).or_insert(distance);
+                *entry = entry.min(distance);
+            }
+        }
+    }
+
+    let mut blocks: Vec<(i32, i32, f64)> = distances
+        .into_iter()
+        .map(|((x, z), distance)| (x, z, distance))
+        .collect();
+    blocks.sort_by_key(|&(x, z, _)| (x, z));
+    blocks
+}
+
+/// Returns the blocks inside an ellipse around the center with the given radii along the x
+/// and z axes.
+pub fn filled_ellipse(
+    center_x: i32,
+    center_z: i32,
+    radius_x: f64,
+    radius_z: f64,
+) -> Vec<(i32, i32)> {
+    let (reach_x, reach_z) = (radius_x.floor() as i32, radius_z.floor() as i32);
+    let mut blocks: Vec<(i32, i32)> = vec![];
+    for dx in -reach_x..=reach_x {
+        for dz in -reach_z..=reach_z {
+            if is_in_ellipse(dx, dz, radius_x, radius_z) {
+                blocks.push((center_x + dx, center_z + dz));
+            }
+        }
+    }
+    blocks
+}
+
+/// Returns the blocks inside a circle around the center
+pub fn filled_circle(center_x: i32, center_z: i32, radius: f64) -> Vec<(i32, i32)> {
+    filled_ellipse(center_x, center_z, radius, radius)
+}
+
+/// Returns the outer ring of an ellipse, the blocks inside it with a direct neighbor
+/// outside. The ring is closed for walking, i.e. without diagonal gaps.
+pub fn ellipse_outline(
+    center_x: i32,
+    center_z: i32,
+    radius_x: f64,
+    radius_z: f64,
+) -> Vec<(i32, i32)> {
+    filled_ellipse(center_x, center_z, radius_x, radius_z)
+        .into_iter()
+        .filter(|&(x, z)| {
+            [(1, 0), (-1, 0), (0, 1), (0, -1)].iter().any(|(dx, dz)| {
+                !is_in_ellipse(x + dx - center_x, z + dz - center_z, radius_x, radius_z)
+            })
+        })
+        .collect()
+}
+
+fn is_in_ellipse(dx: i32, dz: i32, radius_x: f64, radius_z: f64) -> bool {
+    let (nx, nz) = (
+        f64::from(dx) / radius_x.max(0.5),
+        f64::from(dz) / radius_z.max(0.5),
+    );
+    nx * nx + nz * nz <= 1.0
+}
+
+/// Distance of a point from the segment between two points
+fn segment_distance(point: (i32, i32), start: (i32, i32), end: (i32, i32)) -> f64 {
+    let (px, pz) = (f64::from(point.0 - start.0), f64::from(point.1 - start.1));
+    let (dx, dz) = (f64::from(end.0 - start.0), f64::from(end.1 - start.1));
+    let length_sq: f64 = dx * dx + dz * dz;
+    let t: f64 = if length_sq == 0.0 {
+        0.0
+    } else {
+        ((px * dx + pz * dz) / length_sq).clamp(0.0, 1.0)
+    };
+    (px - t * dx).hypot(pz - t * dz)
+}
@@ -0,0 +1,78 @@
+use crate::block_definitions::*;
+use clap::ValueEnum;
+use std::ops::RangeInclusive;
+
+/// Regional building style, used for buildings without detailed tags
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildingStyle {
+    /// Random palettes and heights, no regional profile
+    Default,
+    /// Dense old town with plastered walls, brick and tiled gabled roofs
+    European,
+    /// Low detached houses with light siding and shingled roofs
+    Suburban,
+    /// High-rise city with concrete and glass towers
+    Asian,
+}
+
+impl BuildingStyle {
+    /// Wall blocks used for buildings without a 'building:colour' tag
+    pub fn wall_variations(&self) -> Vec<Block> {
+        match self {
+            Self::Default => building_wall_variations(),
+            Self::European => vec![
+                WHITE_TERRACOTTA,
+                SMOOTH_SANDSTONE,
+                YELLOW_TERRACOTTA,
+                BRICK,
+                ORANGE_TERRACOTTA,
+                POLISHED_DIORITE,
+            ],
+            Self::Suburban => vec![
+                WHITE_CONCRETE,
+                SMOOTH_SANDSTONE,
+                WHITE_TERRACOTTA,
+                LIGHT_BLUE_TERRACOTTA,
+                SPRUCE_PLANKS,
+                BRICK,
+            ],
+            Self::Asian => vec![
+                LIGHT_GRAY_CONCRETE,
+                WHITE_CONCRETE,
+                GRAY_CONCRETE,
+                POLISHED_ANDESITE,
+                SMOOTH_STONE,
+            ],
+        }
+    }
+
+    /// Corner blocks used for the building outline
+    pub fn corner_variations(&self) -> Vec<Block> {
+        match self {
+            Self::Default => building_corner_variations(),
+            Self::European => vec![STONE_BRICKS, CHISELED_STONE_BRICKS, SANDSTONE, BRICK],
+            Self::Suburban => vec![SMOOTH_QUARTZ, STONE_BRICKS, BRICK],
+            Self::Asian => vec![POLISHED_ANDESITE, SMOOTH_STONE, POLISHED_BLACKSTONE_BRICKS],
+        }
+    }
+
+    /// Floor and flat roof blocks used for buildings without a 'roof:colour' tag
+    pub fn floor_variations(&self) -> Vec<Block> {
+        match self {
+            Self::Default => building_floor_variations(),
+            Self::European => vec![TERRACOTTA, SPRUCE_PLANKS, STONE_BRICKS],
+            Self::Suburban => vec![OAK_PLANKS, SPRUCE_PLANKS, DARK_OAK_PLANKS],
+            Self::Asian => vec![STONE_BRICKS, POLISHED_ANDESITE, SMOOTH_STONE],
+        }
+    }
+
+    /// Number of levels of buildings without height tags, or None to keep the default height
+    pub fn default_levels(&self) -> Option<RangeInclusive<i32>> {
+        match self {
+            Self::Default => None,
+            Self::European => Some(3..=5),
+            Self::Suburban => Some(1..=2),
+            Self::Asian => Some(8..=30),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use colored::Colorize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Error message of a generation stopped by the user
+pub const CANCELLED_MESSAGE: &str = "生成已取消";
+
+/// Stops a generation at its next check when cancelled, e.g. by the cancel button or Ctrl+C.
+/// Clones share the same state, so the caller keeps a clone of the token it passes to the
+/// generation.
+#[derive(Clone, Default, Debug)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks the generation to stop at the next check
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Returns an error once the generation was cancelled, so the stages can stop with `?`
+    pub fn check(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            Err(CANCELLED_MESSAGE.to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sleeps for the given duration, waking up early when the generation is cancelled
+    pub fn sleep(&self, duration: Duration) {
+        let start: Instant = Instant::now();
+        while !self.is_cancelled() && start.elapsed() < duration {
+            sleep(Duration::from_millis(100).min(duration - start.elapsed()));
+        }
+    }
+}
+
+/// Cancels the generation on Ctrl+C in the command line, so the world is left as it was
+/// before instead of half written. A second Ctrl+C exits immediately.
+pub fn install_ctrl_c_handler(token: CancelToken) {
+    std::thread::spawn(move || {
+        let Ok(runtime) = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        else {
+            return;
+        };
+        runtime.block_on(async {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                eprintln!("{}", "正在取消生成……再次按 Ctrl+C 立即退出".yellow().bold());
+                token.cancel();
+            }
+            if tokio::signal::ctrl_c().await.is_ok() {
+                std::process::exit(130);
+            }
+        });
+    });
+}
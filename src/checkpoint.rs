@@ -0,0 +1,86 @@
+use crate::world_editor::WorldCheckpoint;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// File name of the checkpoint stored next to the output while generating with --resume
+pub const CHECKPOINT_FILE_NAME: &str = "arnis_checkpoint.nbt.gz";
+
+/// State of a run after all elements were processed. Fetching and parsing the data is
+/// cheap on a rerun thanks to the download cache, so resuming starts at the ground layer.
+#[derive(Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Identifies the area and settings of the run, see checkpoint_key
+    key: String,
+    /// Elements that were too large to fill and only got an outline
+    pub outline_only: Vec<i64>,
+    pub world: WorldCheckpoint,
+}
+
+impl Checkpoint {
+    pub fn new(key: String, outline_only: &[u64], world: WorldCheckpoint) -> Self {
+        Self {
+            key,
+            outline_only: outline_only.iter().map(|id: &u64| *id as i64).collect(),
+            world,
+        }
+    }
+}
+
+/// Returns the key identifying runs whose checkpoints can be resumed by each other. The
+/// elements must end up at the same block coordinates, so the bbox, scale and offset have to
+/// match.
+/// Runs without --seed can resume each other, as their random choices differ anyway.
+pub fn checkpoint_key(
+    bbox: (f64, f64, f64, f64),
+    scale: f64,
+    offset: (i32, i32),
+    ground_level: i32,
+    seed: Option<u64>,
+    elements: usize,
+) -> String {
+    format!(
+        "{:?}/{}/{:?}/{}/{:?}/{}",
+        bbox, scale, offset, ground_level, seed, elements
+    )
+}
+
+fn checkpoint_path(output_dir: &Path) -> PathBuf {
+    output_dir.join(CHECKPOINT_FILE_NAME)
+}
+
+/// Reads the checkpoint of an interrupted run with the same key, if there is one
+pub fn read_checkpoint(output_dir: &Path, key: &str) -> Option<Checkpoint> {
+    let file: File = File::open(checkpoint_path(output_dir)).ok()?;
+    let checkpoint: Checkpoint = fastnbt::from_reader(GzDecoder::new(BufReader::new(file))).ok()?;
+    (checkpoint.key == key).then_some(checkpoint)
+}
+
+/// Writes a checkpoint. It is written next to the previous one first and moved into place,
+/// so a run killed while writing still leaves the previous checkpoint intact.
+pub fn write_checkpoint(output_dir: &Path, checkpoint: &Checkpoint) -> Result<(), String> {
+    let path: PathBuf = checkpoint_path(output_dir);
+    let temporary: PathBuf = path.with_extension("tmp");
+    let data: Vec<u8> = fastnbt::to_bytes(checkpoint)
+        .map_err(|e: fastnbt::error::Error| format!("无法序列化检查点：{}", e))?;
+
+    let file: File = File::create(&temporary)
+        .map_err(|e: std::io::Error| format!("无法创建检查点文件：{}", e))?;
+    let mut encoder: GzEncoder<BufWriter<File>> =
+        GzEncoder::new(BufWriter::new(file), Compression::fast());
+    encoder
+        .write_all(&data)
+        .and_then(|_| encoder.finish())
+        .and_then(|mut writer: BufWriter<File>| writer.flush())
+        .and_then(|_| fs::rename(&temporary, &path))
+        .map_err(|e: std::io::Error| format!("无法写入检查点：{}", e))
+}
+
+/// Removes the checkpoint once the run is complete
+pub fn remove_checkpoint(output_dir: &Path) {
+    let _ = fs::remove_file(checkpoint_path(output_dir));
+}
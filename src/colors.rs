@@ -1,3 +1,5 @@
+use clap::ValueEnum;
+
 pub type RGBTuple = (u8, u8, u8);
 
 pub fn color_text_to_rgb_tuple(text: &str) -> Option<RGBTuple> {
@@ -86,3 +88,49 @@ pub fn rgb_distance(from: &RGBTuple, to: &RGBTuple) -> u32 {
     let distance: i32 = difference.0.pow(2) + difference.1.pow(2) + difference.2.pow(2);
     distance as u32
 }
+
+/// Named color sets for heatmaps and other data visualizations
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorPalette {
+    /// Blue, green, yellow and red
+    Default,
+    /// Viridis ramp, readable with red-green color blindness and in grayscale prints
+    Colorblind,
+    /// Dark to light gray
+    Grayscale,
+}
+
+impl ColorPalette {
+    /// Colors of the ramp, from the lowest to the highest value
+    fn stops(&self) -> &'static [RGBTuple] {
+        match self {
+            Self::Default => &[(40, 60, 200), (40, 190, 80), (240, 220, 40), (220, 40, 30)],
+            Self::Colorblind => &[
+                (68, 1, 84),
+                (59, 82, 139),
+                (33, 145, 140),
+                (94, 201, 98),
+                (253, 231, 37),
+            ],
+            Self::Grayscale => &[(70, 70, 70), (245, 245, 245)],
+        }
+    }
+
+    /// Maps a value between 0 and 1 onto the color ramp of the palette
+    pub fn ramp_color(&self, value: f64) -> RGBTuple {
+        let stops: &[RGBTuple] = self.stops();
+        let position: f64 = value.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+        let index: usize = (position.floor() as usize).min(stops.len() - 2);
+        let t: f64 = position - index as f64;
+
+        let mix = |start: u8, end: u8| -> u8 {
+            (f64::from(start) + (f64::from(end) - f64::from(start)) * t).round() as u8
+        };
+        let (start, end) = (stops[index], stops[index + 1]);
+        (
+            mix(start.0, end.0),
+            mix(start.1, end.1),
+            mix(start.2, end.2),
+        )
+    }
+}
@@ -0,0 +1,80 @@
+use crate::cancel::CancelToken;
+use crate::progress::{ProgressEvent, Stage, StageClock};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Receives the progress events of a generation, e.g. to show them in the GUI or in the
+/// status of a job of the HTTP server
+pub type ProgressListener = Arc<dyn Fn(&ProgressEvent) + Send + Sync>;
+
+/// State of a single generation that the caller of `generate_world` controls: the token to
+/// cancel it and the listener receiving its progress. Nothing of it is shared between
+/// generations, so several of them can run in the same process.
+#[derive(Default)]
+pub struct GenerationContext {
+    cancel: CancelToken,
+    listener: Option<ProgressListener>,
+    clock: StageClock,
+}
+
+impl GenerationContext {
+    /// Creates a context without a progress listener, whose generation runs until it is done
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops the generation once the given token, or a clone of it, is cancelled
+    pub fn with_cancel_token(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Sends every progress event of the generation to the given function
+    pub fn with_progress_listener(
+        mut self,
+        listener: impl Fn(&ProgressEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.listener = Some(Arc::new(listener));
+        self
+    }
+
+    /// Returns a token cancelling the generation of this context
+    pub fn cancel_token(&self) -> CancelToken {
+        self.cancel.clone()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Returns an error once the generation was cancelled, so the stages can stop with `?`
+    pub fn check_cancelled(&self) -> Result<(), String> {
+        self.cancel.check()
+    }
+
+    /// Sleeps for the given duration, waking up early when the generation is cancelled
+    pub fn sleep_unless_cancelled(&self, duration: Duration) {
+        self.cancel.sleep(duration);
+    }
+
+    /// Reports the progress of the generation to the listener, if one is set
+    pub fn emit_progress(&self, event: ProgressEvent) {
+        if let Some(listener) = &self.listener {
+            listener(&event.timed(&self.clock));
+        }
+    }
+
+    /// Reports a problem stopping the generation, with a message for the user
+    pub fn emit_error(&self, message: &str) {
+        let mut event: ProgressEvent = ProgressEvent::new(Stage::Error, 0.0);
+        event.error = Some(message.to_string());
+        self.emit_progress(event);
+    }
+
+    /// Reports the end of the generation with a summary of what was generated
+    pub fn emit_completion(&self, summary: &str) {
+        let mut event: ProgressEvent = ProgressEvent::new(Stage::Complete, 100.0);
+        event.summary = Some(summary.to_string());
+        self.emit_progress(event);
+    }
+}
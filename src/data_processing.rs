@@ -1,169 +1,628 @@
+use crate::area_polygon::AreaMask;
 use crate::args::Args;
 use crate::block_definitions::{DIRT, GRASS_BLOCK, SNOW_BLOCK};
+use crate::block_palette::{palette_block, BlockCategory};
+use crate::checkpoint::{
+    checkpoint_key, read_checkpoint, remove_checkpoint, write_checkpoint, Checkpoint,
+};
+use crate::context::GenerationContext;
+use crate::datapack::write_coordinate_hud;
 use crate::element_processing::*;
-use crate::osm_parser::ProcessedElement;
-use crate::progress::emit_gui_progress_update;
+use crate::floodfill::skipped_fill_count;
+use crate::foliage::add_foliage;
+use crate::heatmap::write_heatmaps;
+use crate::incremental::{read_generated_elements, write_generated_elements, GeneratedElements};
+use crate::layers::{element_layer, Layer};
+use crate::level_dat::{set_spawn_point, set_version, set_world_border};
+use crate::mc_version::McVersion;
+use crate::metadata::{write_metadata, WorldMetadata};
+use crate::night_lights::{add_night_lights, NightLights};
+use crate::osm_parser::{area_size, get_priority, ProcessedElement, ProcessedNode, ProcessedWay};
+use crate::output::{OutputBackend, OutputFormat};
+use crate::progress::{stage_progress_bar, ProgressEvent, Stage};
+use crate::scheduling::{schedule_elements, ElementGroup};
+use crate::water_validation::{fix_water, WaterFixes};
 use crate::world_editor::WorldEditor;
 use colored::Colorize;
-use indicatif::{ProgressBar, ProgressStyle};
+use fnv::FnvHashMap;
+use indicatif::ProgressBar;
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::time::Instant;
 
 pub fn generate_world(
-    elements: Vec<ProcessedElement>,
+    mut elements: Vec<ProcessedElement>,
     args: &Args,
+    bbox: (f64, f64, f64, f64),
     scale_factor_x: f64,
     scale_factor_z: f64,
+    output: &mut dyn OutputBackend,
+    context: &GenerationContext,
 ) -> Result<(), String> {
     println!("{} 正在处理数据...", "[3/5]".bold());
-    emit_gui_progress_update(10.0, "正在处理数据...");
+    context.emit_progress(ProgressEvent::new(Stage::Processing, 10.0));
+    let start_time: Instant = Instant::now();
 
     let ground_level: i32 = args.ground_level;
-    let region_dir: String = format!("{}/region", args.path);
+    // Every random choice derives from the seed, so the same seed gives the same world
+    let seed: u64 = args.seed.unwrap_or_else(rand::random);
+    println!("世界种子：{}", seed);
+
+    let offset: (i32, i32) = (args.x_offset, args.z_offset);
+    let metadata: WorldMetadata =
+        WorldMetadata::new(bbox, area_size(bbox, args.scale), args.rotation, offset);
+
+    // Elements near the point of interest come first within each priority, so the most
+    // interesting area is complete as early as possible
+    let focus: (i32, i32) = match args.focus {
+        Some((lat, lng)) => {
+            let (x, z) = metadata.lat_lon_to_block(lat, lng);
+            (x - offset.0, z - offset.1)
+        }
+        None => (scale_factor_x as i32 / 2, scale_factor_z as i32 / 2),
+    };
+    elements.sort_by_cached_key(|element: &ProcessedElement| {
+        (get_priority(element), distance_to_focus(element, focus))
+    });
+    elements.retain(|element: &ProcessedElement| args.includes_layer(element_layer(element)));
+    // Roads joining a roundabout end on the circle it is drawn as
+    roundabouts::align_roundabout_arms(&mut elements);
+
+    // With --incremental, elements generated unchanged by the last run are skipped. The
+    // ground and the other elements are kept as they are in the world, including user edits.
+    let generated: GeneratedElements = GeneratedElements::new(bbox, args.scale, offset, &elements);
+    let previous: Option<GeneratedElements> = if args.incremental {
+        read_generated_elements(&args.path)
+            .filter(|previous: &GeneratedElements| previous.matches(bbox, args.scale, offset))
+    } else {
+        None
+    };
+    if let Some(previous) = &previous {
+        let total: usize = elements.len();
+        elements.retain(|element: &ProcessedElement| !previous.contains_unchanged(element));
+        println!(
+            "增量生成：{} 个元素已新增或更改，{} 个元素未更改",
+            elements.len(),
+            total - elements.len()
+        );
+        let removed: usize = previous.removed_count(&generated);
+        if removed > 0 {
+            eprintln!(
+                "{}",
+                format!(
+                    "警告：{} 个元素已从 OSM 数据中删除，它们的方块仍保留在世界中",
+                    removed
+                )
+                .yellow()
+            );
+        }
+    }
+    let generates_terrain: bool = args.includes_layer(Layer::Terrain) && previous.is_none();
+
     let mut editor: WorldEditor =
-        WorldEditor::new(&region_dir, scale_factor_x, scale_factor_z, args);
-
-    editor.set_sign(
-        "↑".to_string(),
-        "生成的世界".to_string(),
-        "这个方向".to_string(),
-        "".to_string(),
-        9,
-        -61,
-        9,
-        6,
+        WorldEditor::new(scale_factor_x, scale_factor_z, args, seed, context);
+    // With --polygon, nothing is placed outside the polygon of the area
+    if let Some(polygon) = &args.polygon {
+        let points: Vec<(i32, i32)> = polygon
+            .points()
+            .iter()
+            .map(|&(lng, lat)| {
+                let (x, z) = metadata.lat_lon_to_block(lat, lng);
+                (x - offset.0, z - offset.1)
+            })
+            .collect();
+        editor.set_area_mask(AreaMask::new(&points, editor.get_max_coords()));
+    }
+
+    // With --resume, a run interrupted after processing the elements continues from there
+    let checkpoint_key: String = checkpoint_key(
+        bbox,
+        args.scale,
+        offset,
+        args.ground_level,
+        args.seed,
+        elements.len(),
     );
+    let resumed: Option<Checkpoint> = if args.resume {
+        read_checkpoint(args.output_dir(), &checkpoint_key)
+    } else {
+        None
+    };
+    let is_resumed: bool = resumed.is_some();
+    // Elements too large to fill, which are rendered as outlines only
+    let mut outline_only: Vec<u64> = vec![];
+    if let Some(checkpoint) = resumed {
+        println!("{}", "已从检查点恢复，跳过元素处理".green());
+        outline_only = checkpoint
+            .outline_only
+            .iter()
+            .map(|id: &i64| *id as u64)
+            .collect();
+        editor.restore(checkpoint.world);
+    }
+    let pending: &[ProcessedElement] = if is_resumed { &[] } else { &elements };
+
+    if generates_terrain && !is_resumed {
+        editor.set_sign(
+            "↑".to_string(),
+            "生成的世界".to_string(),
+            "这个方向".to_string(),
+            "".to_string(),
+            9,
+            -61,
+            9,
+            6,
+        );
+    }
 
     // Process data
     let elements_count: usize = elements.len();
-    let process_pb: ProgressBar = ProgressBar::new(elements_count as u64);
-    process_pb.set_style(ProgressStyle::default_bar()
-        .template("{spinner:.green} [{elapsed_precise}] [{bar:45.white/black}] {pos}/{len} 元素 ({eta}) {msg}")
-        .unwrap()
-        .progress_chars("█▓░"));
-
-    let progress_increment_prcs: f64 = 50.0 / elements_count as f64;
-    let mut current_progress_prcs: f64 = 10.0;
-    let mut last_emitted_progress: f64 = current_progress_prcs;
-    for element in &elements {
-        process_pb.inc(1);
-        current_progress_prcs += progress_increment_prcs;
-        if (current_progress_prcs - last_emitted_progress).abs() > 0.25 {
-            emit_gui_progress_update(current_progress_prcs, "");
-            last_emitted_progress = current_progress_prcs;
-        }
-
-        if args.debug {
-            process_pb.set_message(format!(
-                "（元素 ID：{} / 类型：{}）",
-                element.id(),
-                element.kind()
-            ));
-        } else {
-            process_pb.set_message("");
-        }
-
-        match element {
-            ProcessedElement::Way(way) => {
-                if way.tags.contains_key("building") || way.tags.contains_key("building:part") {
-                    buildings::generate_buildings(&mut editor, way, ground_level, args);
-                } else if way.tags.contains_key("highway") {
-                    highways::generate_highways(&mut editor, element, ground_level, args);
-                } else if way.tags.contains_key("landuse") {
-                    landuse::generate_landuse(&mut editor, way, ground_level, args);
-                } else if way.tags.contains_key("natural") {
-                    natural::generate_natural(&mut editor, element, ground_level, args);
-                } else if way.tags.contains_key("amenity") {
-                    amenities::generate_amenities(&mut editor, element, ground_level, args);
-                } else if way.tags.contains_key("leisure") {
-                    leisure::generate_leisure(&mut editor, way, ground_level, args);
-                } else if way.tags.contains_key("barrier") {
-                    barriers::generate_barriers(&mut editor, element, ground_level);
-                } else if way.tags.contains_key("waterway") {
-                    waterways::generate_waterways(&mut editor, way, ground_level);
-                } else if way.tags.contains_key("bridge") {
-                    bridges::generate_bridges(&mut editor, way, ground_level);
-                } else if way.tags.contains_key("railway") {
-                    railways::generate_railways(&mut editor, way, ground_level);
-                } else if way.tags.get("service") == Some(&"siding".to_string()) {
-                    highways::generate_siding(&mut editor, way, ground_level);
-                }
-            }
-            ProcessedElement::Node(node) => {
-                if node.tags.contains_key("door") || node.tags.contains_key("entrance") {
-                    doors::generate_doors(&mut editor, node, ground_level);
-                } else if node.tags.contains_key("natural")
-                    && node.tags.get("natural") == Some(&"tree".to_string())
-                {
-                    natural::generate_natural(&mut editor, element, ground_level, args);
-                } else if node.tags.contains_key("amenity") {
-                    amenities::generate_amenities(&mut editor, element, ground_level, args);
-                } else if node.tags.contains_key("barrier") {
-                    barriers::generate_barriers(&mut editor, element, ground_level);
-                } else if node.tags.contains_key("highway") {
-                    highways::generate_highways(&mut editor, element, ground_level, args);
-                } else if node.tags.contains_key("tourism") {
-                    tourisms::generate_tourisms(&mut editor, node, ground_level);
-                }
-            }
-            ProcessedElement::Relation(rel) => {
-                if rel.tags.contains_key("water") {
-                    water_areas::generate_water_areas(&mut editor, rel, ground_level);
+    let process_pb: ProgressBar =
+        stage_progress_bar(Stage::Processing, elements_count as u64, "元素");
+    // The GUI progress moves from 10 to 60 percent in steps of a quarter percent
+    let progress_step: u64 = (elements_count as u64 / 200).max(1);
+
+    // Groups of elements placing blocks in different regions are processed in parallel, each
+    // on an editor holding only its regions, which is merged back once the stage is done
+    let mut outcomes: Vec<ElementOutcome> = vec![];
+    for stage in schedule_elements(pending, scale_factor_x, scale_factor_z, args) {
+        let mut group_editors: Vec<WorldEditor> = stage
+            .iter()
+            .map(|group: &ElementGroup| editor.split_off(&group.regions))
+            .collect();
+        let results: Vec<Result<Vec<ElementOutcome>, String>> = group_editors
+            .par_iter_mut()
+            .zip(stage.par_iter())
+            .map(|(group_editor, group)| {
+                let mut group_outcomes: Vec<ElementOutcome> = vec![];
+                for &index in &group.elements {
+                    context.check_cancelled()?;
+                    let element: &ProcessedElement = &pending[index];
+                    if args.debug {
+                        process_pb.set_message(format!(
+                            "（元素 ID：{} / 类型：{}）",
+                            element.id(),
+                            element.kind()
+                        ));
+                    }
+
+                    let skipped_fills: usize = skipped_fill_count();
+                    let deferred: Option<DeferredElement> =
+                        process_element(group_editor, element, ground_level, args);
+                    group_outcomes.push(ElementOutcome {
+                        index,
+                        deferred,
+                        outline_only: skipped_fill_count() > skipped_fills,
+                    });
+
+                    process_pb.inc(1);
+                    let done: u64 = process_pb.position();
+                    if done.is_multiple_of(progress_step) {
+                        let progress: f64 = 10.0 + 50.0 * done as f64 / elements_count as f64;
+                        context.emit_progress(
+                            ProgressEvent::new(Stage::Processing, progress)
+                                .counts(done, elements_count as u64),
+                        );
+                    }
                 }
-            }
+                Ok(group_outcomes)
+            })
+            .collect();
+        for group_editor in group_editors {
+            editor.merge(group_editor);
+        }
+        for result in results {
+            outcomes.extend(result?);
+        }
+    }
+    // Deferred elements are generated in the order of the element list, as if all elements
+    // had been processed one after another
+    outcomes.sort_unstable_by_key(|outcome: &ElementOutcome| outcome.index);
+
+    // Subway stations and entrances are generated once all tunnels are in place
+    let mut subway_stations: Vec<&ProcessedNode> = vec![];
+    let mut subway_entrances: Vec<&ProcessedNode> = vec![];
+    // Cuttings are carved once bridges and roads crossing them are in place
+    let mut cutting_ways: Vec<&ProcessedWay> = vec![];
+    // Waterfalls and weirs are built into the water once all waterways are in place
+    let mut water_drops: Vec<&ProcessedElement> = vec![];
+    // Ambulance bays are laid out once the buildings holding their entrances are known
+    let mut ambulance_bays: Vec<&ProcessedNode> = vec![];
+    for outcome in &outcomes {
+        if outcome.outline_only {
+            outline_only.push(pending[outcome.index].id());
+        }
+        match outcome.deferred {
+            Some(DeferredElement::Cutting(way)) => cutting_ways.push(way),
+            Some(DeferredElement::WaterDrop(element)) => water_drops.push(element),
+            Some(DeferredElement::SubwayStation(node)) => subway_stations.push(node),
+            Some(DeferredElement::SubwayEntrance(node)) => subway_entrances.push(node),
+            Some(DeferredElement::AmbulanceBay(node)) => ambulance_bays.push(node),
+            None => {}
         }
     }
 
+    for way in &cutting_ways {
+        cuttings::generate_cutting(&mut editor, way, ground_level);
+    }
+
+    for drop in &water_drops {
+        waterways::generate_water_drop(&mut editor, drop, &elements, ground_level, args);
+    }
+
+    for station in &subway_stations {
+        tunnels::generate_subway_station(&mut editor, station, ground_level);
+    }
+    let station_positions: Vec<(i32, i32)> = subway_stations
+        .iter()
+        .map(|station: &&ProcessedNode| (station.x, station.z))
+        .collect();
+    for entrance in &subway_entrances {
+        tunnels::generate_subway_entrance(&mut editor, entrance, &station_positions, ground_level);
+    }
+
+    for entrance in &ambulance_bays {
+        doors::generate_ambulance_bay(&mut editor, entrance, &elements, ground_level);
+    }
+
     process_pb.finish();
 
+    if args.resume && !is_resumed {
+        let checkpoint: Checkpoint =
+            Checkpoint::new(checkpoint_key, &outline_only, editor.checkpoint());
+        if let Err(e) = write_checkpoint(args.output_dir(), &checkpoint) {
+            eprintln!("{}", format!("警告：{}", e).yellow());
+        }
+    }
+
+    if !outline_only.is_empty() {
+        let ids: Vec<String> = outline_only.iter().map(|id: &u64| id.to_string()).collect();
+        let warning: String = format!(
+            "{} 个元素超出填充限制，仅生成了轮廓：{}",
+            outline_only.len(),
+            ids.join(", ")
+        );
+        eprintln!("{}", format!("警告：{}", warning).yellow());
+        context.emit_progress(
+            ProgressEvent::new(Stage::Processing, 60.0)
+                .counts(elements_count as u64, elements_count as u64)
+                .warnings(vec![warning]),
+        );
+    }
+
+    // Block counts are taken before the ground layer, which would cover every chunk equally
+    if args.heatmap {
+        write_heatmaps(
+            args.output_dir(),
+            editor.modified_blocks(),
+            &element_density(&elements, offset),
+            scale_factor_x,
+            scale_factor_z,
+            offset,
+            args.palette,
+        )?;
+    }
+
+    // Vegetation for unmapped green space, placed before the ground layer covers every column
+    if generates_terrain && args.foliage && args.includes_layer(Layer::Vegetation) {
+        let latitude: f64 = (bbox.1 + bbox.3) / 2.0;
+        let trees: usize = add_foliage(&mut editor, &elements, ground_level, latitude, args);
+        println!("已在未映射的绿地上种植 {} 棵树", trees);
+    }
+
     // Generate ground layer
-    let total_blocks: u64 = (scale_factor_x as i32 + 1) as u64 * (scale_factor_z as i32 + 1) as u64;
-    let desired_updates: u64 = 1500;
-    let batch_size: u64 = (total_blocks / desired_updates).max(1);
+    if generates_terrain {
+        let total_blocks: u64 =
+            (scale_factor_x as i32 + 1) as u64 * (scale_factor_z as i32 + 1) as u64;
+        let desired_updates: u64 = 1500;
+        let batch_size: u64 = (total_blocks / desired_updates).max(1);
 
-    let mut block_counter: u64 = 0;
+        let mut block_counter: u64 = 0;
 
-    println!("{} 生成地面层...", "[4/5]".bold());
-    emit_gui_progress_update(60.0, "生成地面层...");
+        println!("{} 生成地面层...", "[4/5]".bold());
+        context.emit_progress(ProgressEvent::new(Stage::Ground, 60.0).counts(0, total_blocks));
 
-    let ground_pb: ProgressBar = ProgressBar::new(total_blocks);
-    ground_pb.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:45}] {pos}/{len} 块 ({eta})")
-            .unwrap()
-            .progress_chars("█▓░"),
-    );
+        let ground_pb: ProgressBar = stage_progress_bar(Stage::Ground, total_blocks, "块");
 
-    let mut gui_progress_grnd: f64 = 60.0;
-    let mut last_emitted_progress: f64 = gui_progress_grnd;
-    let total_iterations_grnd: f64 = (scale_factor_x + 1.0) * (scale_factor_z + 1.0);
-    let progress_increment_grnd: f64 = 30.0 / total_iterations_grnd;
+        let mut gui_progress_grnd: f64 = 60.0;
+        let mut last_emitted_progress: f64 = gui_progress_grnd;
+        let total_iterations_grnd: f64 = (scale_factor_x + 1.0) * (scale_factor_z + 1.0);
+        let progress_increment_grnd: f64 = 30.0 / total_iterations_grnd;
 
-    let groundlayer_block = if args.winter { SNOW_BLOCK } else { GRASS_BLOCK };
+        let groundlayer_block = if args.winter {
+            SNOW_BLOCK
+        } else {
+            palette_block(BlockCategory::Grass, GRASS_BLOCK)
+        };
 
-    for x in 0..=(scale_factor_x as i32) {
-        for z in 0..=(scale_factor_z as i32) {
-            editor.set_block(groundlayer_block, x, ground_level, z, None, None);
-            editor.set_block(DIRT, x, ground_level - 1, z, None, None);
+        for x in 0..=(scale_factor_x as i32) {
+            context.check_cancelled()?;
+            for z in 0..=(scale_factor_z as i32) {
+                editor.set_block(groundlayer_block, x, ground_level, z, None, None);
+                editor.set_block(DIRT, x, ground_level - 1, z, None, None);
 
-            block_counter += 1;
-            if block_counter % batch_size == 0 {
-                ground_pb.inc(batch_size);
-            }
+                block_counter += 1;
+                if block_counter % batch_size == 0 {
+                    ground_pb.inc(batch_size);
+                }
 
-            gui_progress_grnd += progress_increment_grnd;
-            if (gui_progress_grnd - last_emitted_progress).abs() > 0.25 {
-                emit_gui_progress_update(gui_progress_grnd, "");
-                last_emitted_progress = gui_progress_grnd;
+                gui_progress_grnd += progress_increment_grnd;
+                if (gui_progress_grnd - last_emitted_progress).abs() > 0.25 {
+                    context.emit_progress(
+                        ProgressEvent::new(Stage::Ground, gui_progress_grnd)
+                            .counts(block_counter, total_blocks),
+                    );
+                    last_emitted_progress = gui_progress_grnd;
+                }
             }
         }
+
+        ground_pb.inc(block_counter % batch_size);
+        ground_pb.finish();
+    }
+
+    // Patch water that would flow out once the world is loaded
+    let water_fixes: WaterFixes = fix_water(
+        &mut editor,
+        ground_level,
+        scale_factor_x as i32,
+        scale_factor_z as i32,
+    );
+    if water_fixes.total() > 0 {
+        println!(
+            "已修复 {} 处不稳定的水（支撑 {}，围挡 {}，移除 {}）",
+            water_fixes.total(),
+            water_fixes.supported,
+            water_fixes.contained,
+            water_fixes.removed
+        );
     }
 
-    ground_pb.inc(block_counter % batch_size);
-    ground_pb.finish();
+    if args.night {
+        let lights: NightLights = add_night_lights(&mut editor, &elements, ground_level);
+        println!(
+            "已放置 {} 处夜间灯光（窗户 {}，店面 {}，路灯 {}）",
+            lights.total(),
+            lights.windows,
+            lights.shop_fronts,
+            lights.street_lamps
+        );
+    }
 
     // Save world
-    editor.save();
+    editor.save(output, focus)?;
+    if args.resume {
+        remove_checkpoint(args.output_dir());
+    }
+
+    // Store the projection, so real-world coordinates can be derived from the world later
+    if args.format == OutputFormat::Anvil {
+        write_metadata(&args.path, &metadata)?;
+
+        if args.coordinate_hud {
+            if args.mc_version.supports_function_macros() {
+                write_coordinate_hud(&args.path, &metadata, args.mc_version)?;
+            } else {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "警告：坐标显示需要 Minecraft 1.20.2 或更高版本，已跳过（{}）",
+                        args.mc_version.name()
+                    )
+                    .yellow()
+                );
+            }
+        }
+
+        if args.incremental {
+            write_generated_elements(&args.path, &generated)?;
+        }
+
+        // Spawn in the center of a new world, on top of whatever was placed there
+        if generates_terrain {
+            let (spawn_x, spawn_z) = (scale_factor_x as i32 / 2, scale_factor_z as i32 / 2);
+            let spawn_y: i32 = editor
+                .get_highest_block_y(spawn_x, spawn_z)
+                .unwrap_or(ground_level)
+                + 1
+                + args.mc_version.height_offset();
+            let (world_x, world_z) = (spawn_x + offset.0, spawn_z + offset.1);
+            if let Err(e) = set_spawn_point(&args.path, world_x, spawn_y, world_z) {
+                eprintln!("{}", format!("警告：无法设置出生点：{}", e).yellow());
+            }
+        }
+
+        if args.mc_version != McVersion::default() {
+            set_version(&args.path, args.mc_version)?;
+        }
 
-    emit_gui_progress_update(100.0, "完成！世界生成完成。");
+        if args.world_border {
+            let size: (i32, i32) = (scale_factor_x as i32 + 1, scale_factor_z as i32 + 1);
+            if let Err(e) = set_world_border(&args.path, offset, size.0, size.1) {
+                eprintln!("{}", format!("警告：无法设置世界边界：{}", e).yellow());
+            }
+        }
+    }
+
+    let summary: String = format!(
+        "已处理 {} 个元素，生成 {}×{} 方块的区域，用时 {} 秒。",
+        elements_count,
+        scale_factor_x as i32 + 1,
+        scale_factor_z as i32 + 1,
+        start_time.elapsed().as_secs()
+    );
+    context.emit_completion(&summary);
     println!("{}", "完成！世界生成完成。".green().bold());
+    println!("{}", summary);
     Ok(())
 }
+
+/// Elements generated after all others, once what they are built into is in place
+enum DeferredElement<'e> {
+    Cutting(&'e ProcessedWay),
+    WaterDrop(&'e ProcessedElement),
+    SubwayStation(&'e ProcessedNode),
+    SubwayEntrance(&'e ProcessedNode),
+    AmbulanceBay(&'e ProcessedNode),
+}
+
+/// Result of processing one element, collected from the threads processing the elements
+struct ElementOutcome<'e> {
+    /// Position of the element in the list of processed elements
+    index: usize,
+    deferred: Option<DeferredElement<'e>>,
+    /// Whether the element exceeded the fill limit and was rendered as an outline only
+    outline_only: bool,
+}
+
+/// Generates an element with the generator for its tags. Elements generated after all
+/// others are returned instead.
+fn process_element<'e>(
+    editor: &mut WorldEditor,
+    element: &'e ProcessedElement,
+    ground_level: i32,
+    args: &Args,
+) -> Option<DeferredElement<'e>> {
+    match element {
+        ProcessedElement::Way(way) => {
+            if way.tags.contains_key("building") || way.tags.contains_key("building:part") {
+                buildings::generate_buildings(editor, way, ground_level, args);
+            } else if way
+                .tags
+                .get("bridge")
+                .is_some_and(|bridge: &String| bridge != "no")
+            {
+                bridges::generate_bridges(editor, way, ground_level);
+            } else if ((way.tags.get("tunnel") == Some(&"yes".to_string())
+                && (way.tags.contains_key("highway") || way.tags.contains_key("railway")))
+                || way.tags.get("railway") == Some(&"subway".to_string()))
+                && tunnels::tunnel_fits(way, ground_level)
+            {
+                tunnels::generate_tunnels(editor, way, ground_level);
+            } else if cuttings::is_cutting(way) && cuttings::cutting_fits(way, ground_level) {
+                return Some(DeferredElement::Cutting(way));
+            } else if waterways::is_water_drop(element) {
+                return Some(DeferredElement::WaterDrop(element));
+            } else if way.tags.get("place") == Some(&"square".to_string())
+                || (way.tags.get("highway") == Some(&"pedestrian".to_string())
+                    && way.tags.get("area") == Some(&"yes".to_string()))
+            {
+                squares::generate_squares(editor, element, ground_level, args);
+            } else if way.tags.get("man_made") == Some(&"pier".to_string()) {
+                // Piers are often tagged as footways too, but stand on pilings over water
+                man_made::generate_man_made(editor, element, ground_level, args);
+            } else if way.tags.contains_key("highway") {
+                highways::generate_highways(editor, element, ground_level, args);
+            } else if way.tags.contains_key("landuse") {
+                landuse::generate_landuse(editor, way, ground_level, args);
+            } else if way.tags.contains_key("natural") {
+                natural::generate_natural(editor, element, ground_level, args);
+            } else if way.tags.contains_key("amenity") {
+                amenities::generate_amenities(editor, element, ground_level, args);
+            } else if way.tags.contains_key("leisure") {
+                leisure::generate_leisure(editor, way, ground_level, args);
+            } else if way.tags.contains_key("barrier") {
+                barriers::generate_barriers(editor, element, ground_level);
+            } else if way.tags.contains_key("waterway") {
+                waterways::generate_waterways(editor, way, ground_level, args);
+            } else if way.tags.contains_key("railway") {
+                railways::generate_railways(editor, way, ground_level);
+            } else if way.tags.get("service") == Some(&"siding".to_string()) {
+                highways::generate_siding(editor, way, ground_level);
+            } else if way.tags.contains_key("aeroway") {
+                aeroways::generate_aeroways(editor, element, ground_level, args);
+            } else if way.tags.contains_key("man_made") {
+                man_made::generate_man_made(editor, element, ground_level, args);
+            } else if way.tags.contains_key("power") {
+                power::generate_power(editor, element, ground_level, args);
+            }
+        }
+        ProcessedElement::Node(node) => {
+            if is_subway_station(node) && tunnels::subway_station_fits(node, ground_level) {
+                return Some(DeferredElement::SubwayStation(node));
+            } else if node.tags.get("railway") == Some(&"subway_entrance".to_string())
+                && tunnels::subway_entrance_fits(ground_level)
+            {
+                return Some(DeferredElement::SubwayEntrance(node));
+            } else if waterways::is_water_drop(element) {
+                return Some(DeferredElement::WaterDrop(element));
+            } else if node.tags.contains_key("door") || node.tags.contains_key("entrance") {
+                doors::generate_doors(editor, node, ground_level);
+                if doors::is_emergency_entrance(node) {
+                    return Some(DeferredElement::AmbulanceBay(node));
+                }
+            } else if node.tags.contains_key("natural")
+                && node.tags.get("natural") == Some(&"tree".to_string())
+            {
+                natural::generate_natural(editor, element, ground_level, args);
+            } else if node.tags.contains_key("amenity") {
+                amenities::generate_amenities(editor, element, ground_level, args);
+            } else if node.tags.contains_key("barrier") {
+                barriers::generate_barriers(editor, element, ground_level);
+            } else if node.tags.contains_key("highway") {
+                highways::generate_highways(editor, element, ground_level, args);
+            } else if node.tags.contains_key("tourism") {
+                tourisms::generate_tourisms(editor, node, ground_level);
+            } else if node.tags.contains_key("aeroway") {
+                aeroways::generate_aeroways(editor, element, ground_level, args);
+            } else if node.tags.get("place") == Some(&"square".to_string()) {
+                squares::generate_squares(editor, element, ground_level, args);
+            } else if node.tags.contains_key("man_made") {
+                man_made::generate_man_made(editor, element, ground_level, args);
+            } else if node.tags.contains_key("power") {
+                power::generate_power(editor, element, ground_level, args);
+            }
+        }
+        ProcessedElement::Relation(rel) => {
+            if rel.tags.contains_key("water") {
+                water_areas::generate_water_areas(editor, rel, ground_level);
+            }
+        }
+    }
+    None
+}
+
+/// Counts every element once for each chunk its nodes are located in
+fn element_density(
+    elements: &[ProcessedElement],
+    offset: (i32, i32),
+) -> FnvHashMap<(i32, i32), u32> {
+    let mut density: FnvHashMap<(i32, i32), u32> = FnvHashMap::default();
+    for element in elements {
+        let chunks: HashSet<(i32, i32)> = element
+            .nodes()
+            .map(|node: &ProcessedNode| ((node.x + offset.0) >> 4, (node.z + offset.1) >> 4))
+            .collect();
+        for chunk in chunks {
+            *density.entry(chunk).or_insert(0) += 1;
+        }
+    }
+    density
+}
+
+/// Returns whether a node marks a station of an underground subway line
+fn is_subway_station(node: &ProcessedNode) -> bool {
+    matches!(
+        node.tags
+            .get("railway")
+            .map(|railway: &String| railway.as_str()),
+        Some("station" | "halt")
+    ) && (node.tags.get("station") == Some(&"subway".to_string())
+        || node.tags.get("subway") == Some(&"yes".to_string()))
+}
+
+/// Returns the squared distance between the center of an element and the point of interest
+fn distance_to_focus(element: &ProcessedElement, focus: (i32, i32)) -> i64 {
+    let (mut sum_x, mut sum_z, mut count) = (0i64, 0i64, 0i64);
+    for node in element.nodes() {
+        sum_x += i64::from(node.x);
+        sum_z += i64::from(node.z);
+        count += 1;
+    }
+    if count == 0 {
+        return i64::MAX;
+    }
+    let dx: i64 = sum_x / count - i64::from(focus.0);
+    let dz: i64 = sum_z / count - i64::from(focus.1);
+    dx * dx + dz * dz
+}
@@ -0,0 +1,145 @@
+use crate::mc_version::McVersion;
+use crate::metadata::WorldMetadata;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the datapack directory inside the world's datapacks folder
+const DATAPACK_NAME: &str = "arnis_coordinates";
+
+/// Writes a datapack that shows the real-world latitude and longitude of every player
+/// on the action bar. Coordinates are computed with scoreboard arithmetic in millionths
+/// of a degree from the projection metadata of the world.
+pub fn write_coordinate_hud(
+    world_path: &str,
+    metadata: &WorldMetadata,
+    version: McVersion,
+) -> Result<(), String> {
+    // Data pack folders are named in the singular since 1.21
+    let function_folder: &str = if version >= McVersion::V1_21_1 {
+        "function"
+    } else {
+        "functions"
+    };
+
+    let pack_dir: PathBuf = Path::new(world_path).join("datapacks").join(DATAPACK_NAME);
+    let function_dir: PathBuf = pack_dir.join("data").join("arnis").join(function_folder);
+    let tags_dir: PathBuf = pack_dir
+        .join("data")
+        .join("minecraft")
+        .join("tags")
+        .join(function_folder);
+
+    // Degrees per block, scaled so that (block * step) / 1000 yields millionths of a degree
+    let lon_step: i64 = (metadata.lon_per_block * 1e9).round() as i64;
+    let lat_step: i64 = (metadata.lat_per_block * 1e9).round() as i64;
+    // Rotated areas change longitude along z and latitude along x as well
+    let lon_step_z: i64 = (metadata.lon_per_block_z * 1e9).round() as i64;
+    let lat_step_x: i64 = (metadata.lat_per_block_x * 1e9).round() as i64;
+    let lon_origin: i64 = (metadata.lon_origin * 1e6).round() as i64;
+    let lat_origin: i64 = (metadata.lat_origin * 1e6).round() as i64;
+
+    let files: Vec<(PathBuf, String)> = vec![
+        (
+            pack_dir.join("pack.mcmeta"),
+            format!(
+                "{{\n  \"pack\": {{\n    \"pack_format\": {},\n    \"description\": \"Arnis 坐标显示\"\n  }}\n}}\n",
+                version.pack_format()
+            ),
+        ),
+        (
+            tags_dir.join("load.json"),
+            "{\n  \"values\": [\"arnis:load\"]\n}\n".to_string(),
+        ),
+        (
+            tags_dir.join("tick.json"),
+            "{\n  \"values\": [\"arnis:tick\"]\n}\n".to_string(),
+        ),
+        (
+            function_dir.join("load.mcfunction"),
+            format!(
+                "scoreboard objectives add arnis_hud dummy\n\
+                 scoreboard players set #-1 arnis_hud -1\n\
+                 scoreboard players set #1000 arnis_hud 1000\n\
+                 scoreboard players set #1000000 arnis_hud 1000000\n\
+                 scoreboard players set #lon_origin arnis_hud {}\n\
+                 scoreboard players set #lon_step arnis_hud {}\n\
+                 scoreboard players set #lat_origin arnis_hud {}\n\
+                 scoreboard players set #lat_step arnis_hud {}\n\
+                 scoreboard players set #lon_step_z arnis_hud {}\n\
+                 scoreboard players set #lat_step_x arnis_hud {}\n",
+                lon_origin, lon_step, lat_origin, lat_step, lon_step_z, lat_step_x
+            ),
+        ),
+        (
+            function_dir.join("tick.mcfunction"),
+            "scoreboard players add #timer arnis_hud 1\n\
+             execute if score #timer arnis_hud matches 10.. run function arnis:update\n"
+                .to_string(),
+        ),
+        (
+            function_dir.join("update.mcfunction"),
+            "scoreboard players set #timer arnis_hud 0\n\
+             execute as @a run function arnis:player\n"
+                .to_string(),
+        ),
+        (
+            function_dir.join("player.mcfunction"),
+            "execute store result score #x arnis_hud run data get entity @s Pos[0]\n\
+             execute store result score #z arnis_hud run data get entity @s Pos[2]\n\
+             scoreboard players operation #lon arnis_hud = #x arnis_hud\n\
+             scoreboard players operation #lon arnis_hud *= #lon_step arnis_hud\n\
+             scoreboard players operation #lon arnis_hud /= #1000 arnis_hud\n\
+             scoreboard players operation #cross arnis_hud = #z arnis_hud\n\
+             scoreboard players operation #cross arnis_hud *= #lon_step_z arnis_hud\n\
+             scoreboard players operation #cross arnis_hud /= #1000 arnis_hud\n\
+             scoreboard players operation #lon arnis_hud += #cross arnis_hud\n\
+             scoreboard players operation #lon arnis_hud += #lon_origin arnis_hud\n\
+             scoreboard players operation #lat arnis_hud = #z arnis_hud\n\
+             scoreboard players operation #lat arnis_hud *= #lat_step arnis_hud\n\
+             scoreboard players operation #lat arnis_hud /= #1000 arnis_hud\n\
+             scoreboard players operation #cross arnis_hud = #x arnis_hud\n\
+             scoreboard players operation #cross arnis_hud *= #lat_step_x arnis_hud\n\
+             scoreboard players operation #cross arnis_hud /= #1000 arnis_hud\n\
+             scoreboard players operation #lat arnis_hud += #cross arnis_hud\n\
+             scoreboard players operation #lat arnis_hud += #lat_origin arnis_hud\n\
+             scoreboard players operation #value arnis_hud = #lon arnis_hud\n\
+             function arnis:format {axis:\"lon\"}\n\
+             scoreboard players operation #value arnis_hud = #lat arnis_hud\n\
+             function arnis:format {axis:\"lat\"}\n\
+             function arnis:show with storage arnis:hud\n"
+                .to_string(),
+        ),
+        (
+            // Splits #value into sign, integer degrees and zero padded fraction digits.
+            // The fraction is offset by 1000000 and converted to a string without its first digit.
+            function_dir.join("format.mcfunction"),
+            "$data modify storage arnis:hud $(axis)_sign set value \"\"\n\
+             $execute if score #value arnis_hud matches ..-1 run data modify storage arnis:hud $(axis)_sign set value \"-\"\n\
+             execute if score #value arnis_hud matches ..-1 run scoreboard players operation #value arnis_hud *= #-1 arnis_hud\n\
+             scoreboard players operation #fraction arnis_hud = #value arnis_hud\n\
+             scoreboard players operation #fraction arnis_hud %= #1000000 arnis_hud\n\
+             scoreboard players operation #fraction arnis_hud += #1000000 arnis_hud\n\
+             scoreboard players operation #value arnis_hud /= #1000000 arnis_hud\n\
+             $execute store result storage arnis:hud $(axis)_int int 1 run scoreboard players get #value arnis_hud\n\
+             execute store result storage arnis:hud fraction int 1 run scoreboard players get #fraction arnis_hud\n\
+             $data modify storage arnis:hud $(axis)_fraction set string storage arnis:hud fraction 1\n"
+                .to_string(),
+        ),
+        (
+            function_dir.join("show.mcfunction"),
+            "$title @s actionbar {\"text\":\"纬度 $(lat_sign)$(lat_int).$(lat_fraction)°  经度 $(lon_sign)$(lon_int).$(lon_fraction)°\",\"color\":\"gold\"}\n"
+                .to_string(),
+        ),
+    ];
+
+    for (path, content) in files {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e: std::io::Error| format!("无法创建数据包目录：{}", e))?;
+        }
+        fs::write(&path, content)
+            .map_err(|e: std::io::Error| format!("无法写入数据包文件：{}", e))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use crate::args::DebugQueryArgs;
+use crate::osm_parser::{ProcessedElement, ProcessedNode};
+use colored::Colorize;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+/// Directory of the debug dump of the parsed elements, written with --debug
+pub const DEBUG_DUMP_DIR: &str = "arnis_debug";
+/// Elements per dump file, so no single file grows too large to open
+const RECORDS_PER_FILE: usize = 50_000;
+/// File name of the index mapping regions to the dump files with elements inside them
+const INDEX_FILE_NAME: &str = "index.json";
+
+/// One parsed element in the debug dump
+#[derive(Serialize, Deserialize, Debug)]
+struct DumpRecord {
+    id: u64,
+    kind: String,
+    tags: HashMap<String, String>,
+    /// Chunks the nodes of the element are located in
+    chunks: BTreeSet<(i32, i32)>,
+}
+
+/// Index of a debug dump, listing for every region (32x32 chunks) the files holding
+/// elements located in it
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct DumpIndex {
+    files: Vec<String>,
+    regions: BTreeMap<String, BTreeSet<usize>>,
+}
+
+fn region_key(chunk: (i32, i32)) -> String {
+    format!("{},{}", chunk.0 >> 5, chunk.1 >> 5)
+}
+
+/// Writes the parsed elements into rotated, gzip-compressed JSON lines files with an index
+/// of the regions they are located in. The records are streamed to disk one at a time, so
+/// large cities do not need the whole dump in memory.
+pub fn write_debug_dump(elements: &[ProcessedElement], dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e: std::io::Error| format!("无法创建调试目录：{}", e))?;
+    // Files of a previous, larger dump would otherwise be mixed into the new one
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().ends_with(".jsonl.gz") {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+
+    let mut index: DumpIndex = DumpIndex::default();
+    for (file_index, records) in elements.chunks(RECORDS_PER_FILE).enumerate() {
+        let file_name: String = format!("parsed_osm_data.{:03}.jsonl.gz", file_index);
+        let file: File = File::create(dir.join(&file_name))
+            .map_err(|e: std::io::Error| format!("无法创建调试文件：{}", e))?;
+        let mut encoder: GzEncoder<BufWriter<File>> =
+            GzEncoder::new(BufWriter::new(file), Compression::fast());
+
+        for element in records {
+            let record: DumpRecord = DumpRecord {
+                id: element.id(),
+                kind: element.kind().to_string(),
+                tags: element.tags().clone(),
+                chunks: element
+                    .nodes()
+                    .map(|node: &ProcessedNode| (node.x >> 4, node.z >> 4))
+                    .collect(),
+            };
+            for chunk in &record.chunks {
+                index
+                    .regions
+                    .entry(region_key(*chunk))
+                    .or_default()
+                    .insert(file_index);
+            }
+            serde_json::to_writer(&mut encoder, &record)
+                .map_err(|e: serde_json::Error| format!("无法写入调试文件：{}", e))?;
+            encoder
+                .write_all(b"\n")
+                .map_err(|e: std::io::Error| format!("无法写入调试文件：{}", e))?;
+        }
+
+        encoder
+            .finish()
+            .and_then(|mut writer: BufWriter<File>| writer.flush())
+            .map_err(|e: std::io::Error| format!("无法写入调试文件：{}", e))?;
+        index.files.push(file_name);
+    }
+
+    let file: File = File::create(dir.join(INDEX_FILE_NAME))
+        .map_err(|e: std::io::Error| format!("无法创建调试索引：{}", e))?;
+    serde_json::to_writer(BufWriter::new(file), &index)
+        .map_err(|e: serde_json::Error| format!("无法写入调试索引：{}", e))
+}
+
+/// Runs the `arnis debug-query` command, printing the elements of a debug dump matching
+/// all given filters. With --chunk, only the files listed in the index for its region are read.
+pub fn run_debug_query(args: &DebugQueryArgs) {
+    let dir: PathBuf = PathBuf::from(&args.dir);
+    let index: DumpIndex = match File::open(dir.join(INDEX_FILE_NAME))
+        .map_err(|e: std::io::Error| e.to_string())
+        .and_then(|file: File| {
+            serde_json::from_reader(BufReader::new(file))
+                .map_err(|e: serde_json::Error| e.to_string())
+        }) {
+        Ok(index) => index,
+        Err(e) => {
+            eprintln!("{}", format!("错误！无法读取调试索引：{}", e).red().bold());
+            exit(1);
+        }
+    };
+
+    let files: Vec<&String> = match args.chunk {
+        Some(chunk) => index
+            .regions
+            .get(&region_key(chunk))
+            .map(|files: &BTreeSet<usize>| files.iter().map(|i| &index.files[*i]).collect())
+            .unwrap_or_default(),
+        None => index.files.iter().collect(),
+    };
+    let tag_filter: Option<(&str, Option<&str>)> =
+        args.tag
+            .as_deref()
+            .map(|tag: &str| match tag.split_once('=') {
+                Some((key, value)) => (key, Some(value)),
+                None => (tag, None),
+            });
+
+    let mut matches: usize = 0;
+    for file_name in files {
+        let file: File = match File::open(dir.join(file_name)) {
+            Ok(file) => file,
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    format!("警告：无法读取 {}：{}", file_name, e).yellow()
+                );
+                continue;
+            }
+        };
+        for line in BufReader::new(GzDecoder::new(file)).lines() {
+            let Ok(record) =
+                line.map_err(|e: std::io::Error| e.to_string())
+                    .and_then(|line: String| {
+                        serde_json::from_str::<DumpRecord>(&line).map_err(|e| e.to_string())
+                    })
+            else {
+                continue;
+            };
+
+            let matches_filters: bool = args.id.is_none_or(|id: u64| record.id == id)
+                && args
+                    .kind
+                    .as_deref()
+                    .is_none_or(|kind: &str| record.kind == kind)
+                && args
+                    .chunk
+                    .is_none_or(|chunk: (i32, i32)| record.chunks.contains(&chunk))
+                && tag_filter.is_none_or(|(key, value)| {
+                    record.tags.get(key).is_some_and(|tag_value: &String| {
+                        value.is_none_or(|value: &str| tag_value == value)
+                    })
+                });
+            if matches_filters {
+                println!(
+                    "元素 ID：{}，类型：{}，标签：{:?}",
+                    record.id, record.kind, record.tags
+                );
+                matches += 1;
+            }
+        }
+    }
+
+    println!("{}", format!("找到 {} 个匹配的元素", matches).green());
+}
@@ -0,0 +1,96 @@
+use crate::block_definitions::*;
+use crate::osm_parser::{ProcessedNode, ProcessedWay};
+use crate::transliteration::{name_lines, SignScript};
+use crate::world_editor::WorldEditor;
+use std::collections::HashSet;
+
+/// Places a sign with the address of a building next to its entrance. Entrances mapped on
+/// the outline are preferred, otherwise the sign faces the road closest to the building.
+pub fn generate_address_sign(
+    editor: &mut WorldEditor,
+    element: &ProcessedWay,
+    outline: &[(i32, i32)],
+    floor_area: &[(i32, i32)],
+    ground_level: i32,
+    sign_script: SignScript,
+) {
+    let Some(housenumber) = element
+        .tags
+        .get("addr:housenumber")
+        .or_else(|| element.tags.get("addr:housename"))
+    else {
+        return;
+    };
+    let street: String = element
+        .tags
+        .get("addr:street")
+        .or_else(|| element.tags.get("addr:place"))
+        .cloned()
+        .unwrap_or_default();
+
+    let inside: HashSet<(i32, i32)> = floor_area.iter().chain(outline).copied().collect();
+    let is_free = |editor: &WorldEditor, x: i32, z: i32| -> bool {
+        !inside.contains(&(x, z))
+            && !matches!(editor.get_highest_block_y(x, z), Some(y) if y > ground_level)
+    };
+
+    // Sign positions outside the walls, with the rotation facing away from the building
+    let directions: [(i32, i32, i8); 4] = [(0, 1, 0), (-1, 0, 4), (0, -1, 8), (1, 0, 12)];
+    let entrances: Vec<(i32, i32)> = element
+        .nodes
+        .iter()
+        .filter(|node: &&ProcessedNode| {
+            node.tags.contains_key("entrance") || node.tags.contains_key("door")
+        })
+        .map(|node: &ProcessedNode| (node.x, node.z))
+        .collect();
+
+    let mut best: Option<(i32, i32, i8, i32)> = None;
+    for &(x, z) in outline {
+        for (dx, dz, rotation) in directions {
+            let at_entrance: bool = entrances.contains(&(x, z));
+            // Next to the door rather than in front of it, so the entrance stays walkable
+            let (sx, sz) = if at_entrance {
+                (x + dx + dz, z + dz + dx)
+            } else {
+                (x + dx, z + dz)
+            };
+            if !is_free(editor, x + dx, z + dz) || !is_free(editor, sx, sz) {
+                continue;
+            }
+
+            let road_blocks: i32 = (1..=4)
+                .filter(|distance: &i32| {
+                    editor.check_for_block(
+                        sx + dx * distance,
+                        ground_level,
+                        sz + dz * distance,
+                        Some(&[BLACK_CONCRETE, GRAY_CONCRETE, LIGHT_GRAY_CONCRETE]),
+                        None,
+                    )
+                })
+                .count() as i32;
+            let score: i32 = road_blocks + if at_entrance { 10 } else { 0 };
+            if best.is_none_or(|(_, _, _, best_score)| score > best_score) {
+                best = Some((sx, sz, rotation, score));
+            }
+        }
+    }
+
+    let Some((x, z, rotation, _)) = best else {
+        return;
+    };
+    // With both scripts, the transliterated street name goes on the line below
+    let (housenumber, _) = name_lines(housenumber, sign_script);
+    let (street, street_latin) = name_lines(&street, sign_script);
+    editor.set_sign(
+        housenumber,
+        street,
+        street_latin.unwrap_or_default(),
+        String::new(),
+        x,
+        ground_level + 1,
+        z,
+        rotation,
+    );
+}
@@ -0,0 +1,238 @@
+use crate::args::Args;
+use crate::block_definitions::*;
+use crate::element_processing::buildings::generate_buildings;
+use crate::floodfill::flood_fill_area;
+use crate::osm_parser::{ProcessedElement, ProcessedWay};
+use crate::world_editor::WorldEditor;
+
+/// Width of runways without a width tag, in meters
+const RUNWAY_WIDTH: f64 = 45.0;
+/// Width of taxiways without a width tag, in meters
+const TAXIWAY_WIDTH: f64 = 18.0;
+/// Length of the threshold stripes at both ends of a runway
+const THRESHOLD_LENGTH: f64 = 15.0;
+/// Length of a dash of the runway centerline
+const DASH_LENGTH: f64 = 12.0;
+/// Length of the gap between two dashes of the runway centerline
+const DASH_GAP: f64 = 8.0;
+
+/// Blocks that paved airport surfaces may replace
+const PAVABLE: [Block; 3] = [GRASS_BLOCK, SNOW_BLOCK, DIRT];
+
+pub fn generate_aeroways(
+    editor: &mut WorldEditor,
+    element: &ProcessedElement,
+    ground_level: i32,
+    args: &Args,
+) {
+    let Some(aeroway_type) = element.tags().get("aeroway") else {
+        return;
+    };
+
+    match (aeroway_type.as_str(), element) {
+        ("helipad", _) => generate_helipad(editor, element, ground_level, args),
+        ("runway", ProcessedElement::Way(way)) => generate_runway(editor, way, ground_level, true),
+        ("taxiway", ProcessedElement::Way(way)) => {
+            generate_runway(editor, way, ground_level, false)
+        }
+        ("apron", ProcessedElement::Way(way)) => generate_apron(editor, way, ground_level, args),
+        ("terminal", ProcessedElement::Way(way)) => {
+            generate_terminal(editor, way, ground_level, args)
+        }
+        _ => {}
+    }
+}
+
+/// Paves a runway or taxiway along its centerline, using the width tag if present.
+/// Runways get white edge lines, a dashed centerline and threshold stripes at both ends,
+/// taxiways a yellow centerline. Grass runways are left without markings.
+fn generate_runway(editor: &mut WorldEditor, way: &ProcessedWay, ground_level: i32, runway: bool) {
+    let is_grass: bool = matches!(
+        way.tags.get("surface").map(|s| s.as_str()),
+        Some("grass" | "dirt" | "ground")
+    );
+    let surface: Block = if is_grass { GRASS_BLOCK } else { GRAY_CONCRETE };
+    let half_width: f64 = way
+        .tags
+        .get("width")
+        .and_then(|width: &String| width.trim_end_matches('m').trim().parse::<f64>().ok())
+        .unwrap_or(if runway { RUNWAY_WIDTH } else { TAXIWAY_WIDTH })
+        .clamp(4.0, 100.0)
+        / 2.0;
+
+    let total_length: f64 = way
+        .nodes
+        .windows(2)
+        .map(|span| f64::from(span[1].x - span[0].x).hypot(f64::from(span[1].z - span[0].z)))
+        .sum();
+
+    let marking = |distance: f64, offset: f64| -> Option<Block> {
+        if is_grass {
+            return None;
+        }
+        if !runway {
+            return (offset.abs() < 0.75).then_some(YELLOW_CONCRETE);
+        }
+
+        let from_end: f64 = distance.min(total_length - distance);
+        let is_edge: bool = offset.abs() > half_width - 1.0;
+        let is_threshold: bool = (2.0..THRESHOLD_LENGTH).contains(&from_end)
+            && offset.abs() < half_width - 3.0
+            && (offset.abs().round() as i32) % 4 < 2;
+        let is_centerline: bool = offset.abs() < 0.75
+            && from_end >= THRESHOLD_LENGTH
+            && distance % (DASH_LENGTH + DASH_GAP) < DASH_LENGTH;
+        (is_edge || is_threshold || is_centerline).then_some(WHITE_CONCRETE)
+    };
+
+    let mut start_distance: f64 = 0.0;
+    for span in way.nodes.windows(2) {
+        let (x1, z1) = (f64::from(span[0].x), f64::from(span[0].z));
+        let (dx, dz) = (f64::from(span[1].x) - x1, f64::from(span[1].z) - z1);
+        let length: f64 = dx.hypot(dz);
+        if length == 0.0 {
+            continue;
+        }
+        let (ux, uz) = (dx / length, dz / length);
+
+        // Half-block steps along and across the span leave no gaps on diagonals
+        let steps: i32 = (length * 2.0).ceil() as i32;
+        let side_steps: i32 = (half_width * 2.0).round() as i32;
+        for step in 0..=steps {
+            let along: f64 = f64::from(step) / 2.0;
+            for side_step in -side_steps..=side_steps {
+                let offset: f64 = f64::from(side_step) / 2.0;
+                let x: i32 = (x1 + ux * along - uz * offset).round() as i32;
+                let z: i32 = (z1 + uz * along + ux * offset).round() as i32;
+
+                match marking(start_distance + along, offset) {
+                    Some(line) => {
+                        let mut replaceable: Vec<Block> = PAVABLE.to_vec();
+                        replaceable.push(surface);
+                        editor.set_block(line, x, ground_level, z, Some(&replaceable), None);
+                    }
+                    None => {
+                        editor.set_block(surface, x, ground_level, z, Some(&PAVABLE), None);
+                    }
+                }
+            }
+        }
+
+        start_distance += length;
+    }
+}
+
+/// Paves an apron, the area where aircraft park, load and refuel.
+fn generate_apron(editor: &mut WorldEditor, way: &ProcessedWay, ground_level: i32, args: &Args) {
+    let polygon_coords: Vec<(i32, i32)> = way.nodes.iter().map(|n| (n.x, n.z)).collect();
+    for (x, z) in flood_fill_area(&polygon_coords, &args.fill_options()) {
+        editor.set_block(
+            LIGHT_GRAY_CONCRETE,
+            x,
+            ground_level,
+            z,
+            Some(&PAVABLE),
+            None,
+        );
+    }
+}
+
+/// Builds a terminal that is not tagged as a building as a glass-walled building. Terminals
+/// tagged as buildings are handled by the building processing.
+fn generate_terminal(editor: &mut WorldEditor, way: &ProcessedWay, ground_level: i32, args: &Args) {
+    let mut terminal: ProcessedWay = way.clone();
+    terminal
+        .tags
+        .insert("building".to_string(), "terminal".to_string());
+    terminal
+        .tags
+        .entry("building:levels".to_string())
+        .or_insert_with(|| "3".to_string());
+    terminal
+        .tags
+        .entry("building:material".to_string())
+        .or_insert_with(|| "glass".to_string());
+    generate_buildings(editor, &terminal, ground_level, args);
+}
+
+/// Generates a helipad. If the pad is mapped on top of an already generated
+/// building (e.g. a hospital roof), it is placed on the roof instead of the ground.
+fn generate_helipad(
+    editor: &mut WorldEditor,
+    element: &ProcessedElement,
+    ground_level: i32,
+    args: &Args,
+) {
+    let polygon_coords: Vec<(i32, i32)> = element
+        .nodes()
+        .map(|n: &crate::osm_parser::ProcessedNode| (n.x, n.z))
+        .collect();
+    if polygon_coords.is_empty() {
+        return;
+    }
+
+    let node_count: i32 = polygon_coords.len() as i32;
+    let center_x: i32 = polygon_coords.iter().map(|(x, _)| x).sum::<i32>() / node_count;
+    let center_z: i32 = polygon_coords.iter().map(|(_, z)| z).sum::<i32>() / node_count;
+
+    // Buildings are processed before aeroways, so a roof below the pad already exists
+    let pad_level: i32 = editor
+        .get_highest_block_y(center_x, center_z)
+        .filter(|roof_y: &i32| *roof_y > ground_level)
+        .map(|roof_y: i32| roof_y + 1)
+        .unwrap_or(ground_level);
+
+    // Pad surface
+    if let ProcessedElement::Way(_) = element {
+        let pad_area: Vec<(i32, i32)> = flood_fill_area(&polygon_coords, &args.fill_options());
+        for (x, z) in pad_area {
+            editor.set_block(LIGHT_GRAY_CONCRETE, x, pad_level, z, None, None);
+        }
+    } else {
+        let radius: i32 = 4;
+        for dx in -radius..=radius {
+            for dz in -radius..=radius {
+                let distance_sq: i32 = dx * dx + dz * dz;
+                if distance_sq > radius * radius {
+                    continue;
+                }
+
+                let pad_block: Block = if distance_sq > (radius - 1) * (radius - 1) {
+                    YELLOW_CONCRETE // Outer marking ring
+                } else {
+                    LIGHT_GRAY_CONCRETE
+                };
+                editor.set_block(
+                    pad_block,
+                    center_x + dx,
+                    pad_level,
+                    center_z + dz,
+                    None,
+                    None,
+                );
+            }
+        }
+    }
+
+    // Paint the "H" marking in the center of the pad
+    for dz in -2..=2 {
+        for x in [center_x - 1, center_x + 1] {
+            editor.set_block(
+                WHITE_CONCRETE,
+                x,
+                pad_level,
+                center_z + dz,
+                Some(&[LIGHT_GRAY_CONCRETE]),
+                None,
+            );
+        }
+    }
+    editor.set_block(
+        WHITE_CONCRETE,
+        center_x,
+        pad_level,
+        center_z,
+        Some(&[LIGHT_GRAY_CONCRETE]),
+        None,
+    );
+}
@@ -1,6 +1,9 @@
 use crate::args::Args;
 use crate::block_definitions::*;
+use crate::block_palette::{palette_block, BlockCategory};
 use crate::bresenham::bresenham_line;
+use crate::element_processing::parking::generate_parking_lot;
+use crate::element_processing::perimeters::generate_secure_perimeter;
 use crate::floodfill::flood_fill_area;
 use crate::osm_parser::ProcessedElement;
 use crate::world_editor::WorldEditor;
@@ -51,7 +54,7 @@ pub fn generate_amenities(
                     .map(|n: &crate::osm_parser::ProcessedNode| (n.x, n.z))
                     .collect();
                 let floor_area: Vec<(i32, i32)> =
-                    flood_fill_area(&polygon_coords, args.timeout.as_ref());
+                    flood_fill_area(&polygon_coords, &args.fill_options());
 
                 // Fill the floor area
                 for (x, z) in floor_area.iter() {
@@ -99,7 +102,7 @@ pub fn generate_amenities(
 
                 let block_type = match amenity_type.as_str() {
                     "fountain" => WATER,
-                    "parking" => GRAY_CONCRETE,
+                    "parking" => palette_block(BlockCategory::RoadSurface, BLACK_CONCRETE),
                     _ => GRAY_CONCRETE,
                 };
                 for node in element.nodes() {
@@ -151,9 +154,9 @@ pub fn generate_amenities(
                 if corner_addup.2 > 0 {
                     let polygon_coords: Vec<(i32, i32)> = current_amenity.to_vec();
                     let flood_area: Vec<(i32, i32)> =
-                        flood_fill_area(&polygon_coords, args.timeout.as_ref());
+                        flood_fill_area(&polygon_coords, &args.fill_options());
 
-                    for (x, z) in flood_area {
+                    for &(x, z) in &flood_area {
                         editor.set_block(
                             block_type,
                             x,
@@ -162,22 +165,233 @@ pub fn generate_amenities(
                             Some(&[BLACK_CONCRETE, GRAY_CONCRETE]),
                             None,
                         );
+                    }
 
-                        // Add parking spot markings
-                        if amenity_type == "parking" && (x + z) % 8 == 0 && (x * z) % 32 != 0 {
-                            editor.set_block(
-                                LIGHT_GRAY_CONCRETE,
-                                x,
-                                ground_level,
-                                z,
-                                Some(&[BLACK_CONCRETE, GRAY_CONCRETE]),
-                                None,
-                            );
-                        }
+                    if amenity_type == "parking" {
+                        generate_parking_lot(editor, &flood_area, block_type, ground_level, args);
                     }
                 }
             }
+            "fuel" => {
+                generate_fuel_station(editor, element, ground_level, args);
+            }
+            "prison" => {
+                if let ProcessedElement::Way(way) = element {
+                    generate_secure_perimeter(
+                        editor,
+                        &way.nodes,
+                        ground_level,
+                        &args.fill_options(),
+                        STONE_BRICKS,
+                        true,
+                    );
+                }
+            }
             _ => {}
         }
     }
 }
+
+/// Generates a fuel station with a canopy on pillars, pump islands and a small shop.
+/// The layout faces the side of the lot with the most road blocks next to it.
+fn generate_fuel_station(
+    editor: &mut WorldEditor,
+    element: &ProcessedElement,
+    ground_level: i32,
+    args: &Args,
+) {
+    let polygon_coords: Vec<(i32, i32)> = element
+        .nodes()
+        .map(|n: &crate::osm_parser::ProcessedNode| (n.x, n.z))
+        .collect();
+    if polygon_coords.is_empty() {
+        return;
+    }
+
+    // Determine the lot, mapped stations get a default sized lot around the node
+    let (min_x, max_x, min_z, max_z): (i32, i32, i32, i32) =
+        if let ProcessedElement::Way(_) = element {
+            let lot_area: Vec<(i32, i32)> = flood_fill_area(&polygon_coords, &args.fill_options());
+            for (x, z) in lot_area.iter() {
+                editor.set_block(GRAY_CONCRETE, *x, ground_level, *z, None, None);
+            }
+
+            (
+                polygon_coords.iter().map(|(x, _)| *x).min().unwrap(),
+                polygon_coords.iter().map(|(x, _)| *x).max().unwrap(),
+                polygon_coords.iter().map(|(_, z)| *z).min().unwrap(),
+                polygon_coords.iter().map(|(_, z)| *z).max().unwrap(),
+            )
+        } else {
+            let (x, z) = polygon_coords[0];
+            for lot_x in (x - 8)..=(x + 7) {
+                for lot_z in (z - 7)..=(z + 6) {
+                    editor.set_block(GRAY_CONCRETE, lot_x, ground_level, lot_z, None, None);
+                }
+            }
+
+            (x - 8, x + 7, z - 7, z + 6)
+        };
+
+    // Count road blocks along each side of the lot: north, east, south, west
+    let road_blocks: [Block; 4] = [
+        BLACK_CONCRETE,
+        GRAY_CONCRETE,
+        WHITE_CONCRETE,
+        YELLOW_CONCRETE,
+    ];
+    let count_road_blocks = |points: Vec<(i32, i32)>| -> usize {
+        points
+            .into_iter()
+            .filter(|(x, z)| editor.check_for_block(*x, ground_level, *z, Some(&road_blocks), None))
+            .count()
+    };
+    let mut road_counts: [usize; 4] = [0; 4];
+    for distance in 1..=4 {
+        road_counts[0] +=
+            count_road_blocks((min_x..=max_x).map(|x| (x, min_z - distance)).collect());
+        road_counts[1] +=
+            count_road_blocks((min_z..=max_z).map(|z| (max_x + distance, z)).collect());
+        road_counts[2] +=
+            count_road_blocks((min_x..=max_x).map(|x| (x, max_z + distance)).collect());
+        road_counts[3] +=
+            count_road_blocks((min_z..=max_z).map(|z| (min_x - distance, z)).collect());
+    }
+    let road_side: usize = (0..4).max_by_key(|side| road_counts[*side]).unwrap_or(2);
+
+    // Local coordinates: u runs along the road, v points away from the road into the lot
+    let (lot_u, lot_v): (i32, i32) = if matches!(road_side, 0 | 2) {
+        (max_x - min_x + 1, max_z - min_z + 1)
+    } else {
+        (max_z - min_z + 1, max_x - min_x + 1)
+    };
+    let to_world = |u: i32, v: i32| -> (i32, i32) {
+        match road_side {
+            0 => (min_x + u, min_z + v),
+            1 => (max_x - v, min_z + u),
+            2 => (min_x + u, max_z - v),
+            _ => (min_x + v, min_z + u),
+        }
+    };
+
+    if lot_u < 6 || lot_v < 6 {
+        return;
+    }
+
+    // Canopy on pillars
+    let canopy_width: i32 = (lot_u - 2).clamp(5, 14);
+    let canopy_depth: i32 = ((lot_v - 2) / 2).clamp(4, 7);
+    let canopy_u: i32 = (lot_u - canopy_width) / 2;
+    let canopy_v: i32 = 2;
+    let canopy_y: i32 = ground_level + 5;
+
+    for u in canopy_u..canopy_u + canopy_width {
+        for v in canopy_v..canopy_v + canopy_depth {
+            let (x, z) = to_world(u, v);
+            let is_edge: bool = u == canopy_u
+                || u == canopy_u + canopy_width - 1
+                || v == canopy_v
+                || v == canopy_v + canopy_depth - 1;
+            let canopy_block: Block = if is_edge {
+                RED_CONCRETE
+            } else {
+                WHITE_CONCRETE
+            };
+            editor.set_block(canopy_block, x, canopy_y, z, None, None);
+        }
+    }
+
+    for u in [canopy_u + 1, canopy_u + canopy_width - 2] {
+        for v in [canopy_v + 1, canopy_v + canopy_depth - 2] {
+            let (x, z) = to_world(u, v);
+            for y in (ground_level + 1)..canopy_y {
+                editor.set_block(WHITE_CONCRETE, x, y, z, None, None);
+            }
+        }
+    }
+
+    // Pump islands in a row below the canopy
+    let island_v: i32 = canopy_v + canopy_depth / 2;
+    for u in (canopy_u + 3..canopy_u + canopy_width - 3).step_by(4) {
+        for v in (island_v - 1)..=(island_v + 1) {
+            let (x, z) = to_world(u, v);
+            editor.set_block(STONE_BLOCK_SLAB, x, ground_level + 1, z, None, None);
+        }
+
+        let (x, z) = to_world(u, island_v);
+        editor.set_block(
+            IRON_BLOCK,
+            x,
+            ground_level + 1,
+            z,
+            Some(&[STONE_BLOCK_SLAB]),
+            None,
+        );
+        editor.set_block(IRON_BLOCK, x, ground_level + 2, z, None, None);
+    }
+
+    // Small shop behind the canopy, facing the road
+    let shop_v: i32 = canopy_v + canopy_depth + 2;
+    let shop_depth: i32 = (lot_v - shop_v - 1).min(6);
+    if shop_depth < 4 {
+        return;
+    }
+    let shop_width: i32 = (lot_u - 2).min(8);
+    let shop_u: i32 = (lot_u - shop_width) / 2;
+    let shop_height: i32 = 4;
+    let door_u: i32 = shop_u + shop_width / 2;
+
+    for u in shop_u..shop_u + shop_width {
+        for v in shop_v..shop_v + shop_depth {
+            let (x, z) = to_world(u, v);
+            editor.set_block(
+                SMOOTH_STONE,
+                x,
+                ground_level,
+                z,
+                Some(&[GRAY_CONCRETE]),
+                None,
+            );
+            editor.set_block(SMOOTH_STONE, x, ground_level + shop_height, z, None, None);
+
+            let is_wall: bool = u == shop_u
+                || u == shop_u + shop_width - 1
+                || v == shop_v
+                || v == shop_v + shop_depth - 1;
+            if !is_wall {
+                continue;
+            }
+
+            for y in (ground_level + 1)..(ground_level + shop_height) {
+                if v == shop_v && u == door_u && y <= ground_level + 2 {
+                    continue; // Leave space for the door
+                }
+
+                let wall_block: Block = if v == shop_v && y == ground_level + 2 {
+                    GLASS // Shop window towards the pumps
+                } else {
+                    WHITE_CONCRETE
+                };
+                editor.set_block(wall_block, x, y, z, None, None);
+            }
+        }
+    }
+
+    let (door_x, door_z) = to_world(door_u, shop_v);
+    editor.set_block(
+        DARK_OAK_DOOR_LOWER,
+        door_x,
+        ground_level + 1,
+        door_z,
+        None,
+        None,
+    );
+    editor.set_block(
+        DARK_OAK_DOOR_UPPER,
+        door_x,
+        ground_level + 2,
+        door_z,
+        None,
+        None,
+    );
+}
@@ -18,13 +18,22 @@ pub fn generate_barriers(editor: &mut WorldEditor, element: &ProcessedElement, g
                 // Place bollard
             }
         } else if let ProcessedElement::Way(way) = element {
+            let (barrier_block, default_height, capped): (Block, i32, bool) =
+                match barrier_type.as_str() {
+                    "fence" => (fence_block(element), 1, false),
+                    "hedge" => (OAK_LEAVES, 2, false),
+                    "retaining_wall" => (STONE_BRICKS, 1, false),
+                    "wall" | "city_wall" => (wall_block(element), 2, true),
+                    _ => (COBBLESTONE_WALL, 2, true),
+                };
+
             // Determine wall height
             let wall_height: i32 = element
                 .tags()
                 .get("height")
-                .and_then(|height: &String| height.parse::<f32>().ok())
-                .map(|height: f32| f32::min(3.0, height).round() as i32)
-                .unwrap_or(2); // Default height is 2 if not specified or invalid
+                .and_then(|height: &String| height.trim_end_matches(" m").parse::<f32>().ok())
+                .map(|height: f32| f32::clamp(height, 1.0, 3.0).round() as i32)
+                .unwrap_or(default_height);
 
             // Process nodes to create the barrier wall
             for i in 1..way.nodes.len() {
@@ -43,12 +52,12 @@ pub fn generate_barriers(editor: &mut WorldEditor, element: &ProcessedElement, g
                 for (bx, _, bz) in bresenham_points {
                     // Build the barrier wall to the specified height
                     for y in (ground_level + 1)..=(ground_level + wall_height) {
-                        editor.set_block(COBBLESTONE_WALL, bx, y, bz, None, None);
+                        editor.set_block(barrier_block, bx, y, bz, None, None);
                         // Barrier wall
                     }
 
                     // Add an optional top to the barrier if the height is more than 1
-                    if wall_height > 1 {
+                    if capped && wall_height > 1 {
                         editor.set_block(
                             STONE_BRICK_SLAB,
                             bx,
@@ -63,3 +72,22 @@ pub fn generate_barriers(editor: &mut WorldEditor, element: &ProcessedElement, g
         }
     }
 }
+
+/// Returns the block of a fence, iron bars for metal fences and wooden fences otherwise
+fn fence_block(element: &ProcessedElement) -> Block {
+    match element.tags().get("fence_type").map(|s| s.as_str()) {
+        Some("metal" | "chain_link" | "railing" | "metal_bars" | "wire" | "barbed_wire") => {
+            IRON_BARS
+        }
+        _ => OAK_FENCE,
+    }
+}
+
+/// Returns the block of a wall depending on its material
+fn wall_block(element: &ProcessedElement) -> Block {
+    match element.tags().get("material").map(|s| s.as_str()) {
+        Some("brick") => BRICK,
+        Some("stone" | "concrete") => STONE_BRICKS,
+        _ => COBBLESTONE_WALL,
+    }
+}
@@ -3,67 +3,133 @@ use crate::bresenham::bresenham_line;
 use crate::osm_parser::ProcessedWay;
 use crate::world_editor::WorldEditor;
 
+/// Generates bridges carrying roads, paths or railways. The deck rises from both ends to a
+/// height derived from the 'layer' tag, is bordered by railings and rests on pillars
+/// reaching down to the ground or the water below.
 pub fn generate_bridges(editor: &mut WorldEditor, element: &ProcessedWay, ground_level: i32) {
-    if let Some(_bridge_type) = element.tags.get("bridge") {
-        let bridge_height: i32 = element
-            .tags
-            .get("layer")
-            .and_then(|layer: &String| layer.parse::<i32>().ok())
-            .unwrap_or(1); // Default height if not specified
+    if element.tags.get("bridge") == Some(&"no".to_string()) {
+        return;
+    }
+
+    // Every layer above the ground adds a clearance of four blocks below the deck
+    let layer: i32 = element
+        .tags
+        .get("layer")
+        .and_then(|layer: &String| layer.parse::<i32>().ok())
+        .unwrap_or(1)
+        .clamp(1, 5);
+    let deck_height: i32 = layer * 4 + 1;
+
+    let is_railway: bool = element.tags.contains_key("railway");
+    let highway_type: &str = element
+        .tags
+        .get("highway")
+        .map(|highway: &String| highway.as_str())
+        .unwrap_or("");
+    let is_footway: bool = matches!(
+        highway_type,
+        "footway" | "path" | "cycleway" | "pedestrian" | "steps" | "bridleway"
+    );
+
+    // Half width of the deck without the railings
+    let deck_radius: i32 = if is_railway || is_footway {
+        1
+    } else {
+        match highway_type {
+            "motorway" | "trunk" | "primary" => 4,
+            "secondary" | "tertiary" => 3,
+            _ => 2,
+        }
+    };
+    let surface_block: Block = if is_railway {
+        STONE_BRICKS
+    } else if is_footway {
+        OAK_PLANKS
+    } else {
+        BLACK_CONCRETE
+    };
+    let railing_block: Block = if is_footway {
+        OAK_FENCE
+    } else {
+        COBBLESTONE_WALL
+    };
 
-        // Calculate the total length of the bridge
-        let total_steps: usize = element
-            .nodes
-            .windows(2)
-            .map(|nodes: &[crate::osm_parser::ProcessedNode]| {
-                let x1: i32 = nodes[0].x;
-                let z1: i32 = nodes[0].z;
-                let x2: i32 = nodes[1].x;
-                let z2: i32 = nodes[1].z;
+    // Collect the center line of the bridge, together with the axis the deck spreads along
+    let mut center_line: Vec<(i32, i32, bool)> = vec![];
+    for nodes in element.nodes.windows(2) {
+        let (x1, z1) = (nodes[0].x, nodes[0].z);
+        let (x2, z2) = (nodes[1].x, nodes[1].z);
+        let spreads_along_z: bool = (x2 - x1).abs() >= (z2 - z1).abs();
+
+        for (bx, _, bz) in bresenham_line(x1, ground_level, z1, x2, ground_level, z2) {
+            if center_line.last().map(|&(x, z, _)| (x, z)) != Some((bx, bz)) {
+                center_line.push((bx, bz, spreads_along_z));
+            }
+        }
+    }
 
-                bresenham_line(x1, ground_level, z1, x2, ground_level, z2).len()
-            })
-            .sum();
+    let total_steps: usize = center_line.len();
+    if total_steps == 0 {
+        return;
+    }
 
-        let half_steps: usize = total_steps / 2; // Calculate midpoint for descending after rising
-        let mut current_step: usize = 0;
+    // Ramps rise one block every two blocks from both ends up to the deck height
+    let deck_y = |step: usize| -> i32 {
+        let distance_to_end: usize = step.min(total_steps - 1 - step);
+        ground_level + (distance_to_end as i32 / 2).min(deck_height)
+    };
+    let offset = |x: i32, z: i32, spreads_along_z: bool, offset: i32| -> (i32, i32) {
+        if spreads_along_z {
+            (x, z + offset)
+        } else {
+            (x + offset, z)
+        }
+    };
 
-        for i in 1..element.nodes.len() {
-            let prev: &crate::osm_parser::ProcessedNode = &element.nodes[i - 1];
-            let x1: i32 = prev.x;
-            let z1: i32 = prev.z;
+    // Deck with an edge beam below each railing
+    for (step, &(x, z, spreads_along_z)) in center_line.iter().enumerate() {
+        let y: i32 = deck_y(step);
+        for o in -(deck_radius + 1)..=(deck_radius + 1) {
+            let (dx, dz) = offset(x, z, spreads_along_z, o);
+            let deck_block: Block = if o.abs() <= deck_radius {
+                surface_block
+            } else {
+                LIGHT_GRAY_CONCRETE
+            };
+            editor.set_block(deck_block, dx, y, dz, Some(&[WATER]), None);
+        }
+    }
 
-            let cur: &crate::osm_parser::ProcessedNode = &element.nodes[i];
-            let x2: i32 = cur.x;
-            let z2: i32 = cur.z;
+    // Railings, rails and pillars are placed after the deck, so they do not cut into it
+    for (step, &(x, z, spreads_along_z)) in center_line.iter().enumerate() {
+        let y: i32 = deck_y(step);
+        if y == ground_level {
+            continue;
+        }
 
-            // Generate the line of coordinates between the two nodes
-            let bresenham_points: Vec<(i32, i32, i32)> =
-                bresenham_line(x1, ground_level, z1, x2, ground_level, z2);
+        for side in [-(deck_radius + 1), deck_radius + 1] {
+            let (rx, rz) = offset(x, z, spreads_along_z, side);
+            editor.set_block(railing_block, rx, y + 1, rz, None, None);
+        }
 
-            for (bx, _, bz) in bresenham_points {
-                // Calculate the current height of the bridge
-                let current_height: i32 = if current_step <= half_steps {
-                    ground_level + bridge_height + current_step as i32 / 5 // Rise for the first half
-                } else {
-                    ground_level + bridge_height + (half_steps as i32 / 5)
-                        - ((current_step - half_steps) as i32 / 5) // Descend for the second half
-                };
+        if is_railway {
+            editor.set_block(RAIL, x, y + 1, z, None, None);
+        }
 
-                // Set bridge blocks
-                editor.set_block(LIGHT_GRAY_CONCRETE, bx, current_height, bz, None, None);
-                for (offset_x, offset_z) in &[(-1, -1), (1, -1), (1, 1), (-1, 1)] {
+        // Pillars every ten blocks where the deck is high enough to need support
+        if step % 10 == 5 && y - ground_level >= 3 {
+            for o in -deck_radius..=deck_radius {
+                let (px, pz) = offset(x, z, spreads_along_z, o);
+                for pillar_y in ground_level..y {
                     editor.set_block(
-                        LIGHT_GRAY_CONCRETE,
-                        bx + offset_x,
-                        current_height,
-                        bz + offset_z,
-                        None,
+                        STONE_BRICKS,
+                        px,
+                        pillar_y,
+                        pz,
+                        Some(&[WATER, GRASS_BLOCK, SNOW_BLOCK, BLACK_CONCRETE]),
                         None,
                     );
                 }
-
-                current_step += 1;
             }
         }
     }
@@ -1,13 +1,22 @@
 use crate::args::Args;
 use crate::block_definitions::*;
+use crate::block_palette::{palette_block, BlockCategory};
 use crate::bresenham::bresenham_line;
+use crate::building_styles::BuildingStyle;
 use crate::colors::{color_text_to_rgb_tuple, rgb_distance, RGBTuple};
-use crate::floodfill::flood_fill_area;
+use crate::element_processing::address_signs::generate_address_sign;
+use crate::element_processing::landmarks::{generate_landmark, Landmark};
+use crate::element_processing::man_made::generate_antenna;
+use crate::element_processing::stadiums::generate_stadium;
+use crate::element_processing::villagers::{generate_poi_villager, generate_resident_villager};
+use crate::floodfill::{flood_fill_area, FillOptions};
 use crate::osm_parser::ProcessedWay;
+use crate::random::seeded_rng;
 use crate::world_editor::WorldEditor;
+use fastnbt::Value;
+use rand::rngs::StdRng;
 use rand::Rng;
-use std::collections::HashSet;
-use std::time::Duration;
+use std::collections::{HashMap, HashSet};
 
 pub fn generate_buildings(
     editor: &mut WorldEditor,
@@ -19,13 +28,16 @@ pub fn generate_buildings(
     let mut corner_addup: (i32, i32, i32) = (0, 0, 0);
     let mut current_building: Vec<(i32, i32)> = vec![];
 
-    // Randomly select block variations for corners, walls, and floors
-    let mut rng: rand::prelude::ThreadRng = rand::thread_rng();
-    let variation_index_corner: usize = rng.gen_range(0..building_corner_variations().len());
-    let variation_index_wall: usize = rng.gen_range(0..building_wall_variations().len());
-    let variation_index_floor: usize = rng.gen_range(0..building_floor_variations().len());
+    // Randomly select block variations for corners, walls, and floors from the regional style
+    let mut rng: StdRng = seeded_rng(editor.seed(), element.id);
+    let corner_variations: Vec<Block> = args.style.corner_variations();
+    let wall_variations: Vec<Block> = args.style.wall_variations();
+    let floor_variations: Vec<Block> = args.style.floor_variations();
+    let variation_index_corner: usize = rng.gen_range(0..corner_variations.len());
+    let variation_index_wall: usize = rng.gen_range(0..wall_variations.len());
+    let variation_index_floor: usize = rng.gen_range(0..floor_variations.len());
 
-    let corner_block: Block = building_corner_variations()[variation_index_corner];
+    let corner_block: Block = corner_variations[variation_index_corner];
     let wall_block: Block = element
         .tags
         .get("building:colour")
@@ -35,7 +47,18 @@ pub fn generate_buildings(
             })
         })
         .flatten()
-        .unwrap_or_else(|| building_wall_variations()[variation_index_wall]);
+        .or_else(|| {
+            element
+                .tags
+                .get("building:material")
+                .and_then(|material: &String| wall_block_for_material(material))
+        })
+        .unwrap_or_else(|| {
+            palette_block(
+                BlockCategory::BuildingWall,
+                wall_variations[variation_index_wall],
+            )
+        });
     let floor_block: Block = element
         .tags
         .get("roof:colour")
@@ -45,7 +68,7 @@ pub fn generate_buildings(
             })
         })
         .flatten()
-        .unwrap_or_else(|| building_floor_variations()[variation_index_floor]);
+        .unwrap_or_else(|| floor_variations[variation_index_floor]);
     let window_block: Block = WHITE_STAINED_GLASS;
 
     // Set to store processed flood fill points
@@ -65,11 +88,24 @@ pub fn generate_buildings(
         }
     }
 
+    // Stadiums are open bowls of seating rather than closed buildings
+    if element.tags.get("building") == Some(&"stadium".to_string()) {
+        generate_stadium(editor, element, ground_level, args);
+        return;
+    }
+
+    // Untagged buildings get a height typical for the regional style
+    let has_height_tag: bool =
+        element.tags.contains_key("building:levels") || element.tags.contains_key("height");
+    if let Some(levels) = args.style.default_levels().filter(|_| !has_height_tag) {
+        building_height = rng.gen_range(levels) * args.blocks_per_level + 2;
+    }
+
     // Determine building height from tags
     if let Some(levels_str) = element.tags.get("building:levels") {
         if let Ok(levels) = levels_str.parse::<i32>() {
-            if levels >= 1 && (levels * 4 + 2) > building_height {
-                building_height = levels * 4 + 2;
+            if levels >= 1 && (levels * args.blocks_per_level + 2) > building_height {
+                building_height = levels * args.blocks_per_level + 2;
             }
         }
     }
@@ -80,6 +116,22 @@ pub fn generate_buildings(
         }
     }
 
+    // Determine where the building starts, e.g. for building parts above passages
+    let mut min_height: i32 = 0;
+    if let Some(min_level_str) = element.tags.get("building:min_level") {
+        if let Ok(min_level) = min_level_str.parse::<i32>() {
+            if min_level >= 1 {
+                min_height = min_level * args.blocks_per_level;
+            }
+        }
+    }
+
+    if let Some(min_height_str) = element.tags.get("min_height") {
+        if let Ok(height) = min_height_str.trim_end_matches("m").trim().parse::<f64>() {
+            min_height = height.round() as i32;
+        }
+    }
+
     if let Some(building_type) = element.tags.get("building") {
         if building_type == "garage" {
             building_height = 2;
@@ -96,7 +148,7 @@ pub fn generate_buildings(
                     .map(|n: &crate::osm_parser::ProcessedNode| (n.x, n.z))
                     .collect();
                 let floor_area: Vec<(i32, i32)> =
-                    flood_fill_area(&polygon_coords, args.timeout.as_ref());
+                    flood_fill_area(&polygon_coords, &args.fill_options());
 
                 // Fill the floor area
                 for (x, z) in floor_area.iter() {
@@ -153,8 +205,7 @@ pub fn generate_buildings(
                 .iter()
                 .map(|node: &crate::osm_parser::ProcessedNode| (node.x, node.z))
                 .collect();
-            let roof_area: Vec<(i32, i32)> =
-                flood_fill_area(&polygon_coords, args.timeout.as_ref()); // Use flood-fill to determine the area
+            let roof_area: Vec<(i32, i32)> = flood_fill_area(&polygon_coords, &args.fill_options()); // Use flood-fill to determine the area
 
             // Fill the interior of the roof with STONE_BRICK_SLAB
             for (x, z) in roof_area.iter() {
@@ -174,11 +225,20 @@ pub fn generate_buildings(
                 building_height = 23
             }
         } else if building_type == "bridge" {
-            generate_bridge(editor, element, ground_level, args.timeout.as_ref());
+            generate_bridge(editor, element, ground_level, &args.fill_options());
             return;
         }
     }
 
+    // Churches, mosques, temples and towers are built from a template on top of the walls
+    let landmark: Option<Landmark> = Landmark::from_tags(&element.tags);
+    if let Some(landmark) = landmark.filter(|_| !has_height_tag) {
+        building_height = landmark.default_height();
+    }
+
+    let min_height: i32 = min_height.clamp(0, (building_height - 1).max(0));
+    let base_level: i32 = ground_level + min_height;
+
     // Process nodes to create walls and corners
     for node in &element.nodes {
         let x: i32 = node.x;
@@ -189,12 +249,13 @@ pub fn generate_buildings(
             let bresenham_points: Vec<(i32, i32, i32)> =
                 bresenham_line(prev.0, ground_level, prev.1, x, ground_level, z);
             for (bx, _, bz) in bresenham_points {
-                for h in (ground_level + 1)..=(ground_level + building_height) {
+                for h in (base_level + 1)..=(ground_level + building_height) {
                     if element.nodes[0].x == bx && element.nodes[0].x == bz {
                         editor.set_block(corner_block, bx, h, bz, None, None); // Corner block
                     } else {
                         // Add windows to the walls at intervals
-                        if h > ground_level + 1 && h % 4 != 0 && (bx + bz) % 6 < 3 {
+                        if h > base_level + 1 && h % args.blocks_per_level != 0 && (bx + bz) % 6 < 3
+                        {
                             editor.set_block(window_block, bx, h, bz, None, None);
                         // Window block
                         } else {
@@ -239,15 +300,84 @@ pub fn generate_buildings(
             .iter()
             .map(|n: &crate::osm_parser::ProcessedNode| (n.x, n.z))
             .collect();
-        let floor_area: Vec<(i32, i32)> = flood_fill_area(&polygon_coords, args.timeout.as_ref());
+        let floor_area: Vec<(i32, i32)> = flood_fill_area(&polygon_coords, &args.fill_options());
 
-        for (x, z) in floor_area {
-            if processed_points.insert((x, z)) {
-                editor.set_block(floor_block, x, ground_level, z, None, None); // Set floor
+        // Distant buildings keep a flat roof and no interior with --lod-radius
+        let low_detail: bool = element
+            .nodes
+            .first()
+            .is_some_and(|node| editor.is_low_detail(node.x, node.z));
+
+        // Sloped roofs are placed before the ceiling is filled, so they replace the flat top
+        let roof_shape: Option<RoofShape> = match element.tags.get("roof:shape") {
+            _ if low_detail => None,
+            Some(shape) => RoofShape::from_tag(shape),
+            None => landmark
+                .and_then(Landmark::roof_shape)
+                .and_then(RoofShape::from_tag)
+                .or_else(|| RoofShape::default_for_style(args.style, floor_area.len(), &mut rng)),
+        };
+        if let Some(roof_shape) = roof_shape {
+            generate_roof(
+                editor,
+                element,
+                &floor_area,
+                ground_level + building_height + 1,
+                roof_shape,
+                args,
+            );
+        }
+
+        // Mark hospital roofs before the ceiling is filled, so the marking is kept
+        let is_hospital: bool = element.tags.get("building") == Some(&"hospital".to_string())
+            || element.tags.get("amenity") == Some(&"hospital".to_string());
+        if is_hospital && roof_shape.is_none() {
+            generate_hospital_roof_marking(editor, &floor_area, ground_level + building_height + 1);
+        }
 
-                // Set level ceilings if height > 4
-                if building_height > 4 {
-                    for h in (ground_level + 2 + 4..ground_level + building_height).step_by(4) {
+        // Interior partitions and stairwell are placed first, so the floors leave a gap for the ladder
+        if args.interior && !low_detail {
+            let mut storeys: Vec<(i32, i32)> = vec![];
+            let mut storey_floor: i32 = base_level;
+            if building_height - min_height > args.blocks_per_level {
+                for h in (base_level + 2 + args.blocks_per_level..ground_level + building_height)
+                    .step_by(args.blocks_per_level as usize)
+                {
+                    storeys.push((storey_floor, h));
+                    storey_floor = h;
+                }
+            }
+            storeys.push((storey_floor, ground_level + building_height + 1));
+
+            generate_interior(editor, &floor_area, &storeys, wall_block);
+        }
+
+        let roof_center: Option<(i32, i32)> = if floor_area.is_empty() {
+            None
+        } else {
+            let cell_count: i32 = floor_area.len() as i32;
+            Some((
+                floor_area.iter().map(|(x, _)| x).sum::<i32>() / cell_count,
+                floor_area.iter().map(|(_, z)| z).sum::<i32>() / cell_count,
+            ))
+        };
+
+        // A custom roof block replaces the ceiling unless the roof colour is tagged
+        let roof_block: Block = if element.tags.contains_key("roof:colour") {
+            floor_block
+        } else {
+            palette_block(BlockCategory::Roof, floor_block)
+        };
+        for (x, z) in floor_area.iter().copied() {
+            if processed_points.insert((x, z)) {
+                editor.set_block(floor_block, x, base_level, z, None, None); // Set floor
+
+                // Set level ceilings if height > blocks per level
+                if building_height - min_height > args.blocks_per_level {
+                    for h in (base_level + 2 + args.blocks_per_level
+                        ..ground_level + building_height)
+                        .step_by(args.blocks_per_level as usize)
+                    {
                         if x % 6 == 0 && z % 6 == 0 {
                             editor.set_block(GLOWSTONE, x, h, z, None, None); // Light fixtures
                         } else {
@@ -261,7 +391,7 @@ pub fn generate_buildings(
 
                 // Set the house ceiling
                 editor.set_block(
-                    floor_block,
+                    roof_block,
                     x,
                     ground_level + building_height + 1,
                     z,
@@ -269,7 +399,7 @@ pub fn generate_buildings(
                     None,
                 );
 
-                if args.winter {
+                if args.winter && roof_shape.is_none() {
                     editor.set_block(
                         SNOW_LAYER,
                         x,
@@ -281,9 +411,303 @@ pub fn generate_buildings(
                 }
             }
         }
+
+        // Buildings carrying communication equipment get an antenna on the roof
+        let has_antenna: bool = element
+            .tags
+            .iter()
+            .any(|(key, value)| key.starts_with("communication:") && value != "no")
+            || element.tags.get("man_made") == Some(&"antenna".to_string());
+        if let Some((x, z)) = roof_center.filter(|_| has_antenna) {
+            generate_antenna(editor, x, z, ground_level, 4);
+        }
+
+        if let Some(landmark) = landmark {
+            generate_landmark(
+                editor,
+                landmark,
+                &element.tags,
+                &floor_area,
+                ground_level,
+                building_height,
+            );
+        }
+
+        // Shops and amenities get a villager standing at the entrance
+        if args.villagers
+            && (element.tags.contains_key("shop") || element.tags.contains_key("amenity"))
+        {
+            generate_poi_villager(
+                editor,
+                element,
+                &current_building,
+                &floor_area,
+                ground_level,
+                args.sign_script,
+            );
+        } else if args.villagers {
+            // Some houses get a resident walking around outside
+            generate_resident_villager(
+                editor,
+                element,
+                &current_building,
+                &floor_area,
+                ground_level,
+            );
+        }
+
+        // Addressed buildings get a sign with the house number and street at the entrance
+        generate_address_sign(
+            editor,
+            element,
+            &current_building,
+            &floor_area,
+            ground_level,
+            args.sign_script,
+        );
     }
 }
 
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum RoofShape {
+    Gabled,
+    Hipped,
+    Pyramidal,
+    Skillion,
+    Dome,
+}
+
+impl RoofShape {
+    fn from_tag(shape: &str) -> Option<Self> {
+        match shape {
+            "gabled" => Some(Self::Gabled),
+            "hipped" | "half-hipped" => Some(Self::Hipped),
+            "pyramidal" => Some(Self::Pyramidal),
+            "skillion" => Some(Self::Skillion),
+            "dome" | "onion" => Some(Self::Dome),
+            _ => None,
+        }
+    }
+
+    /// Roof shape of untagged buildings in a regional style. Large footprints keep a
+    /// flat roof, as they are usually commercial or industrial buildings.
+    fn default_for_style(
+        style: BuildingStyle,
+        footprint: usize,
+        rng: &mut impl Rng,
+    ) -> Option<Self> {
+        match style {
+            BuildingStyle::European if footprint <= 600 => Some(Self::Gabled),
+            BuildingStyle::Suburban if footprint <= 400 => {
+                if rng.gen_bool(0.3) {
+                    Some(Self::Hipped)
+                } else {
+                    Some(Self::Gabled)
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Builds a sloped roof on top of the building. The height of every roof column is
+/// derived from its position inside the building's bounding box, stairs are placed on
+/// the slopes and slabs along the ridge.
+fn generate_roof(
+    editor: &mut WorldEditor,
+    element: &ProcessedWay,
+    roof_area: &[(i32, i32)],
+    roof_y: i32,
+    roof_shape: RoofShape,
+    args: &Args,
+) {
+    if roof_area.is_empty() {
+        return;
+    }
+
+    let min_x: i32 = roof_area.iter().map(|(x, _)| *x).min().unwrap();
+    let max_x: i32 = roof_area.iter().map(|(x, _)| *x).max().unwrap();
+    let min_z: i32 = roof_area.iter().map(|(_, z)| *z).min().unwrap();
+    let max_z: i32 = roof_area.iter().map(|(_, z)| *z).max().unwrap();
+
+    // Limit the roof height by roof:height or roof:levels if given
+    let mut max_roof_height: i32 = 12;
+    if let Some(levels_str) = element.tags.get("roof:levels") {
+        if let Ok(levels) = levels_str.parse::<i32>() {
+            if levels >= 1 {
+                max_roof_height = levels * args.blocks_per_level;
+            }
+        }
+    }
+    if let Some(height_str) = element.tags.get("roof:height") {
+        if let Ok(height) = height_str.trim_end_matches("m").trim().parse::<f64>() {
+            max_roof_height = (height.round() as i32).max(1);
+        }
+    }
+
+    let (stairs, full_block, slab): ([Block; 4], Block, Block) =
+        roof_palette(element, args.style, editor.seed());
+    let full_block: Block = palette_block(BlockCategory::Roof, full_block);
+    let roof_points: HashSet<(i32, i32)> = roof_area.iter().copied().collect();
+
+    // Ridges run along the longer side of the building
+    let ridge_along_x: bool = (max_x - min_x) >= (max_z - min_z);
+    let half_x: f64 = f64::from(max_x - min_x + 1) / 2.0;
+    let half_z: f64 = f64::from(max_z - min_z + 1) / 2.0;
+    let center_x: f64 = f64::from(min_x + max_x) / 2.0;
+    let center_z: f64 = f64::from(min_z + max_z) / 2.0;
+
+    let column_height = |x: i32, z: i32| -> i32 {
+        if !roof_points.contains(&(x, z)) {
+            return 0;
+        }
+
+        let edge_x: i32 = (x - min_x).min(max_x - x) + 1;
+        let edge_z: i32 = (z - min_z).min(max_z - z) + 1;
+        let height: i32 = match roof_shape {
+            RoofShape::Gabled => {
+                if ridge_along_x {
+                    edge_z
+                } else {
+                    edge_x
+                }
+            }
+            RoofShape::Hipped => edge_x.min(edge_z),
+            RoofShape::Pyramidal => {
+                // Scale both axes so all four faces meet in a single apex
+                let apex: f64 = half_x.min(half_z);
+                let from_x: f64 = f64::from(edge_x) / half_x * apex;
+                let from_z: f64 = f64::from(edge_z) / half_z * apex;
+                from_x.min(from_z).ceil() as i32
+            }
+            RoofShape::Skillion => {
+                // Single slope rising across the short side of the building
+                if ridge_along_x {
+                    ((z - min_z + 1) * max_roof_height) / (max_z - min_z + 1)
+                } else {
+                    ((x - min_x + 1) * max_roof_height) / (max_x - min_x + 1)
+                }
+            }
+            RoofShape::Dome => {
+                let radius: f64 = half_x.min(half_z);
+                let dx: f64 = (f64::from(x) - center_x) / half_x * radius;
+                let dz: f64 = (f64::from(z) - center_z) / half_z * radius;
+                let distance_sq: f64 = dx * dx + dz * dz;
+                if distance_sq >= radius * radius {
+                    1
+                } else {
+                    (radius * radius - distance_sq).sqrt().round() as i32
+                }
+            }
+        };
+
+        height.clamp(1, max_roof_height)
+    };
+
+    for (x, z) in roof_area.iter().copied() {
+        let height: i32 = column_height(x, z);
+
+        // Neighbors in the order north, east, south, west
+        let neighbor_heights: [i32; 4] = [
+            column_height(x, z - 1),
+            column_height(x + 1, z),
+            column_height(x, z + 1),
+            column_height(x - 1, z),
+        ];
+
+        // Fill the column below the surface down to the lowest neighbor to close gaps
+        let lowest_neighbor: i32 = *neighbor_heights.iter().min().unwrap();
+        for y in (roof_y + lowest_neighbor.max(0))..(roof_y + height) {
+            editor.set_block(full_block, x, y, z, None, None);
+        }
+
+        // Stairs face towards the highest neighbor, local maxima become the ridge
+        let (steepest_direction, highest_neighbor): (usize, i32) = neighbor_heights
+            .iter()
+            .copied()
+            .enumerate()
+            .max_by_key(|(_, neighbor_height)| *neighbor_height)
+            .unwrap();
+        let surface_block: Block = if highest_neighbor > height {
+            stairs[steepest_direction]
+        } else {
+            slab
+        };
+        editor.set_block(surface_block, x, roof_y + height, z, None, None);
+    }
+}
+
+/// Picks stairs (north, east, south, west), full block and slab for the roof material.
+fn roof_palette(
+    element: &ProcessedWay,
+    style: BuildingStyle,
+    seed: u64,
+) -> ([Block; 4], Block, Block) {
+    let brick: ([Block; 4], Block, Block) = (
+        [
+            BRICK_STAIRS_NORTH,
+            BRICK_STAIRS_EAST,
+            BRICK_STAIRS_SOUTH,
+            BRICK_STAIRS_WEST,
+        ],
+        BRICK,
+        BRICK_SLAB,
+    );
+    let dark_oak: ([Block; 4], Block, Block) = (
+        [
+            DARK_OAK_STAIRS_NORTH,
+            DARK_OAK_STAIRS_EAST,
+            DARK_OAK_STAIRS_SOUTH,
+            DARK_OAK_STAIRS_WEST,
+        ],
+        DARK_OAK_PLANKS,
+        DARK_OAK_SLAB,
+    );
+    let stone_brick: ([Block; 4], Block, Block) = (
+        [
+            STONE_BRICK_STAIRS_NORTH,
+            STONE_BRICK_STAIRS_EAST,
+            STONE_BRICK_STAIRS_SOUTH,
+            STONE_BRICK_STAIRS_WEST,
+        ],
+        STONE_BRICKS,
+        STONE_BRICK_SLAB,
+    );
+
+    match element
+        .tags
+        .get("roof:material")
+        .map(|m: &String| m.as_str())
+    {
+        Some("roof_tiles" | "tiles" | "clay") => brick,
+        Some("wood" | "wooden" | "shingle" | "thatch") => dark_oak,
+        Some(_) => stone_brick,
+        None if style == BuildingStyle::European => brick,
+        None if style == BuildingStyle::Suburban => dark_oak,
+        None => {
+            let palettes: [([Block; 4], Block, Block); 3] = [brick, dark_oak, stone_brick];
+            palettes[seeded_rng(seed, (element.id, "roof")).gen_range(0..palettes.len())]
+        }
+    }
+}
+
+/// Picks the wall block for a 'building:material' tag
+fn wall_block_for_material(material: &str) -> Option<Block> {
+    Some(match material {
+        "brick" | "bricks" => BRICK,
+        "glass" | "mirror" => GLASS,
+        "wood" | "timber_framing" => OAK_PLANKS,
+        "log" => SPRUCE_PLANKS,
+        "concrete" | "reinforced_concrete" => LIGHT_GRAY_CONCRETE,
+        "stone" => STONE_BRICKS,
+        "sandstone" | "limestone" => SMOOTH_SANDSTONE,
+        "plaster" | "render" => WHITE_TERRACOTTA,
+        "metal" | "steel" => IRON_BLOCK,
+        "clay" | "adobe" | "mud" => MUD_BRICKS,
+        _ => return None,
+    })
+}
+
 fn find_nearest_block_in_color_map(
     rgb: &RGBTuple,
     color_map: Vec<(RGBTuple, Block)>,
@@ -294,12 +718,180 @@ fn find_nearest_block_in_color_map(
         .map(|(_, block)| block)
 }
 
+/// Generates simple room partitions on every storey and a ladder stairwell connecting them.
+/// Storeys are given as (floor, ceiling) heights.
+fn generate_interior(
+    editor: &mut WorldEditor,
+    floor_area: &[(i32, i32)],
+    storeys: &[(i32, i32)],
+    wall_block: Block,
+) {
+    if floor_area.is_empty() || storeys.is_empty() {
+        return;
+    }
+
+    let area_points: HashSet<(i32, i32)> = floor_area.iter().copied().collect();
+    let cell_count: i32 = floor_area.len() as i32;
+    let center_x: i32 = floor_area.iter().map(|(x, _)| x).sum::<i32>() / cell_count;
+    let center_z: i32 = floor_area.iter().map(|(_, z)| z).sum::<i32>() / cell_count;
+
+    // The stairwell sits close to the center, its ladder is attached to a wall block to the south
+    let stairwell: Option<(i32, i32)> = if storeys.len() > 1 {
+        floor_area
+            .iter()
+            .copied()
+            .filter(|(x, z)| {
+                area_points.contains(&(*x, *z + 1)) && area_points.contains(&(*x, *z - 1))
+            })
+            .min_by_key(|(x, z)| (x - center_x).pow(2) + (z - center_z).pow(2))
+    } else {
+        None
+    };
+
+    if let Some((stairwell_x, stairwell_z)) = stairwell {
+        let top: i32 = storeys[storeys.len() - 1].1 - 1;
+        for y in (storeys[0].0 + 1)..=top {
+            editor.set_block(wall_block, stairwell_x, y, stairwell_z + 1, None, None);
+            editor.set_block(LADDER, stairwell_x, y, stairwell_z, None, None);
+        }
+    }
+
+    // Partitions on a regular grid with a doorway in the middle of every wall segment
+    for (x, z) in floor_area.iter().copied() {
+        if let Some((stairwell_x, stairwell_z)) = stairwell {
+            if x == stairwell_x && (z - stairwell_z).abs() <= 1 {
+                continue; // Keep the stairwell accessible
+            }
+        }
+
+        let on_x_grid: bool = x.rem_euclid(8) == 0;
+        let on_z_grid: bool = z.rem_euclid(8) == 0;
+        if !on_x_grid && !on_z_grid {
+            continue;
+        }
+
+        let is_doorway: bool =
+            (on_x_grid && z.rem_euclid(8) == 4) || (on_z_grid && x.rem_euclid(8) == 4);
+        for (storey_floor, storey_ceiling) in storeys.iter().copied() {
+            for y in (storey_floor + 1)..storey_ceiling {
+                if is_doorway && y <= storey_floor + 2 {
+                    continue;
+                }
+                editor.set_block(wall_block, x, y, z, None, None);
+            }
+        }
+    }
+
+    // One piece of furniture in the corner of every room, next to the partitions
+    for (x, z) in floor_area.iter().copied() {
+        if x.rem_euclid(8) != 1 || z.rem_euclid(8) != 1 {
+            continue;
+        }
+        if let Some((stairwell_x, stairwell_z)) = stairwell {
+            if (x - stairwell_x).abs() <= 1 && (z - stairwell_z).abs() <= 2 {
+                continue;
+            }
+        }
+
+        let room: i32 = x.div_euclid(8) + z.div_euclid(8);
+        for (index, (storey_floor, _)) in storeys.iter().copied().enumerate() {
+            place_furniture(editor, room + index as i32, x, storey_floor + 1, z);
+        }
+    }
+}
+
+/// Places a chest, furnace, lectern, flower pot or banner, chosen by the given number.
+fn place_furniture(editor: &mut WorldEditor, choice: i32, x: i32, y: i32, z: i32) {
+    match choice.rem_euclid(5) {
+        0 => {
+            let items: Vec<Value> = vec![
+                item_stack(Some(0), "minecraft:bread", 3),
+                item_stack(Some(13), "minecraft:torch", 8),
+            ];
+            let data: HashMap<String, Value> =
+                HashMap::from([("Items".to_string(), Value::List(items))]);
+            editor.set_block_entity(CHEST, x, y, z, "minecraft:chest", data);
+        }
+        1 => {
+            // Fuel slot
+            let items: Vec<Value> = vec![item_stack(Some(1), "minecraft:coal", 8)];
+            let data: HashMap<String, Value> =
+                HashMap::from([("Items".to_string(), Value::List(items))]);
+            editor.set_block_entity(FURNACE, x, y, z, "minecraft:furnace", data);
+        }
+        2 => {
+            let data: HashMap<String, Value> = HashMap::from([
+                (
+                    "Book".to_string(),
+                    item_stack(None, "minecraft:writable_book", 1),
+                ),
+                ("Page".to_string(), Value::Int(0)),
+            ]);
+            editor.set_block_entity(LECTERN, x, y, z, "minecraft:lectern", data);
+        }
+        3 => editor.set_block(POTTED_POPPY, x, y, z, None, None),
+        _ => editor.set_block_entity(WHITE_BANNER, x, y, z, "minecraft:banner", HashMap::new()),
+    }
+}
+
+/// Item stack of a container, the slot is left out by block entities holding a single item
+fn item_stack(slot: Option<i8>, id: &str, count: i32) -> Value {
+    let mut item: HashMap<String, Value> = HashMap::new();
+    if let Some(slot) = slot {
+        item.insert("Slot".to_string(), Value::Byte(slot));
+    }
+    item.insert("id".to_string(), Value::String(id.to_string()));
+    item.insert("count".to_string(), Value::Int(count));
+    Value::Compound(item)
+}
+
+/// Paints a red cross on a white square in the middle of a hospital roof.
+fn generate_hospital_roof_marking(editor: &mut WorldEditor, roof_area: &[(i32, i32)], roof_y: i32) {
+    if roof_area.is_empty() {
+        return;
+    }
+
+    let min_x: i32 = roof_area.iter().map(|(x, _)| *x).min().unwrap();
+    let max_x: i32 = roof_area.iter().map(|(x, _)| *x).max().unwrap();
+    let min_z: i32 = roof_area.iter().map(|(_, z)| *z).min().unwrap();
+    let max_z: i32 = roof_area.iter().map(|(_, z)| *z).max().unwrap();
+
+    let center_x: i32 = (min_x + max_x) / 2;
+    let center_z: i32 = (min_z + max_z) / 2;
+
+    // Scale the marking with the roof, but keep it recognizable on small and large roofs
+    let half_size: i32 = ((max_x - min_x).min(max_z - min_z) / 3).clamp(2, 8);
+    let arm_half_width: i32 = (half_size / 3).max(1);
+
+    let roof_points: HashSet<(i32, i32)> = roof_area.iter().copied().collect();
+
+    for dx in -half_size..=half_size {
+        for dz in -half_size..=half_size {
+            let x: i32 = center_x + dx;
+            let z: i32 = center_z + dz;
+            if !roof_points.contains(&(x, z)) {
+                continue;
+            }
+
+            let is_cross: bool = (dx.abs() <= arm_half_width || dz.abs() <= arm_half_width)
+                && dx.abs() < half_size
+                && dz.abs() < half_size;
+            let marking_block: Block = if is_cross {
+                RED_CONCRETE
+            } else {
+                WHITE_CONCRETE
+            };
+            editor.set_block(marking_block, x, roof_y, z, None, None);
+        }
+    }
+}
+
 /// Generates a bridge structure, paying attention to the "level" tag.
 fn generate_bridge(
     editor: &mut WorldEditor,
     element: &ProcessedWay,
     base_level: i32,
-    floodfill_timeout: Option<&Duration>,
+    fill_options: &FillOptions,
 ) {
     // Calculate the bridge level
     let mut bridge_level: i32 = base_level;
@@ -336,7 +928,7 @@ fn generate_bridge(
         .iter()
         .map(|n: &crate::osm_parser::ProcessedNode| (n.x, n.z))
         .collect();
-    let bridge_area: Vec<(i32, i32)> = flood_fill_area(&polygon_coords, floodfill_timeout);
+    let bridge_area: Vec<(i32, i32)> = flood_fill_area(&polygon_coords, fill_options);
     for (x, z) in bridge_area {
         editor.set_block(floor_block, x, bridge_level, z, None, None);
     }
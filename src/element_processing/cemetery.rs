@@ -0,0 +1,76 @@
+use crate::args::Args;
+use crate::block_definitions::*;
+use crate::element_processing::tree::create_tree;
+use crate::random::seeded_rng;
+use crate::world_editor::WorldEditor;
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+
+/// Distance between the gravel paths dividing a cemetery into burial fields
+const PATH_SPACING: i32 = 12;
+/// Distance between two rows of graves within a field
+const ROW_SPACING: i32 = 3;
+/// Distance between two graves within a row
+const GRAVE_SPACING: i32 = 2;
+/// Share of grave spots that are taken
+const OCCUPANCY: f64 = 0.8;
+/// Chance of a tree at a field corner next to the paths
+const TREE_CHANCE: f64 = 0.35;
+
+/// Lays out a cemetery as a grid of gravel paths with rows of graves in between. Each grave
+/// is a headstone with the burial plot in front of it, and trees line the paths.
+pub fn generate_cemetery(
+    editor: &mut WorldEditor,
+    area: &[(i32, i32)],
+    ground_block: Block,
+    ground_level: i32,
+    tags: &HashMap<String, String>,
+    args: &Args,
+) {
+    let inside: HashSet<(i32, i32)> = area.iter().copied().collect();
+    let mut rng: StdRng = seeded_rng(editor.seed(), area);
+
+    for &(x, z) in area {
+        let (local_x, local_z) = (x.rem_euclid(PATH_SPACING), z.rem_euclid(PATH_SPACING));
+
+        if local_x == 0 || local_z == 0 {
+            editor.set_block(GRAVEL, x, ground_level, z, Some(&[ground_block]), None);
+            continue;
+        }
+
+        // Trees at the field corners, so they stand along the paths
+        if local_x == 1 && local_z == 1 {
+            if rng.gen_bool(TREE_CHANCE) {
+                create_tree(editor, x, ground_level + 1, z, tags, args.winter);
+            }
+            continue;
+        }
+
+        // Headstones face the plot south of them, which stays within the field
+        let is_headstone: bool = local_z % ROW_SPACING == 2
+            && local_z + 1 < PATH_SPACING
+            && local_x % GRAVE_SPACING == 1
+            && local_x > 1;
+        if !is_headstone || !inside.contains(&(x, z + 1)) || !rng.gen_bool(OCCUPANCY) {
+            continue;
+        }
+
+        match rng.gen_range(0..3) {
+            0 => {
+                editor.set_block(COBBLESTONE_WALL, x, ground_level + 1, z, None, None);
+            }
+            1 => {
+                editor.set_block(STONE_BRICK_SLAB, x, ground_level + 1, z, None, None);
+            }
+            _ => {
+                editor.set_block(COBBLESTONE_WALL, x, ground_level + 1, z, None, None);
+                editor.set_block(COBBLESTONE_WALL, x, ground_level + 2, z, None, None);
+            }
+        }
+        editor.set_block(PODZOL, x, ground_level, z + 1, Some(&[ground_block]), None);
+        if rng.gen_bool(0.3) {
+            editor.set_block(RED_FLOWER, x, ground_level + 1, z + 1, None, None);
+        }
+    }
+}
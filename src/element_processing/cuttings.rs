@@ -0,0 +1,160 @@
+use crate::block_definitions::*;
+use crate::block_palette::{palette_block, BlockCategory};
+use crate::bresenham::bresenham_line;
+use crate::osm_parser::ProcessedWay;
+use crate::world_editor::{WorldEditor, MIN_Y};
+
+/// Lowest floor level of a cutting, right above the bedrock at the bottom of the world
+const MIN_CUTTING_FLOOR: i32 = MIN_Y + 1;
+
+/// Blocks of the surface that a cutting digs through
+const CARVABLE: [Block; 4] = [GRASS_BLOCK, SNOW_BLOCK, DIRT, GRASS];
+
+/// Returns whether a road or railway runs in a cutting below the surrounding ground, either
+/// tagged with 'cutting' or with a negative 'layer' outside of a tunnel.
+pub fn is_cutting(element: &ProcessedWay) -> bool {
+    let tags = &element.tags;
+    if !tags.contains_key("highway") && !tags.contains_key("railway") {
+        return false;
+    }
+    if tags
+        .get("tunnel")
+        .is_some_and(|tunnel: &String| tunnel != "no")
+    {
+        return false;
+    }
+
+    let has_cutting_tag: bool = tags
+        .get("cutting")
+        .is_some_and(|cutting: &String| cutting != "no");
+    let below_ground: bool = tags
+        .get("layer")
+        .and_then(|layer: &String| layer.parse::<i32>().ok())
+        .is_some_and(|layer: i32| layer < 0);
+    has_cutting_tag || below_ground
+}
+
+/// Returns how deep the cutting of a way is carved below the ground level, limited by the
+/// bedrock. Every layer below the surface adds four blocks of clearance.
+fn cutting_depth(element: &ProcessedWay, ground_level: i32) -> i32 {
+    let layer: i32 = element
+        .tags
+        .get("layer")
+        .and_then(|layer: &String| layer.parse::<i32>().ok())
+        .filter(|layer: &i32| *layer < 0)
+        .unwrap_or(-1)
+        .clamp(-3, -1);
+    (layer.abs() * 4 + 1).min(ground_level - MIN_CUTTING_FLOOR)
+}
+
+/// Returns whether a cutting is deep enough to be carved above the bedrock. Ways whose
+/// cutting does not fit are generated at ground level like other ways.
+pub fn cutting_fits(element: &ProcessedWay, ground_level: i32) -> bool {
+    cutting_depth(element, ground_level) >= 2
+}
+
+/// Carves a sunken corridor for a road or railway. The floor descends from both ends to a
+/// depth of four blocks of clearance per layer, so roads crossing at ground level on bridges
+/// keep their deck. Ways tagged as cuttings get sloped grass banks, other sunken ways are
+/// lined with retaining walls. Cuttings are carved after all other ways, so crossing
+/// bridges and roads are left in place.
+pub fn generate_cutting(editor: &mut WorldEditor, element: &ProcessedWay, ground_level: i32) {
+    let depth: i32 = cutting_depth(element, ground_level);
+    if depth < 2 {
+        return;
+    }
+
+    let is_railway: bool = element.tags.contains_key("railway");
+    let sloped: bool = element
+        .tags
+        .get("cutting")
+        .is_some_and(|cutting: &String| cutting != "no");
+    let highway_type: &str = element
+        .tags
+        .get("highway")
+        .map(|highway: &String| highway.as_str())
+        .unwrap_or("");
+
+    // Half width of the corridor floor
+    let floor_radius: i32 = if is_railway {
+        2
+    } else {
+        match highway_type {
+            "motorway" | "trunk" | "primary" => 5,
+            "secondary" | "tertiary" => 4,
+            "footway" | "path" | "cycleway" | "steps" | "bridleway" => 1,
+            _ => 3,
+        }
+    };
+    let floor_block: Block = if is_railway {
+        GRAVEL
+    } else {
+        palette_block(BlockCategory::RoadSurface, BLACK_CONCRETE)
+    };
+    let side_width: i32 = if sloped { depth } else { 1 };
+
+    // Center line of the cutting, together with the axis the corridor spreads along
+    let mut center_line: Vec<(i32, i32, bool)> = vec![];
+    for nodes in element.nodes.windows(2) {
+        let (x1, z1) = (nodes[0].x, nodes[0].z);
+        let (x2, z2) = (nodes[1].x, nodes[1].z);
+        let spreads_along_z: bool = (x2 - x1).abs() >= (z2 - z1).abs();
+
+        for (bx, _, bz) in bresenham_line(x1, ground_level, z1, x2, ground_level, z2) {
+            if center_line.last().map(|&(x, z, _)| (x, z)) != Some((bx, bz)) {
+                center_line.push((bx, bz, spreads_along_z));
+            }
+        }
+    }
+
+    let total_steps: usize = center_line.len();
+    if total_steps == 0 {
+        return;
+    }
+
+    // Ramps descend one block every two blocks from both ends down to the full depth
+    let floor_y = |step: usize| -> i32 {
+        let distance_to_end: usize = step.min(total_steps - 1 - step);
+        ground_level - (distance_to_end as i32 / 2).min(depth)
+    };
+
+    for (step, &(x, z, spreads_along_z)) in center_line.iter().enumerate() {
+        let y: i32 = floor_y(step);
+        for o in -(floor_radius + side_width)..=(floor_radius + side_width) {
+            let (cx, cz) = if spreads_along_z {
+                (x, z + o)
+            } else {
+                (x + o, z)
+            };
+            let beyond_floor: i32 = o.abs() - floor_radius;
+
+            if beyond_floor <= 0 {
+                editor.set_block(floor_block, cx, y, cz, Some(&CARVABLE), None);
+                carve(editor, cx, y + 1, ground_level, cz);
+                if is_railway && o == 0 {
+                    editor.set_block(RAIL, cx, y + 1, cz, Some(&[CAVE_AIR]), None);
+                }
+            } else if sloped {
+                // Banks rise one block per block away from the floor
+                let bank_y: i32 = (y + beyond_floor).min(ground_level);
+                for fill_y in y..bank_y {
+                    editor.set_block(DIRT, cx, fill_y, cz, Some(&CARVABLE), None);
+                }
+                editor.set_block(GRASS_BLOCK, cx, bank_y, cz, Some(&CARVABLE), None);
+                carve(editor, cx, bank_y + 1, ground_level, cz);
+            } else if y < ground_level {
+                for wall_y in y..=ground_level {
+                    editor.set_block(STONE_BRICKS, cx, wall_y, cz, Some(&CARVABLE), None);
+                }
+                editor.set_block(COBBLESTONE_WALL, cx, ground_level + 1, cz, None, None);
+            }
+        }
+    }
+}
+
+/// Clears the surface blocks of a column from the given level up to the ground level
+fn carve(editor: &mut WorldEditor, x: i32, from_y: i32, ground_level: i32, z: i32) {
+    for y in from_y..=ground_level {
+        editor.set_block(CAVE_AIR, x, y, z, Some(&CARVABLE), None);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::block_definitions::*;
-use crate::osm_parser::ProcessedNode;
+use crate::osm_parser::{ProcessedElement, ProcessedNode};
 use crate::world_editor::WorldEditor;
 
 pub fn generate_doors(editor: &mut WorldEditor, element: &ProcessedNode, ground_level: i32) {
@@ -23,3 +23,96 @@ pub fn generate_doors(editor: &mut WorldEditor, element: &ProcessedNode, ground_
         editor.set_block(DARK_OAK_DOOR_UPPER, x, ground_level + 2, z, None, None);
     }
 }
+
+/// Returns whether a door or entrance is the emergency entrance of a hospital, which gets an
+/// ambulance bay in front of it
+pub fn is_emergency_entrance(element: &ProcessedNode) -> bool {
+    element.tags.get("entrance") == Some(&"emergency".to_string())
+        || element
+            .tags
+            .get("emergency")
+            .is_some_and(|emergency: &String| emergency != "no")
+}
+
+/// Marks a yellow-bordered ambulance bay in front of an emergency entrance. The bay lies
+/// outside the building whose outline holds the entrance, or around the entrance if it is
+/// not part of a building.
+pub fn generate_ambulance_bay(
+    editor: &mut WorldEditor,
+    entrance: &ProcessedNode,
+    elements: &[ProcessedElement],
+    ground_level: i32,
+) {
+    let bay_radius: i32 = 3;
+    let (out_x, out_z) = outward_direction(entrance, elements).unwrap_or((0, 0));
+    let x: i32 = entrance.x + out_x * (bay_radius + 1);
+    let z: i32 = entrance.z + out_z * (bay_radius + 1);
+    for dx in -bay_radius..=bay_radius {
+        for dz in -bay_radius..=bay_radius {
+            let bay_block: Block = if dx.abs() == bay_radius || dz.abs() == bay_radius {
+                YELLOW_CONCRETE
+            } else {
+                GRAY_CONCRETE
+            };
+            editor.set_block(bay_block, x + dx, ground_level, z + dz, None, None);
+        }
+    }
+}
+
+/// Returns the axis direction closest to the one pointing out of the building whose outline
+/// holds the entrance, from the wall next to the entrance and the winding of the outline
+fn outward_direction(
+    entrance: &ProcessedNode,
+    elements: &[ProcessedElement],
+) -> Option<(i32, i32)> {
+    elements.iter().find_map(|element: &ProcessedElement| {
+        let ProcessedElement::Way(way) = element else {
+            return None;
+        };
+        if !way.tags.contains_key("building") && !way.tags.contains_key("building:part") {
+            return None;
+        }
+        let nodes: &[ProcessedNode] = &way.nodes;
+        let index: usize = nodes
+            .iter()
+            .position(|node: &ProcessedNode| node.id == entrance.id)?;
+        // Closed outlines repeat their first node at the end
+        let closed: bool = nodes.len() > 3 && nodes[0].id == nodes[nodes.len() - 1].id;
+        if !closed {
+            return None;
+        }
+        let previous: &ProcessedNode = if index == 0 {
+            &nodes[nodes.len() - 2]
+        } else {
+            &nodes[index - 1]
+        };
+        let next: &ProcessedNode = if index == nodes.len() - 1 {
+            &nodes[1]
+        } else {
+            &nodes[index + 1]
+        };
+
+        // Twice the signed area, positive if the outline runs from +x towards +z
+        let winding: i64 = nodes
+            .windows(2)
+            .map(|pair: &[ProcessedNode]| {
+                i64::from(pair[0].x) * i64::from(pair[1].z)
+                    - i64::from(pair[1].x) * i64::from(pair[0].z)
+            })
+            .sum();
+        let (wall_x, wall_z) = (next.x - previous.x, next.z - previous.z);
+        let (normal_x, normal_z) = if winding > 0 {
+            (wall_z, -wall_x)
+        } else {
+            (-wall_z, wall_x)
+        };
+        if normal_x == 0 && normal_z == 0 {
+            return None;
+        }
+        Some(if normal_x.abs() >= normal_z.abs() {
+            (normal_x.signum(), 0)
+        } else {
+            (0, normal_z.signum())
+        })
+    })
+}
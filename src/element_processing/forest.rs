@@ -0,0 +1,91 @@
+use crate::args::Args;
+use crate::block_definitions::*;
+use crate::element_processing::tree::create_tree;
+use crate::world_editor::WorldEditor;
+use rand::Rng;
+use std::collections::HashMap;
+
+/// Chance of a tree on a block of forest floor at a density of 1.0
+const TREE_CHANCE: f64 = 0.06;
+/// Edge length of the patches of ground cover, so the floor is not a random speckle
+const PATCH_SIZE: i32 = 4;
+/// Height of the merged canopy covering distant forests outside the --lod-radius
+const LOD_CANOPY_HEIGHT: i32 = 7;
+
+/// Fills one block of a forest or wood with trees, undergrowth and varied ground cover.
+/// The tree density is scaled with --forest-density.
+pub fn generate_forest_floor(
+    editor: &mut WorldEditor,
+    tags: &HashMap<String, String>,
+    x: i32,
+    z: i32,
+    ground_level: i32,
+    args: &Args,
+    rng: &mut impl Rng,
+) {
+    if editor.check_for_block(x, ground_level, z, None, Some(&[WATER])) {
+        return;
+    }
+
+    // Patches of podzol, coarse dirt and moss between the grass, hidden below snow in winter
+    if !args.winter {
+        let patch: i32 = (x.div_euclid(PATCH_SIZE).wrapping_mul(73_856_093)
+            ^ z.div_euclid(PATCH_SIZE).wrapping_mul(19_349_663))
+        .rem_euclid(10);
+        let ground_cover: Option<Block> = match patch {
+            0 | 1 => Some(PODZOL),
+            2 => Some(COARSE_DIRT),
+            3 => Some(MOSS_BLOCK),
+            _ => None,
+        };
+        if let Some(ground_cover) = ground_cover {
+            editor.set_block(ground_cover, x, ground_level, z, Some(&[GRASS_BLOCK]), None);
+        }
+    }
+
+    let tree_chance: f64 = (TREE_CHANCE * args.forest_density).clamp(0.0, 1.0);
+
+    // Distant forests are a closed canopy on scattered trunks instead of single trees
+    if editor.is_low_detail(x, z) {
+        if args.forest_density > 0.0 {
+            let (log, leaves): (Block, Block) = match tags.get("leaf_type").map(String::as_str) {
+                Some("needleleaved") => (SPRUCE_LOG, SPRUCE_LEAVES),
+                _ => (OAK_LOG, OAK_LEAVES),
+            };
+            let canopy_y: i32 = ground_level + LOD_CANOPY_HEIGHT;
+            if rng.gen_bool(tree_chance) {
+                editor.fill_blocks(log, x, ground_level + 1, z, x, canopy_y - 1, z, None, None);
+            }
+            editor.set_block(leaves, x, canopy_y, z, None, None);
+            if args.winter {
+                editor.set_block(SNOW_LAYER, x, canopy_y + 1, z, None, None);
+            }
+        }
+        return;
+    }
+
+    if rng.gen_bool(tree_chance) {
+        create_tree(editor, x, ground_level + 1, z, tags, args.winter);
+        return;
+    }
+
+    // Undergrowth is buried by snow in winter
+    if args.winter {
+        return;
+    }
+    let undergrowth: Option<Block> = match rng.gen_range(0..100) {
+        0..=14 => Some(FERN),
+        15..=19 => Some(OAK_LEAVES),
+        20..=44 => Some(GRASS),
+        45 => Some(match rng.gen_range(1..=4) {
+            1 => RED_FLOWER,
+            2 => BLUE_FLOWER,
+            3 => YELLOW_FLOWER,
+            _ => WHITE_FLOWER,
+        }),
+        _ => None,
+    };
+    if let Some(undergrowth) = undergrowth {
+        editor.set_block(undergrowth, x, ground_level + 1, z, None, None);
+    }
+}
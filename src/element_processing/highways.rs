@@ -1,9 +1,21 @@
 use crate::args::Args;
 use crate::block_definitions::*;
-use crate::bresenham::bresenham_line;
+use crate::block_palette::{palette_block, BlockCategory};
+use crate::bresenham::{bresenham_line, smooth_polyline, thick_line};
+use crate::element_processing::roundabouts::{generate_roundabout, is_roundabout};
 use crate::floodfill::flood_fill_area;
-use crate::osm_parser::{ProcessedElement, ProcessedWay};
+use crate::osm_parser::{ProcessedElement, ProcessedNode, ProcessedWay};
 use crate::world_editor::WorldEditor;
+use std::collections::HashMap;
+
+/// Width of a lane in meters, for roads tagged with their number of lanes
+const LANE_WIDTH: f64 = 3.5;
+/// Blocks a zebra crossing extends along the road on each side of the crossing node
+const CROSSWALK_HALF_LENGTH: i32 = 1;
+/// Distance of the arrow tips from the junction
+const ARROW_DISTANCE: i32 = 6;
+/// Length of the arrows painted on the lanes
+const ARROW_LENGTH: i32 = 5;
 
 pub fn generate_highways(
     editor: &mut WorldEditor,
@@ -15,31 +27,24 @@ pub fn generate_highways(
         if highway_type == "street_lamp" {
             // Handle street lamps
             if let ProcessedElement::Node(first_node) = element {
-                let x: i32 = first_node.x;
-                let z: i32 = first_node.z;
+                let (x, z) = find_roadside(editor, first_node.x, first_node.z, ground_level);
                 for y in 1..=4 {
-                    editor.set_block(OAK_FENCE, x, ground_level + y, z, None, None);
+                    editor.set_block(COBBLESTONE_WALL, x, ground_level + y, z, None, None);
                 }
-                editor.set_block(GLOWSTONE, x, ground_level + 5, z, None, None);
+                editor.set_block(LANTERN, x, ground_level + 5, z, None, None);
+            }
+        } else if highway_type == "traffic_signals" {
+            // Signals are usually mapped on the road itself, so the pole is moved beside it
+            if let ProcessedElement::Node(node) = element {
+                let (x, z) = find_roadside(editor, node.x, node.z, ground_level);
+                generate_traffic_signal(editor, x, z, ground_level, args.winter);
             }
         } else if highway_type == "crossing" {
             // Handle traffic signals for crossings
             if let Some(crossing_type) = element.tags().get("crossing") {
                 if crossing_type == "traffic_signals" {
                     if let ProcessedElement::Node(node) = element {
-                        let x: i32 = node.x;
-                        let z: i32 = node.z;
-                        for y in 1..=3 {
-                            editor.set_block(COBBLESTONE_WALL, x, ground_level + y, z, None, None);
-                        }
-
-                        editor.set_block(GREEN_WOOL, x, ground_level + 4, z, None, None);
-                        editor.set_block(YELLOW_WOOL, x, ground_level + 5, z, None, None);
-                        editor.set_block(RED_WOOL, x, ground_level + 6, z, None, None);
-
-                        if args.winter {
-                            editor.set_block(SNOW_LAYER, x, ground_level + 7, z, None, None);
-                        }
+                        generate_traffic_signal(editor, node.x, node.z, ground_level, args.winter);
                     }
                 }
             }
@@ -96,16 +101,16 @@ pub fn generate_highways(
                 .map(|n: &crate::osm_parser::ProcessedNode| (n.x, n.z))
                 .collect();
             let filled_area: Vec<(i32, i32)> =
-                flood_fill_area(&polygon_coords, args.timeout.as_ref());
+                flood_fill_area(&polygon_coords, &args.fill_options());
 
             for (x, z) in filled_area {
                 editor.set_block(surface_block, x, ground_level, z, None, None);
             }
         } else {
             let mut previous_node: Option<(i32, i32)> = None;
-            let mut block_type = BLACK_CONCRETE;
-            let mut block_range: i32 = 2;
-            let mut add_stripe = false;
+            let road_surface: Block = palette_block(BlockCategory::RoadSurface, BLACK_CONCRETE);
+            let mut block_type = road_surface;
+            let mut add_stripe: bool = false;
 
             // Skip if 'layer' or 'level' is negative in the tags
             if let Some(layer) = element.tags().get("layer") {
@@ -120,136 +125,164 @@ pub fn generate_highways(
                 }
             }
 
-            // Determine block type and range based on highway type
+            // Roundabouts are drawn as circles around an island
+            if let ProcessedElement::Way(way) = element {
+                if is_roundabout(way) {
+                    generate_roundabout(editor, way, ground_level, args);
+                    return;
+                }
+            }
+
+            let lanes: Option<i32> = element
+                .tags()
+                .get("lanes")
+                .and_then(|lanes: &String| lanes.parse::<i32>().ok())
+                .filter(|lanes: &i32| *lanes > 0);
+
+            // Determine block type and markings based on highway type
             match highway_type.as_str() {
                 "footway" | "pedestrian" => {
                     block_type = GRAY_CONCRETE;
-                    block_range = 1;
                 }
                 "path" => {
                     block_type = LIGHT_GRAY_CONCRETE;
-                    block_range = 1;
                 }
-                "motorway" | "primary" => {
-                    block_range = 5;
+                "motorway" | "trunk" | "primary" => {
                     add_stripe = true; // Add stripes for motorways and primary roads
                 }
-                "track" => {
-                    block_range = 1;
-                }
                 "service" => {
                     block_type = GRAY_CONCRETE;
-                    block_range = 2;
                 }
                 _ => {
-                    if let Some(lanes) = element.tags().get("lanes") {
-                        if lanes == "2" {
-                            block_range = 3;
-                            add_stripe = true;
-                        } else if lanes != "1" {
-                            block_range = 4;
-                            add_stripe = true;
-                        }
-                    }
+                    add_stripe = lanes.is_some_and(|lanes: i32| lanes > 1);
                 }
             }
+            let block_range: i32 = road_half_width(element.tags(), highway_type, lanes, args);
+            // Covers the full width along the axes and keeps diagonal roads as wide
+            let road_radius: f64 = f64::from(block_range) + 0.5;
 
             let ProcessedElement::Way(way) = element else {
                 return;
             };
 
-            // Iterate over nodes to create the highway
-            for node in &way.nodes {
+            // Lane separators across the road, the center line of two-way roads is yellow
+            let lane_count: i32 = lanes.unwrap_or(2);
+            let oneway: Oneway = oneway(element.tags(), highway_type);
+            let road_width: f64 = f64::from(block_range * 2 + 1);
+            let lane_width: f64 = road_width / f64::from(lane_count);
+            let separators: Vec<(f64, Block)> = (1..lane_count)
+                .map(|lane: i32| {
+                    let offset: f64 = -road_width / 2.0 + f64::from(lane) * lane_width;
+                    let is_center: bool = oneway == Oneway::No && lane == lane_count / 2;
+                    (
+                        offset,
+                        if is_center {
+                            YELLOW_CONCRETE
+                        } else {
+                            WHITE_CONCRETE
+                        },
+                    )
+                })
+                .collect();
+
+            // Variables to manage dashed line pattern, continued through the curves
+            let mut stripe_length: i32 = 0;
+            let dash_length: i32 = 5; // Length of the solid part of the stripe
+            let gap_length: i32 = 5; // Length of the gap part of the stripe
+
+            // Iterate over the points of the smoothed way to create the highway
+            let node_points: Vec<(i32, i32)> = way
+                .nodes
+                .iter()
+                .map(|node: &ProcessedNode| (node.x, node.z))
+                .collect();
+            for (x2, z2) in smooth_polyline(&node_points) {
                 if let Some(prev) = previous_node {
                     let (x1, z1) = prev;
-                    let x2: i32 = node.x;
-                    let z2: i32 = node.z;
+                    let normal: (f64, f64) = segment_normal(x1, z1, x2, z2);
 
-                    // Generate the line of coordinates between the two nodes
+                    // Generate the line of coordinates between the two points
                     let bresenham_points: Vec<(i32, i32, i32)> =
                         bresenham_line(x1, ground_level, z1, x2, ground_level, z2);
 
-                    // Variables to manage dashed line pattern
-                    let mut stripe_length: i32 = 0;
-                    let dash_length: i32 = 5; // Length of the solid part of the stripe
-                    let gap_length: i32 = 5; // Length of the gap part of the stripe
-
-                    for (x, _, z) in bresenham_points {
-                        // Draw the road surface for the entire width
-                        for dx in -block_range..=block_range {
-                            for dz in -block_range..=block_range {
-                                let set_x: i32 = x + dx;
-                                let set_z: i32 = z + dz;
-
-                                // Zebra crossing logic
-                                if highway_type == "footway"
-                                    && element.tags().get("footway")
-                                        == Some(&"crossing".to_string())
-                                {
-                                    let is_horizontal: bool = (x2 - x1).abs() >= (z2 - z1).abs();
-                                    if is_horizontal {
-                                        if set_x % 2 < 1 {
-                                            editor.set_block(
-                                                WHITE_CONCRETE,
-                                                set_x,
-                                                ground_level,
-                                                set_z,
-                                                Some(&[BLACK_CONCRETE]),
-                                                None,
-                                            );
-                                        } else {
-                                            editor.set_block(
-                                                BLACK_CONCRETE,
-                                                set_x,
-                                                ground_level,
-                                                set_z,
-                                                None,
-                                                None,
-                                            );
-                                        }
-                                    } else if set_z % 2 < 1 {
-                                        editor.set_block(
-                                            WHITE_CONCRETE,
-                                            set_x,
-                                            ground_level,
-                                            set_z,
-                                            Some(&[BLACK_CONCRETE]),
-                                            None,
-                                        );
-                                    } else {
-                                        editor.set_block(
-                                            BLACK_CONCRETE,
-                                            set_x,
-                                            ground_level,
-                                            set_z,
-                                            None,
-                                            None,
-                                        );
-                                    }
+                    // Draw the road surface for the entire width
+                    for (set_x, set_z, _) in thick_line(x1, z1, x2, z2, road_radius) {
+                        // Zebra crossing logic
+                        if highway_type == "footway"
+                            && element.tags().get("footway") == Some(&"crossing".to_string())
+                        {
+                            let is_horizontal: bool = (x2 - x1).abs() >= (z2 - z1).abs();
+                            if is_horizontal {
+                                if set_x % 2 < 1 {
+                                    editor.set_block(
+                                        WHITE_CONCRETE,
+                                        set_x,
+                                        ground_level,
+                                        set_z,
+                                        Some(&[BLACK_CONCRETE]),
+                                        None,
+                                    );
                                 } else {
                                     editor.set_block(
-                                        block_type,
+                                        BLACK_CONCRETE,
                                         set_x,
                                         ground_level,
                                         set_z,
                                         None,
-                                        Some(&[BLACK_CONCRETE, WHITE_CONCRETE]),
+                                        None,
                                     );
                                 }
+                            } else if set_z % 2 < 1 {
+                                editor.set_block(
+                                    WHITE_CONCRETE,
+                                    set_x,
+                                    ground_level,
+                                    set_z,
+                                    Some(&[BLACK_CONCRETE]),
+                                    None,
+                                );
+                            } else {
+                                editor.set_block(
+                                    BLACK_CONCRETE,
+                                    set_x,
+                                    ground_level,
+                                    set_z,
+                                    None,
+                                    None,
+                                );
                             }
+                        } else {
+                            editor.set_block(
+                                block_type,
+                                set_x,
+                                ground_level,
+                                set_z,
+                                None,
+                                Some(&[
+                                    BLACK_CONCRETE,
+                                    WHITE_CONCRETE,
+                                    YELLOW_CONCRETE,
+                                    road_surface,
+                                ]),
+                            );
                         }
+                    }
 
-                        // Add a dashed white line in the middle for larger roads
+                    for (x, _, z) in bresenham_points {
+                        // Add dashed white lines between the lanes and a solid yellow center
+                        // line for larger roads
                         if add_stripe {
-                            if stripe_length < dash_length {
-                                let stripe_x: i32 = x;
-                                let stripe_z: i32 = z;
+                            for &(offset, stripe_block) in &separators {
+                                if stripe_block == WHITE_CONCRETE && stripe_length >= dash_length {
+                                    continue;
+                                }
+                                let (stripe_x, stripe_z) = offset_point(x, z, normal, offset);
                                 editor.set_block(
-                                    WHITE_CONCRETE,
+                                    stripe_block,
                                     stripe_x,
                                     ground_level,
                                     stripe_z,
-                                    Some(&[BLACK_CONCRETE]),
+                                    Some(&[BLACK_CONCRETE, road_surface]),
                                     None,
                                 );
                             }
@@ -262,12 +295,264 @@ pub fn generate_highways(
                         }
                     }
                 }
-                previous_node = Some((node.x, node.z));
+                previous_node = Some((x2, z2));
+            }
+
+            if add_stripe {
+                let markings: RoadMarkings = RoadMarkings {
+                    block_range,
+                    lane_count,
+                    oneway,
+                    road_surface,
+                };
+                generate_crosswalks(editor, way, ground_level, &markings);
+                generate_lane_arrows(editor, way, ground_level, &markings);
+            }
+        }
+    }
+}
+
+/// Blocks the road extends to each side of its center line. The width comes from the width
+/// tag in meters, otherwise from the lanes or a typical width for the class of the road,
+/// whichever is wider.
+pub fn road_half_width(
+    tags: &HashMap<String, String>,
+    highway_type: &str,
+    lanes: Option<i32>,
+    args: &Args,
+) -> i32 {
+    let class_width: f64 = match highway_type {
+        "motorway" | "trunk" => 13.0,
+        "primary" => 11.0,
+        "secondary" => 9.0,
+        "tertiary" => 7.0,
+        "footway" | "pedestrian" | "path" | "cycleway" | "bridleway" | "steps" | "track"
+        | "service" => 3.0,
+        _ => 5.0,
+    };
+    let width_meters: f64 = tags
+        .get("width")
+        .and_then(|width: &String| width.trim_end_matches('m').trim().parse::<f64>().ok())
+        .filter(|width: &f64| *width > 0.0)
+        .unwrap_or_else(|| class_width.max(f64::from(lanes.unwrap_or(0)) * LANE_WIDTH));
+    ((width_meters * args.scale - 1.0) / 2.0).round().max(0.0) as i32
+}
+
+/// Travel direction of a road relative to the order of its nodes
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Oneway {
+    No,
+    Forward,
+    Backward,
+}
+
+/// Determines the travel direction from the oneway tag, motorways and roundabouts are
+/// one-way unless tagged otherwise
+fn oneway(tags: &HashMap<String, String>, highway_type: &str) -> Oneway {
+    match tags.get("oneway").map(|s: &String| s.as_str()) {
+        Some("yes" | "true" | "1") => Oneway::Forward,
+        Some("-1" | "reverse") => Oneway::Backward,
+        Some(_) => Oneway::No,
+        None if matches!(highway_type, "motorway" | "motorway_link")
+            || tags.get("junction").map(|s: &String| s.as_str()) == Some("roundabout") =>
+        {
+            Oneway::Forward
+        }
+        None => Oneway::No,
+    }
+}
+
+/// Layout of a road with painted markings
+struct RoadMarkings {
+    block_range: i32,
+    lane_count: i32,
+    oneway: Oneway,
+    road_surface: Block,
+}
+
+impl RoadMarkings {
+    /// Offsets of the lane centers from the center of the road, together with whether the
+    /// lane is driven in the direction of the nodes. Traffic drives on the right.
+    fn lanes(&self) -> Vec<(f64, bool)> {
+        let road_width: f64 = f64::from(self.block_range * 2 + 1);
+        let lane_width: f64 = road_width / f64::from(self.lane_count);
+        (0..self.lane_count)
+            .filter_map(|lane: i32| {
+                let offset: f64 = -road_width / 2.0 + (f64::from(lane) + 0.5) * lane_width;
+                match self.oneway {
+                    Oneway::Forward => Some((offset, true)),
+                    Oneway::Backward => Some((offset, false)),
+                    // The middle lane of an odd lane count is shared by both directions
+                    Oneway::No if offset.abs() < 0.5 => None,
+                    Oneway::No => Some((offset, offset > 0.0)),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Paints zebra crossings across the road at its highway=crossing nodes. The stripes run
+/// along the road and replace the lane markings within the crossing.
+fn generate_crosswalks(
+    editor: &mut WorldEditor,
+    way: &ProcessedWay,
+    ground_level: i32,
+    markings: &RoadMarkings,
+) {
+    for (index, node) in way.nodes.iter().enumerate() {
+        let is_marked_crossing: bool = node.tags.get("highway") == Some(&"crossing".to_string())
+            && !matches!(
+                node.tags.get("crossing").map(|s: &String| s.as_str()),
+                Some("unmarked" | "no")
+            );
+        if !is_marked_crossing {
+            continue;
+        }
+
+        // Direction of the road at the crossing
+        let (from, to) = if index + 1 < way.nodes.len() {
+            (node, &way.nodes[index + 1])
+        } else if index > 0 {
+            (&way.nodes[index - 1], node)
+        } else {
+            continue;
+        };
+        let direction: (f64, f64) = segment_direction(from.x, from.z, to.x, to.z);
+        let normal: (f64, f64) = (-direction.1, direction.0);
+
+        for along in -CROSSWALK_HALF_LENGTH..=CROSSWALK_HALF_LENGTH {
+            let (center_x, center_z) = offset_point(node.x, node.z, direction, f64::from(along));
+            for across in -markings.block_range..=markings.block_range {
+                let (x, z) = offset_point(center_x, center_z, normal, f64::from(across));
+                let stripe_block: Block = if across.rem_euclid(2) == 0 {
+                    WHITE_CONCRETE
+                } else {
+                    markings.road_surface
+                };
+                editor.set_block(
+                    stripe_block,
+                    x,
+                    ground_level,
+                    z,
+                    Some(&[
+                        BLACK_CONCRETE,
+                        WHITE_CONCRETE,
+                        YELLOW_CONCRETE,
+                        markings.road_surface,
+                    ]),
+                    None,
+                );
+            }
+        }
+    }
+}
+
+/// Paints arrows on the lanes approaching a junction, i.e. the ends of the road and traffic
+/// signals on it. Each arrow points in the travel direction of its lane.
+fn generate_lane_arrows(
+    editor: &mut WorldEditor,
+    way: &ProcessedWay,
+    ground_level: i32,
+    markings: &RoadMarkings,
+) {
+    let lanes: Vec<(f64, bool)> = markings.lanes();
+    let road_width: f64 = f64::from(markings.block_range * 2 + 1);
+    if way.nodes.len() < 2 || road_width / f64::from(markings.lane_count) < 3.0 {
+        return;
+    }
+
+    let last: usize = way.nodes.len() - 1;
+    for (index, node) in way.nodes.iter().enumerate() {
+        let is_junction: bool = index == 0
+            || index == last
+            || node.tags.get("highway") == Some(&"traffic_signals".to_string());
+        if !is_junction {
+            continue;
+        }
+
+        // Lanes driven along the nodes approach from the previous node, the others from the
+        // next one
+        for (neighbor, forward) in [(index.checked_sub(1), true), (Some(index + 1), false)] {
+            let Some(neighbor) = neighbor.and_then(|neighbor: usize| way.nodes.get(neighbor))
+            else {
+                continue;
+            };
+            let length: f64 = f64::from(node.x - neighbor.x).hypot(f64::from(node.z - neighbor.z));
+            if length < f64::from(ARROW_DISTANCE + ARROW_LENGTH) {
+                continue;
+            }
+
+            // Direction of travel towards the junction
+            let direction: (f64, f64) = segment_direction(neighbor.x, neighbor.z, node.x, node.z);
+            let normal: (f64, f64) = segment_normal(neighbor.x, neighbor.z, node.x, node.z);
+            for &(offset, lane_forward) in &lanes {
+                if lane_forward != forward {
+                    continue;
+                }
+                // The lane offsets are measured to the right of the direction of the nodes
+                let offset: f64 = if forward { offset } else { -offset };
+                let (lane_x, lane_z) = offset_point(node.x, node.z, normal, offset);
+                let (tip_x, tip_z) =
+                    offset_point(lane_x, lane_z, direction, -f64::from(ARROW_DISTANCE));
+                paint_arrow(editor, tip_x, tip_z, direction, ground_level, markings);
             }
         }
     }
 }
 
+/// Paints a straight arrow with its tip at the given position, pointing in the direction
+fn paint_arrow(
+    editor: &mut WorldEditor,
+    tip_x: i32,
+    tip_z: i32,
+    direction: (f64, f64),
+    ground_level: i32,
+    markings: &RoadMarkings,
+) {
+    let normal: (f64, f64) = (-direction.1, direction.0);
+    for back in 0..ARROW_LENGTH {
+        let (x, z) = offset_point(tip_x, tip_z, direction, -f64::from(back));
+        // The head is three blocks wide right behind the tip
+        let half_width: i32 = if back == 1 { 1 } else { 0 };
+        for across in -half_width..=half_width {
+            let (x, z) = offset_point(x, z, normal, f64::from(across));
+            editor.set_block(
+                WHITE_CONCRETE,
+                x,
+                ground_level,
+                z,
+                Some(&[BLACK_CONCRETE, markings.road_surface]),
+                None,
+            );
+        }
+    }
+}
+
+/// Unit vector pointing from the first to the second point
+fn segment_direction(x1: i32, z1: i32, x2: i32, z2: i32) -> (f64, f64) {
+    let (dx, dz) = (f64::from(x2 - x1), f64::from(z2 - z1));
+    let length: f64 = dx.hypot(dz);
+    if length == 0.0 {
+        (1.0, 0.0)
+    } else {
+        (dx / length, dz / length)
+    }
+}
+
+/// Unit vector pointing to the right of the direction from the first to the second point
+fn segment_normal(x1: i32, z1: i32, x2: i32, z2: i32) -> (f64, f64) {
+    let (dx, dz) = segment_direction(x1, z1, x2, z2);
+    (-dz, dx)
+}
+
+/// Moves a position by the given distance along a unit vector, rounded to the nearest block
+fn offset_point(x: i32, z: i32, vector: (f64, f64), distance: f64) -> (i32, i32) {
+    (
+        x + (vector.0 * distance).round() as i32,
+        z + (vector.1 * distance).round() as i32,
+    )
+}
+
 /// Generates a siding using stone brick slabs
 pub fn generate_siding(editor: &mut WorldEditor, element: &ProcessedWay, ground_level: i32) {
     let mut previous_node: Option<(i32, i32)> = None;
@@ -287,7 +572,7 @@ pub fn generate_siding(editor: &mut WorldEditor, element: &ProcessedWay, ground_
                     by - 1,
                     bz,
                     None,
-                    Some(&[BLACK_CONCRETE, WHITE_CONCRETE]),
+                    Some(&[BLACK_CONCRETE, WHITE_CONCRETE, YELLOW_CONCRETE]),
                 ) {
                     editor.set_block(siding_block, bx, by, bz, None, None);
                 }
@@ -297,3 +582,54 @@ pub fn generate_siding(editor: &mut WorldEditor, element: &ProcessedWay, ground_
         previous_node = Some((x, z));
     }
 }
+
+/// Places a signal pole with a red, yellow and green light on top.
+fn generate_traffic_signal(
+    editor: &mut WorldEditor,
+    x: i32,
+    z: i32,
+    ground_level: i32,
+    winter: bool,
+) {
+    for y in 1..=3 {
+        editor.set_block(COBBLESTONE_WALL, x, ground_level + y, z, None, None);
+    }
+
+    editor.set_block(GREEN_WOOL, x, ground_level + 4, z, None, None);
+    editor.set_block(YELLOW_WOOL, x, ground_level + 5, z, None, None);
+    editor.set_block(RED_WOOL, x, ground_level + 6, z, None, None);
+
+    if winter {
+        editor.set_block(SNOW_LAYER, x, ground_level + 7, z, None, None);
+    }
+}
+
+/// Returns the closest position next to the road for street furniture mapped on a road.
+/// Positions that are not on a road are returned unchanged.
+pub fn find_roadside(editor: &WorldEditor, x: i32, z: i32, ground_level: i32) -> (i32, i32) {
+    let road_blocks: [Block; 5] = [
+        BLACK_CONCRETE,
+        WHITE_CONCRETE,
+        YELLOW_CONCRETE,
+        GRAY_CONCRETE,
+        LIGHT_GRAY_CONCRETE,
+    ];
+    let is_road = |x: i32, z: i32| -> bool {
+        editor.check_for_block(x, ground_level, z, Some(&road_blocks), None)
+    };
+
+    if !is_road(x, z) {
+        return (x, z);
+    }
+
+    for distance in 1..=8 {
+        for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let (side_x, side_z) = (x + dx * distance, z + dz * distance);
+            if !is_road(side_x, side_z) {
+                return (side_x, side_z);
+            }
+        }
+    }
+
+    (x, z)
+}
@@ -0,0 +1,404 @@
+use crate::args::Args;
+use crate::block_definitions::*;
+use crate::element_processing::man_made::generate_antenna;
+use crate::world_editor::WorldEditor;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Smallest footprint of a church that gets a bell tower, smaller chapels only get a cross
+const MIN_TOWER_FOOTPRINT: usize = 150;
+/// Footprint above which church towers and minarets get larger or more numerous
+const LARGE_FOOTPRINT: usize = 600;
+/// Height of one tier of a temple roof
+const TIER_HEIGHT: i32 = 4;
+/// Most tiers stacked on a temple
+const MAX_TIERS: i32 = 3;
+/// Height of a freestanding tower mapped as a node without a height tag
+const DEFAULT_TOWER_HEIGHT: i32 = 20;
+
+/// Buildings built from a template instead of the generic box
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Landmark {
+    Church,
+    Cathedral,
+    Mosque,
+    Temple,
+    Tower,
+}
+
+impl Landmark {
+    /// Detects a landmark from the building type, or from the religion of a place of
+    /// worship tagged as a generic building
+    pub fn from_tags(tags: &HashMap<String, String>) -> Option<Self> {
+        let is =
+            |key: &str, value: &str| -> bool { tags.get(key).map(|s| s.as_str()) == Some(value) };
+
+        match tags.get("building").map(|s| s.as_str()) {
+            Some("church" | "chapel") => Some(Self::Church),
+            Some("cathedral") => Some(Self::Cathedral),
+            Some("mosque") => Some(Self::Mosque),
+            Some("temple" | "shrine" | "pagoda") => Some(Self::Temple),
+            _ if is("man_made", "tower") => Some(Self::Tower),
+            _ if is("amenity", "place_of_worship") => {
+                match tags.get("religion").map(|s| s.as_str()) {
+                    Some("christian") => Some(Self::Church),
+                    Some("muslim") => Some(Self::Mosque),
+                    Some("buddhist" | "hindu" | "shinto" | "taoist") => Some(Self::Temple),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Height of the main body when no height is tagged
+    pub fn default_height(self) -> i32 {
+        match self {
+            Self::Church => 10,
+            Self::Cathedral => 16,
+            Self::Mosque => 8,
+            Self::Temple => 6,
+            Self::Tower => 24,
+        }
+    }
+
+    /// Roof shape tag used when no roof shape is tagged. Temples get stacked tiers and
+    /// towers an observation deck instead, so both keep a flat roof.
+    pub fn roof_shape(self) -> Option<&'static str> {
+        match self {
+            Self::Church | Self::Cathedral => Some("gabled"),
+            Self::Mosque => Some("dome"),
+            Self::Temple | Self::Tower => None,
+        }
+    }
+}
+
+/// Adds the features of a landmark to a building whose walls and roof are in place
+pub fn generate_landmark(
+    editor: &mut WorldEditor,
+    landmark: Landmark,
+    tags: &HashMap<String, String>,
+    floor_area: &[(i32, i32)],
+    ground_level: i32,
+    building_height: i32,
+) {
+    if floor_area.is_empty() {
+        return;
+    }
+    let roof_y: i32 = ground_level + building_height + 1;
+
+    match landmark {
+        Landmark::Church | Landmark::Cathedral => {
+            generate_church_towers(
+                editor,
+                floor_area,
+                ground_level,
+                building_height,
+                landmark == Landmark::Cathedral,
+            );
+        }
+        Landmark::Mosque => generate_minarets(editor, floor_area, ground_level, building_height),
+        Landmark::Temple => generate_temple_tiers(editor, floor_area, roof_y),
+        Landmark::Tower => {
+            let distances: HashMap<(i32, i32), i32> = inset_distances(floor_area);
+            let (cx, cz) = innermost(&distances);
+            match tags.get("tower:type").map(|s| s.as_str()) {
+                Some("communication") => generate_antenna(editor, cx, cz, ground_level, 10),
+                Some("bell_tower") => {
+                    let half: i32 = distances[&(cx, cz)];
+                    build_spire(editor, cx, cz, half, roof_y + 1, half * 3 + 2);
+                }
+                _ => {
+                    // Observation deck with a railing along the edge of the roof
+                    for (&(x, z), _) in distances.iter().filter(|(_, &d)| d == 0) {
+                        editor.set_block(OAK_FENCE, x, roof_y + 1, z, None, None);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Builds a freestanding tower mapped as a node, e.g. an observation or bell tower
+pub fn generate_tower_node(
+    editor: &mut WorldEditor,
+    x: i32,
+    z: i32,
+    ground_level: i32,
+    tags: &HashMap<String, String>,
+    args: &Args,
+) {
+    let height: i32 = tags
+        .get("height")
+        .and_then(|height: &String| height.trim_end_matches('m').trim().parse::<f64>().ok())
+        .map(|height: f64| (height * args.scale).round() as i32)
+        .unwrap_or(DEFAULT_TOWER_HEIGHT)
+        .clamp(4, 120);
+    let top_y: i32 = ground_level + height;
+    build_tower(editor, x, z, 2, ground_level + 1, top_y, STONE_BRICKS);
+
+    match tags.get("tower:type").map(|s| s.as_str()) {
+        Some("communication") => generate_antenna(editor, x, z, ground_level, 10),
+        Some("bell_tower") => build_spire(editor, x, z, 2, top_y + 1, 8),
+        _ => {
+            for dx in -3..=3 {
+                for dz in -3..=3 {
+                    let on_edge: bool = dx * dx == 9 || dz * dz == 9;
+                    editor.set_block(STONE_BRICK_SLAB, x + dx, top_y, z + dz, None, None);
+                    if on_edge {
+                        editor.set_block(OAK_FENCE, x + dx, top_y + 1, z + dz, None, None);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Places a bell tower with a copper spire at the front of a church, which is the end of
+/// the longer side facing west or north. Cathedrals get a pair of towers on both corners
+/// of the front, small chapels only a cross on the roof.
+fn generate_church_towers(
+    editor: &mut WorldEditor,
+    floor_area: &[(i32, i32)],
+    ground_level: i32,
+    building_height: i32,
+    cathedral: bool,
+) {
+    let (min_x, max_x, min_z, max_z) = bounds(floor_area);
+    let along_x: bool = (max_x - min_x) >= (max_z - min_z);
+
+    if floor_area.len() < MIN_TOWER_FOOTPRINT {
+        let (x, z) = if along_x {
+            (min_x + 1, (min_z + max_z) / 2)
+        } else {
+            ((min_x + max_x) / 2, min_z + 1)
+        };
+        // The cross stands on the ridge of the roof
+        let ridge_y: i32 = editor
+            .get_highest_block_y(x, z)
+            .unwrap_or(ground_level + building_height + 1);
+        build_cross(editor, x, z, ridge_y + 1);
+        return;
+    }
+
+    let half: i32 = if floor_area.len() > LARGE_FOOTPRINT {
+        2
+    } else {
+        1
+    };
+    let tower_top: i32 = ground_level + building_height + building_height.max(8);
+    let towers: Vec<(i32, i32)> = match (along_x, cathedral) {
+        (true, false) => vec![(min_x + half, (min_z + max_z) / 2)],
+        (false, false) => vec![((min_x + max_x) / 2, min_z + half)],
+        (true, true) => vec![(min_x + half, min_z + half), (min_x + half, max_z - half)],
+        (false, true) => vec![(min_x + half, min_z + half), (max_x - half, min_z + half)],
+    };
+
+    for (x, z) in towers {
+        build_tower(
+            editor,
+            x,
+            z,
+            half,
+            ground_level + 1,
+            tower_top,
+            STONE_BRICKS,
+        );
+        let spire_height: i32 = (half + 1) * 4;
+        build_spire(editor, x, z, half, tower_top + 1, spire_height);
+        build_cross(editor, x, z, tower_top + spire_height + 1);
+    }
+}
+
+/// Places slender minarets with a balcony and a pointed cap at the corners of a mosque,
+/// one at small mosques, two at opposite corners or four at large ones
+fn generate_minarets(
+    editor: &mut WorldEditor,
+    floor_area: &[(i32, i32)],
+    ground_level: i32,
+    building_height: i32,
+) {
+    let (min_x, max_x, min_z, max_z) = bounds(floor_area);
+    let corners: Vec<(i32, i32)> = match floor_area.len() {
+        0..=80 => vec![(min_x, min_z)],
+        81..=LARGE_FOOTPRINT => vec![(min_x, min_z), (max_x, max_z)],
+        _ => vec![
+            (min_x, min_z),
+            (max_x, min_z),
+            (min_x, max_z),
+            (max_x, max_z),
+        ],
+    };
+    let top_y: i32 = ground_level + (building_height * 2 + 8).min(60);
+
+    for (corner_x, corner_z) in corners {
+        // Minarets stand on the part of the footprint closest to the corner
+        let &(x, z) = floor_area
+            .iter()
+            .min_by_key(|&&(x, z)| (x - corner_x).pow(2) + (z - corner_z).pow(2))
+            .unwrap();
+
+        for y in ground_level + 1..=top_y {
+            for (dx, dz) in [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)] {
+                editor.set_block(SMOOTH_QUARTZ, x + dx, y, z + dz, None, Some(&[]));
+            }
+        }
+
+        // Balcony for the call to prayer below the top
+        let balcony_y: i32 = top_y - 4;
+        for dx in -2..=2_i32 {
+            for dz in -2..=2_i32 {
+                if dx.abs() + dz.abs() > 1 {
+                    editor.set_block(STONE_BRICK_SLAB, x + dx, balcony_y, z + dz, None, None);
+                }
+            }
+        }
+
+        for (dx, dz) in [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)] {
+            editor.set_block(LIGHT_BLUE_TERRACOTTA, x + dx, top_y + 1, z + dz, None, None);
+        }
+        editor.set_block(LIGHT_BLUE_TERRACOTTA, x, top_y + 2, z, None, None);
+        editor.set_block(OXIDIZED_COPPER, x, top_y + 3, z, None, None);
+        editor.set_block(IRON_BARS, x, top_y + 4, z, None, None);
+    }
+}
+
+/// Stacks shrinking tiers with overhanging eaves on the roof of a temple, crowned by a
+/// golden finial
+fn generate_temple_tiers(editor: &mut WorldEditor, floor_area: &[(i32, i32)], roof_y: i32) {
+    let distances: HashMap<(i32, i32), i32> = inset_distances(floor_area);
+    let center: (i32, i32) = innermost(&distances);
+    let tiers: i32 = (distances[&center] / 2).min(MAX_TIERS);
+
+    // Eaves along the edge of the main roof
+    for (&(x, z), _) in distances.iter().filter(|(_, &d)| d == 0) {
+        editor.set_block(DARK_OAK_SLAB, x, roof_y + 1, z, None, None);
+    }
+
+    for tier in 1..=tiers {
+        let inset: i32 = tier * 2;
+        let base_y: i32 = roof_y + (tier - 1) * TIER_HEIGHT;
+        for (&(x, z), &distance) in &distances {
+            if distance == inset {
+                let pillar: bool = (x + z) % 3 == 0;
+                for y in base_y + 1..base_y + TIER_HEIGHT {
+                    let block: Block = if pillar {
+                        RED_CONCRETE
+                    } else {
+                        DARK_OAK_PLANKS
+                    };
+                    editor.set_block(block, x, y, z, None, None);
+                }
+            }
+            if distance == inset - 1 {
+                editor.set_block(DARK_OAK_SLAB, x, base_y + TIER_HEIGHT, z, None, None);
+            } else if distance >= inset {
+                editor.set_block(DARK_OAK_PLANKS, x, base_y + TIER_HEIGHT, z, None, None);
+            }
+        }
+    }
+
+    let finial_y: i32 = roof_y + tiers * TIER_HEIGHT + 1;
+    for y in finial_y..finial_y + 3 {
+        editor.set_block(YELLOW_CONCRETE, center.0, y, center.1, None, None);
+    }
+}
+
+/// Builds the walls of a square tower around a center, replacing the walls and roof of the
+/// building it rises from. Louvred openings let the bells be seen below the top.
+fn build_tower(
+    editor: &mut WorldEditor,
+    x: i32,
+    z: i32,
+    half: i32,
+    from_y: i32,
+    top_y: i32,
+    block: Block,
+) {
+    for y in from_y..=top_y {
+        for dx in -half..=half {
+            for dz in -half..=half {
+                if dx.abs() != half && dz.abs() != half {
+                    continue;
+                }
+                let is_opening: bool =
+                    y > top_y - 3 && y < top_y && (dx == 0 || dz == 0) && half > 0;
+                let wall: Block = if is_opening { IRON_BARS } else { block };
+                editor.set_block(wall, x + dx, y, z + dz, None, Some(&[]));
+            }
+        }
+    }
+    for dx in -half..=half {
+        for dz in -half..=half {
+            editor.set_block(block, x + dx, top_y + 1, z + dz, None, Some(&[]));
+        }
+    }
+}
+
+/// Builds a pointed copper spire tapering from a square base to a single block
+fn build_spire(editor: &mut WorldEditor, x: i32, z: i32, half: i32, base_y: i32, height: i32) {
+    for level in 0..height {
+        let radius: i32 =
+            (f64::from(half + 1) * f64::from(height - level) / f64::from(height)).round() as i32;
+        for dx in -radius..=radius {
+            for dz in -radius..=radius {
+                if dx.abs() == radius || dz.abs() == radius {
+                    editor.set_block(OXIDIZED_COPPER, x + dx, base_y + level, z + dz, None, None);
+                }
+            }
+        }
+    }
+}
+
+/// Places a cross standing on the given height
+fn build_cross(editor: &mut WorldEditor, x: i32, z: i32, base_y: i32) {
+    for y in base_y..base_y + 3 {
+        editor.set_block(OAK_FENCE, x, y, z, None, None);
+    }
+    editor.set_block(OAK_FENCE, x + 1, base_y + 1, z, None, None);
+    editor.set_block(OAK_FENCE, x - 1, base_y + 1, z, None, None);
+}
+
+fn bounds(area: &[(i32, i32)]) -> (i32, i32, i32, i32) {
+    let min_x: i32 = area.iter().map(|&(x, _)| x).min().unwrap_or(0);
+    let max_x: i32 = area.iter().map(|&(x, _)| x).max().unwrap_or(0);
+    let min_z: i32 = area.iter().map(|&(_, z)| z).min().unwrap_or(0);
+    let max_z: i32 = area.iter().map(|&(_, z)| z).max().unwrap_or(0);
+    (min_x, max_x, min_z, max_z)
+}
+
+/// Distance of every block of an area from its edge, spreading inwards from the edge
+fn inset_distances(area: &[(i32, i32)]) -> HashMap<(i32, i32), i32> {
+    let area: HashSet<(i32, i32)> = area.iter().copied().collect();
+    let mut distances: HashMap<(i32, i32), i32> = HashMap::new();
+    let mut queue: VecDeque<(i32, i32)> = VecDeque::new();
+    for &(x, z) in &area {
+        let on_edge: bool = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .iter()
+            .any(|(dx, dz)| !area.contains(&(x + dx, z + dz)));
+        if on_edge {
+            distances.insert((x, z), 0);
+            queue.push_back((x, z));
+        }
+    }
+    while let Some((x, z)) = queue.pop_front() {
+        let distance: i32 = distances[&(x, z)];
+        for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let neighbor: (i32, i32) = (x + dx, z + dz);
+            if area.contains(&neighbor) && !distances.contains_key(&neighbor) {
+                distances.insert(neighbor, distance + 1);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    distances
+}
+
+/// Returns the block furthest from the edge, preferring the lowest coordinates on ties so
+/// the result does not depend on the iteration order
+fn innermost(distances: &HashMap<(i32, i32), i32>) -> (i32, i32) {
+    distances
+        .iter()
+        .max_by_key(|(&(x, z), &d)| (d, -x, -z))
+        .map(|(&position, _)| position)
+        .unwrap_or((0, 0))
+}
@@ -1,11 +1,23 @@
 use crate::args::Args;
+use crate::biome_definitions::*;
 use crate::block_definitions::*;
 use crate::bresenham::bresenham_line;
+use crate::element_processing::cemetery::generate_cemetery;
+use crate::element_processing::forest::generate_forest_floor;
+use crate::element_processing::perimeters::generate_secure_perimeter;
 use crate::element_processing::tree::create_tree;
 use crate::floodfill::flood_fill_area;
 use crate::osm_parser::ProcessedWay;
+use crate::random::seeded_rng;
 use crate::world_editor::WorldEditor;
+use fastnbt::Value;
+use rand::rngs::StdRng;
 use rand::Rng;
+use std::collections::HashMap;
+
+/// Width of a strip of crops, including the irrigation channel. Farmland stays hydrated
+/// up to 4 blocks away from water.
+const FIELD_STRIP_WIDTH: i32 = 9;
 
 pub fn generate_landuse(
     editor: &mut WorldEditor,
@@ -19,7 +31,12 @@ pub fn generate_landuse(
 
     // Determine block type based on landuse tag
     let binding: String = "".to_string();
-    let landuse_tag: &String = element.tags.get("landuse").unwrap_or(&binding);
+    let pasture: String = "meadow".to_string();
+    let mut landuse_tag: &String = element.tags.get("landuse").unwrap_or(&binding);
+    // Farmland tagged with animals is grazed instead of ploughed
+    if landuse_tag == "farmland" && element.tags.contains_key("animal") {
+        landuse_tag = &pasture;
+    }
 
     let block_type = match landuse_tag.as_str() {
         "greenfield" | "meadow" | "grass" => {
@@ -37,7 +54,6 @@ pub fn generate_landuse(
                 GRASS_BLOCK
             }
         }
-        "cemetery" => PODZOL,
         "beach" => SAND,
         "construction" => DIRT,
         "traffic_island" => STONE_BLOCK_SLAB,
@@ -56,6 +72,13 @@ pub fn generate_landuse(
         }
     };
 
+    let biome: Option<Biome> = match landuse_tag.as_str() {
+        "forest" => Some(FOREST),
+        "meadow" | "grass" | "greenfield" => Some(MEADOW),
+        "beach" => Some(BEACH),
+        _ => None,
+    };
+
     let bresenham_block: Block = if args.winter { SNOW_BLOCK } else { GRASS_BLOCK };
 
     // Process landuse nodes to fill the area
@@ -81,11 +104,15 @@ pub fn generate_landuse(
     // If there are landuse nodes, flood-fill the area
     if !current_landuse.is_empty() {
         let polygon_coords: Vec<(i32, i32)> = element.nodes.iter().map(|n| (n.x, n.z)).collect();
-        let floor_area: Vec<(i32, i32)> = flood_fill_area(&polygon_coords, args.timeout.as_ref());
+        let floor_area: Vec<(i32, i32)> = flood_fill_area(&polygon_coords, &args.fill_options());
 
-        let mut rng: rand::prelude::ThreadRng = rand::thread_rng();
+        let mut rng: StdRng = seeded_rng(editor.seed(), element.id);
+
+        for (x, z) in floor_area.iter().copied() {
+            if let Some(biome) = biome {
+                editor.set_biome(biome, x, z);
+            }
 
-        for (x, z) in floor_area {
             if landuse_tag == "traffic_island" {
                 editor.set_block(block_type, x, ground_level + 1, z, None, None);
             } else if landuse_tag == "construction" || landuse_tag == "railway" {
@@ -96,133 +123,29 @@ pub fn generate_landuse(
 
             // Add specific features for different landuse types
             match landuse_tag.as_str() {
-                "cemetery" => {
-                    if (x % 3 == 0) && (z % 3 == 0) {
-                        let random_choice: i32 = rng.gen_range(0..100);
-                        if random_choice < 15 {
-                            // Place graves
-                            if editor.check_for_block(x, ground_level, z, Some(&[PODZOL]), None) {
-                                if rng.gen_bool(0.5) {
-                                    editor.set_block(
-                                        COBBLESTONE,
-                                        x - 1,
-                                        ground_level + 1,
-                                        z,
-                                        None,
-                                        None,
-                                    );
-                                    editor.set_block(
-                                        STONE_BRICK_SLAB,
-                                        x - 1,
-                                        ground_level + 2,
-                                        z,
-                                        None,
-                                        None,
-                                    );
-                                    editor.set_block(
-                                        STONE_BRICK_SLAB,
-                                        x,
-                                        ground_level + 1,
-                                        z,
-                                        None,
-                                        None,
-                                    );
-                                    editor.set_block(
-                                        STONE_BRICK_SLAB,
-                                        x + 1,
-                                        ground_level + 1,
-                                        z,
-                                        None,
-                                        None,
-                                    );
-                                } else {
-                                    editor.set_block(
-                                        COBBLESTONE,
-                                        x,
-                                        ground_level + 1,
-                                        z - 1,
-                                        None,
-                                        None,
-                                    );
-                                    editor.set_block(
-                                        STONE_BRICK_SLAB,
-                                        x,
-                                        ground_level + 2,
-                                        z - 1,
-                                        None,
-                                        None,
-                                    );
-                                    editor.set_block(
-                                        STONE_BRICK_SLAB,
-                                        x,
-                                        ground_level + 1,
-                                        z,
-                                        None,
-                                        None,
-                                    );
-                                    editor.set_block(
-                                        STONE_BRICK_SLAB,
-                                        x,
-                                        ground_level + 1,
-                                        z + 1,
-                                        None,
-                                        None,
-                                    );
-                                }
-                            }
-                        } else if random_choice < 30 {
-                            if editor.check_for_block(x, ground_level, z, Some(&[PODZOL]), None) {
-                                editor.set_block(RED_FLOWER, x, ground_level + 1, z, None, None);
-                            }
-                        } else if random_choice < 33 {
-                            create_tree(
-                                editor,
-                                x,
-                                ground_level + 1,
-                                z,
-                                rng.gen_range(1..=3),
-                                args.winter,
-                            );
-                        }
-                    }
-                }
                 "forest" => {
-                    if !editor.check_for_block(x, ground_level, z, None, Some(&[WATER])) {
-                        let random_choice: i32 = rng.gen_range(0..21);
-                        if random_choice == 20 {
-                            create_tree(
-                                editor,
-                                x,
-                                ground_level + 1,
-                                z,
-                                rng.gen_range(1..=3),
-                                args.winter,
-                            );
-                        } else if random_choice == 2 {
-                            let flower_block: Block = match rng.gen_range(1..=4) {
-                                1 => RED_FLOWER,
-                                2 => BLUE_FLOWER,
-                                3 => YELLOW_FLOWER,
-                                _ => WHITE_FLOWER,
-                            };
-                            editor.set_block(flower_block, x, ground_level + 1, z, None, None);
-                        } else if random_choice <= 1 {
-                            editor.set_block(GRASS, x, ground_level + 1, z, None, None);
-                        }
-                    }
+                    generate_forest_floor(
+                        editor,
+                        &element.tags,
+                        x,
+                        z,
+                        ground_level,
+                        args,
+                        &mut rng,
+                    );
                 }
                 "farmland" => {
                     // Check if the current block is not water or another undesired block
                     if !editor.check_for_block(x, ground_level, z, None, Some(&[WATER])) {
-                        if x % 15 == 0 || z % 15 == 0 {
-                            // Place water on the edges
+                        if x.rem_euclid(FIELD_STRIP_WIDTH) == 0 {
+                            // Irrigation channels between the strips keep all farmland hydrated
                             editor.set_block(WATER, x, ground_level, z, Some(&[FARMLAND]), None);
                             editor.set_block(
                                 AIR,
                                 x,
                                 ground_level + 1,
                                 z,
-                                Some(&[GRASS, WHEAT, CARROTS, POTATOES]),
+                                Some(&[GRASS, WHEAT, CARROTS, POTATOES, BEETROOTS]),
                                 None,
                             );
                         } else {
@@ -230,53 +153,75 @@ pub fn generate_landuse(
                             editor.set_block(FARMLAND, x, ground_level, z, None, None);
 
                             // If a random condition is met, place a special object
-                            if rng.gen_range(0..76) == 0 {
-                                let special_choice: i32 = rng.gen_range(1..=10);
-                                if special_choice <= 2 {
+                            if rng.gen_range(0..150) == 0 {
+                                if rng.gen_bool(0.3) {
                                     create_tree(
                                         editor,
                                         x,
                                         ground_level + 1,
                                         z,
-                                        rng.gen_range(1..=3),
+                                        &element.tags,
                                         args.winter,
                                     );
-                                } else if special_choice <= 6 {
-                                    editor.set_block(HAY_BALE, x, ground_level + 1, z, None, None);
                                 } else {
-                                    editor.set_block(
-                                        OAK_LEAVES,
-                                        x,
-                                        ground_level + 1,
-                                        z,
-                                        None,
-                                        None,
-                                    );
+                                    editor.set_block(HAY_BALE, x, ground_level + 1, z, None, None);
                                 }
-                            } else {
+                            } else if editor.check_for_block(
+                                x,
+                                ground_level,
+                                z,
+                                Some(&[FARMLAND]),
+                                None,
+                            ) {
                                 // Set crops only if the block below is farmland
-                                if editor.check_for_block(
-                                    x,
-                                    ground_level,
-                                    z,
-                                    Some(&[FARMLAND]),
-                                    None,
-                                ) {
-                                    let crop_choice =
-                                        [WHEAT, CARROTS, POTATOES][rng.gen_range(0..3)];
-                                    editor.set_block(
-                                        crop_choice,
-                                        x,
-                                        ground_level + 1,
-                                        z,
-                                        None,
-                                        None,
-                                    );
+                                let crop: Block = field_crop(&element.tags, x, element.id);
+                                // Pumpkins and melons grow with gaps in between
+                                let is_gap: bool =
+                                    matches!(crop, PUMPKIN | MELON) && (x + z).rem_euclid(2) == 0;
+                                if !is_gap {
+                                    editor.set_block(crop, x, ground_level + 1, z, None, None);
                                 }
                             }
                         }
                     }
                 }
+                "allotments" => {
+                    // Patchwork of 8x8 plots separated by gravel paths
+                    let (plot_x, plot_z) = (x.div_euclid(8), z.div_euclid(8));
+                    let (local_x, local_z) = (x.rem_euclid(8), z.rem_euclid(8));
+                    let is_path: bool = local_x == 0 || local_z == 0;
+                    let is_fence: bool =
+                        local_x == 1 || local_x == 7 || local_z == 1 || local_z == 7;
+                    let has_shed: bool = (plot_x * 7 + plot_z * 13).rem_euclid(3) == 0;
+
+                    if is_path {
+                        editor.set_block(
+                            GRAVEL,
+                            x,
+                            ground_level,
+                            z,
+                            Some(&[GRASS_BLOCK, SNOW_BLOCK]),
+                            None,
+                        );
+                    } else if is_fence {
+                        // Leave a gate towards the path
+                        if !(local_x == 4 && local_z == 1) {
+                            editor.set_block(OAK_FENCE, x, ground_level + 1, z, None, None);
+                        }
+                    } else if has_shed && local_x >= 5 && local_z >= 5 {
+                        editor.set_block(OAK_PLANKS, x, ground_level + 1, z, None, None);
+                        editor.set_block(OAK_PLANKS, x, ground_level + 2, z, None, None);
+                        editor.set_block(OAK_SLAB, x, ground_level + 3, z, None, None);
+                    } else if local_z <= 4 && !args.winter {
+                        // Crop beds, every plot grows its own crop
+                        editor.set_block(FARMLAND, x, ground_level, z, Some(&[GRASS_BLOCK]), None);
+                        let crop: Block =
+                            [WHEAT, CARROTS, POTATOES][(plot_x + plot_z).rem_euclid(3) as usize];
+                        editor.set_block(crop, x, ground_level + 1, z, None, None);
+                    } else if rng.gen_range(0..4) == 0 && !args.winter {
+                        editor.set_block(GRASS, x, ground_level + 1, z, None, None);
+                    }
+                }
                 "construction" => {
                     let random_choice: i32 = rng.gen_range(0..1501);
                     if random_choice < 6 {
@@ -353,14 +298,7 @@ pub fn generate_landuse(
                     if !editor.check_for_block(x, ground_level, z, None, Some(&[WATER])) {
                         let random_choice: i32 = rng.gen_range(0..1001);
                         if random_choice < 5 {
-                            create_tree(
-                                editor,
-                                x,
-                                ground_level + 1,
-                                z,
-                                rng.gen_range(1..=3),
-                                args.winter,
-                            );
+                            create_tree(editor, x, ground_level + 1, z, &element.tags, args.winter);
                         } else if random_choice < 800 {
                             editor.set_block(GRASS, x, ground_level + 1, z, None, None);
                         }
@@ -369,5 +307,160 @@ pub fn generate_landuse(
                 _ => {}
             }
         }
+
+        // Refineries, chemical plants and other hazardous sites are secured by walls
+        let is_sensitive_site: bool = landuse_tag == "industrial"
+            && matches!(
+                element.tags.get("industrial").map(|s: &String| s.as_str()),
+                Some("refinery" | "chemical" | "nuclear" | "oil" | "gas" | "depot")
+            );
+        if is_sensitive_site {
+            generate_secure_perimeter(
+                editor,
+                &element.nodes,
+                ground_level,
+                &args.fill_options(),
+                GRAY_CONCRETE,
+                false,
+            );
+        }
+
+        if landuse_tag == "cemetery" {
+            generate_cemetery(
+                editor,
+                &floor_area,
+                block_type,
+                ground_level,
+                &element.tags,
+                args,
+            );
+        }
+
+        // Fenced pastures with grazing animals
+        if args.mobs && (landuse_tag == "meadow" || landuse_tag == "farmyard") {
+            generate_pasture(editor, element, &floor_area, ground_level, landuse_tag);
+        }
     }
 }
+
+/// Fences the landuse area and spawns farm animals at a low density inside. The animals come
+/// from the animal tag, otherwise meadows are grazed by cows, sheep and horses and farmyards
+/// house pigs, chickens and cows.
+fn generate_pasture(
+    editor: &mut WorldEditor,
+    element: &ProcessedWay,
+    floor_area: &[(i32, i32)],
+    ground_level: i32,
+    landuse_tag: &str,
+) {
+    // Perimeter fence, replacing the grass placed before
+    for nodes in element.nodes.windows(2) {
+        let bresenham_points: Vec<(i32, i32, i32)> = bresenham_line(
+            nodes[0].x,
+            ground_level,
+            nodes[0].z,
+            nodes[1].x,
+            ground_level,
+            nodes[1].z,
+        );
+        for (bx, _, bz) in bresenham_points {
+            if !editor.check_for_block(bx, ground_level, bz, Some(&[WATER]), None) {
+                editor.set_block(OAK_FENCE, bx, ground_level + 1, bz, Some(&[GRASS]), None);
+            }
+        }
+    }
+
+    // Areas that were not filled, e.g. above the fill limit, only get the fence
+    if floor_area.is_empty() {
+        return;
+    }
+
+    let animals: Vec<&str> = pasture_animals(&element.tags, landuse_tag);
+
+    // About one animal per 150 blocks, limited to keep large pastures playable
+    let animal_count: usize = (floor_area.len() / 150).clamp(1, 24);
+    let mut rng: StdRng = seeded_rng(editor.seed(), element.id);
+    let mut spawned: usize = 0;
+    for _ in 0..animal_count * 4 {
+        if spawned == animal_count {
+            break;
+        }
+
+        let (x, z) = floor_area[rng.gen_range(0..floor_area.len())];
+        let is_free: bool = !editor.check_for_block(x, ground_level, z, Some(&[WATER]), None)
+            && (editor.check_for_block(x, ground_level + 1, z, Some(&[GRASS]), None)
+                || !matches!(editor.get_highest_block_y(x, z), Some(y) if y > ground_level));
+        if !is_free {
+            continue;
+        }
+
+        let mut animal: HashMap<String, Value> = HashMap::new();
+        animal.insert("PersistenceRequired".to_string(), Value::Byte(1));
+        editor.add_entity(
+            animals[rng.gen_range(0..animals.len())],
+            x,
+            ground_level + 1,
+            z,
+            rng.gen_range(0.0..360.0),
+            animal,
+        );
+        spawned += 1;
+    }
+}
+
+/// Minecraft entities for the animal tag of a pasture, e.g. "cattle;sheep", or the default
+/// animals of the landuse if none of the tagged animals exist in Minecraft
+fn pasture_animals(tags: &HashMap<String, String>, landuse_tag: &str) -> Vec<&'static str> {
+    let tagged: Vec<&str> = tags
+        .get("animal")
+        .map(|animal: &String| {
+            animal
+                .split(';')
+                .filter_map(|animal: &str| match animal.trim() {
+                    "cattle" | "cow" | "cows" | "bull" | "dairy" | "beef" => Some("minecraft:cow"),
+                    "sheep" => Some("minecraft:sheep"),
+                    "horse" | "horses" | "pony" => Some("minecraft:horse"),
+                    "donkey" => Some("minecraft:donkey"),
+                    "goat" | "goats" => Some("minecraft:goat"),
+                    "pig" | "pigs" => Some("minecraft:pig"),
+                    "chicken" | "chickens" | "poultry" | "hen" => Some("minecraft:chicken"),
+                    "rabbit" | "rabbits" => Some("minecraft:rabbit"),
+                    "llama" | "alpaca" => Some("minecraft:llama"),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if !tagged.is_empty() {
+        return tagged;
+    }
+
+    if landuse_tag == "farmyard" {
+        vec!["minecraft:pig", "minecraft:chicken", "minecraft:cow"]
+    } else {
+        vec!["minecraft:cow", "minecraft:sheep", "minecraft:horse"]
+    }
+}
+
+/// Crop growing on a strip of a field, from the crop tag. Fields without a single known
+/// crop get a different random crop on each strip.
+fn field_crop(tags: &HashMap<String, String>, x: i32, element_id: u64) -> Block {
+    let crops: [Block; 6] = [WHEAT, CARROTS, POTATOES, BEETROOTS, PUMPKIN, MELON];
+    let crop: Option<Block> = tags.get("crop").and_then(|crop: &String| {
+        match crop.split(';').next().unwrap_or_default().trim() {
+            "wheat" | "barley" | "rye" | "oats" | "grain" | "cereal" | "maize" | "corn"
+            | "rice" | "spelt" | "triticale" => Some(WHEAT),
+            "carrot" | "carrots" => Some(CARROTS),
+            "potato" | "potatoes" => Some(POTATOES),
+            "sugar_beet" | "beet" | "beetroot" | "beetroots" => Some(BEETROOTS),
+            "pumpkin" | "pumpkins" | "squash" => Some(PUMPKIN),
+            "melon" | "melons" | "watermelon" => Some(MELON),
+            _ => None,
+        }
+    });
+
+    crop.unwrap_or_else(|| {
+        let strip: u64 = x.div_euclid(FIELD_STRIP_WIDTH) as u64;
+        crops[(strip.wrapping_mul(31) ^ element_id).wrapping_rem(crops.len() as u64) as usize]
+    })
+}
@@ -1,11 +1,18 @@
 use crate::args::Args;
 use crate::block_definitions::*;
 use crate::bresenham::bresenham_line;
+use crate::element_processing::piers::generate_marina;
+use crate::element_processing::pitches::{generate_pitch_markings, pitch_surface};
+use crate::element_processing::stadiums::generate_stadium;
 use crate::element_processing::tree::create_tree;
 use crate::floodfill::flood_fill_area;
 use crate::osm_parser::ProcessedWay;
+use crate::random::seeded_rng;
 use crate::world_editor::WorldEditor;
+use fastnbt::Value;
+use rand::rngs::StdRng;
 use rand::Rng;
+use std::collections::HashMap;
 
 pub fn generate_leisure(
     editor: &mut WorldEditor,
@@ -14,6 +21,11 @@ pub fn generate_leisure(
     args: &Args,
 ) {
     if let Some(leisure_type) = element.tags.get("leisure") {
+        if leisure_type == "stadium" {
+            generate_stadium(editor, element, ground_level, args);
+            return;
+        }
+
         let mut previous_node: Option<(i32, i32)> = None;
         let mut corner_addup: (i32, i32, i32) = (0, 0, 0);
         let mut current_leisure: Vec<(i32, i32)> = vec![];
@@ -27,7 +39,8 @@ pub fn generate_leisure(
                     GRASS_BLOCK
                 }
             }
-            "playground" | "recreation_ground" | "pitch" => {
+            "pitch" => pitch_surface(element),
+            "playground" | "recreation_ground" => {
                 if let Some(surface) = element.tags.get("surface") {
                     match surface.as_str() {
                         "clay" => TERRACOTTA,
@@ -96,16 +109,16 @@ pub fn generate_leisure(
                 .map(|n: &crate::osm_parser::ProcessedNode| (n.x, n.z))
                 .collect();
             let filled_area: Vec<(i32, i32)> =
-                flood_fill_area(&polygon_coords, args.timeout.as_ref());
+                flood_fill_area(&polygon_coords, &args.fill_options());
+            let mut rng: StdRng = seeded_rng(editor.seed(), element.id);
 
-            for (x, z) in filled_area {
+            for &(x, z) in &filled_area {
                 editor.set_block(block_type, x, ground_level, z, Some(&[GRASS_BLOCK]), None);
 
                 // Add decorative elements for parks and gardens
                 if matches!(leisure_type.as_str(), "park" | "garden")
                     && editor.check_for_block(x, ground_level, z, Some(&[GRASS_BLOCK]), None)
                 {
-                    let mut rng: rand::prelude::ThreadRng = rand::thread_rng();
                     let random_choice: i32 = rng.gen_range(0..1000);
 
                     match random_choice {
@@ -131,13 +144,22 @@ pub fn generate_leisure(
                         }
                         71..=80 => {
                             // Tree
-                            create_tree(
-                                editor,
+                            create_tree(editor, x, ground_level + 1, z, &element.tags, args.winter);
+                        }
+                        81 if args.mobs => {
+                            // Small animals
+                            let animal: &str =
+                                ["minecraft:rabbit", "minecraft:chicken", "minecraft:fox"]
+                                    [rng.gen_range(0..3)];
+                            let mut entity: HashMap<String, Value> = HashMap::new();
+                            entity.insert("PersistenceRequired".to_string(), Value::Byte(1));
+                            editor.add_entity(
+                                animal,
                                 x,
                                 ground_level + 1,
                                 z,
-                                rng.gen_range(1..=3),
-                                args.winter,
+                                rng.gen_range(0.0..360.0),
+                                entity,
                             );
                         }
                         _ => {}
@@ -146,7 +168,6 @@ pub fn generate_leisure(
 
                 // Add playground or recreation ground features
                 if matches!(leisure_type.as_str(), "playground" | "recreation_ground") {
-                    let mut rng: rand::prelude::ThreadRng = rand::thread_rng();
                     let random_choice: i32 = rng.gen_range(0..5000);
 
                     match random_choice {
@@ -189,6 +210,12 @@ pub fn generate_leisure(
                     }
                 }
             }
+
+            if leisure_type == "pitch" {
+                generate_pitch_markings(editor, element, &filled_area, block_type, ground_level);
+            } else if leisure_type == "marina" {
+                generate_marina(editor, &filled_area, ground_level, args.mobs);
+            }
         }
     }
 }
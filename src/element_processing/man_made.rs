@@ -0,0 +1,126 @@
+use crate::args::Args;
+use crate::block_definitions::*;
+use crate::bresenham::thick_polyline;
+use crate::element_processing::buildings::generate_buildings;
+use crate::element_processing::landmarks::generate_tower_node;
+use crate::element_processing::piers::generate_pier;
+use crate::osm_parser::{ProcessedElement, ProcessedWay};
+use crate::world_editor::WorldEditor;
+
+pub fn generate_man_made(
+    editor: &mut WorldEditor,
+    element: &ProcessedElement,
+    ground_level: i32,
+    args: &Args,
+) {
+    // Skip if 'layer' or 'level' is negative in the tags
+    if let Some(layer) = element.tags().get("layer") {
+        if layer.parse::<i32>().unwrap_or(0) < 0 {
+            return;
+        }
+    }
+
+    if let Some(man_made_type) = element.tags().get("man_made") {
+        if let ProcessedElement::Way(way) = element {
+            match man_made_type.as_str() {
+                "dyke" | "embankment" => generate_dyke(editor, way, ground_level, args),
+                "pier" => generate_pier(editor, way, ground_level, args),
+                "tower" => {
+                    // Towers mapped without a building tag are built like tower buildings
+                    let mut tower: ProcessedWay = way.clone();
+                    tower
+                        .tags
+                        .insert("building".to_string(), "tower".to_string());
+                    generate_buildings(editor, &tower, ground_level, args);
+                }
+                _ => {}
+            }
+        } else if let ProcessedElement::Node(node) = element {
+            match man_made_type.as_str() {
+                "tower" => {
+                    generate_tower_node(editor, node.x, node.z, ground_level, &node.tags, args);
+                }
+                "antenna" => {
+                    generate_antenna(editor, node.x, node.z, ground_level, 4);
+                }
+                "mast" => {
+                    let height: i32 = element
+                        .tags()
+                        .get("height")
+                        .and_then(|height: &String| {
+                            height.trim_end_matches("m").trim().parse::<f64>().ok()
+                        })
+                        .map(|height: f64| height.round() as i32)
+                        .unwrap_or(15)
+                        .clamp(4, 60);
+                    generate_antenna(editor, node.x, node.z, ground_level, height);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Generates a dyke or levee as an earthen embankment with sloped sides and a gravel
+/// path along the crest. The world has no terrain elevation, so both sides of the
+/// embankment stay at ground level.
+fn generate_dyke(editor: &mut WorldEditor, element: &ProcessedWay, ground_level: i32, args: &Args) {
+    let crest_height: i32 = 3;
+    let crest_radius: i32 = 1;
+    let surface_block: Block = if args.winter { SNOW_BLOCK } else { GRASS_BLOCK };
+    let embankment_radius: i32 = crest_radius + crest_height;
+
+    let node_points: Vec<(i32, i32)> = element.nodes.iter().map(|n| (n.x, n.z)).collect();
+    for (x, z, distance) in thick_polyline(&node_points, f64::from(embankment_radius) + 0.5) {
+        // Height drops by one block per block of distance from the crest
+        let distance: i32 = distance.round() as i32;
+        let height: i32 = crest_height - (distance - crest_radius).max(0);
+        if height <= 0 {
+            continue;
+        }
+
+        for y in (ground_level + 1)..(ground_level + height) {
+            editor.set_block(DIRT, x, y, z, Some(&[GRASS_BLOCK, SNOW_BLOCK]), None);
+        }
+
+        let top_block: Block = if distance <= crest_radius {
+            GRAVEL
+        } else {
+            surface_block
+        };
+        editor.set_block(
+            top_block,
+            x,
+            ground_level + height,
+            z,
+            Some(&[GRASS_BLOCK, SNOW_BLOCK]),
+            None,
+        );
+    }
+}
+
+/// Places an antenna mast with a small dish. The mast stands on top of whatever was
+/// generated at the position before, e.g. a building roof, or on the ground otherwise.
+pub fn generate_antenna(editor: &mut WorldEditor, x: i32, z: i32, ground_level: i32, height: i32) {
+    let base_y: i32 = match editor.get_highest_block_y(x, z) {
+        // Replace snow on the roof instead of placing the mast on top of it
+        Some(top_y) if editor.check_for_block(x, top_y, z, Some(&[SNOW_LAYER]), None) => top_y,
+        Some(top_y) if top_y >= ground_level => top_y + 1,
+        _ => ground_level + 1,
+    };
+
+    for y in base_y..(base_y + height) {
+        editor.set_block(IRON_BARS, x, y, z, Some(&[SNOW_LAYER]), None);
+    }
+
+    // Dish facing away from the mast and a warning light on the tip
+    editor.set_block(
+        LIGHT_GRAY_CONCRETE,
+        x + 1,
+        base_y + height / 2,
+        z,
+        None,
+        None,
+    );
+    editor.set_block(RED_CONCRETE, x, base_y + height, z, None, None);
+}
@@ -1,14 +1,31 @@
+pub mod address_signs;
+pub mod aeroways;
 pub mod amenities;
 pub mod barriers;
 pub mod bridges;
 pub mod buildings;
+pub mod cemetery;
+pub mod cuttings;
 pub mod doors;
+pub mod forest;
 pub mod highways;
+pub mod landmarks;
 pub mod landuse;
 pub mod leisure;
+pub mod man_made;
 pub mod natural;
+pub mod parking;
+pub mod perimeters;
+pub mod piers;
+pub mod pitches;
+pub mod power;
 pub mod railways;
+pub mod roundabouts;
+pub mod squares;
+pub mod stadiums;
 pub mod tourisms;
 pub mod tree;
+pub mod tunnels;
+pub mod villagers;
 pub mod water_areas;
 pub mod waterways;
@@ -1,10 +1,15 @@
 use crate::args::Args;
+use crate::biome_definitions::*;
 use crate::block_definitions::*;
+use crate::block_palette::{palette_block, BlockCategory};
 use crate::bresenham::bresenham_line;
+use crate::element_processing::forest::generate_forest_floor;
 use crate::element_processing::tree::create_tree;
 use crate::floodfill::flood_fill_area;
 use crate::osm_parser::ProcessedElement;
+use crate::random::seeded_rng;
 use crate::world_editor::WorldEditor;
+use rand::rngs::StdRng;
 use rand::Rng;
 
 pub fn generate_natural(
@@ -19,15 +24,7 @@ pub fn generate_natural(
                 let x: i32 = node.x;
                 let z: i32 = node.z;
 
-                let mut rng: rand::prelude::ThreadRng = rand::thread_rng();
-                create_tree(
-                    editor,
-                    x,
-                    ground_level + 1,
-                    z,
-                    rng.gen_range(1..=3),
-                    args.winter,
-                );
+                create_tree(editor, x, ground_level + 1, z, &node.tags, args.winter);
             }
         } else {
             let mut previous_node: Option<(i32, i32)> = None;
@@ -51,7 +48,7 @@ pub fn generate_natural(
                         GRASS_BLOCK
                     }
                 }
-                "wetland" | "water" => WATER,
+                "wetland" | "water" => palette_block(BlockCategory::Water, WATER),
                 _ => {
                     if args.winter {
                         SNOW_BLOCK
@@ -61,6 +58,15 @@ pub fn generate_natural(
                 }
             };
 
+            let biome: Option<Biome> = match natural_type.as_str() {
+                "wood" => Some(FOREST),
+                "beach" => Some(BEACH),
+                "sand" => Some(DESERT),
+                "wetland" => Some(SWAMP),
+                "water" => Some(RIVER),
+                _ => None,
+            };
+
             let ProcessedElement::Way(way) = element else {
                 return;
             };
@@ -93,15 +99,30 @@ pub fn generate_natural(
                     .map(|n: &crate::osm_parser::ProcessedNode| (n.x, n.z))
                     .collect();
                 let filled_area: Vec<(i32, i32)> =
-                    flood_fill_area(&polygon_coords, args.timeout.as_ref());
+                    flood_fill_area(&polygon_coords, &args.fill_options());
 
-                let mut rng: rand::prelude::ThreadRng = rand::thread_rng();
+                let mut rng: StdRng = seeded_rng(editor.seed(), way.id);
 
                 for (x, z) in filled_area {
                     editor.set_block(block_type, x, ground_level, z, None, None);
+                    if let Some(biome) = biome {
+                        editor.set_biome(biome, x, z);
+                    }
+
+                    if natural_type == "wood" {
+                        generate_forest_floor(
+                            editor,
+                            element.tags(),
+                            x,
+                            z,
+                            ground_level,
+                            args,
+                            &mut rng,
+                        );
+                    }
 
-                    // Generate elements for "wood" and "tree_row"
-                    if natural_type == "wood" || natural_type == "tree_row" {
+                    // Generate elements for "tree_row"
+                    if natural_type == "tree_row" {
                         if editor.check_for_block(x, ground_level, z, None, Some(&[WATER])) {
                             continue;
                         }
@@ -113,7 +134,7 @@ pub fn generate_natural(
                                 x,
                                 ground_level + 1,
                                 z,
-                                rng.gen_range(1..=3),
+                                element.tags(),
                                 args.winter,
                             );
                         } else if random_choice == 2 {
@@ -0,0 +1,115 @@
+use crate::args::Args;
+use crate::block_definitions::*;
+use crate::random::seeded_rng;
+use crate::world_editor::WorldEditor;
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::collections::HashSet;
+
+/// Width of a parking stall including its line
+const STALL_WIDTH: i32 = 3;
+/// Depth of a parking stall
+const STALL_DEPTH: i32 = 5;
+/// Width of the driving aisle between two rows of stalls
+const AISLE_WIDTH: i32 = 6;
+/// Share of stalls with a parked car
+const OCCUPANCY: f64 = 0.6;
+
+/// Paints parking stalls onto a parking lot, in double rows along its longer side with
+/// an aisle in between. With --details, some of the stalls get a parked car.
+pub fn generate_parking_lot(
+    editor: &mut WorldEditor,
+    area: &[(i32, i32)],
+    surface: Block,
+    ground_level: i32,
+    args: &Args,
+) {
+    let Some(min_x) = area.iter().map(|&(x, _)| x).min() else {
+        return;
+    };
+    let max_x: i32 = area.iter().map(|&(x, _)| x).max().unwrap_or(min_x);
+    let min_z: i32 = area.iter().map(|&(_, z)| z).min().unwrap_or(0);
+    let max_z: i32 = area.iter().map(|&(_, z)| z).max().unwrap_or(min_z);
+
+    // Rows run along the longer side, positions are given as (along, depth)
+    let along_x: bool = max_x - min_x >= max_z - min_z;
+    let to_world = |along: i32, depth: i32| -> (i32, i32) {
+        if along_x {
+            (min_x + along, min_z + depth)
+        } else {
+            (min_x + depth, min_z + along)
+        }
+    };
+    let to_local = |x: i32, z: i32| -> (i32, i32) {
+        if along_x {
+            (x - min_x, z - min_z)
+        } else {
+            (z - min_z, x - min_x)
+        }
+    };
+
+    let inside: HashSet<(i32, i32)> = area.iter().copied().collect();
+    let period: i32 = STALL_DEPTH * 2 + AISLE_WIDTH;
+    let stall_row_start = |depth: i32| -> Option<i32> {
+        let row_depth: i32 = depth.rem_euclid(period);
+        if row_depth < STALL_DEPTH {
+            Some(depth - row_depth)
+        } else if row_depth >= STALL_DEPTH + AISLE_WIDTH {
+            Some(depth - row_depth + STALL_DEPTH + AISLE_WIDTH)
+        } else {
+            None
+        }
+    };
+
+    // Lines between the stalls
+    for &(x, z) in area {
+        let (along, depth) = to_local(x, z);
+        if stall_row_start(depth).is_some() && along.rem_euclid(STALL_WIDTH) == 0 {
+            editor.set_block(WHITE_CONCRETE, x, ground_level, z, Some(&[surface]), None);
+        }
+    }
+
+    if !args.details {
+        return;
+    }
+
+    // Cars fill the space between two lines, leaving a gap towards the aisle
+    let car_colors: [Block; 6] = [
+        WHITE_CONCRETE,
+        BLACK_CONCRETE,
+        GRAY_CONCRETE,
+        RED_CONCRETE,
+        BLUE_CONCRETE,
+        LIGHT_GRAY_CONCRETE,
+    ];
+    let mut rng: StdRng = seeded_rng(editor.seed(), area);
+    for &(x, z) in area {
+        let (along, depth) = to_local(x, z);
+        let is_stall_corner: bool = along.rem_euclid(STALL_WIDTH) == 1
+            && stall_row_start(depth) == Some(depth)
+            && editor.check_for_block(x, ground_level, z, Some(&[surface]), None);
+        if !is_stall_corner || !rng.gen_bool(OCCUPANCY) {
+            continue;
+        }
+
+        // Rows facing the aisle from the far side start one block later
+        let offset: i32 = if depth.rem_euclid(period) == 0 { 0 } else { 1 };
+        let car: Vec<(i32, i32)> = (0..STALL_WIDTH - 1)
+            .flat_map(|da: i32| (0..STALL_DEPTH - 1).map(move |dd: i32| (da, dd)))
+            .map(|(da, dd)| to_world(along + da, depth + offset + dd))
+            .collect();
+        if !car.iter().all(|position| inside.contains(position)) {
+            continue;
+        }
+
+        let color: Block = car_colors[rng.gen_range(0..car_colors.len())];
+        for (index, &(cx, cz)) in car.iter().enumerate() {
+            editor.set_block(color, cx, ground_level + 1, cz, None, None);
+            // Cabin with windows over the middle of the car
+            let row: i32 = index as i32 % (STALL_DEPTH - 1);
+            if row == 1 || row == 2 {
+                editor.set_block(GLASS, cx, ground_level + 2, cz, None, None);
+            }
+        }
+    }
+}
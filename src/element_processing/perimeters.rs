@@ -0,0 +1,150 @@
+use crate::block_definitions::*;
+use crate::bresenham::bresenham_line;
+use crate::floodfill::{flood_fill_area, FillOptions};
+use crate::osm_parser::ProcessedNode;
+use crate::world_editor::WorldEditor;
+use std::collections::HashSet;
+
+/// Height of the outer perimeter wall
+const OUTER_WALL_HEIGHT: i32 = 6;
+/// Height of the inner perimeter wall
+const INNER_WALL_HEIGHT: i32 = 4;
+/// Distance between the outer and the inner wall
+const WALL_SPACING: i32 = 3;
+/// Minimum distance between two watchtowers
+const WATCHTOWER_SPACING: i32 = 24;
+
+/// Generates a secured perimeter along the outline of a prison or a sensitive industrial
+/// site: a high outer wall and a lower inner wall, both topped with iron bars as razor
+/// wire. Prisons also get watchtowers at the corners of the outline.
+pub fn generate_secure_perimeter(
+    editor: &mut WorldEditor,
+    nodes: &[ProcessedNode],
+    ground_level: i32,
+    fill_options: &FillOptions,
+    wall_block: Block,
+    watchtowers: bool,
+) {
+    if nodes.len() < 3 {
+        return;
+    }
+
+    let polygon_coords: Vec<(i32, i32)> =
+        nodes.iter().map(|n: &ProcessedNode| (n.x, n.z)).collect();
+    let area: HashSet<(i32, i32)> = flood_fill_area(&polygon_coords, fill_options)
+        .into_iter()
+        .collect();
+
+    // Outer wall along the outline
+    let mut outline: HashSet<(i32, i32)> = HashSet::new();
+    for span in nodes.windows(2) {
+        for (bx, _, bz) in bresenham_line(
+            span[0].x,
+            ground_level,
+            span[0].z,
+            span[1].x,
+            ground_level,
+            span[1].z,
+        ) {
+            outline.insert((bx, bz));
+        }
+    }
+    for &(x, z) in &outline {
+        build_wall(editor, x, z, ground_level, OUTER_WALL_HEIGHT, wall_block);
+    }
+
+    // Inner wall on the cells at exactly the wall spacing from the outside
+    let is_inside =
+        |x: i32, z: i32| -> bool { area.contains(&(x, z)) || outline.contains(&(x, z)) };
+    let keeps_distance = |x: i32, z: i32, distance: i32| -> bool {
+        (-distance..=distance)
+            .all(|dx: i32| (-distance..=distance).all(|dz: i32| is_inside(x + dx, z + dz)))
+    };
+    for &(x, z) in &area {
+        if keeps_distance(x, z, WALL_SPACING - 1) && !keeps_distance(x, z, WALL_SPACING) {
+            build_wall(editor, x, z, ground_level, INNER_WALL_HEIGHT, wall_block);
+        }
+    }
+
+    if !watchtowers {
+        return;
+    }
+
+    // Watchtowers at the corners, skipping corners close to an existing tower
+    let mut towers: Vec<(i32, i32)> = vec![];
+    for node in nodes {
+        let too_close: bool = towers
+            .iter()
+            .any(|&(tx, tz)| (tx - node.x).abs().max((tz - node.z).abs()) < WATCHTOWER_SPACING);
+        if !too_close {
+            generate_watchtower(editor, node.x, node.z, ground_level, wall_block);
+            towers.push((node.x, node.z));
+        }
+    }
+}
+
+fn build_wall(
+    editor: &mut WorldEditor,
+    x: i32,
+    z: i32,
+    ground_level: i32,
+    height: i32,
+    block: Block,
+) {
+    for y in 1..=height {
+        editor.set_block(block, x, ground_level + y, z, None, None);
+    }
+    editor.set_block(IRON_BARS, x, ground_level + height + 1, z, None, None);
+}
+
+/// Builds a watchtower with a shaft, an enclosed lookout platform, a flat roof and a light.
+fn generate_watchtower(
+    editor: &mut WorldEditor,
+    x: i32,
+    z: i32,
+    ground_level: i32,
+    wall_block: Block,
+) {
+    let platform_y: i32 = ground_level + OUTER_WALL_HEIGHT + 4;
+
+    // The shaft replaces the perimeter wall and its razor wire
+    for dx in -1..=1 {
+        for dz in -1..=1 {
+            for y in (ground_level + 1)..platform_y {
+                editor.set_block(wall_block, x + dx, y, z + dz, Some(&[IRON_BARS]), None);
+            }
+        }
+    }
+
+    for dx in -2..=2 {
+        for dz in -2..=2 {
+            let is_edge: bool = dx == -2 || dx == 2 || dz == -2 || dz == 2;
+            editor.set_block(
+                wall_block,
+                x + dx,
+                platform_y,
+                z + dz,
+                Some(&[IRON_BARS]),
+                None,
+            );
+            if is_edge {
+                // Corner posts carry the roof, the sides are windows
+                let is_corner: bool = dx.abs() == 2 && dz.abs() == 2;
+                for y in (platform_y + 1)..=(platform_y + 2) {
+                    let block: Block = if is_corner { wall_block } else { GLASS };
+                    editor.set_block(block, x + dx, y, z + dz, None, None);
+                }
+            }
+            editor.set_block(STONE_BRICK_SLAB, x + dx, platform_y + 3, z + dz, None, None);
+        }
+    }
+
+    editor.set_block(
+        GLOWSTONE,
+        x,
+        platform_y + 3,
+        z,
+        Some(&[STONE_BRICK_SLAB]),
+        None,
+    );
+}
@@ -0,0 +1,209 @@
+use crate::args::Args;
+use crate::block_definitions::*;
+use crate::bresenham::{bresenham_line, thick_polyline};
+use crate::floodfill::flood_fill_area;
+use crate::osm_parser::ProcessedWay;
+use crate::random::seeded_rng;
+use crate::world_editor::WorldEditor;
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+
+/// Width of piers mapped as lines without a width tag
+const PIER_WIDTH: i32 = 3;
+/// Distance between two pilings along the edge of a pier
+const PILING_SPACING: i32 = 4;
+/// Deepest a piling reaches into the water
+const MAX_PILING_DEPTH: i32 = 12;
+/// Distance between the main docks of a marina
+const DOCK_SPACING: i32 = 16;
+/// Distance between the finger piers branching off a main dock
+const BERTH_WIDTH: i32 = 5;
+/// Length of the finger piers
+const FINGER_LENGTH: i32 = 6;
+/// Share of the berths with a moored boat
+const OCCUPANCY: f64 = 0.6;
+
+/// Builds a pier as a wooden deck one block above the water, resting on log pilings that
+/// reach down to the bottom. Piers mapped as areas are filled, piers mapped as lines get
+/// their width from the width tag and a railing on both sides.
+pub fn generate_pier(
+    editor: &mut WorldEditor,
+    element: &ProcessedWay,
+    ground_level: i32,
+    args: &Args,
+) {
+    let deck_y: i32 = ground_level + 1;
+    let is_area: bool = element.nodes.len() > 2
+        && (element.nodes.first().map(|n| (n.x, n.z)) == element.nodes.last().map(|n| (n.x, n.z))
+            || element.tags.get("area") == Some(&"yes".to_string()));
+
+    let deck: HashSet<(i32, i32)> = if is_area {
+        let polygon_coords: Vec<(i32, i32)> = element.nodes.iter().map(|n| (n.x, n.z)).collect();
+        flood_fill_area(&polygon_coords, &args.fill_options())
+            .into_iter()
+            .chain(outline(element, ground_level))
+            .collect()
+    } else {
+        let radius: i32 = element
+            .tags
+            .get("width")
+            .and_then(|width: &String| width.trim_end_matches('m').trim().parse::<f64>().ok())
+            .map(|width: f64| (width.round() as i32).clamp(1, 15))
+            .unwrap_or(PIER_WIDTH)
+            / 2;
+        let node_points: Vec<(i32, i32)> = element.nodes.iter().map(|n| (n.x, n.z)).collect();
+        thick_polyline(&node_points, f64::from(radius) + 0.5)
+            .into_iter()
+            .map(|(x, z, _)| (x, z))
+            .collect()
+    };
+
+    for &(x, z) in &deck {
+        editor.set_block(OAK_PLANKS, x, deck_y, z, None, None);
+    }
+
+    // Pilings and railings along the edges of the deck
+    for &(x, z) in &deck {
+        let is_edge: bool = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .iter()
+            .any(|(dx, dz)| !deck.contains(&(x + dx, z + dz)));
+        if !is_edge {
+            continue;
+        }
+        if (x + z).rem_euclid(PILING_SPACING) == 0 {
+            place_piling(editor, x, z, ground_level);
+        }
+        if !is_area {
+            editor.set_block(OAK_FENCE, x, deck_y + 1, z, None, None);
+        }
+    }
+}
+
+/// Lays out docks over the water of a marina: main docks running north to south with
+/// finger piers on both sides, and boats moored in some of the berths in between.
+pub fn generate_marina(
+    editor: &mut WorldEditor,
+    area: &[(i32, i32)],
+    ground_level: i32,
+    rowing_boats: bool,
+) {
+    let deck_y: i32 = ground_level + 1;
+    let water: HashSet<(i32, i32)> = area
+        .iter()
+        .copied()
+        .filter(|&(x, z)| editor.check_for_block(x, ground_level, z, Some(&[WATER]), None))
+        .collect();
+    let mut rng: StdRng = seeded_rng(editor.seed(), area);
+
+    for &(x, z) in &water {
+        let local_x: i32 = x.rem_euclid(DOCK_SPACING);
+        let distance_to_dock: i32 = local_x.min(DOCK_SPACING - local_x);
+        let local_z: i32 = z.rem_euclid(BERTH_WIDTH);
+
+        if local_x <= 1 {
+            // Main dock, two blocks wide
+            editor.set_block(OAK_PLANKS, x, deck_y, z, None, None);
+            if local_x == 0 && z.rem_euclid(PILING_SPACING) == 0 {
+                place_piling(editor, x, z, ground_level);
+            }
+        } else if local_z == 0 && distance_to_dock <= FINGER_LENGTH {
+            editor.set_block(OAK_SLAB, x, deck_y, z, None, None);
+        } else if local_z == 1 && matches!(local_x, 3 | 10) {
+            // Boats fill the berths on both sides of the dock, bow towards it
+            let towards_dock: i32 = if local_x == 3 { -1 } else { 1 };
+            let fits: bool = (0..4).all(|length: i32| {
+                (0..2).all(|width: i32| water.contains(&(x - towards_dock * length, z + width)))
+            });
+            if !fits {
+                continue;
+            }
+            if rng.gen_bool(OCCUPANCY) {
+                place_boat(editor, x, z, -towards_dock, ground_level, &mut rng);
+            } else if rowing_boats {
+                // Empty berths keep a rowing boat that can be used to explore the water
+                let yaw: f32 = if towards_dock < 0 { 90.0 } else { 270.0 };
+                editor.add_entity(
+                    "minecraft:oak_boat",
+                    x,
+                    ground_level + 1,
+                    z,
+                    yaw,
+                    HashMap::new(),
+                );
+            }
+        }
+    }
+}
+
+/// Places a small boat of four by two blocks floating on the water, starting at the given
+/// position and extending in the given x direction. Some boats get a mast.
+fn place_boat(
+    editor: &mut WorldEditor,
+    x: i32,
+    z: i32,
+    direction: i32,
+    ground_level: i32,
+    rng: &mut impl Rng,
+) {
+    let hull_block: Block = if rng.gen_bool(0.5) {
+        WHITE_CONCRETE
+    } else {
+        SPRUCE_PLANKS
+    };
+    for length in 0..4 {
+        for width in 0..2 {
+            let (bx, bz) = (x + direction * length, z + width);
+            editor.set_block(hull_block, bx, ground_level, bz, Some(&[WATER]), None);
+            // Gunwales along the sides, leaving the deck open
+            if length == 0 || length == 3 {
+                editor.set_block(OAK_SLAB, bx, ground_level + 1, bz, None, None);
+            }
+        }
+    }
+
+    if rng.gen_bool(0.4) {
+        let mast_x: i32 = x + direction;
+        for y in 1..=5 {
+            editor.set_block(OAK_FENCE, mast_x, ground_level + y, z, None, None);
+        }
+        for y in 2..=4 {
+            editor.set_block(
+                WHITE_WOOL,
+                mast_x + direction,
+                ground_level + y,
+                z,
+                None,
+                None,
+            );
+        }
+    }
+}
+
+/// Places a log piling below the deck, replacing the water down to the bottom
+fn place_piling(editor: &mut WorldEditor, x: i32, z: i32, ground_level: i32) {
+    for depth in 0..MAX_PILING_DEPTH {
+        let y: i32 = ground_level - depth;
+        let in_water: bool = editor.check_for_block(x, y, z, Some(&[WATER]), None);
+        if depth > 0 && !in_water {
+            break;
+        }
+        editor.set_block(SPRUCE_LOG, x, y, z, Some(&[WATER]), None);
+    }
+}
+
+/// Returns the blocks along the nodes of a way
+fn outline(element: &ProcessedWay, ground_level: i32) -> impl Iterator<Item = (i32, i32)> + '_ {
+    element.nodes.windows(2).flat_map(move |span| {
+        bresenham_line(
+            span[0].x,
+            ground_level,
+            span[0].z,
+            span[1].x,
+            ground_level,
+            span[1].z,
+        )
+        .into_iter()
+        .map(|(x, _, z)| (x, z))
+    })
+}
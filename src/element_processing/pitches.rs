@@ -0,0 +1,182 @@
+use crate::block_definitions::*;
+use crate::osm_parser::ProcessedWay;
+use crate::world_editor::WorldEditor;
+use std::collections::HashSet;
+
+/// Surface block of a pitch, from its surface tag or the typical surface of the sport
+pub fn pitch_surface(element: &ProcessedWay) -> Block {
+    let by_surface: Option<Block> =
+        element
+            .tags
+            .get("surface")
+            .and_then(|surface: &String| match surface.as_str() {
+                "clay" => Some(ORANGE_TERRACOTTA),
+                "sand" => Some(SAND),
+                "tartan" => Some(RED_TERRACOTTA),
+                "asphalt" | "concrete" => Some(GRAY_CONCRETE),
+                "grass" | "artificial_turf" => Some(GREEN_STAINED_HARDENED_CLAY),
+                _ => None,
+            });
+
+    by_surface.unwrap_or(match element.tags.get("sport").map(|s| s.as_str()) {
+        Some("tennis") => ORANGE_TERRACOTTA,
+        Some("basketball") => GRAY_CONCRETE,
+        _ => GREEN_STAINED_HARDENED_CLAY,
+    })
+}
+
+/// Draws the line markings of a pitch and places goals, hoops or a net depending on the
+/// sport. The markings follow the longest side of the outline, so rotated pitches work too.
+pub fn generate_pitch_markings(
+    editor: &mut WorldEditor,
+    element: &ProcessedWay,
+    area: &[(i32, i32)],
+    surface: Block,
+    ground_level: i32,
+) {
+    if area.is_empty() {
+        return;
+    }
+    let sport: &str = element.tags.get("sport").map_or("", |s| s.as_str());
+
+    // Direction of the longest side, which the pitch is oriented along
+    let Some((dir_x, dir_z)) = element
+        .nodes
+        .windows(2)
+        .map(|pair| {
+            (
+                f64::from(pair[1].x - pair[0].x),
+                f64::from(pair[1].z - pair[0].z),
+            )
+        })
+        .max_by(|a, b| (a.0.hypot(a.1)).total_cmp(&b.0.hypot(b.1)))
+    else {
+        return;
+    };
+    let length: f64 = dir_x.hypot(dir_z);
+    if length == 0.0 {
+        return;
+    }
+    let (ux, uz) = (dir_x / length, dir_z / length);
+
+    // Local coordinates along (u) and across (v) the pitch, relative to its center
+    let count: f64 = area.len() as f64;
+    let center_x: f64 = area.iter().map(|&(x, _)| f64::from(x)).sum::<f64>() / count;
+    let center_z: f64 = area.iter().map(|&(_, z)| f64::from(z)).sum::<f64>() / count;
+    let to_local = |x: i32, z: i32| -> (f64, f64) {
+        let (dx, dz) = (f64::from(x) - center_x, f64::from(z) - center_z);
+        (dx * ux + dz * uz, -dx * uz + dz * ux)
+    };
+    let to_world = |u: f64, v: f64| -> (i32, i32) {
+        (
+            (center_x + u * ux - v * uz).round() as i32,
+            (center_z + u * uz + v * ux).round() as i32,
+        )
+    };
+
+    let half_length: f64 = area
+        .iter()
+        .map(|&(x, z)| to_local(x, z).0.abs())
+        .fold(0.0, f64::max);
+    let half_width: f64 = area
+        .iter()
+        .map(|&(x, z)| to_local(x, z).1.abs())
+        .fold(0.0, f64::max);
+    if half_length < 4.0 || half_width < 2.0 {
+        return;
+    }
+
+    let inside: HashSet<(i32, i32)> = area.iter().copied().collect();
+    let circle_radius: f64 = (half_width * 0.3).max(2.0);
+    for &(x, z) in area {
+        let (u, v) = to_local(x, z);
+        let on_border: bool = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .iter()
+            .any(|(dx, dz)| !inside.contains(&(x + dx, z + dz)));
+        let on_halfway_line: bool = u.abs() < 0.5;
+        let on_center_circle: bool =
+            matches!(sport, "soccer" | "basketball") && (u.hypot(v) - circle_radius).abs() < 0.5;
+
+        if on_border || on_halfway_line || on_center_circle {
+            editor.set_block(WHITE_CONCRETE, x, ground_level, z, Some(&[surface]), None);
+        }
+    }
+
+    match sport {
+        "soccer" => {
+            // Goals at both ends, a frame of two posts and a crossbar
+            let goal_half_width: f64 = (half_width * 0.2).clamp(1.0, 4.0);
+            for end in [-1.0, 1.0] {
+                let u: f64 = end * (half_length - 1.0);
+                let steps: i32 = (goal_half_width * 2.0).round() as i32;
+                for step in 0..=steps {
+                    let v: f64 = -goal_half_width + f64::from(step);
+                    let (x, z) = to_world(u, v);
+                    if step == 0 || step == steps {
+                        editor.fill_blocks(
+                            IRON_BARS,
+                            x,
+                            ground_level + 1,
+                            z,
+                            x,
+                            ground_level + 2,
+                            z,
+                            None,
+                            None,
+                        );
+                    }
+                    editor.set_block(IRON_BARS, x, ground_level + 3, z, None, None);
+                }
+            }
+        }
+        "basketball" => {
+            // Hoops on posts at both ends, with the backboard facing the court
+            for end in [-1.0, 1.0] {
+                let (x, z) = to_world(end * (half_length - 1.0), 0.0);
+                editor.fill_blocks(
+                    IRON_BARS,
+                    x,
+                    ground_level + 1,
+                    z,
+                    x,
+                    ground_level + 3,
+                    z,
+                    None,
+                    None,
+                );
+                editor.set_block(WHITE_CONCRETE, x, ground_level + 4, z, None, None);
+                let (hoop_x, hoop_z) = to_world(end * (half_length - 2.0), 0.0);
+                editor.set_block(
+                    ORANGE_TERRACOTTA,
+                    hoop_x,
+                    ground_level + 4,
+                    hoop_z,
+                    None,
+                    None,
+                );
+            }
+        }
+        "tennis" | "volleyball" | "badminton" => {
+            // Net across the halfway line
+            let net_height: i32 = if sport == "tennis" { 1 } else { 2 };
+            let steps: i32 = (half_width * 2.0).round() as i32;
+            for step in 0..=steps {
+                let (x, z) = to_world(0.0, -half_width + f64::from(step));
+                if inside.contains(&(x, z)) {
+                    editor.fill_blocks(
+                        IRON_BARS,
+                        x,
+                        ground_level + 1,
+                        z,
+                        x,
+                        ground_level + net_height,
+                        z,
+                        None,
+                        None,
+                    );
+                }
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,236 @@
+use crate::args::Args;
+use crate::block_definitions::*;
+use crate::bresenham::bresenham_line;
+use crate::floodfill::flood_fill_area;
+use crate::osm_parser::{ProcessedElement, ProcessedNode, ProcessedWay};
+use crate::world_editor::WorldEditor;
+use std::collections::HashMap;
+
+/// Height of transmission towers carrying power lines
+const TOWER_HEIGHT: i32 = 20;
+/// Height of wooden poles carrying minor power lines
+const POLE_HEIGHT: i32 = 8;
+/// Distance of the outer cables from the tower center
+const CROSSARM_LENGTH: i32 = 3;
+/// Hub height of wind turbines without a height:hub tag
+const TURBINE_HUB_HEIGHT: i32 = 40;
+/// Distance between the rows of solar panels, leaving room to walk between them
+const PANEL_ROW_SPACING: i32 = 4;
+
+pub fn generate_power(
+    editor: &mut WorldEditor,
+    element: &ProcessedElement,
+    ground_level: i32,
+    args: &Args,
+) {
+    let Some(power_type) = element.tags().get("power") else {
+        return;
+    };
+    let source: Option<&str> = element
+        .tags()
+        .get("generator:source")
+        .or_else(|| element.tags().get("plant:source"))
+        .map(|s| s.as_str());
+
+    match element {
+        ProcessedElement::Node(node) => match (power_type.as_str(), source) {
+            ("tower", _) => generate_tower(editor, node.x, node.z, ground_level),
+            ("pole", _) => generate_pole(editor, node.x, node.z, ground_level),
+            ("generator", Some("wind")) => {
+                generate_wind_turbine(editor, &node.tags, node.x, node.z, ground_level)
+            }
+            _ => {}
+        },
+        ProcessedElement::Way(way) => match (power_type.as_str(), source) {
+            ("line", _) => generate_cables(editor, &way.nodes, ground_level + TOWER_HEIGHT, true),
+            ("minor_line", _) => {
+                generate_cables(editor, &way.nodes, ground_level + POLE_HEIGHT, false)
+            }
+            ("plant" | "generator", Some("solar")) => {
+                generate_solar_panels(editor, way, ground_level, args)
+            }
+            _ => {}
+        },
+        ProcessedElement::Relation(_) => {}
+    }
+}
+
+/// Builds a wind turbine with a tower, a nacelle and three rotor blades. The rotor faces
+/// north and has one blade pointing up. Hub height and rotor size follow the height:hub
+/// and rotor:diameter tags.
+fn generate_wind_turbine(
+    editor: &mut WorldEditor,
+    tags: &HashMap<String, String>,
+    x: i32,
+    z: i32,
+    ground_level: i32,
+) {
+    let meters = |key: &str| -> Option<f64> {
+        tags.get(key)
+            .and_then(|value: &String| value.trim_end_matches('m').trim().parse::<f64>().ok())
+    };
+    let hub_height: i32 = meters("height:hub")
+        .map(|height: f64| height.round() as i32)
+        .unwrap_or(TURBINE_HUB_HEIGHT)
+        .clamp(20, 120);
+    let blade_length: i32 = meters("rotor:diameter")
+        .map(|diameter: f64| (diameter / 2.0).round() as i32)
+        .unwrap_or(hub_height * 2 / 5)
+        .clamp(6, hub_height - 4);
+
+    // Tower
+    for h in 1..hub_height {
+        editor.set_block(SMOOTH_QUARTZ, x, ground_level + h, z, None, None);
+    }
+
+    // Nacelle on top of the tower, with the hub at its northern end
+    let hub_y: i32 = ground_level + hub_height;
+    for dz in -1..=2 {
+        editor.set_block(WHITE_CONCRETE, x, hub_y, z + dz, None, None);
+    }
+    let hub_z: i32 = z - 2;
+    editor.set_block(IRON_BLOCK, x, hub_y, hub_z, None, None);
+
+    // Blades in the vertical plane in front of the hub, 120 degrees apart
+    for angle in [90.0_f64, 210.0, 330.0] {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        for step in 1..=blade_length {
+            let bx: i32 = x + (cos * f64::from(step)).round() as i32;
+            let by: i32 = hub_y + (sin * f64::from(step)).round() as i32;
+            editor.set_block(WHITE_CONCRETE, bx, by, hub_z, None, None);
+        }
+    }
+}
+
+/// Covers a solar farm with rows of solar panels running east to west. The panels are
+/// tilted towards the south, standing on posts at their northern edge.
+fn generate_solar_panels(
+    editor: &mut WorldEditor,
+    way: &ProcessedWay,
+    ground_level: i32,
+    args: &Args,
+) {
+    let polygon_coords: Vec<(i32, i32)> = way.nodes.iter().map(|n| (n.x, n.z)).collect();
+    let area: Vec<(i32, i32)> = flood_fill_area(&polygon_coords, &args.fill_options());
+
+    for (x, z) in area {
+        match z.rem_euclid(PANEL_ROW_SPACING) {
+            0 => {
+                editor.set_block(IRON_BARS, x, ground_level + 1, z, None, None);
+                editor.set_block(DAYLIGHT_DETECTOR, x, ground_level + 2, z, None, None);
+            }
+            1 => {
+                editor.set_block(DAYLIGHT_DETECTOR, x, ground_level + 1, z, None, None);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Builds a lattice transmission tower that narrows towards the top, with crossarms
+/// in both directions, so cables can attach regardless of the line direction.
+fn generate_tower(editor: &mut WorldEditor, x: i32, z: i32, ground_level: i32) {
+    for h in 1..=TOWER_HEIGHT {
+        // Legs step inwards every third of the height
+        let spread: i32 = 2 - (h - 1) * 3 / TOWER_HEIGHT;
+        for (dx, dz) in [(-1, -1), (1, -1), (1, 1), (-1, 1)] {
+            editor.set_block(
+                IRON_BARS,
+                x + dx * spread,
+                ground_level + h,
+                z + dz * spread,
+                None,
+                None,
+            );
+        }
+
+        // Horizontal bracing between the legs
+        if h % 6 == 0 && spread > 0 {
+            for offset in -spread..=spread {
+                for (bx, bz) in [
+                    (x + offset, z - spread),
+                    (x + offset, z + spread),
+                    (x - spread, z + offset),
+                    (x + spread, z + offset),
+                ] {
+                    editor.set_block(IRON_BARS, bx, ground_level + h, bz, None, None);
+                }
+            }
+        }
+    }
+
+    // Crossarms at the top
+    let top_y: i32 = ground_level + TOWER_HEIGHT;
+    for offset in -CROSSARM_LENGTH..=CROSSARM_LENGTH {
+        editor.set_block(IRON_BLOCK, x + offset, top_y, z, None, None);
+        editor.set_block(IRON_BLOCK, x, top_y, z + offset, None, None);
+    }
+}
+
+/// Builds a wooden utility pole with a short crossarm.
+fn generate_pole(editor: &mut WorldEditor, x: i32, z: i32, ground_level: i32) {
+    for h in 1..=POLE_HEIGHT {
+        editor.set_block(SPRUCE_LOG, x, ground_level + h, z, None, None);
+    }
+    for offset in [-1, 1] {
+        editor.set_block(
+            OAK_FENCE,
+            x + offset,
+            ground_level + POLE_HEIGHT,
+            z,
+            None,
+            None,
+        );
+        editor.set_block(
+            OAK_FENCE,
+            x,
+            ground_level + POLE_HEIGHT,
+            z + offset,
+            None,
+            None,
+        );
+    }
+}
+
+/// Strings cables between consecutive nodes of a power line. The cables sag towards the
+/// middle of each span. Lines on towers carry three cables, lines on poles a single one.
+fn generate_cables(
+    editor: &mut WorldEditor,
+    nodes: &[ProcessedNode],
+    attachment_y: i32,
+    on_towers: bool,
+) {
+    for span in nodes.windows(2) {
+        let (x1, z1) = (span[0].x, span[0].z);
+        let (x2, z2) = (span[1].x, span[1].z);
+
+        // Outer cables are offset perpendicular to the span, along the crossarm
+        let spreads_along_z: bool = (x2 - x1).abs() >= (z2 - z1).abs();
+        let offsets: &[i32] = if on_towers {
+            &[-CROSSARM_LENGTH, 0, CROSSARM_LENGTH]
+        } else {
+            &[0]
+        };
+
+        let points: Vec<(i32, i32, i32)> =
+            bresenham_line(x1, attachment_y, z1, x2, attachment_y, z2);
+        let span_length: i32 = points.len() as i32;
+        let sag: f64 = f64::from((span_length / 20).min(4));
+
+        for (i, (bx, _, bz)) in points.into_iter().enumerate() {
+            // Parabolic sag, zero at both ends of the span
+            let t: f64 = i as f64 / f64::from((span_length - 1).max(1));
+            let y: i32 = attachment_y - (sag * 4.0 * t * (1.0 - t)).round() as i32;
+
+            for &offset in offsets {
+                let (cx, cz) = if spreads_along_z {
+                    (bx, bz + offset)
+                } else {
+                    (bx + offset, bz)
+                };
+                // The cable hangs below the crossarm at the attachment points
+                editor.set_block(IRON_BARS, cx, y - 1, cz, None, None);
+            }
+        }
+    }
+}
@@ -1,8 +1,15 @@
 use crate::block_definitions::*;
-use crate::bresenham::bresenham_line;
-use crate::osm_parser::ProcessedWay;
+use crate::bresenham::{bresenham_line, smooth_polyline};
+use crate::osm_parser::{ProcessedNode, ProcessedWay};
 use crate::world_editor::WorldEditor;
 
+/// Distance between the center lines of parallel tracks
+const TRACK_SPACING: i32 = 3;
+/// Distance between two catenary masts along the track
+const CATENARY_SPACING: usize = 16;
+/// Height of the contact wire above the ground
+const CONTACT_WIRE_HEIGHT: i32 = 6;
+
 pub fn generate_railways(editor: &mut WorldEditor, element: &ProcessedWay, ground_level: i32) {
     if let Some(railway_type) = element.tags.get("railway") {
         if ["proposed", "abandoned", "subway", "construction"].contains(&railway_type.as_str()) {
@@ -21,28 +28,185 @@ pub fn generate_railways(editor: &mut WorldEditor, element: &ProcessedWay, groun
             }
         }
 
-        for i in 1..element.nodes.len() {
-            let prev: &crate::osm_parser::ProcessedNode = &element.nodes[i - 1];
-            let x1: i32 = prev.x;
-            let z1: i32 = prev.z;
+        // Number of parallel tracks, centered on the mapped way
+        let track_count: i32 = element
+            .tags
+            .get("tracks")
+            .and_then(|tracks: &String| tracks.parse::<i32>().ok())
+            .unwrap_or(1)
+            .clamp(1, 6);
+        let node_points: Vec<(i32, i32)> = element
+            .nodes
+            .iter()
+            .map(|node: &ProcessedNode| (node.x, node.z))
+            .collect();
+        let center_line: Vec<(i32, i32)> = smooth_polyline(&node_points);
+        let tracks: Vec<Vec<(i32, i32)>> = (0..track_count)
+            .map(|track: i32| {
+                let offset: f64 = f64::from(track) * f64::from(TRACK_SPACING)
+                    - f64::from((track_count - 1) * TRACK_SPACING) / 2.0;
+                track_path(&center_line, offset, ground_level)
+            })
+            .collect();
+
+        // Gravel ballast below all tracks
+        for path in &tracks {
+            for &(x, z) in path {
+                for dx in -1..=1 {
+                    for dz in -1..=1 {
+                        editor.set_block(GRAVEL, x + dx, ground_level, z + dz, None, None);
+                    }
+                }
+            }
+        }
+
+        // Sleepers across the ballast on every other block, then the rails on top
+        for path in &tracks {
+            for (i, &(x, z)) in path.iter().enumerate() {
+                let (along_x, _) = direction_at(path, i);
+                if i % 2 == 0 {
+                    for offset in -1..=1 {
+                        let (sx, sz) = if along_x {
+                            (x, z + offset)
+                        } else {
+                            (x + offset, z)
+                        };
+                        editor.set_block(
+                            SPRUCE_PLANKS,
+                            sx,
+                            ground_level,
+                            sz,
+                            Some(&[GRAVEL]),
+                            None,
+                        );
+                    }
+                }
 
-            let cur: &crate::osm_parser::ProcessedNode = &element.nodes[i];
-            let x2: i32 = cur.x;
-            let z2: i32 = cur.z;
+                editor.set_block(rail_shape(path, i), x, ground_level + 1, z, None, None);
+            }
+        }
 
-            // Generate the line of coordinates between the two nodes
-            let bresenham_points: Vec<(i32, i32, i32)> =
-                bresenham_line(x1, ground_level, z1, x2, ground_level, z2);
+        if element.tags.get("electrified") == Some(&"contact_line".to_string()) {
+            generate_catenary(editor, &tracks, ground_level);
+        }
+    }
+}
 
-            for (bx, _, bz) in bresenham_points {
-                // TODO: Set direction of rail
-                editor.set_block(IRON_BLOCK, bx, ground_level, bz, None, None);
-                editor.set_block(RAIL, bx, ground_level + 1, bz, None, None);
+/// Builds a 4-connected path of rail positions along the center line, shifted sideways by
+/// the given offset. Diagonal steps get an extra corner block, as rails only connect to
+/// their direct neighbors.
+fn track_path(points: &[(i32, i32)], offset: f64, ground_level: i32) -> Vec<(i32, i32)> {
+    let shifted: Vec<(i32, i32)> = (0..points.len())
+        .map(|i: usize| {
+            let (prev_x, prev_z) = points[i.saturating_sub(1)];
+            let (next_x, next_z) = points[(i + 1).min(points.len() - 1)];
+            let (dx, dz) = (f64::from(next_x - prev_x), f64::from(next_z - prev_z));
+            let length: f64 = (dx * dx + dz * dz).sqrt();
+            let (x, z) = points[i];
+            if length == 0.0 {
+                return (x, z);
+            }
+            (
+                x + (-dz / length * offset).round() as i32,
+                z + (dx / length * offset).round() as i32,
+            )
+        })
+        .collect();
 
-                if bx % 4 == 0 {
-                    editor.set_block(OAK_LOG, bx, ground_level, bz, None, None);
+    let mut path: Vec<(i32, i32)> = vec![];
+    for span in shifted.windows(2) {
+        let (x1, z1) = span[0];
+        let (x2, z2) = span[1];
+        for (bx, _, bz) in bresenham_line(x1, ground_level, z1, x2, ground_level, z2) {
+            if let Some(&(px, pz)) = path.last() {
+                if (px, pz) == (bx, bz) {
+                    continue;
+                }
+                if px != bx && pz != bz {
+                    path.push((bx, pz));
                 }
             }
+            path.push((bx, bz));
+        }
+    }
+    path
+}
+
+/// Returns whether the path runs along the x axis at the given index, and the
+/// directions (dx, dz) towards the previous and next position
+fn direction_at(path: &[(i32, i32)], i: usize) -> (bool, [(i32, i32); 2]) {
+    let (x, z) = path[i];
+    let neighbors: Vec<(i32, i32)> = [i.checked_sub(1), Some(i + 1)]
+        .into_iter()
+        .flatten()
+        .filter_map(|j: usize| path.get(j))
+        .map(|&(nx, nz)| (nx - x, nz - z))
+        .collect();
+
+    let directions: [(i32, i32); 2] = match neighbors.as_slice() {
+        [a, b] => [*a, *b],
+        // Track ends continue straight
+        [a] => [*a, (-a.0, -a.1)],
+        _ => [(0, -1), (0, 1)],
+    };
+    (directions[0].1 == 0, directions)
+}
+
+/// Picks the rail shape connecting the previous and the next position
+fn rail_shape(path: &[(i32, i32)], i: usize) -> Block {
+    let (_, directions) = direction_at(path, i);
+    let north: bool = directions.contains(&(0, -1));
+    let south: bool = directions.contains(&(0, 1));
+    let east: bool = directions.contains(&(1, 0));
+    let west: bool = directions.contains(&(-1, 0));
+
+    match (north, south, east, west) {
+        (true, false, true, false) => RAIL_NORTH_EAST,
+        (true, false, false, true) => RAIL_NORTH_WEST,
+        (false, true, true, false) => RAIL_SOUTH_EAST,
+        (false, true, false, true) => RAIL_SOUTH_WEST,
+        (false, false, _, _) => RAIL_EAST_WEST,
+        _ => RAIL,
+    }
+}
+
+/// Places catenary masts beside the first track with arms reaching over all tracks, and
+/// a contact wire above every track.
+fn generate_catenary(editor: &mut WorldEditor, tracks: &[Vec<(i32, i32)>], ground_level: i32) {
+    let wire_y: i32 = ground_level + CONTACT_WIRE_HEIGHT;
+    for path in tracks {
+        for &(x, z) in path {
+            editor.set_block(IRON_BARS, x, wire_y, z, None, None);
+        }
+    }
+
+    let Some(first_track) = tracks.first() else {
+        return;
+    };
+    let arm_length: i32 = 2 + (tracks.len() as i32 - 1) * TRACK_SPACING;
+    for (i, &(x, z)) in first_track.iter().enumerate() {
+        if i % CATENARY_SPACING != CATENARY_SPACING / 2 {
+            continue;
+        }
+
+        // Masts stand on the side of the first track, which has the lowest offset
+        let (_, directions) = direction_at(first_track, i);
+        let forward: (i32, i32) = directions[1];
+        let side: (i32, i32) = (forward.1, -forward.0);
+
+        let (mast_x, mast_z) = (x + side.0 * 2, z + side.1 * 2);
+        for y in (ground_level + 1)..=wire_y {
+            editor.set_block(IRON_BLOCK, mast_x, y, mast_z, None, None);
+        }
+        for step in 1..=arm_length {
+            editor.set_block(
+                IRON_BARS,
+                mast_x - side.0 * step,
+                wire_y + 1,
+                mast_z - side.1 * step,
+                None,
+                None,
+            );
         }
     }
 }
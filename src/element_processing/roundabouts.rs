@@ -0,0 +1,246 @@
+use crate::args::Args;
+use crate::block_definitions::*;
+use crate::block_palette::{palette_block, BlockCategory};
+use crate::bresenham::{ellipse_outline, filled_circle};
+use crate::element_processing::highways::road_half_width;
+use crate::element_processing::tree::create_tree;
+use crate::osm_parser::{ProcessedElement, ProcessedNode, ProcessedWay};
+use crate::world_editor::WorldEditor;
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+
+/// Smallest radius of the circle a roundabout is drawn as, smaller ones are drawn as roads
+const MIN_RADIUS: f64 = 3.0;
+/// Largest radius of the circle a roundabout is drawn as, larger ones are usually mapped
+/// with an irregular shape and drawn as roads
+const MAX_RADIUS: f64 = 120.0;
+/// Smallest radius of the center island that gets a tree
+const TREE_ISLAND_RADIUS: i32 = 4;
+
+/// Circle fitted to the nodes of a roundabout
+struct Circle {
+    center_x: f64,
+    center_z: f64,
+    radius: f64,
+}
+
+impl Circle {
+    /// Fits a circle to a roundabout. Closed ways use the centroid and the mean distance of
+    /// their nodes, roundabouts split into several ways use the circle through the first,
+    /// middle and last node of their arc.
+    fn fit(nodes: &[ProcessedNode]) -> Option<Self> {
+        if nodes.len() < 3 {
+            return None;
+        }
+
+        let points: Vec<(f64, f64)> = nodes
+            .iter()
+            .map(|node: &ProcessedNode| (f64::from(node.x), f64::from(node.z)))
+            .collect();
+        let circle: Circle = if is_closed(nodes) {
+            let unique: &[(f64, f64)] = &points[..points.len() - 1];
+            let count: f64 = unique.len() as f64;
+            let center_x: f64 = unique.iter().map(|(x, _)| x).sum::<f64>() / count;
+            let center_z: f64 = unique.iter().map(|(_, z)| z).sum::<f64>() / count;
+            let radius: f64 = unique
+                .iter()
+                .map(|(x, z)| (x - center_x).hypot(z - center_z))
+                .sum::<f64>()
+                / count;
+            Circle {
+                center_x,
+                center_z,
+                radius,
+            }
+        } else {
+            circumcircle(
+                points[0],
+                points[points.len() / 2],
+                points[points.len() - 1],
+            )?
+        };
+
+        (MIN_RADIUS..=MAX_RADIUS)
+            .contains(&circle.radius)
+            .then_some(circle)
+    }
+
+    fn angle(&self, x: f64, z: f64) -> f64 {
+        (z - self.center_z).atan2(x - self.center_x)
+    }
+
+    /// Moves a position onto the circle, keeping its direction from the center
+    fn project(&self, x: i32, z: i32) -> (i32, i32) {
+        let angle: f64 = self.angle(f64::from(x), f64::from(z));
+        (
+            (self.center_x + self.radius * angle.cos()).round() as i32,
+            (self.center_z + self.radius * angle.sin()).round() as i32,
+        )
+    }
+}
+
+/// Returns whether a way is a roundabout that is drawn as a circle
+pub fn is_roundabout(way: &ProcessedWay) -> bool {
+    way.tags.get("junction").map(|s: &String| s.as_str()) == Some("roundabout")
+        && Circle::fit(&way.nodes).is_some()
+}
+
+/// Moves the ends of the roads joining a roundabout onto its circle, so the arms meet the
+/// ring instead of ending short of it or running into the center island.
+pub fn align_roundabout_arms(elements: &mut [ProcessedElement]) {
+    let mut ring_nodes: HashMap<u64, (i32, i32)> = HashMap::new();
+    for element in elements.iter() {
+        let ProcessedElement::Way(way) = element else {
+            continue;
+        };
+        if way.tags.get("junction").map(|s: &String| s.as_str()) != Some("roundabout") {
+            continue;
+        }
+        let Some(circle) = Circle::fit(&way.nodes) else {
+            continue;
+        };
+        for node in &way.nodes {
+            ring_nodes.insert(node.id, circle.project(node.x, node.z));
+        }
+    }
+    if ring_nodes.is_empty() {
+        return;
+    }
+
+    for element in elements.iter_mut() {
+        let ProcessedElement::Way(way) = element else {
+            continue;
+        };
+        let is_arm: bool = way.tags.contains_key("highway")
+            && way.tags.get("junction").map(|s: &String| s.as_str()) != Some("roundabout");
+        if !is_arm {
+            continue;
+        }
+        for node in &mut way.nodes {
+            if let Some(&(x, z)) = ring_nodes.get(&node.id) {
+                node.x = x;
+                node.z = z;
+            }
+        }
+    }
+}
+
+/// Draws a roundabout as a circular road around a center island with a curb, planted with
+/// grass and a tree or flowers. Roundabouts split into several ways draw their arc of the
+/// ring, each with the same island.
+pub fn generate_roundabout(
+    editor: &mut WorldEditor,
+    way: &ProcessedWay,
+    ground_level: i32,
+    args: &Args,
+) {
+    let Some(circle) = Circle::fit(&way.nodes) else {
+        return;
+    };
+    let highway_type: &str = way.tags.get("highway").map_or("", |s: &String| s.as_str());
+    let lanes: Option<i32> = way
+        .tags
+        .get("lanes")
+        .and_then(|lanes: &String| lanes.parse::<i32>().ok());
+    let half_width: f64 = f64::from(road_half_width(&way.tags, highway_type, lanes, args)) + 0.5;
+    let road_surface: Block = palette_block(BlockCategory::RoadSurface, BLACK_CONCRETE);
+
+    // Angles covered by the way, extended by the road width to close the gaps to the next arc
+    let arc: Option<(f64, f64)> = if is_closed(&way.nodes) {
+        None
+    } else {
+        let first: &ProcessedNode = &way.nodes[0];
+        let middle: &ProcessedNode = &way.nodes[way.nodes.len() / 2];
+        let last: &ProcessedNode = &way.nodes[way.nodes.len() - 1];
+        let [start, through, end] = [first, middle, last]
+            .map(|node: &ProcessedNode| circle.angle(f64::from(node.x), f64::from(node.z)));
+        let margin: f64 = half_width / circle.radius;
+        // Arcs run from the start angle counterclockwise, unless the middle lies the other way
+        let (from, span) = if ccw_delta(start, through) <= ccw_delta(start, end) {
+            (start, ccw_delta(start, end))
+        } else {
+            (end, ccw_delta(end, start))
+        };
+        Some((from - margin, span + 2.0 * margin))
+    };
+
+    let (center_x, center_z) = (
+        circle.center_x.round() as i32,
+        circle.center_z.round() as i32,
+    );
+    for (x, z) in filled_circle(center_x, center_z, circle.radius + half_width) {
+        let (fx, fz) = (f64::from(x), f64::from(z));
+        let distance: f64 = (fx - circle.center_x).hypot(fz - circle.center_z);
+        if (distance - circle.radius).abs() > half_width {
+            continue;
+        }
+        if let Some((from, span)) = arc {
+            if ccw_delta(from, circle.angle(fx, fz)) > span {
+                continue;
+            }
+        }
+        editor.set_block(
+            road_surface,
+            x,
+            ground_level,
+            z,
+            None,
+            Some(&[WHITE_CONCRETE, YELLOW_CONCRETE]),
+        );
+    }
+
+    // Center island inside the ring, replacing arms reaching into it
+    let island_radius: f64 = circle.radius - half_width - 0.5;
+    if island_radius < 1.0 {
+        return;
+    }
+    let island_block: Block = if args.winter { SNOW_BLOCK } else { GRASS_BLOCK };
+    for (x, z) in filled_circle(center_x, center_z, island_radius) {
+        editor.set_block(island_block, x, ground_level, z, None, None);
+    }
+    for (x, z) in ellipse_outline(center_x, center_z, island_radius, island_radius) {
+        editor.set_block(STONE_BRICK_SLAB, x, ground_level + 1, z, None, None);
+    }
+
+    if island_radius as i32 >= TREE_ISLAND_RADIUS {
+        create_tree(
+            editor,
+            center_x,
+            ground_level + 1,
+            center_z,
+            &way.tags,
+            args.winter,
+        );
+    } else if !args.winter {
+        editor.set_block(RED_FLOWER, center_x, ground_level + 1, center_z, None, None);
+    }
+}
+
+fn is_closed(nodes: &[ProcessedNode]) -> bool {
+    nodes.len() > 3 && nodes[0].id == nodes[nodes.len() - 1].id
+}
+
+/// Counterclockwise angle from one angle to another, between 0 and a full turn
+fn ccw_delta(from: f64, to: f64) -> f64 {
+    (to - from).rem_euclid(TAU)
+}
+
+/// Circle through three points, if they are not on a line
+fn circumcircle(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> Option<Circle> {
+    let d: f64 = 2.0 * (a.0 * (b.1 - c.1) + b.0 * (c.1 - a.1) + c.0 * (a.1 - b.1));
+    if d.abs() < f64::EPSILON {
+        return None;
+    }
+    let (a_sq, b_sq, c_sq) = (
+        a.0 * a.0 + a.1 * a.1,
+        b.0 * b.0 + b.1 * b.1,
+        c.0 * c.0 + c.1 * c.1,
+    );
+    let center_x: f64 = (a_sq * (b.1 - c.1) + b_sq * (c.1 - a.1) + c_sq * (a.1 - b.1)) / d;
+    let center_z: f64 = (a_sq * (c.0 - b.0) + b_sq * (a.0 - c.0) + c_sq * (b.0 - a.0)) / d;
+    Some(Circle {
+        center_x,
+        center_z,
+        radius: (a.0 - center_x).hypot(a.1 - center_z),
+    })
+}
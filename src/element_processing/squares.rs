@@ -0,0 +1,177 @@
+use crate::args::Args;
+use crate::block_definitions::*;
+use crate::bresenham::filled_circle;
+use crate::floodfill::flood_fill_area;
+use crate::osm_parser::ProcessedElement;
+use crate::world_editor::WorldEditor;
+use std::collections::HashSet;
+
+/// Generates public squares and pedestrian plazas with a paving pattern, a central
+/// feature and benches and planters along the edges.
+pub fn generate_squares(
+    editor: &mut WorldEditor,
+    element: &ProcessedElement,
+    ground_level: i32,
+    args: &Args,
+) {
+    // Skip if 'layer' or 'level' is negative in the tags
+    if let Some(layer) = element.tags().get("layer") {
+        if layer.parse::<i32>().unwrap_or(0) < 0 {
+            return;
+        }
+    }
+
+    let polygon_coords: Vec<(i32, i32)> = element
+        .nodes()
+        .map(|n: &crate::osm_parser::ProcessedNode| (n.x, n.z))
+        .collect();
+    if polygon_coords.is_empty() {
+        return;
+    }
+
+    // Mapped squares are filled, squares mapped as a node get a small round plaza
+    let square_area: Vec<(i32, i32)> = if let ProcessedElement::Way(_) = element {
+        flood_fill_area(&polygon_coords, &args.fill_options())
+    } else {
+        let (x, z) = polygon_coords[0];
+        filled_circle(x, z, 6.0)
+    };
+    if square_area.is_empty() {
+        return;
+    }
+
+    // Determine the paving from the 'surface' tag
+    let paving_block: Block = match element.tags().get("surface").map(|s: &String| s.as_str()) {
+        Some("bricks") => BRICK,
+        Some("wood") => OAK_PLANKS,
+        Some("asphalt") => BLACK_CONCRETE,
+        Some("concrete") => LIGHT_GRAY_CONCRETE,
+        Some("sand") => SAND,
+        _ => STONE_BRICKS,
+    };
+
+    let area_points: HashSet<(i32, i32)> = square_area.iter().copied().collect();
+    let is_edge = |x: i32, z: i32| -> bool {
+        !area_points.contains(&(x + 1, z))
+            || !area_points.contains(&(x - 1, z))
+            || !area_points.contains(&(x, z + 1))
+            || !area_points.contains(&(x, z - 1))
+    };
+
+    // Paving with a diagonal lattice pattern
+    for (x, z) in square_area.iter().copied() {
+        let paving: Block = if (x + z).rem_euclid(8) == 0 || (x - z).rem_euclid(8) == 0 {
+            POLISHED_ANDESITE
+        } else {
+            paving_block
+        };
+        editor.set_block(paving, x, ground_level, z, None, None);
+    }
+
+    // Benches and planters alternate along the edges
+    for (x, z) in square_area.iter().copied() {
+        if !is_edge(x, z) {
+            continue;
+        }
+
+        match (x + z).rem_euclid(12) {
+            0 => editor.set_block(SMOOTH_STONE, x, ground_level + 1, z, None, None),
+            6 => {
+                editor.set_block(MOSSY_COBBLESTONE, x, ground_level + 1, z, None, None);
+                let planter_top: Block = if args.winter { SNOW_LAYER } else { OAK_LEAVES };
+                editor.set_block(planter_top, x, ground_level + 2, z, None, None);
+            }
+            _ => {}
+        }
+    }
+
+    // Central feature in the middle of the square
+    let cell_count: i32 = square_area.len() as i32;
+    let center_x: i32 = square_area.iter().map(|(x, _)| x).sum::<i32>() / cell_count;
+    let center_z: i32 = square_area.iter().map(|(_, z)| z).sum::<i32>() / cell_count;
+
+    let tags = element.tags();
+    let has_fountain: bool =
+        tags.contains_key("fountain") || tags.get("amenity") == Some(&"fountain".to_string());
+    let has_statue: bool = tags.get("memorial") == Some(&"statue".to_string())
+        || tags.get("artwork_type") == Some(&"statue".to_string())
+        || matches!(
+            tags.get("historic").map(|s: &String| s.as_str()),
+            Some("memorial" | "monument")
+        );
+
+    if has_fountain {
+        for dx in -2..=2 {
+            for dz in -2..=2 {
+                let rim: bool = i32::abs(dx) == 2 || i32::abs(dz) == 2;
+                let (x, z) = (center_x + dx, center_z + dz);
+                if rim {
+                    editor.set_block(STONE_BRICK_SLAB, x, ground_level + 1, z, None, None);
+                } else {
+                    editor.set_block(
+                        WATER,
+                        x,
+                        ground_level,
+                        z,
+                        Some(&[paving_block, POLISHED_ANDESITE]),
+                        None,
+                    );
+                }
+            }
+        }
+        editor.set_block(
+            STONE_BRICKS,
+            center_x,
+            ground_level + 1,
+            center_z,
+            None,
+            None,
+        );
+        editor.set_block(WATER, center_x, ground_level + 2, center_z, None, None);
+    } else if has_statue {
+        for dx in -1..=1 {
+            for dz in -1..=1 {
+                editor.set_block(
+                    POLISHED_ANDESITE,
+                    center_x + dx,
+                    ground_level + 1,
+                    center_z + dz,
+                    None,
+                    None,
+                );
+            }
+        }
+        editor.set_block(
+            CHISELED_STONE_BRICKS,
+            center_x,
+            ground_level + 2,
+            center_z,
+            None,
+            None,
+        );
+        editor.set_block(
+            STONE_BRICKS,
+            center_x,
+            ground_level + 3,
+            center_z,
+            None,
+            None,
+        );
+        editor.set_block(
+            STONE_BRICKS,
+            center_x,
+            ground_level + 4,
+            center_z,
+            None,
+            None,
+        );
+        editor.set_block(
+            STONE_BRICK_SLAB,
+            center_x,
+            ground_level + 5,
+            center_z,
+            None,
+            None,
+        );
+    }
+}
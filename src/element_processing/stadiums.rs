@@ -0,0 +1,172 @@
+use crate::args::Args;
+use crate::block_definitions::*;
+use crate::floodfill::flood_fill_area;
+use crate::osm_parser::{ProcessedNode, ProcessedWay};
+use crate::world_editor::WorldEditor;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::f64::consts::TAU;
+
+/// Rows of the smallest grandstand
+const MIN_STAND_DEPTH: i32 = 3;
+/// Rows of the largest grandstand
+const MAX_STAND_DEPTH: i32 = 30;
+/// Share of the distance from the outline to the center taken by the stands
+const STAND_SHARE: f64 = 0.4;
+/// Distance between the aisles running up the stands, measured along the outline
+const AISLE_SPACING: i32 = 12;
+/// Height of the wall at the back of the upper row
+const BACK_WALL_HEIGHT: i32 = 3;
+/// Width of the running track around the pitch of athletics stadiums
+const TRACK_WIDTH: i32 = 6;
+
+/// Generates a stadium as a bowl of tiered seating following the outline, rising by one
+/// block per row towards the outside. The upper rows are covered by a roof and the
+/// remaining area in the middle becomes the pitch, surrounded by a running track in
+/// stadiums used for athletics.
+pub fn generate_stadium(
+    editor: &mut WorldEditor,
+    element: &ProcessedWay,
+    ground_level: i32,
+    args: &Args,
+) {
+    let polygon_coords: Vec<(i32, i32)> = element
+        .nodes
+        .iter()
+        .map(|n: &ProcessedNode| (n.x, n.z))
+        .collect();
+    let area: HashSet<(i32, i32)> = flood_fill_area(&polygon_coords, &args.fill_options())
+        .into_iter()
+        .collect();
+    if area.is_empty() {
+        return;
+    }
+
+    // Distance of every block from the outline, spreading inwards from the edge of the area
+    let mut distances: HashMap<(i32, i32), i32> = HashMap::new();
+    let mut queue: VecDeque<(i32, i32)> = VecDeque::new();
+    for &(x, z) in &area {
+        let on_outline: bool = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .iter()
+            .any(|(dx, dz)| !area.contains(&(x + dx, z + dz)));
+        if on_outline {
+            distances.insert((x, z), 0);
+            queue.push_back((x, z));
+        }
+    }
+    while let Some((x, z)) = queue.pop_front() {
+        let distance: i32 = distances[&(x, z)];
+        for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let neighbor: (i32, i32) = (x + dx, z + dz);
+            if area.contains(&neighbor) && !distances.contains_key(&neighbor) {
+                distances.insert(neighbor, distance + 1);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    // The stands leave at least half of the distance to the center for the pitch
+    let center_distance: i32 = distances.values().copied().max().unwrap_or(0);
+    let height_limit: Option<i32> = element
+        .tags
+        .get("height")
+        .and_then(|height: &String| height.trim_end_matches('m').trim().parse::<f64>().ok())
+        .map(|height: f64| (height * args.scale).round() as i32 - BACK_WALL_HEIGHT);
+    let stand_depth: i32 = height_limit
+        .unwrap_or((f64::from(center_distance) * STAND_SHARE).round() as i32)
+        .clamp(MIN_STAND_DEPTH, MAX_STAND_DEPTH)
+        .min(center_distance / 2);
+    let has_track: bool = element.tags.get("sport").is_some_and(|sport: &String| {
+        sport
+            .split(';')
+            .any(|s: &str| matches!(s, "athletics" | "multi"))
+    }) && center_distance - stand_depth > TRACK_WIDTH * 3;
+
+    // Aisles are placed by the angle around the center, so they run straight up the stands
+    let count: f64 = area.len() as f64;
+    let center: (f64, f64) = (
+        area.iter().map(|&(x, _)| f64::from(x)).sum::<f64>() / count,
+        area.iter().map(|&(_, z)| f64::from(z)).sum::<f64>() / count,
+    );
+    let perimeter: f64 = distances.values().filter(|&&d| d == 0).count() as f64;
+
+    for (&(x, z), &distance) in &distances {
+        if distance >= stand_depth {
+            let is_track: bool = has_track && distance < stand_depth + TRACK_WIDTH;
+            let surface: Block = if is_track {
+                RED_TERRACOTTA
+            } else {
+                GRASS_BLOCK
+            };
+            editor.set_block(surface, x, ground_level, z, None, None);
+            continue;
+        }
+
+        let row_height: i32 = stand_depth - distance;
+        for y in ground_level + 1..ground_level + row_height {
+            editor.set_block(LIGHT_GRAY_CONCRETE, x, y, z, None, None);
+        }
+
+        let angle: f64 = (f64::from(z) - center.1).atan2(f64::from(x) - center.0);
+        let is_aisle: bool = ((angle / TAU + 0.5) * perimeter) as i32 % AISLE_SPACING == 0;
+        let seat: Block = if is_aisle {
+            SMOOTH_STONE
+        } else {
+            seat_facing_away(&distances, x, z, distance)
+        };
+        editor.set_block(seat, x, ground_level + row_height, z, None, None);
+
+        if distance == 0 {
+            // Back wall with floodlights along the rim
+            for y in 1..=BACK_WALL_HEIGHT {
+                editor.set_block(
+                    STONE_BRICKS,
+                    x,
+                    ground_level + row_height + y,
+                    z,
+                    None,
+                    None,
+                );
+            }
+            if is_aisle {
+                editor.set_block(
+                    GLOWSTONE,
+                    x,
+                    ground_level + row_height + BACK_WALL_HEIGHT + 1,
+                    z,
+                    None,
+                    None,
+                );
+            }
+        }
+
+        // Roof over the upper third of the rows
+        if distance <= stand_depth / 3 {
+            editor.set_block(
+                WHITE_CONCRETE,
+                x,
+                ground_level + stand_depth + BACK_WALL_HEIGHT,
+                z,
+                None,
+                None,
+            );
+        }
+    }
+}
+
+/// Returns a stair facing the outline, so spectators sitting on it look at the pitch
+fn seat_facing_away(distances: &HashMap<(i32, i32), i32>, x: i32, z: i32, distance: i32) -> Block {
+    let is_outer = |dx: i32, dz: i32| -> bool {
+        distances
+            .get(&(x + dx, z + dz))
+            .is_none_or(|&d: &i32| d < distance)
+    };
+    if is_outer(0, -1) {
+        STONE_BRICK_STAIRS_NORTH
+    } else if is_outer(0, 1) {
+        STONE_BRICK_STAIRS_SOUTH
+    } else if is_outer(1, 0) {
+        STONE_BRICK_STAIRS_EAST
+    } else {
+        STONE_BRICK_STAIRS_WEST
+    }
+}
@@ -1,5 +1,10 @@
 use crate::block_definitions::*;
+use crate::random::seeded_rng;
+use crate::tree_templates::tree_template;
 use crate::world_editor::WorldEditor;
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::collections::HashMap;
 
 /// Helper function to set blocks in a circular pattern around a central point.
 fn round1(editor: &mut WorldEditor, material: Block, x: i32, y: i32, z: i32) {
@@ -45,8 +50,138 @@ fn round3(editor: &mut WorldEditor, material: Block, x: i32, y: i32, z: i32) {
     editor.set_block(material, x - 1, y, z - 3, None, None);
 }
 
-/// Function to create different types of trees.
-pub fn create_tree(editor: &mut WorldEditor, x: i32, y: i32, z: i32, typetree: u8, snow: bool) {
+/// Horizontal reach of the widest tree crown
+const CROWN_RADIUS: i32 = 3;
+/// Height of the tallest tree, including its snow cover
+const TREE_HEIGHT: i32 = 14;
+/// Height above the ground at which the crowns start, so low objects like fences can sit below
+const CROWN_BASE: i32 = 2;
+/// Trunks grow up to this many blocks taller than the base shape
+const MAX_EXTRA_HEIGHT: i32 = 2;
+
+/// Tree shapes that can be generated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeType {
+    Oak,
+    Spruce,
+    Birch,
+    Jungle,
+}
+
+impl TreeType {
+    /// Name of the tree type, also used as a key for tree templates
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Oak => "oak",
+            Self::Spruce => "spruce",
+            Self::Birch => "birch",
+            Self::Jungle => "jungle",
+        }
+    }
+
+    /// Picks the tree shape for an element from its genus, species and leaf_type tags.
+    /// Without any of these tags, oak, spruce and birch trees are mixed randomly.
+    pub fn from_tags(tags: &HashMap<String, String>, rng: &mut impl Rng) -> Self {
+        // The genus is also the first word of the scientific species name
+        let genus: Option<String> = tags
+            .get("genus")
+            .or_else(|| tags.get("species"))
+            .and_then(|name: &String| name.split_whitespace().next())
+            .map(|name: &str| name.to_lowercase());
+        let by_genus: Option<Self> = match genus.as_deref() {
+            Some(
+                "quercus" | "acer" | "fagus" | "tilia" | "fraxinus" | "ulmus" | "platanus"
+                | "castanea" | "aesculus" | "juglans" | "carpinus" | "populus" | "salix",
+            ) => Some(Self::Oak),
+            Some("betula" | "alnus" | "sorbus" | "prunus" | "malus") => Some(Self::Birch),
+            Some(
+                "picea" | "abies" | "pinus" | "larix" | "pseudotsuga" | "taxus" | "thuja"
+                | "cedrus" | "cupressus" | "tsuga",
+            ) => Some(Self::Spruce),
+            Some(
+                "ficus" | "ceiba" | "tectona" | "mangifera" | "cocos" | "phoenix" | "swietenia"
+                | "dipterocarpus" | "artocarpus",
+            ) => Some(Self::Jungle),
+            _ => None,
+        };
+        if let Some(tree_type) = by_genus {
+            return tree_type;
+        }
+
+        let evergreen: bool = tags.get("leaf_cycle").map(|s| s.as_str()) == Some("evergreen");
+        match tags.get("leaf_type").map(|s| s.as_str()) {
+            Some("needleleaved") => Self::Spruce,
+            Some("broadleaved") if evergreen => Self::Jungle,
+            Some("broadleaved") => {
+                if rng.gen_bool(0.5) {
+                    Self::Oak
+                } else {
+                    Self::Birch
+                }
+            }
+            _ => match rng.gen_range(0..3) {
+                0 => Self::Oak,
+                1 => Self::Spruce,
+                _ => Self::Birch,
+            },
+        }
+    }
+}
+
+/// Checks that a tree at the given position neither grows into a structure nor pokes
+/// through one above it. Other vegetation does not count as an obstacle.
+fn has_clearance(editor: &WorldEditor, x: i32, y: i32, z: i32) -> bool {
+    let vegetation: [Block; 15] = [
+        OAK_LEAVES,
+        BIRCH_LEAVES,
+        SPRUCE_LEAVES,
+        JUNGLE_LEAVES,
+        OAK_LOG,
+        BIRCH_LOG,
+        SPRUCE_LOG,
+        JUNGLE_LOG,
+        SNOW_LAYER,
+        GRASS,
+        RED_FLOWER,
+        BLUE_FLOWER,
+        YELLOW_FLOWER,
+        WHITE_FLOWER,
+        AIR,
+    ];
+    let is_free = |bx: i32, by: i32, bz: i32| -> bool {
+        editor
+            .get_block(bx, by, bz)
+            .is_none_or(|block: Block| vegetation.contains(&block))
+    };
+
+    // The trunk needs its whole column, the crown the space around it
+    if !(y..y + TREE_HEIGHT).all(|by: i32| is_free(x, by, z)) {
+        return false;
+    }
+    for dx in -CROWN_RADIUS..=CROWN_RADIUS {
+        for dz in -CROWN_RADIUS..=CROWN_RADIUS {
+            if dx * dx + dz * dz > CROWN_RADIUS * CROWN_RADIUS + 1 {
+                continue;
+            }
+            if !(y + CROWN_BASE..y + TREE_HEIGHT).all(|by: i32| is_free(x + dx, by, z + dz)) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Function to create different types of trees, picked from the tags of the element.
+/// Trunks get a random extra height, which lifts the whole crown. User-provided tree
+/// templates for the species, genus or tree type replace the built-in shapes.
+pub fn create_tree(
+    editor: &mut WorldEditor,
+    x: i32,
+    y: i32,
+    z: i32,
+    tags: &HashMap<String, String>,
+    snow: bool,
+) {
     let mut blacklist: Vec<Block> = Vec::new();
     blacklist.extend(building_corner_variations());
     blacklist.extend(building_wall_variations());
@@ -57,110 +192,151 @@ pub fn create_tree(editor: &mut WorldEditor, x: i32, y: i32, z: i32, typetree: u
         return;
     }
 
-    match typetree {
-        1 => {
-            // Oak tree
-            editor.fill_blocks(OAK_LOG, x, y, z, x, y + 8, z, None, None);
-            editor.fill_blocks(OAK_LEAVES, x - 1, y + 3, z, x - 1, y + 9, z, None, None);
-            editor.fill_blocks(OAK_LEAVES, x + 1, y + 3, z, x + 1, y + 9, z, None, None);
-            editor.fill_blocks(OAK_LEAVES, x, y + 3, z - 1, x, y + 9, z - 1, None, None);
-            editor.fill_blocks(OAK_LEAVES, x, y + 3, z + 1, x, y + 9, z + 1, None, None);
-            editor.fill_blocks(OAK_LEAVES, x, y + 9, z, x, y + 10, z, None, None);
-            round1(editor, OAK_LEAVES, x, y + 8, z);
-            round1(editor, OAK_LEAVES, x, y + 7, z);
-            round1(editor, OAK_LEAVES, x, y + 6, z);
-            round1(editor, OAK_LEAVES, x, y + 5, z);
-            round1(editor, OAK_LEAVES, x, y + 4, z);
-            round1(editor, OAK_LEAVES, x, y + 3, z);
-            round2(editor, OAK_LEAVES, x, y + 7, z);
-            round2(editor, OAK_LEAVES, x, y + 6, z);
-            round2(editor, OAK_LEAVES, x, y + 5, z);
-            round2(editor, OAK_LEAVES, x, y + 4, z);
-            round3(editor, OAK_LEAVES, x, y + 6, z);
-            round3(editor, OAK_LEAVES, x, y + 5, z);
+    // Keep trees out of buildings, bridges and other structures placed before them
+    if !has_clearance(editor, x, y, z) {
+        return;
+    }
+
+    let mut rng: StdRng = seeded_rng(editor.seed(), (x, y, z));
+    let tree_type: TreeType = TreeType::from_tags(tags, &mut rng);
+
+    let template_keys: Vec<String> = ["species", "genus"]
+        .iter()
+        .filter_map(|key: &&str| tags.get(*key))
+        .map(|name: &String| name.to_lowercase())
+        .chain([tree_type.name().to_string()])
+        .collect();
+    if let Some(template) = tree_template(&template_keys, &mut rng) {
+        template.place(editor, x, y, z);
+        return;
+    }
+
+    // Crown shapes are given relative to the lifted base c, the trunk always starts at y
+    let c: i32 = y + rng.gen_range(0..=MAX_EXTRA_HEIGHT);
+
+    match tree_type {
+        TreeType::Oak => {
+            editor.fill_blocks(OAK_LOG, x, y, z, x, c + 8, z, None, None);
+            editor.fill_blocks(OAK_LEAVES, x - 1, c + 3, z, x - 1, c + 9, z, None, None);
+            editor.fill_blocks(OAK_LEAVES, x + 1, c + 3, z, x + 1, c + 9, z, None, None);
+            editor.fill_blocks(OAK_LEAVES, x, c + 3, z - 1, x, c + 9, z - 1, None, None);
+            editor.fill_blocks(OAK_LEAVES, x, c + 3, z + 1, x, c + 9, z + 1, None, None);
+            editor.fill_blocks(OAK_LEAVES, x, c + 9, z, x, c + 10, z, None, None);
+            round1(editor, OAK_LEAVES, x, c + 8, z);
+            round1(editor, OAK_LEAVES, x, c + 7, z);
+            round1(editor, OAK_LEAVES, x, c + 6, z);
+            round1(editor, OAK_LEAVES, x, c + 5, z);
+            round1(editor, OAK_LEAVES, x, c + 4, z);
+            round1(editor, OAK_LEAVES, x, c + 3, z);
+            round2(editor, OAK_LEAVES, x, c + 7, z);
+            round2(editor, OAK_LEAVES, x, c + 6, z);
+            round2(editor, OAK_LEAVES, x, c + 5, z);
+            round2(editor, OAK_LEAVES, x, c + 4, z);
+            round3(editor, OAK_LEAVES, x, c + 6, z);
+            round3(editor, OAK_LEAVES, x, c + 5, z);
+
+            if snow {
+                editor.set_block(SNOW_LAYER, x, c + 11, z, None, None);
+                editor.set_block(SNOW_LAYER, x + 1, c + 10, z, None, None);
+                editor.set_block(SNOW_LAYER, x - 1, c + 10, z, None, None);
+                editor.set_block(SNOW_LAYER, x, c + 10, z - 1, None, None);
+                editor.set_block(SNOW_LAYER, x, c + 10, z + 1, None, None);
+                round1(editor, SNOW_LAYER, x, c + 9, z);
+                round1(editor, SNOW_LAYER, x, c + 8, z);
+                round1(editor, SNOW_LAYER, x, c + 7, z);
+                round1(editor, SNOW_LAYER, x, c + 6, z);
+                round2(editor, SNOW_LAYER, x, c + 8, z);
+                round2(editor, SNOW_LAYER, x, c + 7, z);
+                round2(editor, SNOW_LAYER, x, c + 6, z);
+                round2(editor, SNOW_LAYER, x, c + 5, z);
+                round3(editor, SNOW_LAYER, x, c + 7, z);
+                round3(editor, SNOW_LAYER, x, c + 6, z);
+            }
+        }
+        TreeType::Spruce => {
+            editor.fill_blocks(SPRUCE_LOG, x, y, z, x, c + 9, z, None, None);
+            editor.fill_blocks(SPRUCE_LEAVES, x - 1, c + 3, z, x - 1, c + 10, z, None, None);
+            editor.fill_blocks(SPRUCE_LEAVES, x + 1, c + 3, z, x + 1, c + 10, z, None, None);
+            editor.fill_blocks(SPRUCE_LEAVES, x, c + 3, z - 1, x, c + 10, z - 1, None, None);
+            editor.fill_blocks(SPRUCE_LEAVES, x, c + 3, z + 1, x, c + 10, z + 1, None, None);
+            editor.set_block(SPRUCE_LEAVES, x, c + 10, z, None, None);
+            round1(editor, SPRUCE_LEAVES, x, c + 9, z);
+            round1(editor, SPRUCE_LEAVES, x, c + 7, z);
+            round1(editor, SPRUCE_LEAVES, x, c + 6, z);
+            round1(editor, SPRUCE_LEAVES, x, c + 4, z);
+            round1(editor, SPRUCE_LEAVES, x, c + 3, z);
+            round2(editor, SPRUCE_LEAVES, x, c + 6, z);
+            round2(editor, SPRUCE_LEAVES, x, c + 3, z);
 
             if snow {
-                editor.set_block(SNOW_LAYER, x, y + 11, z, None, None);
-                editor.set_block(SNOW_LAYER, x + 1, y + 10, z, None, None);
-                editor.set_block(SNOW_LAYER, x - 1, y + 10, z, None, None);
-                editor.set_block(SNOW_LAYER, x, y + 10, z - 1, None, None);
-                editor.set_block(SNOW_LAYER, x, y + 10, z + 1, None, None);
-                round1(editor, SNOW_LAYER, x, y + 9, z);
-                round1(editor, SNOW_LAYER, x, y + 8, z);
-                round1(editor, SNOW_LAYER, x, y + 7, z);
-                round1(editor, SNOW_LAYER, x, y + 6, z);
-                round2(editor, SNOW_LAYER, x, y + 8, z);
-                round2(editor, SNOW_LAYER, x, y + 7, z);
-                round2(editor, SNOW_LAYER, x, y + 6, z);
-                round2(editor, SNOW_LAYER, x, y + 5, z);
-                round3(editor, SNOW_LAYER, x, y + 7, z);
-                round3(editor, SNOW_LAYER, x, y + 6, z);
+                editor.set_block(SNOW_LAYER, x, c + 11, z, None, None);
+                editor.set_block(SNOW_LAYER, x + 1, c + 11, z, None, None);
+                editor.set_block(SNOW_LAYER, x - 1, c + 11, z, None, None);
+                editor.set_block(SNOW_LAYER, x, c + 11, z - 1, None, None);
+                editor.set_block(SNOW_LAYER, x, c + 11, z + 1, None, None);
+                round1(editor, SNOW_LAYER, x, c + 10, z);
+                round1(editor, SNOW_LAYER, x, c + 8, z);
+                round1(editor, SNOW_LAYER, x, c + 7, z);
+                round1(editor, SNOW_LAYER, x, c + 5, z);
+                round1(editor, SNOW_LAYER, x, c + 4, z);
+                round2(editor, SNOW_LAYER, x, c + 7, z);
+                round2(editor, SNOW_LAYER, x, c + 4, z);
             }
         }
-        2 => {
-            // Spruce tree
-            editor.fill_blocks(SPRUCE_LOG, x, y, z, x, y + 9, z, None, None);
-            editor.fill_blocks(BIRCH_LEAVES, x - 1, y + 3, z, x - 1, y + 10, z, None, None);
-            editor.fill_blocks(BIRCH_LEAVES, x + 1, y + 3, z, x + 1, y + 10, z, None, None);
-            editor.fill_blocks(BIRCH_LEAVES, x, y + 3, z - 1, x, y + 10, z - 1, None, None);
-            editor.fill_blocks(BIRCH_LEAVES, x, y + 3, z + 1, x, y + 10, z + 1, None, None);
-            editor.set_block(BIRCH_LEAVES, x, y + 10, z, None, None);
-            round1(editor, BIRCH_LEAVES, x, y + 9, z);
-            round1(editor, BIRCH_LEAVES, x, y + 7, z);
-            round1(editor, BIRCH_LEAVES, x, y + 6, z);
-            round1(editor, BIRCH_LEAVES, x, y + 4, z);
-            round1(editor, BIRCH_LEAVES, x, y + 3, z);
-            round2(editor, BIRCH_LEAVES, x, y + 6, z);
-            round2(editor, BIRCH_LEAVES, x, y + 3, z);
+        TreeType::Birch => {
+            editor.fill_blocks(BIRCH_LOG, x, y, z, x, c + 6, z, None, None);
+            editor.fill_blocks(BIRCH_LEAVES, x - 1, c + 2, z, x - 1, c + 7, z, None, None);
+            editor.fill_blocks(BIRCH_LEAVES, x + 1, c + 2, z, x + 1, c + 7, z, None, None);
+            editor.fill_blocks(BIRCH_LEAVES, x, c + 2, z - 1, x, c + 7, z - 1, None, None);
+            editor.fill_blocks(BIRCH_LEAVES, x, c + 2, z + 1, x, c + 7, z + 1, None, None);
+            editor.fill_blocks(BIRCH_LEAVES, x, c + 7, z, x, c + 8, z, None, None);
+            round1(editor, BIRCH_LEAVES, x, c + 6, z);
+            round1(editor, BIRCH_LEAVES, x, c + 5, z);
+            round1(editor, BIRCH_LEAVES, x, c + 4, z);
+            round1(editor, BIRCH_LEAVES, x, c + 3, z);
+            round1(editor, BIRCH_LEAVES, x, c + 2, z);
+            round2(editor, BIRCH_LEAVES, x, c + 2, z);
+            round2(editor, BIRCH_LEAVES, x, c + 3, z);
+            round2(editor, BIRCH_LEAVES, x, c + 4, z);
 
             if snow {
-                editor.set_block(SNOW_LAYER, x, y + 11, z, None, None);
-                editor.set_block(SNOW_LAYER, x + 1, y + 11, z, None, None);
-                editor.set_block(SNOW_LAYER, x - 1, y + 11, z, None, None);
-                editor.set_block(SNOW_LAYER, x, y + 11, z - 1, None, None);
-                editor.set_block(SNOW_LAYER, x, y + 11, z + 1, None, None);
-                round1(editor, SNOW_LAYER, x, y + 10, z);
-                round1(editor, SNOW_LAYER, x, y + 8, z);
-                round1(editor, SNOW_LAYER, x, y + 7, z);
-                round1(editor, SNOW_LAYER, x, y + 5, z);
-                round1(editor, SNOW_LAYER, x, y + 4, z);
-                round2(editor, SNOW_LAYER, x, y + 7, z);
-                round2(editor, SNOW_LAYER, x, y + 4, z);
+                editor.set_block(SNOW_LAYER, x, c + 9, z, None, None);
+                editor.set_block(SNOW_LAYER, x + 1, c + 8, z, None, None);
+                editor.set_block(SNOW_LAYER, x - 1, c + 8, z, None, None);
+                editor.set_block(SNOW_LAYER, x, c + 8, z - 1, None, None);
+                editor.set_block(SNOW_LAYER, x, c + 8, z + 1, None, None);
+                round1(editor, SNOW_LAYER, x, c + 7, z);
+                round1(editor, SNOW_LAYER, x, c + 6, z);
+                round1(editor, SNOW_LAYER, x, c + 5, z);
+                round1(editor, SNOW_LAYER, x, c + 4, z);
+                round1(editor, SNOW_LAYER, x, c + 3, z);
+                round2(editor, SNOW_LAYER, x, c + 3, z);
+                round2(editor, SNOW_LAYER, x, c + 4, z);
+                round2(editor, SNOW_LAYER, x, c + 5, z);
             }
         }
-        3 => {
-            // Birch tree
-            editor.fill_blocks(BIRCH_LOG, x, y, z, x, y + 6, z, None, None);
-            editor.fill_blocks(BIRCH_LEAVES, x - 1, y + 2, z, x - 1, y + 7, z, None, None);
-            editor.fill_blocks(BIRCH_LEAVES, x + 1, y + 2, z, x + 1, y + 7, z, None, None);
-            editor.fill_blocks(BIRCH_LEAVES, x, y + 2, z - 1, x, y + 7, z - 1, None, None);
-            editor.fill_blocks(BIRCH_LEAVES, x, y + 2, z + 1, x, y + 7, z + 1, None, None);
-            editor.fill_blocks(BIRCH_LEAVES, x, y + 7, z, x, y + 8, z, None, None);
-            round1(editor, BIRCH_LEAVES, x, y + 6, z);
-            round1(editor, BIRCH_LEAVES, x, y + 5, z);
-            round1(editor, BIRCH_LEAVES, x, y + 4, z);
-            round1(editor, BIRCH_LEAVES, x, y + 3, z);
-            round1(editor, BIRCH_LEAVES, x, y + 2, z);
-            round2(editor, BIRCH_LEAVES, x, y + 2, z);
-            round2(editor, BIRCH_LEAVES, x, y + 3, z);
-            round2(editor, BIRCH_LEAVES, x, y + 4, z);
+        TreeType::Jungle => {
+            // Tall bare trunk with a wide, flat canopy at the top
+            editor.fill_blocks(JUNGLE_LOG, x, y, z, x, c + 9, z, None, None);
+            editor.fill_blocks(JUNGLE_LEAVES, x - 1, c + 7, z, x + 1, c + 10, z, None, None);
+            editor.fill_blocks(JUNGLE_LEAVES, x, c + 7, z - 1, x, c + 10, z + 1, None, None);
+            round1(editor, JUNGLE_LEAVES, x, c + 9, z);
+            round1(editor, JUNGLE_LEAVES, x, c + 8, z);
+            round1(editor, JUNGLE_LEAVES, x, c + 7, z);
+            round2(editor, JUNGLE_LEAVES, x, c + 8, z);
+            round2(editor, JUNGLE_LEAVES, x, c + 7, z);
+            round3(editor, JUNGLE_LEAVES, x, c + 8, z);
 
             if snow {
-                editor.set_block(SNOW_LAYER, x, y + 9, z, None, None);
-                editor.set_block(SNOW_LAYER, x + 1, y + 8, z, None, None);
-                editor.set_block(SNOW_LAYER, x - 1, y + 8, z, None, None);
-                editor.set_block(SNOW_LAYER, x, y + 8, z - 1, None, None);
-                editor.set_block(SNOW_LAYER, x, y + 8, z + 1, None, None);
-                round1(editor, SNOW_LAYER, x, y + 7, z);
-                round1(editor, SNOW_LAYER, x, y + 6, z);
-                round1(editor, SNOW_LAYER, x, y + 5, z);
-                round1(editor, SNOW_LAYER, x, y + 4, z);
-                round1(editor, SNOW_LAYER, x, y + 3, z);
-                round2(editor, SNOW_LAYER, x, y + 3, z);
-                round2(editor, SNOW_LAYER, x, y + 4, z);
-                round2(editor, SNOW_LAYER, x, y + 5, z);
+                editor.set_block(SNOW_LAYER, x, c + 11, z, None, None);
+                editor.set_block(SNOW_LAYER, x + 1, c + 11, z, None, None);
+                editor.set_block(SNOW_LAYER, x - 1, c + 11, z, None, None);
+                editor.set_block(SNOW_LAYER, x, c + 11, z - 1, None, None);
+                editor.set_block(SNOW_LAYER, x, c + 11, z + 1, None, None);
+                round1(editor, SNOW_LAYER, x, c + 10, z);
+                round2(editor, SNOW_LAYER, x, c + 9, z);
+                round3(editor, SNOW_LAYER, x, c + 9, z);
             }
         }
-        _ => {} // Do nothing if typetree is not recognized
     }
 }
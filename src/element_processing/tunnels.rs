@@ -0,0 +1,326 @@
+use crate::block_definitions::*;
+use crate::bresenham::bresenham_line;
+use crate::osm_parser::{ProcessedNode, ProcessedWay};
+use crate::world_editor::{WorldEditor, MIN_Y};
+use std::collections::HashSet;
+
+/// Lowest floor level of a tunnel, right above the bedrock at the bottom of the world
+const MIN_TUNNEL_FLOOR: i32 = MIN_Y + 1;
+
+/// Height of the free space in railway tunnels and subway stations
+const RAILWAY_CLEARANCE: i32 = 4;
+/// Half size of the hall of a subway station
+const STATION_RADIUS: i32 = 7;
+/// Maximum distance between a subway entrance and the station it leads to
+const MAX_ENTRANCE_DISTANCE: i32 = 120;
+
+/// Returns the floor and ceiling level of a tunnel, or None if the tunnel does not fit
+/// above the bedrock. Every layer below the surface moves the tunnel down by another
+/// four blocks, keeping the dirt below the ground layer intact above the ceiling.
+fn tunnel_levels(layer: Option<&String>, ground_level: i32, clearance: i32) -> Option<(i32, i32)> {
+    let depth: i32 = layer
+        .and_then(|layer: &String| layer.parse::<i32>().ok())
+        .map(|layer: i32| layer.abs())
+        .unwrap_or(1)
+        .clamp(1, 5);
+    let ceiling_y: i32 = ground_level - 2 - (depth - 1) * 4;
+    let floor_y: i32 = ceiling_y - clearance - 1;
+    (floor_y >= MIN_TUNNEL_FLOOR).then_some((floor_y, ceiling_y))
+}
+
+/// Returns whether a highway is a footway, which gets a narrow and low tunnel
+fn is_footway(element: &ProcessedWay) -> bool {
+    matches!(
+        element
+            .tags
+            .get("highway")
+            .map(|highway: &String| highway.as_str()),
+        Some("footway" | "path" | "cycleway" | "pedestrian" | "steps" | "bridleway")
+    )
+}
+
+/// Returns the half width and the height of the free space inside the tunnel of a way
+fn tunnel_size(element: &ProcessedWay) -> (i32, i32) {
+    if is_footway(element) {
+        (1, 3)
+    } else if element.tags.contains_key("railway") {
+        (1, RAILWAY_CLEARANCE)
+    } else {
+        match element
+            .tags
+            .get("highway")
+            .map(|highway: &String| highway.as_str())
+        {
+            Some("motorway" | "trunk" | "primary") => (4, 5),
+            Some("secondary" | "tertiary") => (3, 5),
+            _ => (2, 4),
+        }
+    }
+}
+
+/// Returns whether the tunnel of a way fits between the ground level and the bedrock.
+/// Ways whose tunnel does not fit are generated on the surface like other ways.
+pub fn tunnel_fits(element: &ProcessedWay, ground_level: i32) -> bool {
+    let (_, clearance) = tunnel_size(element);
+    tunnel_levels(element.tags.get("layer"), ground_level, clearance).is_some()
+}
+
+/// Generates road and railway tunnels as lined passages below the ground, with walls,
+/// a ceiling and lights. The 'layer' tag determines how deep the tunnel is placed.
+/// Only called for tunnels that fit, see `tunnel_fits`.
+pub fn generate_tunnels(editor: &mut WorldEditor, element: &ProcessedWay, ground_level: i32) {
+    let is_railway: bool = element.tags.contains_key("railway");
+    let (radius, clearance): (i32, i32) = tunnel_size(element);
+    let floor_block: Block = if is_railway {
+        STONE_BRICKS
+    } else if is_footway(element) {
+        GRAY_CONCRETE
+    } else {
+        BLACK_CONCRETE
+    };
+
+    let Some((floor_y, ceiling_y)) =
+        tunnel_levels(element.tags.get("layer"), ground_level, clearance)
+    else {
+        return;
+    };
+
+    // Collect the center line, together with the axis the cross-section spreads along
+    let mut center_line: Vec<(i32, i32, bool)> = vec![];
+    for nodes in element.nodes.windows(2) {
+        let (x1, z1) = (nodes[0].x, nodes[0].z);
+        let (x2, z2) = (nodes[1].x, nodes[1].z);
+        let spreads_along_z: bool = (x2 - x1).abs() >= (z2 - z1).abs();
+
+        for (bx, _, bz) in bresenham_line(x1, ground_level, z1, x2, ground_level, z2) {
+            if center_line.last().map(|&(x, z, _)| (x, z)) != Some((bx, bz)) {
+                center_line.push((bx, bz, spreads_along_z));
+            }
+        }
+    }
+
+    let offset = |x: i32, z: i32, spreads_along_z: bool, offset: i32| -> (i32, i32) {
+        if spreads_along_z {
+            (x, z + offset)
+        } else {
+            (x + offset, z)
+        }
+    };
+
+    // Free space of the whole tunnel, so walls never block the passage at bends
+    let passage: HashSet<(i32, i32)> = center_line
+        .iter()
+        .flat_map(|&(x, z, spreads_along_z)| {
+            (-radius..=radius).map(move |o: i32| offset(x, z, spreads_along_z, o))
+        })
+        .collect();
+
+    for (step, &(x, z, spreads_along_z)) in center_line.iter().enumerate() {
+        // Lights are placed before the ceiling, so the ceiling does not replace them
+        if step % 8 == 0 {
+            editor.set_block(GLOWSTONE, x, ceiling_y, z, None, None);
+        }
+
+        for o in -(radius + 1)..=(radius + 1) {
+            let (cx, cz) = offset(x, z, spreads_along_z, o);
+            editor.set_block(SMOOTH_STONE, cx, ceiling_y, cz, None, None);
+            editor.set_block(STONE_BRICKS, cx, floor_y - 1, cz, None, None);
+
+            if passage.contains(&(cx, cz)) {
+                editor.set_block(floor_block, cx, floor_y, cz, None, None);
+            } else {
+                for y in floor_y..ceiling_y {
+                    editor.set_block(STONE_BRICKS, cx, y, cz, None, None);
+                }
+            }
+        }
+
+        if is_railway {
+            editor.set_block(RAIL, x, floor_y + 1, z, None, None);
+        }
+    }
+}
+
+/// Generates the underground hall of a subway station at the depth of the subway tunnels.
+/// Stations are generated after the tunnels, so the hall opens up the tunnel walls and
+/// raises platforms next to the tracks running through it.
+pub fn generate_subway_station(editor: &mut WorldEditor, node: &ProcessedNode, ground_level: i32) {
+    let Some((floor_y, ceiling_y)) =
+        tunnel_levels(node.tags.get("layer"), ground_level, RAILWAY_CLEARANCE)
+    else {
+        return;
+    };
+    let rails: [Block; 6] = [
+        RAIL,
+        RAIL_EAST_WEST,
+        RAIL_NORTH_EAST,
+        RAIL_NORTH_WEST,
+        RAIL_SOUTH_EAST,
+        RAIL_SOUTH_WEST,
+    ];
+
+    for dx in -(STATION_RADIUS + 1)..=(STATION_RADIUS + 1) {
+        for dz in -(STATION_RADIUS + 1)..=(STATION_RADIUS + 1) {
+            let (x, z) = (node.x + dx, node.z + dz);
+            let is_wall: bool = dx.abs() > STATION_RADIUS || dz.abs() > STATION_RADIUS;
+
+            editor.set_block(STONE_BRICKS, x, floor_y - 1, z, None, None);
+            let ceiling_block: Block = if !is_wall && dx % 4 == 0 && dz % 4 == 0 {
+                GLOWSTONE
+            } else {
+                SMOOTH_STONE
+            };
+            editor.set_block(ceiling_block, x, ceiling_y, z, Some(&[SMOOTH_STONE]), None);
+
+            if is_wall {
+                for y in floor_y..ceiling_y {
+                    editor.set_block(WHITE_CONCRETE, x, y, z, Some(&[STONE_BRICKS]), None);
+                }
+                continue;
+            }
+
+            // Open up tunnel walls crossing the hall, but keep the tracks
+            editor.set_block(STONE_BRICKS, x, floor_y, z, None, None);
+            for y in (floor_y + 1)..ceiling_y {
+                editor.set_block(CAVE_AIR, x, y, z, Some(&[STONE_BRICKS]), None);
+            }
+
+            // Platforms cover the hall floor except for the track bed
+            let near_track: bool = (-1..=1).any(|tx: i32| {
+                (-1..=1).any(|tz: i32| {
+                    editor.check_for_block(x + tx, floor_y + 1, z + tz, Some(&rails), None)
+                })
+            });
+            if !near_track {
+                editor.set_block(SMOOTH_STONE, x, floor_y + 1, z, Some(&[CAVE_AIR]), None);
+            }
+        }
+    }
+}
+
+/// Returns whether the hall of a subway station fits between the ground level and the
+/// bedrock. Stations that do not fit are generated like other nodes.
+pub fn subway_station_fits(node: &ProcessedNode, ground_level: i32) -> bool {
+    tunnel_levels(node.tags.get("layer"), ground_level, RAILWAY_CLEARANCE).is_some()
+}
+
+/// Returns whether the stairway of a subway entrance reaches down to a station level above
+/// the bedrock. Entrances that do not fit are generated like other entrances.
+pub fn subway_entrance_fits(ground_level: i32) -> bool {
+    tunnel_levels(None, ground_level, RAILWAY_CLEARANCE).is_some()
+}
+
+/// Generates a stairway from a subway entrance down to the nearest station, with a
+/// corridor at the bottom leading into the station hall. The corridor runs along the main
+/// axis towards the station and turns towards the hall once it is level with the station.
+pub fn generate_subway_entrance(
+    editor: &mut WorldEditor,
+    node: &ProcessedNode,
+    stations: &[(i32, i32)],
+    ground_level: i32,
+) {
+    let Some((floor_y, _)) = tunnel_levels(None, ground_level, RAILWAY_CLEARANCE) else {
+        return;
+    };
+
+    // Head towards the nearest station along its main axis, or south without a station
+    let station: Option<(i32, i32)> = stations
+        .iter()
+        .copied()
+        .filter(|&(sx, sz)| (sx - node.x).abs().max((sz - node.z).abs()) <= MAX_ENTRANCE_DISTANCE)
+        .min_by_key(|&(sx, sz)| (sx - node.x).abs() + (sz - node.z).abs());
+    let direction: (i32, i32) = match station {
+        Some((sx, sz)) if (sx - node.x).abs() >= (sz - node.z).abs() => ((sx - node.x).signum(), 0),
+        Some((_, sz)) => (0, (sz - node.z).signum()),
+        None => (0, 1),
+    };
+    let stairs: Block = match direction {
+        (1, _) => STONE_BRICK_STAIRS_WEST,
+        (-1, _) => STONE_BRICK_STAIRS_EAST,
+        (_, -1) => STONE_BRICK_STAIRS_SOUTH,
+        _ => STONE_BRICK_STAIRS_NORTH,
+    };
+
+    // The stairs descend one block per step, the corridor continues until the station hall
+    let stair_steps: i32 = ground_level - floor_y;
+    let mut main_steps: i32 = stair_steps;
+    // A station offset too far to the side is reached by turning at the end of the corridor
+    let mut turn_steps: i32 = 0;
+    let mut turn_direction: (i32, i32) = (0, 0);
+    if let Some((sx, sz)) = station {
+        let (dx, dz) = (sx - node.x, sz - node.z);
+        let distance: i32 = dx * direction.0 + dz * direction.1;
+        turn_direction = (
+            dx.signum() * direction.1.abs(),
+            dz.signum() * direction.0.abs(),
+        );
+        let sideways: i32 = dx * turn_direction.0 + dz * turn_direction.1;
+        if sideways <= STATION_RADIUS {
+            main_steps = (distance - STATION_RADIUS).max(stair_steps);
+        } else {
+            main_steps = distance.max(stair_steps);
+            turn_steps = sideways - STATION_RADIUS;
+        }
+    }
+
+    for step in 0..=main_steps {
+        let (x, z) = (node.x + direction.0 * step, node.z + direction.1 * step);
+        let y: i32 = (ground_level - step).max(floor_y);
+        let tread: Block = if step < stair_steps {
+            stairs
+        } else {
+            STONE_BRICKS
+        };
+        generate_passage_slice(editor, (x, y, z), direction, tread, ground_level);
+    }
+
+    let (x, z) = (
+        node.x + direction.0 * main_steps,
+        node.z + direction.1 * main_steps,
+    );
+    for step in 1..=turn_steps {
+        let position: (i32, i32, i32) = (
+            x + turn_direction.0 * step,
+            floor_y,
+            z + turn_direction.1 * step,
+        );
+        generate_passage_slice(editor, position, turn_direction, STONE_BRICKS, ground_level);
+    }
+}
+
+/// Generates one slice of the stairway or corridor of a subway entrance, three blocks wide
+/// across the direction it leads in, with walls on both sides and a ceiling. Walls are only
+/// placed into empty blocks, so they never close a corridor generated before at a turn.
+fn generate_passage_slice(
+    editor: &mut WorldEditor,
+    (x, y, z): (i32, i32, i32),
+    direction: (i32, i32),
+    tread: Block,
+    ground_level: i32,
+) {
+    let carvable: [Block; 6] = [
+        STONE_BRICKS,
+        SMOOTH_STONE,
+        WHITE_CONCRETE,
+        GRASS_BLOCK,
+        SNOW_BLOCK,
+        DIRT,
+    ];
+    let side: (i32, i32) = (direction.1.abs(), direction.0.abs());
+    let ceiling_y: i32 = (y + 4).min(ground_level + 4);
+
+    for offset in -2..=2 {
+        let (cx, cz) = (x + side.0 * offset, z + side.1 * offset);
+        if offset.abs() == 2 {
+            for wall_y in y..ceiling_y {
+                editor.set_block(STONE_BRICKS, cx, wall_y, cz, None, None);
+            }
+        } else {
+            editor.set_block(tread, cx, y, cz, Some(&carvable), None);
+            for air_y in (y + 1)..ceiling_y {
+                editor.set_block(CAVE_AIR, cx, air_y, cz, Some(&carvable), None);
+            }
+        }
+        editor.set_block(SMOOTH_STONE, cx, ceiling_y, cz, None, None);
+    }
+}
@@ -0,0 +1,182 @@
+use crate::block_definitions::*;
+use crate::osm_parser::ProcessedWay;
+use crate::random::seeded_rng;
+use crate::transliteration::{name_lines, SignScript};
+use crate::world_editor::WorldEditor;
+use fastnbt::Value;
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+
+/// Spawns a villager in front of a shop or amenity building, named after the building and
+/// with a profession matching its purpose. The villager prefers a spot facing a road.
+pub fn generate_poi_villager(
+    editor: &mut WorldEditor,
+    element: &ProcessedWay,
+    outline: &[(i32, i32)],
+    floor_area: &[(i32, i32)],
+    ground_level: i32,
+    sign_script: SignScript,
+) {
+    let Some(profession) = villager_profession(element) else {
+        return;
+    };
+
+    let Some((x, z, yaw)) = entrance_spot(editor, outline, floor_area, ground_level) else {
+        return;
+    };
+
+    let mut villager_data: HashMap<String, Value> = HashMap::new();
+    villager_data.insert(
+        "profession".to_string(),
+        Value::String(format!("minecraft:{}", profession)),
+    );
+    villager_data.insert("level".to_string(), Value::Int(2));
+    villager_data.insert(
+        "type".to_string(),
+        Value::String("minecraft:plains".to_string()),
+    );
+
+    let mut villager: HashMap<String, Value> = HashMap::new();
+    villager.insert("VillagerData".to_string(), Value::Compound(villager_data));
+    // Keep the villager at the entrance and in the world
+    villager.insert("NoAI".to_string(), Value::Byte(1));
+    villager.insert("PersistenceRequired".to_string(), Value::Byte(1));
+    villager.insert("Invulnerable".to_string(), Value::Byte(1));
+
+    if let Some(name) = element.tags.get("name") {
+        let name: String = match name_lines(name, sign_script) {
+            (name, Some(latin)) => format!("{} ({})", name, latin),
+            (name, None) => name,
+        };
+        let custom_name: String = serde_json::json!({ "text": name }).to_string();
+        villager.insert("CustomName".to_string(), Value::String(custom_name));
+        villager.insert("CustomNameVisible".to_string(), Value::Byte(1));
+    }
+
+    editor.add_entity("minecraft:villager", x, ground_level + 1, z, yaw, villager);
+}
+
+/// Spawns an unemployed villager in front of every third residential building. Unlike the
+/// villagers of shops and amenities, residents are free to walk around.
+pub fn generate_resident_villager(
+    editor: &mut WorldEditor,
+    element: &ProcessedWay,
+    outline: &[(i32, i32)],
+    floor_area: &[(i32, i32)],
+    ground_level: i32,
+) {
+    let is_residential: bool = matches!(
+        element.tags.get("building").map(|s: &String| s.as_str()),
+        Some(
+            "house"
+                | "residential"
+                | "apartments"
+                | "detached"
+                | "semidetached_house"
+                | "terrace"
+                | "bungalow"
+        )
+    );
+    if !is_residential || !seeded_rng(editor.seed(), element.id).gen_ratio(1, 3) {
+        return;
+    }
+    let Some((x, z, yaw)) = entrance_spot(editor, outline, floor_area, ground_level) else {
+        return;
+    };
+
+    let mut villager_data: HashMap<String, Value> = HashMap::new();
+    villager_data.insert(
+        "profession".to_string(),
+        Value::String("minecraft:none".to_string()),
+    );
+    villager_data.insert("level".to_string(), Value::Int(1));
+    villager_data.insert(
+        "type".to_string(),
+        Value::String("minecraft:plains".to_string()),
+    );
+
+    let mut villager: HashMap<String, Value> = HashMap::new();
+    villager.insert("VillagerData".to_string(), Value::Compound(villager_data));
+    villager.insert("PersistenceRequired".to_string(), Value::Byte(1));
+
+    editor.add_entity("minecraft:villager", x, ground_level + 1, z, yaw, villager);
+}
+
+/// Finds a free spot directly outside the walls of a building, preferring one facing a
+/// road. Returns the spot together with the yaw of a villager facing away from the building.
+fn entrance_spot(
+    editor: &WorldEditor,
+    outline: &[(i32, i32)],
+    floor_area: &[(i32, i32)],
+    ground_level: i32,
+) -> Option<(i32, i32, f32)> {
+    let inside: HashSet<(i32, i32)> = floor_area.iter().chain(outline).copied().collect();
+    let is_free = |editor: &WorldEditor, x: i32, z: i32| -> bool {
+        !inside.contains(&(x, z))
+            && !matches!(editor.get_highest_block_y(x, z), Some(y) if y > ground_level)
+    };
+
+    // Spots directly outside the walls, together with the yaw of a villager facing away
+    let mut candidates: Vec<(i32, i32, f32, i32)> = vec![];
+    for &(x, z) in outline {
+        for (dx, dz, yaw) in [(0, 1, 0.0), (-1, 0, 90.0), (0, -1, 180.0), (1, 0, 270.0)] {
+            let (vx, vz) = (x + dx, z + dz);
+            if !is_free(editor, vx, vz) {
+                continue;
+            }
+
+            // Count road blocks in front of the spot
+            let road_blocks: i32 = (1..=4)
+                .filter(|distance: &i32| {
+                    editor.check_for_block(
+                        vx + dx * distance,
+                        ground_level,
+                        vz + dz * distance,
+                        Some(&[BLACK_CONCRETE, GRAY_CONCRETE, LIGHT_GRAY_CONCRETE]),
+                        None,
+                    )
+                })
+                .count() as i32;
+            candidates.push((vx, vz, yaw, road_blocks));
+        }
+    }
+
+    candidates
+        .iter()
+        .max_by_key(|&&(_, _, _, road_blocks)| road_blocks)
+        .map(|&(x, z, yaw, _)| (x, z, yaw))
+}
+
+/// Determines the villager profession for a building from its 'shop' or 'amenity' tag
+fn villager_profession(element: &ProcessedWay) -> Option<&'static str> {
+    if let Some(shop) = element.tags.get("shop") {
+        return Some(match shop.as_str() {
+            "butcher" | "deli" => "butcher",
+            "bakery" | "supermarket" | "convenience" | "greengrocer" | "farm" | "florist"
+            | "garden_centre" => "farmer",
+            "books" | "stationery" | "newsagent" => "librarian",
+            "clothes" | "fabric" | "tailor" | "bed" => "shepherd",
+            "shoes" | "bag" | "leather" => "leatherworker",
+            "hardware" | "doityourself" | "tools" => "toolsmith",
+            "seafood" | "fishing" => "fisherman",
+            "weapons" | "hunting" => "weaponsmith",
+            "chemist" | "pharmacy" | "medical_supply" => "cleric",
+            "travel_agency" | "optician" | "electronics" => "cartographer",
+            "sports" | "outdoor" => "fletcher",
+            "jewelry" | "art" | "pottery" => "mason",
+            _ => "nitwit",
+        });
+    }
+
+    let amenity: &str = element.tags.get("amenity")?.as_str();
+    Some(match amenity {
+        "library" | "school" | "university" | "college" | "kindergarten" => "librarian",
+        "place_of_worship" | "pharmacy" | "hospital" | "clinic" | "doctors" => "cleric",
+        "restaurant" | "fast_food" | "cafe" | "pub" | "bar" | "food_court" => "butcher",
+        "bank" | "townhall" | "post_office" | "courthouse" => "cartographer",
+        "police" => "weaponsmith",
+        "fire_station" => "armorer",
+        "marketplace" => "farmer",
+        _ => "nitwit",
+    })
+}
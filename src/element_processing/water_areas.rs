@@ -1,7 +1,9 @@
 use geo::{Contains, Intersects, LineString, Point, Polygon, Rect};
 
 use crate::{
+    biome_definitions::RIVER,
     block_definitions::WATER,
+    block_palette::{palette_block, BlockCategory},
     osm_parser::{ProcessedMemberRole, ProcessedNode, ProcessedRelation},
     world_editor::WorldEditor,
 };
@@ -274,7 +276,15 @@ fn inverse_floodfill_iterative(
             if outers.iter().any(|poly: &Polygon| poly.contains(&p))
                 && inners.iter().all(|poly: &Polygon| !poly.contains(&p))
             {
-                editor.set_block(WATER, x, ground_level, z, None, None);
+                editor.set_block(
+                    palette_block(BlockCategory::Water, WATER),
+                    x,
+                    ground_level,
+                    z,
+                    None,
+                    None,
+                );
+                editor.set_biome(RIVER, x, z);
             }
         }
     }
@@ -290,7 +300,15 @@ fn rect_fill(
 ) {
     for x in min_x..max_x {
         for z in min_z..max_z {
-            editor.set_block(WATER, x, ground_level, z, None, None);
+            editor.set_block(
+                palette_block(BlockCategory::Water, WATER),
+                x,
+                ground_level,
+                z,
+                None,
+                None,
+            );
+            editor.set_biome(RIVER, x, z);
         }
     }
 }
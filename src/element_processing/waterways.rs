@@ -1,52 +1,414 @@
+use crate::args::Args;
+use crate::biome_definitions::RIVER;
 use crate::block_definitions::*;
-use crate::bresenham::bresenham_line;
-use crate::osm_parser::ProcessedWay;
+use crate::block_palette::{palette_block, BlockCategory};
+use crate::bresenham::{bresenham_line, thick_polyline};
+use crate::floodfill::flood_fill_area;
+use crate::osm_parser::{ProcessedElement, ProcessedNode, ProcessedWay};
 use crate::world_editor::WorldEditor;
+use std::collections::{HashMap, HashSet, VecDeque};
 
-pub fn generate_waterways(editor: &mut WorldEditor, element: &ProcessedWay, ground_level: i32) {
-    if let Some(_waterway_type) = element.tags.get("waterway") {
-        let mut previous_node: Option<(i32, i32)> = None;
-        let mut waterway_width: i32 = 4; // Default waterway width
-
-        // Check for custom width in tags
-        if let Some(width_str) = element.tags.get("width") {
-            waterway_width = width_str.parse::<i32>().unwrap_or_else(|_| {
-                width_str
-                    .parse::<f32>()
-                    .map(|f: f32| f as i32)
-                    .unwrap_or(waterway_width)
-            });
-        }
-
-        // Process nodes to create waterways
-        for node in &element.nodes {
-            if let Some(prev) = previous_node {
-                // Skip layers below the ground level
-                if !matches!(
-                    element.tags.get("layer").map(|s| s.as_str()),
-                    Some("-1") | Some("-2") | Some("-3")
+/// Lowest level of a river bed, right above the bedrock of the world template
+const MIN_RIVER_BED: i32 = -63;
+/// Maximum depth of the water in the deepest part of a river
+const MAX_RIVER_DEPTH: i32 = 6;
+/// Height of a waterfall without a height tag, in blocks
+const DEFAULT_WATERFALL_HEIGHT: i32 = 4;
+/// Highest waterfall that is built, as the raised channel above it grows with the height
+const MAX_WATERFALL_HEIGHT: i32 = 12;
+/// Depth of the pool at the foot of a waterfall
+const PLUNGE_POOL_DEPTH: i32 = 4;
+
+pub fn generate_waterways(
+    editor: &mut WorldEditor,
+    element: &ProcessedWay,
+    ground_level: i32,
+    args: &Args,
+) {
+    let Some(waterway_type) = element.tags.get("waterway") else {
+        return;
+    };
+
+    // Skip layers below the ground level
+    if matches!(
+        element.tags.get("layer").map(|s| s.as_str()),
+        Some("-1") | Some("-2") | Some("-3")
+    ) {
+        return;
+    }
+
+    // Riverbanks are mapped as the outline of the water surface
+    if waterway_type == "riverbank" {
+        generate_riverbank(editor, element, ground_level, args);
+        return;
+    }
+
+    let width: f64 = waterway_width(&element.tags, args);
+    let half_width: f64 = width / 2.0;
+    let max_depth: i32 = river_depth(width, ground_level);
+
+    // Distance of every block within the river from the center line
+    let node_points: Vec<(i32, i32)> = element
+        .nodes
+        .iter()
+        .map(|node: &ProcessedNode| (node.x, node.z))
+        .collect();
+    let river: Vec<(i32, i32, f64)> = thick_polyline(&node_points, half_width.max(0.5));
+
+    // The river bed slopes from the center towards the banks
+    for &(x, z, distance) in &river {
+        let share: f64 = 1.0 - distance / (half_width + 1.0);
+        let depth: i32 = ((f64::from(max_depth) * share).ceil() as i32).clamp(1, max_depth);
+        place_water_column(editor, x, z, depth, ground_level);
+    }
+
+    // Sandy banks along wider rivers
+    if width >= 4.0 {
+        let water: Vec<(i32, i32)> = river.iter().map(|&(x, z, _)| (x, z)).collect();
+        place_banks(editor, water.iter(), ground_level);
+    }
+}
+
+/// Width of a waterway in blocks, from the width tag in meters or a typical width for the
+/// type of waterway
+fn waterway_width(tags: &HashMap<String, String>, args: &Args) -> f64 {
+    let width_meters: f64 = tags
+        .get("width")
+        .and_then(|width: &String| width.trim_end_matches(" m").parse::<f64>().ok())
+        .unwrap_or(match tags.get("waterway").map(|s| s.as_str()) {
+            Some("river") => 12.0,
+            Some("canal") => 8.0,
+            Some("stream" | "tidal_channel") => 3.0,
+            Some("ditch" | "drain") => 1.5,
+            _ => 4.0,
+        });
+    (width_meters * args.scale).max(1.0)
+}
+
+/// Returns whether an element is a waterfall or weir, which are built once the waterways
+/// they belong to are in place
+pub fn is_water_drop(element: &ProcessedElement) -> bool {
+    matches!(
+        element.tags().get("waterway").map(|s| s.as_str()),
+        Some("waterfall" | "weir")
+    )
+}
+
+/// Builds a waterfall or weir into the water placed by the waterways. The world has no
+/// terrain elevation, so the drop is built locally: a weir becomes a crest across the river
+/// right below the surface, and a waterfall raises the channel above it between rock walls
+/// and falls into a deep pool. The flow follows the direction of the waterway, which is
+/// mapped downstream.
+pub fn generate_water_drop(
+    editor: &mut WorldEditor,
+    element: &ProcessedElement,
+    elements: &[ProcessedElement],
+    ground_level: i32,
+    args: &Args,
+) {
+    let is_weir: bool = element.tags().get("waterway") == Some(&"weir".to_string());
+
+    match element {
+        ProcessedElement::Way(way) if is_weir => {
+            for span in way.nodes.windows(2) {
+                for (x, _, z) in bresenham_line(
+                    span[0].x,
+                    ground_level,
+                    span[0].z,
+                    span[1].x,
+                    ground_level,
+                    span[1].z,
                 ) {
-                    // Draw a line between the current and previous node
-                    let bresenham_points: Vec<(i32, i32, i32)> =
-                        bresenham_line(prev.0, ground_level, prev.1, node.x, ground_level, node.z);
-                    for (bx, _, bz) in bresenham_points {
-                        for x in (bx - waterway_width / 2)..=(bx + waterway_width / 2) {
-                            for z in (bz - waterway_width / 2)..=(bz + waterway_width / 2) {
-                                editor.set_block(WATER, x, ground_level, z, None, None); // Set water block
-                                editor.set_block(
-                                    AIR,
-                                    x,
-                                    ground_level + 1,
-                                    z,
-                                    Some(&[GRASS, WHEAT, CARROTS, POTATOES]),
-                                    None,
-                                );
-                            }
-                        }
-                    }
+                    place_weir_crest(editor, x, z, ground_level);
                 }
             }
-            previous_node = Some((node.x, node.z));
+        }
+        ProcessedElement::Way(way) => {
+            // Waterfalls mapped as ways run from the top to the foot of the fall
+            let (Some(top), Some(foot)) = (way.nodes.first(), way.nodes.last()) else {
+                return;
+            };
+            let Some(direction) = unit_vector(foot.x - top.x, foot.z - top.z) else {
+                return;
+            };
+            let width: f64 = waterway_width(&way.tags, args);
+            let height: i32 = waterfall_height(&way.tags, args);
+            build_waterfall(
+                editor,
+                (top.x, top.z),
+                direction,
+                width,
+                height,
+                ground_level,
+            );
+        }
+        ProcessedElement::Node(node) => {
+            let Some((direction, width)) = flow_at(node, elements, args) else {
+                return;
+            };
+            if is_weir {
+                // The crest runs across the river, reaching onto both banks
+                let reach: i32 = (width / 2.0).ceil() as i32 + 1;
+                for step in -2 * reach..=2 * reach {
+                    let offset: f64 = f64::from(step) / 2.0;
+                    let x: i32 = (f64::from(node.x) - direction.1 * offset).round() as i32;
+                    let z: i32 = (f64::from(node.z) + direction.0 * offset).round() as i32;
+                    place_weir_crest(editor, x, z, ground_level);
+                }
+            } else {
+                let height: i32 = waterfall_height(&node.tags, args);
+                build_waterfall(
+                    editor,
+                    (node.x, node.z),
+                    direction,
+                    width,
+                    height,
+                    ground_level,
+                );
+            }
+        }
+        ProcessedElement::Relation(_) => {}
+    }
+}
+
+/// Height of a waterfall in blocks, from the height tag in meters
+fn waterfall_height(tags: &HashMap<String, String>, args: &Args) -> i32 {
+    tags.get("height")
+        .and_then(|height: &String| height.trim_end_matches('m').trim().parse::<f64>().ok())
+        .map(|height: f64| (height * args.scale).round() as i32)
+        .unwrap_or(DEFAULT_WATERFALL_HEIGHT)
+        .clamp(1, MAX_WATERFALL_HEIGHT)
+}
+
+/// Finds the waterway passing through a node, returning the direction of the flow at the
+/// node and the width of the waterway
+fn flow_at(
+    node: &ProcessedNode,
+    elements: &[ProcessedElement],
+    args: &Args,
+) -> Option<((f64, f64), f64)> {
+    elements.iter().find_map(|element: &ProcessedElement| {
+        let ProcessedElement::Way(way) = element else {
+            return None;
+        };
+        if !way.tags.contains_key("waterway") || is_water_drop(element) {
+            return None;
+        }
+        let index: usize = way.nodes.iter().position(|n| n.id == node.id)?;
+        let (from, to) = match (index.checked_sub(1), way.nodes.get(index + 1)) {
+            (_, Some(next)) => (&way.nodes[index], next),
+            (Some(previous), None) => (&way.nodes[previous], &way.nodes[index]),
+            (None, None) => return None,
+        };
+        let direction: (f64, f64) = unit_vector(to.x - from.x, to.z - from.z)?;
+        Some((direction, waterway_width(&way.tags, args)))
+    })
+}
+
+fn unit_vector(dx: i32, dz: i32) -> Option<(f64, f64)> {
+    let length: f64 = f64::from(dx).hypot(f64::from(dz));
+    (length > 0.0).then(|| (f64::from(dx) / length, f64::from(dz) / length))
+}
+
+/// Replaces the water at a position with a stone wall reaching down to the river bed,
+/// topped by a waterlogged slab, so the river flows over the crest
+fn place_weir_crest(editor: &mut WorldEditor, x: i32, z: i32, ground_level: i32) {
+    let water_blocks: [Block; 2] = [WATER, palette_block(BlockCategory::Water, WATER)];
+    if !editor.check_for_block(x, ground_level, z, Some(&water_blocks), None) {
+        return;
+    }
+    editor.set_block(
+        WATERLOGGED_STONE_BRICK_SLAB,
+        x,
+        ground_level,
+        z,
+        Some(&water_blocks),
+        None,
+    );
+    let mut y: i32 = ground_level - 1;
+    while y > MIN_RIVER_BED && editor.check_for_block(x, y, z, Some(&water_blocks), None) {
+        editor.set_block(STONE_BRICKS, x, y, z, Some(&water_blocks), None);
+        y -= 1;
+    }
+}
+
+/// Builds a waterfall dropping at the given position in the direction of the flow.
+/// Upstream of the drop the water runs in a channel raised to the height of the fall, which
+/// sinks back to the river over its second half. The water falls down a curtain of falling
+/// water into a pool below.
+fn build_waterfall(
+    editor: &mut WorldEditor,
+    origin: (i32, i32),
+    direction: (f64, f64),
+    width: f64,
+    height: i32,
+    ground_level: i32,
+) {
+    let water_block: Block = palette_block(BlockCategory::Water, WATER);
+    let half_width: f64 = width / 2.0;
+    let channel_length: i32 = height * 3;
+    let position = |along: f64, across: f64| -> (i32, i32) {
+        (
+            (f64::from(origin.0) + direction.0 * along - direction.1 * across).round() as i32,
+            (f64::from(origin.1) + direction.1 * along + direction.0 * across).round() as i32,
+        )
+    };
+
+    // Water level of the raised channel and rock walls on both sides, sampled at half
+    // blocks so diagonal channels have no gaps
+    let mut channel: HashMap<(i32, i32), i32> = HashMap::new();
+    let mut walls: HashMap<(i32, i32), i32> = HashMap::new();
+    for step in 0..channel_length * 2 {
+        let along: i32 = step / 2;
+        let level: i32 = if along < channel_length / 2 {
+            height
+        } else {
+            (height * (channel_length - along) * 2 / channel_length).clamp(1, height)
+        };
+        let reach: i32 = (half_width * 2.0).ceil() as i32 + 2;
+        for side_step in -reach..=reach {
+            let across: f64 = f64::from(side_step) / 2.0;
+            let pos: (i32, i32) = position(-f64::from(step) / 2.0, across);
+            if across.abs() <= half_width {
+                channel.entry(pos).or_insert(level);
+            } else {
+                walls.entry(pos).or_insert(level);
+            }
+        }
+    }
+
+    for (&(x, z), &level) in &channel {
+        for y in ground_level + 1..ground_level + level {
+            editor.set_block(STONE, x, y, z, None, None);
+        }
+        editor.set_block(water_block, x, ground_level + level, z, None, None);
+    }
+    for (&(x, z), &level) in &walls {
+        if channel.contains_key(&(x, z)) {
+            continue;
+        }
+        for y in ground_level + 1..=ground_level + level + 1 {
+            let rock: Block = if (x + y + z) % 3 == 0 {
+                MOSSY_COBBLESTONE
+            } else {
+                STONE
+            };
+            editor.set_block(rock, x, y, z, None, None);
+        }
+    }
+
+    // Curtain of falling water right below the drop, and a deep pool at its foot
+    let pool_depth: i32 = PLUNGE_POOL_DEPTH.min(ground_level - MIN_RIVER_BED).max(1);
+    let pool_length: i32 = (height / 2).max(1) + 1;
+    for step in 1..=pool_length * 2 {
+        for side_step in -(half_width * 2.0).ceil() as i32..=(half_width * 2.0).ceil() as i32 {
+            let (x, z) = position(f64::from(step) / 2.0, f64::from(side_step) / 2.0);
+            if channel.contains_key(&(x, z)) {
+                continue;
+            }
+            if step <= 2 {
+                for y in ground_level + 1..=ground_level + height {
+                    editor.set_block(FALLING_WATER, x, y, z, None, None);
+                }
+            }
+            for y in ground_level - pool_depth + 1..=ground_level {
+                editor.set_block(water_block, x, y, z, Some(&[SAND, GRAVEL]), None);
+            }
+            editor.set_block(GRAVEL, x, ground_level - pool_depth, z, None, None);
+        }
+    }
+}
+
+/// Fills a riverbank polygon with water, getting deeper with the distance from the shore.
+fn generate_riverbank(
+    editor: &mut WorldEditor,
+    element: &ProcessedWay,
+    ground_level: i32,
+    args: &Args,
+) {
+    let polygon_coords: Vec<(i32, i32)> = element
+        .nodes
+        .iter()
+        .map(|n: &ProcessedNode| (n.x, n.z))
+        .collect();
+    let area: HashSet<(i32, i32)> = flood_fill_area(&polygon_coords, &args.fill_options())
+        .into_iter()
+        .collect();
+
+    // Distance of every block from the shore, spreading inwards from the edge of the area
+    let mut distances: HashMap<(i32, i32), i32> = HashMap::new();
+    let mut queue: VecDeque<(i32, i32)> = VecDeque::new();
+    for &(x, z) in &area {
+        let on_shore: bool = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .iter()
+            .any(|(dx, dz)| !area.contains(&(x + dx, z + dz)));
+        if on_shore {
+            distances.insert((x, z), 0);
+            queue.push_back((x, z));
+        }
+    }
+    while let Some((x, z)) = queue.pop_front() {
+        let distance: i32 = distances[&(x, z)];
+        for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let neighbor: (i32, i32) = (x + dx, z + dz);
+            if area.contains(&neighbor) && !distances.contains_key(&neighbor) {
+                distances.insert(neighbor, distance + 1);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    // The widest part of the riverbank determines how deep it gets
+    let widest: i32 = distances.values().copied().max().unwrap_or(0) * 2 + 1;
+    let max_depth: i32 = river_depth(f64::from(widest), ground_level);
+    for (&(x, z), &distance) in &distances {
+        let depth: i32 = (1 + distance / 2).min(max_depth);
+        place_water_column(editor, x, z, depth, ground_level);
+    }
+
+    place_banks(editor, distances.keys(), ground_level);
+}
+
+/// Depth of a river of the given width in blocks, limited by the bedrock below the ground
+fn river_depth(width: f64, ground_level: i32) -> i32 {
+    ((width / 4.0).round() as i32)
+        .clamp(1, MAX_RIVER_DEPTH)
+        .min(ground_level - MIN_RIVER_BED)
+        .max(1)
+}
+
+/// Places a column of water reaching down from the ground level, with a river bed below
+fn place_water_column(editor: &mut WorldEditor, x: i32, z: i32, depth: i32, ground_level: i32) {
+    let water_block: Block = palette_block(BlockCategory::Water, WATER);
+    for y in (ground_level - depth + 1)..=ground_level {
+        editor.set_block(water_block, x, y, z, None, None);
+    }
+    let bed_block: Block = if depth > 2 { GRAVEL } else { SAND };
+    editor.set_block(bed_block, x, ground_level - depth, z, None, None);
+    editor.set_biome(RIVER, x, z);
+    editor.set_block(
+        AIR,
+        x,
+        ground_level + 1,
+        z,
+        Some(&[GRASS, WHEAT, CARROTS, POTATOES, BEETROOTS]),
+        None,
+    );
+}
+
+/// Lines the water with a strip of sand, leaving blocks placed by earlier elements intact
+fn place_banks<'a>(
+    editor: &mut WorldEditor,
+    water: impl Iterator<Item = &'a (i32, i32)>,
+    ground_level: i32,
+) {
+    let water: HashSet<(i32, i32)> = water.copied().collect();
+    for &(x, z) in &water {
+        for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let (bx, bz) = (x + dx, z + dz);
+            if !water.contains(&(bx, bz)) {
+                editor.set_block(SAND, bx, ground_level, bz, None, None);
+            }
         }
     }
 }
@@ -0,0 +1,63 @@
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Exclusive lock on a Minecraft world, held while the world is generated and released
+/// when dropped. Minecraft locks the same session.lock file while a world is open, so
+/// worlds open in the game are refused as well.
+pub struct WorldLock {
+    _file: File,
+}
+
+/// Locks a world for generation, failing with a message if another Arnis process is
+/// already generating it or it is open in Minecraft
+pub fn lock_world(world_path: &str) -> Result<WorldLock, String> {
+    let path: PathBuf = Path::new(world_path).join("session.lock");
+    // The content written by Minecraft is kept, as the game checks it when opening the world
+    let file: File = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(|e: io::Error| format!("无法锁定世界 {}：{}", path.display(), e))?;
+
+    match file.try_lock() {
+        Ok(()) => Ok(WorldLock { _file: file }),
+        Err(TryLockError::WouldBlock) => Err(format!(
+            "世界 {} 正在被另一个 Arnis 进程生成或在 Minecraft 中打开",
+            world_path
+        )),
+        Err(TryLockError::Error(e)) => Err(format!("无法锁定世界 {}：{}", world_path, e)),
+    }
+}
+
+/// Locks the lock file belonging to a shared file, e.g. a cache entry, waiting for other
+/// processes to release it. Readers share the lock, a writer holds it alone. The lock is
+/// released when the returned file is dropped.
+pub fn lock_file(path: &Path, exclusive: bool) -> io::Result<File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file: File = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path.with_extension("lock"))?;
+    if exclusive {
+        file.lock()?;
+    } else {
+        file.lock_shared()?;
+    }
+    Ok(file)
+}
+
+/// Writes a file under its exclusive lock. The content is written next to the file first
+/// and moved into place, so readers without a lock never see a partially written file.
+pub fn write_locked(path: &Path, content: &str) -> io::Result<()> {
+    let _lock: File = lock_file(path, true)?;
+    let temporary: PathBuf = path.with_extension(format!("tmp{}", std::process::id()));
+    fs::write(&temporary, content)?;
+    fs::rename(&temporary, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temporary);
+    })
+}
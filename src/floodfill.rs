@@ -1,27 +1,75 @@
-use geo::{Contains, LineString, Point, Polygon};
+use clap::ValueEnum;
+use fnv::FnvHashSet;
 use itertools::Itertools;
-use std::collections::{HashSet, VecDeque};
+use std::cell::Cell;
 use std::time::{Duration, Instant};
 
-/// Perform a flood-fill to find the area inside a polygon.
-/// Returns a vector of (x, z) coordinates representing the filled area.
-pub fn flood_fill_area(
-    polygon_coords: &[(i32, i32)],
-    timeout: Option<&Duration>,
-) -> Vec<(i32, i32)> {
+/// Distance below which a block center counts as lying on an edge of the polygon
+const CROSSING_EPSILON: f64 = 1e-9;
+
+thread_local! {
+    /// Number of fills skipped so far on this thread for exceeding the area limit or the
+    /// timeout. Elements are processed on several threads, each counting its own fills.
+    static SKIPPED_FILLS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Algorithms filling the area inside a polygon, chosen with --fill-method
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FillMethod {
+    /// Fill between the crossings of every row with the edges of the polygon
+    #[default]
+    Scanline,
+    /// Split the polygon into triangles by ear clipping and fill the triangles
+    Triangles,
+}
+
+/// Limits and algorithm of the fills of a generation, taken from its options with
+/// `Args::fill_options`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FillOptions {
+    /// Largest polygon area in blocks that is filled, larger polygons keep only their outline
+    pub max_area: Option<u64>,
+    pub method: FillMethod,
+    /// Time a single fill may take before the element keeps only its outline
+    pub timeout: Option<Duration>,
+}
+
+/// Returns the number of fills skipped so far on the current thread, so callers can tell
+/// which elements were rendered as outlines only
+pub fn skipped_fill_count() -> usize {
+    SKIPPED_FILLS.get()
+}
+
+/// Finds the area inside a polygon with a scanline fill: every row of the bounding box is
+/// crossed with the edges of the polygon and the blocks between pairs of crossings are filled
+/// (even-odd rule). Blocks on the outline itself are not part of the area.
+/// Returns a vector of (x, z) coordinates representing the filled area, row by row.
+/// Polygons larger than the area limit, and fills exceeding the timeout, return an empty
+/// area, so only the outline of the element is rendered.
+pub fn flood_fill_area(polygon_coords: &[(i32, i32)], options: &FillOptions) -> Vec<(i32, i32)> {
     if polygon_coords.len() < 3 {
         return vec![]; // Not a valid polygon
     }
 
+    if options
+        .max_area
+        .is_some_and(|max_area: u64| polygon_area(polygon_coords) > max_area)
+    {
+        SKIPPED_FILLS.set(SKIPPED_FILLS.get() + 1);
+        return vec![];
+    }
+
     let start_time: Instant = Instant::now();
+    let timeout: Option<&Duration> = options.timeout.as_ref();
 
-    // Calculate bounding box of the polygon using itertools
-    let (min_x, max_x) = polygon_coords
-        .iter()
-        .map(|&(x, _)| x)
-        .minmax()
-        .into_option()
-        .unwrap();
+    if options.method == FillMethod::Triangles {
+        return triangle_fill(polygon_coords, start_time, timeout).unwrap_or_else(|| {
+            SKIPPED_FILLS.set(SKIPPED_FILLS.get() + 1);
+            vec![]
+        });
+    }
+
+    // Calculate the rows of the polygon using itertools
     let (min_z, max_z) = polygon_coords
         .iter()
         .map(|&(_, z)| z)
@@ -29,75 +77,183 @@ pub fn flood_fill_area(
         .into_option()
         .unwrap();
 
-    let mut filled_area: Vec<(i32, i32)> = Vec::new();
-    let mut visited: HashSet<(i32, i32)> = HashSet::new();
-
-    // Convert input to a geo::Polygon for efficient point-in-polygon testing
-    let exterior_coords: Vec<(f64, f64)> = polygon_coords
+    let edges: Vec<((i32, i32), (i32, i32))> = polygon_coords
         .iter()
-        .map(|&(x, z)| (x as f64, z as f64))
-        .collect::<Vec<_>>();
-    let exterior: LineString = LineString::from(exterior_coords); // Create LineString from coordinates
-    let polygon: Polygon<f64> = Polygon::new(exterior, vec![]); // Create Polygon using LineString
-
-    // Determine safe step sizes for grid sampling
-    let step_x: i32 = ((max_x - min_x) / 10).max(1); // Ensure step is at least 1
-    let step_z: i32 = ((max_z - min_z) / 10).max(1); // Ensure step is at least 1
-
-    // Sample multiple starting points within the bounding box
-    let mut candidate_points: VecDeque<(i32, i32)> = VecDeque::new();
-    for x in (min_x..=max_x).step_by(step_x as usize) {
-        for z in (min_z..=max_z).step_by(step_z as usize) {
-            candidate_points.push_back((x, z));
+        .copied()
+        .zip(polygon_coords.iter().copied().cycle().skip(1))
+        .collect();
+
+    let mut filled_area: Vec<(i32, i32)> = Vec::new();
+    let mut crossings: Vec<f64> = Vec::new();
+    let mut outline: Vec<(i32, i32)> = Vec::new();
+    for z in min_z..=max_z {
+        // A partial fill looks broken, so fall back to the outline instead
+        if timeout.is_some_and(|timeout: &Duration| &start_time.elapsed() > timeout) {
+            SKIPPED_FILLS.set(SKIPPED_FILLS.get() + 1);
+            return vec![];
         }
-    }
 
-    // Attempt flood-fill from each candidate point
-    while let Some((start_x, start_z)) = candidate_points.pop_front() {
-        if let Some(timeout) = timeout {
-            if &start_time.elapsed() > timeout {
-                eprintln!("填充超时");
-                break;
+        crossings.clear();
+        outline.clear();
+        for &((x1, z1), (x2, z2)) in &edges {
+            if z1 == z && z2 == z {
+                // Edges along the row are part of the outline
+                outline.push((x1.min(x2), x1.max(x2)));
+            } else if (z1 > z) != (z2 > z) {
+                // Half-open rule, so an edge is crossed once at a vertex shared with the next
+                let x: f64 =
+                    f64::from(x1) + f64::from(z - z1) * f64::from(x2 - x1) / f64::from(z2 - z1);
+                crossings.push(x);
             }
         }
+        crossings.sort_by(f64::total_cmp);
 
-        if polygon.contains(&Point::new(start_x as f64, start_z as f64)) {
-            // Start flood-fill from the valid interior point
-            let mut queue: VecDeque<(i32, i32)> = VecDeque::new();
-            queue.push_back((start_x, start_z));
-            visited.insert((start_x, start_z));
-
-            while let Some((x, z)) = queue.pop_front() {
-                if let Some(timeout) = timeout {
-                    if &start_time.elapsed() > timeout {
-                        eprintln!("填充超时");
-                        break;
-                    }
+        for pair in crossings.chunks_exact(2) {
+            let (start, end) = (pair[0], pair[1]);
+            for x in (start.ceil() as i32)..=(end.floor() as i32) {
+                let on_crossing: bool = (f64::from(x) - start).abs() < CROSSING_EPSILON
+                    || (f64::from(x) - end).abs() < CROSSING_EPSILON;
+                let on_outline: bool = outline.iter().any(|&(from, to)| (from..=to).contains(&x));
+                if !on_crossing && !on_outline {
+                    filled_area.push((x, z));
                 }
+            }
+        }
+    }
 
-                if polygon.contains(&Point::new(x as f64, z as f64)) {
-                    filled_area.push((x, z));
+    filled_area
+}
 
-                    // Check adjacent points
-                    for (nx, nz) in [(x - 1, z), (x + 1, z), (x, z - 1), (x, z + 1)].iter() {
-                        if *nx >= min_x
-                            && *nx <= max_x
-                            && *nz >= min_z
-                            && *nz <= max_z
-                            && !visited.contains(&(*nx, *nz))
-                        {
-                            visited.insert((*nx, *nz));
-                            queue.push_back((*nx, *nz));
-                        }
-                    }
+/// Fills a polygon by splitting it into triangles with ear clipping and filling every
+/// triangle. Self-intersecting polygons, which have no ear left at some point, are clipped
+/// at a convex corner anyway, so their area is still filled without leaking. Blocks on the
+/// outline are left out like in the scanline fill. Returns None if the timeout is exceeded.
+fn triangle_fill(
+    polygon_coords: &[(i32, i32)],
+    start_time: Instant,
+    timeout: Option<&Duration>,
+) -> Option<Vec<(i32, i32)>> {
+    let mut filled: FnvHashSet<(i32, i32)> = FnvHashSet::default();
+    for [a, b, c] in triangulate(polygon_coords, start_time, timeout)? {
+        let (min_x, max_x) = (a.0.min(b.0).min(c.0), a.0.max(b.0).max(c.0));
+        let (min_z, max_z) = (a.1.min(b.1).min(c.1), a.1.max(b.1).max(c.1));
+        for x in min_x..=max_x {
+            for z in min_z..=max_z {
+                // Inclusive on the edges, the triangles are counterclockwise
+                if cross(a, b, (x, z)) >= 0 && cross(b, c, (x, z)) >= 0 && cross(c, a, (x, z)) >= 0
+                {
+                    filled.insert((x, z));
                 }
             }
+        }
+    }
 
-            if !filled_area.is_empty() {
-                break; // Exit if a valid area has been flood-filled
+    // Leave out the blocks on the outline, which lie exactly on the edges
+    for (&(x1, z1), &(x2, z2)) in polygon_coords
+        .iter()
+        .zip(polygon_coords.iter().cycle().skip(1))
+    {
+        let steps: i32 = gcd((x2 - x1).abs(), (z2 - z1).abs()).max(1);
+        let (step_x, step_z) = ((x2 - x1) / steps, (z2 - z1) / steps);
+        for step in 0..=steps {
+            filled.remove(&(x1 + step * step_x, z1 + step * step_z));
+        }
+    }
+
+    let mut area: Vec<(i32, i32)> = filled.into_iter().collect();
+    area.sort_by_key(|&(x, z)| (z, x));
+    Some(area)
+}
+
+/// Splits a polygon into counterclockwise triangles by clipping ears, the convex corners
+/// without any other corner inside. Returns None if the timeout is exceeded.
+fn triangulate(
+    polygon_coords: &[(i32, i32)],
+    start_time: Instant,
+    timeout: Option<&Duration>,
+) -> Option<Vec<[(i32, i32); 3]>> {
+    let mut corners: Vec<(i32, i32)> = polygon_coords.iter().copied().dedup().collect();
+    if corners.len() > 1 && corners.first() == corners.last() {
+        corners.pop();
+    }
+    // Ears are found on counterclockwise polygons
+    let signed_area: i64 = corners
+        .iter()
+        .zip(corners.iter().cycle().skip(1))
+        .map(|(&(x1, z1), &(x2, z2))| i64::from(x1) * i64::from(z2) - i64::from(x2) * i64::from(z1))
+        .sum();
+    if signed_area < 0 {
+        corners.reverse();
+    }
+
+    let mut triangles: Vec<[(i32, i32); 3]> = vec![];
+    let mut index: usize = 0;
+    let mut checked: usize = 0;
+    while corners.len() >= 3 {
+        if timeout.is_some_and(|timeout: &Duration| &start_time.elapsed() > timeout) {
+            return None;
+        }
+
+        let len: usize = corners.len();
+        let (prev, corner, next) = (
+            corners[(index + len - 1) % len],
+            corners[index % len],
+            corners[(index + 1) % len],
+        );
+        let turn: i64 = cross(prev, corner, next);
+        let is_ear: bool = turn > 0
+            && !corners.iter().any(|&point| {
+                point != prev
+                    && point != corner
+                    && point != next
+                    && cross(prev, corner, point) >= 0
+                    && cross(corner, next, point) >= 0
+                    && cross(next, prev, point) >= 0
+            });
+        // Without an ear after a full round the polygon intersects itself, so the next
+        // convex corner is clipped anyway
+        let forced: bool = checked >= len && turn > 0;
+
+        if turn == 0 {
+            // Corners on a straight line add no area
+            corners.remove(index % len);
+            checked = 0;
+        } else if is_ear || forced {
+            triangles.push([prev, corner, next]);
+            corners.remove(index % len);
+            checked = 0;
+        } else {
+            index += 1;
+            checked += 1;
+            if checked > 2 * len {
+                break; // No convex corner left
             }
         }
+        index %= corners.len().max(1);
+    }
+    Some(triangles)
+}
+
+/// Cross product of the vectors from a to b and from a to c, positive if c lies to the left
+/// of the line from a to b in a counterclockwise polygon
+fn cross(a: (i32, i32), b: (i32, i32), c: (i32, i32)) -> i64 {
+    i64::from(b.0 - a.0) * i64::from(c.1 - a.1) - i64::from(b.1 - a.1) * i64::from(c.0 - a.0)
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
     }
+}
 
-    filled_area
+/// Calculates the area of a polygon in blocks using the shoelace formula
+fn polygon_area(polygon_coords: &[(i32, i32)]) -> u64 {
+    let twice_area: i64 = polygon_coords
+        .iter()
+        .zip(polygon_coords.iter().cycle().skip(1))
+        .map(|(&(x1, z1), &(x2, z2))| i64::from(x1) * i64::from(z2) - i64::from(x2) * i64::from(z1))
+        .sum();
+    twice_area.unsigned_abs() / 2
 }
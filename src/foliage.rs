@@ -0,0 +1,201 @@
+use crate::args::Args;
+use crate::block_definitions::*;
+use crate::element_processing::tree::create_tree;
+use crate::osm_parser::ProcessedElement;
+use crate::random::seeded_rng;
+use crate::world_editor::WorldEditor;
+use fnv::FnvHashMap;
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::collections::HashMap;
+
+/// Edge length of the cells in which mapped features are counted
+const CELL_SIZE: i32 = 32;
+/// Cells around a block whose features are taken into account, in each direction
+const NEIGHBORHOOD: i32 = 2;
+/// Chance of a tree on an unmapped block without any nearby features
+const BASE_TREE_CHANCE: f64 = 0.002;
+/// Additional tree chance next to forests and woods
+const FOREST_TREE_CHANCE: f64 = 0.02;
+/// Additional tree chance next to parks and other green spaces
+const PARK_TREE_CHANCE: f64 = 0.006;
+/// Chance of tall grass on an unmapped block
+const GRASS_CHANCE: f64 = 0.12;
+/// Chance of a flower on an unmapped block next to parks and gardens
+const FLOWER_CHANCE: f64 = 0.03;
+/// Trees keep this distance to mapped features, so crowns do not grow into roads and houses
+const TREE_CLEARANCE: i32 = 2;
+
+/// Mapped features that shape the vegetation around them
+#[derive(Debug, Default, Clone, Copy)]
+struct Surroundings {
+    forest: u32,
+    park: u32,
+    urban: u32,
+}
+
+impl Surroundings {
+    fn add(&mut self, other: &Surroundings) {
+        self.forest += other.forest;
+        self.park += other.park;
+        self.urban += other.urban;
+    }
+
+    fn share(&self, count: u32) -> f64 {
+        let total: u32 = self.forest + self.park + self.urban;
+        if total == 0 {
+            0.0
+        } else {
+            f64::from(count) / f64::from(total)
+        }
+    }
+}
+
+/// Sprinkles plausible vegetation over grass that no element was mapped on. Trees are more
+/// common next to forests and parks and rarer between buildings, and the tree species
+/// follow the climate at the latitude of the area. Returns the number of placed trees.
+pub fn add_foliage(
+    editor: &mut WorldEditor,
+    elements: &[ProcessedElement],
+    ground_level: i32,
+    latitude: f64,
+    args: &Args,
+) -> usize {
+    let cells: FnvHashMap<(i32, i32), Surroundings> = count_features(elements);
+    let tree_tags: Vec<HashMap<String, String>> = climate_tree_tags(latitude);
+    let (max_x, max_z) = editor.get_max_coords();
+    let mut rng: StdRng = seeded_rng(editor.seed(), "foliage");
+    let mut trees: usize = 0;
+
+    let mut nearby: FnvHashMap<(i32, i32), Surroundings> = FnvHashMap::default();
+    for x in 0..=max_x {
+        for z in 0..=max_z {
+            if editor.get_highest_block_y(x, z).is_some() {
+                continue;
+            }
+
+            let cell: (i32, i32) = (x.div_euclid(CELL_SIZE), z.div_euclid(CELL_SIZE));
+            let surroundings: Surroundings = *nearby.entry(cell).or_insert_with(|| {
+                let mut surroundings: Surroundings = Surroundings::default();
+                for dx in -NEIGHBORHOOD..=NEIGHBORHOOD {
+                    for dz in -NEIGHBORHOOD..=NEIGHBORHOOD {
+                        if let Some(counts) = cells.get(&(cell.0 + dx, cell.1 + dz)) {
+                            surroundings.add(counts);
+                        }
+                    }
+                }
+                surroundings
+            });
+
+            let tree_chance: f64 = (BASE_TREE_CHANCE
+                + FOREST_TREE_CHANCE * surroundings.share(surroundings.forest)
+                + PARK_TREE_CHANCE * surroundings.share(surroundings.park))
+                * (1.0 - 0.7 * surroundings.share(surroundings.urban))
+                * args.forest_density;
+            if rng.gen_bool(tree_chance.clamp(0.0, 1.0)) && is_clear(editor, x, z) {
+                let tags: &HashMap<String, String> = &tree_tags[rng.gen_range(0..tree_tags.len())];
+                create_tree(editor, x, ground_level + 1, z, tags, args.winter);
+                trees += 1;
+                continue;
+            }
+
+            // Ground plants are buried by snow in winter
+            if args.winter {
+                continue;
+            }
+            let flower_chance: f64 = FLOWER_CHANCE * (surroundings.share(surroundings.park) + 0.2);
+            if rng.gen_bool(flower_chance.min(1.0)) {
+                let flower: Block = match rng.gen_range(0..4) {
+                    0 => RED_FLOWER,
+                    1 => BLUE_FLOWER,
+                    2 => YELLOW_FLOWER,
+                    _ => WHITE_FLOWER,
+                };
+                editor.set_block(flower, x, ground_level + 1, z, None, None);
+            } else if rng.gen_bool(GRASS_CHANCE) {
+                editor.set_block(GRASS, x, ground_level + 1, z, None, None);
+            }
+        }
+    }
+
+    trees
+}
+
+/// Counts the forests, green spaces and buildings per cell by the positions of their nodes
+fn count_features(elements: &[ProcessedElement]) -> FnvHashMap<(i32, i32), Surroundings> {
+    let mut cells: FnvHashMap<(i32, i32), Surroundings> = FnvHashMap::default();
+    for element in elements {
+        let tags: &HashMap<String, String> = element.tags();
+        let tag = |key: &str| -> Option<&str> { tags.get(key).map(String::as_str) };
+        let is_forest: bool =
+            matches!(tag("landuse"), Some("forest")) || matches!(tag("natural"), Some("wood"));
+        let is_park: bool = matches!(tag("leisure"), Some("park" | "garden"))
+            || matches!(
+                tag("landuse"),
+                Some("grass" | "meadow" | "village_green" | "recreation_ground")
+            )
+            || matches!(
+                tag("natural"),
+                Some("scrub" | "heath" | "tree" | "tree_row")
+            );
+        let is_urban: bool = tags.contains_key("building");
+        if !is_forest && !is_park && !is_urban {
+            continue;
+        }
+
+        for node in element.nodes() {
+            let cell: &mut Surroundings = cells
+                .entry((node.x.div_euclid(CELL_SIZE), node.z.div_euclid(CELL_SIZE)))
+                .or_default();
+            if is_forest {
+                cell.forest += 1;
+            } else if is_park {
+                cell.park += 1;
+            } else {
+                cell.urban += 1;
+            }
+        }
+    }
+    cells
+}
+
+/// Returns whether no mapped feature is close enough to be overgrown by a tree crown
+fn is_clear(editor: &WorldEditor, x: i32, z: i32) -> bool {
+    (-TREE_CLEARANCE..=TREE_CLEARANCE).all(|dx: i32| {
+        (-TREE_CLEARANCE..=TREE_CLEARANCE)
+            .all(|dz: i32| editor.get_highest_block_y(x + dx, z + dz).is_none())
+    })
+}
+
+/// Returns the tags of the trees growing at a latitude, picked randomly for every tree:
+/// conifers in the boreal north and south, evergreen broadleaf trees in the tropics and a
+/// mix of broadleaf trees with some conifers in between
+fn climate_tree_tags(latitude: f64) -> Vec<HashMap<String, String>> {
+    let tags = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    };
+    let needleleaved: HashMap<String, String> = tags(&[("leaf_type", "needleleaved")]);
+    let broadleaved: HashMap<String, String> = tags(&[("leaf_type", "broadleaved")]);
+
+    match latitude.abs() {
+        l if l >= 55.0 => vec![
+            needleleaved.clone(),
+            needleleaved.clone(),
+            needleleaved,
+            broadleaved,
+        ],
+        l if l < 23.5 => vec![tags(&[
+            ("leaf_type", "broadleaved"),
+            ("leaf_cycle", "evergreen"),
+        ])],
+        _ => vec![
+            broadleaved.clone(),
+            broadleaved.clone(),
+            broadleaved,
+            needleleaved,
+        ],
+    }
+}
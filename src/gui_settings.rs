@@ -0,0 +1,49 @@
+use crate::file_lock::write_locked;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Settings of the GUI saved when they are used and restored on the next start, so the
+/// area, world and options don't have to be entered again
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct GuiSettings {
+    /// Selected area as shown by the map, "min_lat min_lng max_lat max_lng"
+    pub bbox: Option<String>,
+    pub world_path: Option<String>,
+    pub scale: Option<f64>,
+    pub ground_level: Option<i32>,
+    pub blocks_per_level: Option<i32>,
+    pub floodfill_timeout: Option<u64>,
+    pub winter: bool,
+    pub heatmap: bool,
+    pub palette: Option<String>,
+    /// Name of the selected preset
+    pub preset: Option<String>,
+}
+
+/// Returns the file the settings are stored in, in the config directory
+fn settings_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|config_dir: PathBuf| config_dir.join("arnis").join("gui_settings.json"))
+}
+
+/// Reads the settings of the last session. A missing or unreadable file gives the defaults,
+/// and a world that no longer exists is not selected again.
+pub fn load_settings() -> GuiSettings {
+    let mut settings: GuiSettings = settings_path()
+        .and_then(|path: PathBuf| fs::read_to_string(path).ok())
+        .and_then(|content: String| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    settings.world_path = settings
+        .world_path
+        .filter(|world_path: &String| Path::new(world_path).join("region").is_dir());
+    settings
+}
+
+/// Writes the settings for the next session
+pub fn save_settings(settings: &GuiSettings) -> Result<(), String> {
+    let path: PathBuf = settings_path().ok_or_else(|| "找不到配置目录".to_string())?;
+    let content: String = serde_json::to_string_pretty(settings)
+        .map_err(|e: serde_json::Error| format!("无法保存设置：{}", e))?;
+    write_locked(&path, &content).map_err(|e: std::io::Error| format!("无法保存设置：{}", e))
+}
@@ -0,0 +1,89 @@
+use crate::colors::{ColorPalette, RGBTuple};
+use fnv::FnvHashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Minimum edge length of the heatmap images in pixels
+const MIN_IMAGE_SIZE: u32 = 512;
+
+/// Color of chunks without any data, so empty areas stand out from sparse ones
+const EMPTY_COLOR: RGBTuple = (24, 24, 24);
+
+/// Writes per-chunk heatmaps of the placed blocks and of the processed OSM elements into
+/// the output directory. Each chunk is drawn as a square, north is at the top. The counts
+/// are keyed by chunk coordinates in the world, where the area starts at the offset.
+pub fn write_heatmaps(
+    output_dir: &Path,
+    modified_blocks: &FnvHashMap<(i32, i32), u32>,
+    element_density: &FnvHashMap<(i32, i32), u32>,
+    scale_factor_x: f64,
+    scale_factor_z: f64,
+    offset: (i32, i32),
+    palette: ColorPalette,
+) -> Result<(), String> {
+    let first_chunk: (i32, i32) = (offset.0 >> 4, offset.1 >> 4);
+    let chunks_x: u32 = (((scale_factor_x as i32 + offset.0) >> 4) - first_chunk.0 + 1) as u32;
+    let chunks_z: u32 = (((scale_factor_z as i32 + offset.1) >> 4) - first_chunk.1 + 1) as u32;
+
+    for (file_name, counts) in [
+        ("arnis_heatmap_blocks.png", modified_blocks),
+        ("arnis_heatmap_elements.png", element_density),
+    ] {
+        write_heatmap(
+            &output_dir.join(file_name),
+            counts,
+            first_chunk,
+            chunks_x,
+            chunks_z,
+            palette,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_heatmap(
+    path: &Path,
+    counts: &FnvHashMap<(i32, i32), u32>,
+    first_chunk: (i32, i32),
+    chunks_x: u32,
+    chunks_z: u32,
+    palette: ColorPalette,
+) -> Result<(), String> {
+    let cell_size: u32 = (MIN_IMAGE_SIZE / chunks_x.max(chunks_z)).max(1);
+    let width: u32 = chunks_x * cell_size;
+    let height: u32 = chunks_z * cell_size;
+
+    // Logarithmic scale, so a few dense chunks do not hide the differences elsewhere
+    let max_count: f64 = counts.values().copied().max().unwrap_or(0) as f64;
+    let max_log: f64 = (max_count + 1.0).ln().max(f64::EPSILON);
+
+    let mut pixels: Vec<u8> = Vec::with_capacity((width * height * 3) as usize);
+    for py in 0..height {
+        for px in 0..width {
+            let chunk: (i32, i32) = (
+                first_chunk.0 + (px / cell_size) as i32,
+                first_chunk.1 + (py / cell_size) as i32,
+            );
+            let color: RGBTuple = match counts.get(&chunk) {
+                Some(&count) if count > 0 => {
+                    palette.ramp_color((count as f64 + 1.0).ln() / max_log)
+                }
+                _ => EMPTY_COLOR,
+            };
+            pixels.extend_from_slice(&[color.0, color.1, color.2]);
+        }
+    }
+
+    let file: File =
+        File::create(path).map_err(|e: std::io::Error| format!("无法创建热力图文件：{}", e))?;
+    let mut encoder: png::Encoder<BufWriter<File>> =
+        png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .map_err(|e: png::EncodingError| format!("无法写入热力图：{}", e))
+}
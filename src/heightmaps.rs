@@ -0,0 +1,145 @@
+use crate::block_definitions::Block;
+use crate::world_editor::{ChunkToModify, MIN_SECTION_Y};
+use fastnbt::{LongArray, Value};
+use std::collections::HashMap;
+
+/// Bits of every height in the packed heightmaps, enough for the 384 blocks of the world
+const BITS_PER_HEIGHT: usize = 9;
+/// Heights packed into a long, which never span two longs
+const HEIGHTS_PER_LONG: usize = 64 / BITS_PER_HEIGHT;
+
+/// Name of a heightmap and whether it counts a block
+type Heightmap = (&'static str, fn(BlockKind) -> bool);
+
+/// Heightmaps Minecraft keeps for every chunk and the blocks each of them counts
+const HEIGHTMAPS: [Heightmap; 4] = [
+    ("WORLD_SURFACE", |kind: BlockKind| !kind.air),
+    ("MOTION_BLOCKING", |kind: BlockKind| {
+        kind.blocks_motion || kind.fluid
+    }),
+    ("MOTION_BLOCKING_NO_LEAVES", |kind: BlockKind| {
+        (kind.blocks_motion || kind.fluid) && !kind.leaves
+    }),
+    ("OCEAN_FLOOR", |kind: BlockKind| kind.blocks_motion),
+];
+
+/// What the heightmaps need to know about a block
+#[derive(Clone, Copy)]
+struct BlockKind {
+    air: bool,
+    blocks_motion: bool,
+    fluid: bool,
+    leaves: bool,
+}
+
+impl BlockKind {
+    fn of(block: Block) -> Self {
+        let name: &str = block.name();
+        let air: bool = matches!(name, "air" | "cave_air" | "void_air");
+        let fluid: bool = matches!(name, "water" | "lava")
+            || matches!(
+                block.properties(),
+                Some(Value::Compound(properties))
+                    if properties.get("waterlogged") == Some(&Value::String("true".to_string()))
+            );
+        // Plants, rails, signs and the like can be walked through
+        let passable: bool = matches!(
+            name,
+            "water"
+                | "lava"
+                | "light"
+                | "tall_grass"
+                | "short_grass"
+                | "fern"
+                | "poppy"
+                | "dandelion"
+                | "azure_bluet"
+                | "blue_orchid"
+                | "carrots"
+                | "potatoes"
+                | "wheat"
+                | "beetroots"
+                | "snow"
+                | "ladder"
+                | "torch"
+        ) || [
+            "rail",
+            "_sign",
+            "_torch",
+            "_button",
+            "_pressure_plate",
+            "_banner",
+        ]
+        .iter()
+        .any(|suffix: &&str| name.ends_with(suffix));
+
+        Self {
+            air,
+            blocks_motion: !air && !passable,
+            fluid,
+            leaves: name.ends_with("_leaves"),
+        }
+    }
+}
+
+/// Computes the heightmaps of a new chunk from its placed blocks
+pub fn chunk_heightmaps(chunk: &ChunkToModify) -> Value {
+    let mut kinds: [Option<BlockKind>; 256] = [None; 256];
+    let mut heights: [[u16; 256]; HEIGHTMAPS.len()] = [[0; 256]; HEIGHTMAPS.len()];
+    let Some(highest_section) = chunk.highest_section() else {
+        return heightmaps_value(&heights);
+    };
+
+    // Sections are unpacked once, from the highest down
+    let sections: Vec<(i8, [Block; 4096])> = (MIN_SECTION_Y..=highest_section)
+        .rev()
+        .filter_map(|section_y: i8| Some((section_y, chunk.section_blocks(section_y)?)))
+        .collect();
+
+    for column in 0..256 {
+        let mut missing: usize = HEIGHTMAPS.len();
+        for (section_y, blocks) in &sections {
+            for y in (0..16).rev() {
+                let block: Block = blocks[y * 256 + column];
+                let kind: BlockKind =
+                    *kinds[usize::from(block.id())].get_or_insert_with(|| BlockKind::of(block));
+                // Heights count from the bottom of the world, one above the highest block
+                let height: u16 =
+                    ((i32::from(section_y - MIN_SECTION_Y) * 16) + y as i32 + 1) as u16;
+                for (index, (_, counts)) in HEIGHTMAPS.iter().enumerate() {
+                    if heights[index][column] == 0 && counts(kind) {
+                        heights[index][column] = height;
+                        missing -= 1;
+                    }
+                }
+                if missing == 0 {
+                    break;
+                }
+            }
+            if missing == 0 {
+                break;
+            }
+        }
+    }
+
+    heightmaps_value(&heights)
+}
+
+/// Returns heightmaps with the same height in every column, e.g. for chunks with a floor only
+pub fn flat_heightmaps(height: u16) -> Value {
+    heightmaps_value(&[[height; 256]; HEIGHTMAPS.len()])
+}
+
+/// Packs the heights of the columns, ordered by z and x, into the heightmaps compound
+fn heightmaps_value(heights: &[[u16; 256]; HEIGHTMAPS.len()]) -> Value {
+    let mut heightmaps: HashMap<String, Value> = HashMap::new();
+    for ((name, _), heights) in HEIGHTMAPS.iter().zip(heights) {
+        let mut data: Vec<i64> = vec![0; heights.len().div_ceil(HEIGHTS_PER_LONG)];
+        for (column, height) in heights.iter().enumerate() {
+            data[column / HEIGHTS_PER_LONG] |=
+                i64::from(*height) << ((column % HEIGHTS_PER_LONG) * BITS_PER_HEIGHT);
+        }
+        heightmaps.insert(name.to_string(), Value::LongArray(LongArray::new(data)));
+    }
+    Value::Compound(heightmaps)
+}
@@ -0,0 +1,111 @@
+use crate::osm_parser::{ProcessedElement, ProcessedMemberRole, ProcessedNode};
+use fnv::FnvHasher;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+/// File name of the record of generated elements stored in the world directory
+pub const ELEMENTS_FILE_NAME: &str = "arnis_elements.json";
+
+/// Record of the OSM elements generated into a world, so a later run with --incremental
+/// only generates the elements that were added or changed since
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GeneratedElements {
+    bbox: (f64, f64, f64, f64),
+    scale: f64,
+    /// Block offset of the area given with --x-offset and --z-offset
+    #[serde(default)]
+    offset: (i32, i32),
+    /// Content hash of every element, keyed by element type and id, e.g. way/123
+    elements: HashMap<String, u64>,
+}
+
+impl GeneratedElements {
+    pub fn new(
+        bbox: (f64, f64, f64, f64),
+        scale: f64,
+        offset: (i32, i32),
+        elements: &[ProcessedElement],
+    ) -> Self {
+        Self {
+            bbox,
+            scale,
+            offset,
+            elements: elements
+                .iter()
+                .map(|element: &ProcessedElement| (element_key(element), element_hash(element)))
+                .collect(),
+        }
+    }
+
+    /// Returns whether the record belongs to a world generated from the same area, whose
+    /// block coordinates match the current run
+    pub fn matches(&self, bbox: (f64, f64, f64, f64), scale: f64, offset: (i32, i32)) -> bool {
+        self.bbox == bbox && self.scale == scale && self.offset == offset
+    }
+
+    /// Returns whether an element was generated before with the same content
+    pub fn contains_unchanged(&self, element: &ProcessedElement) -> bool {
+        self.elements.get(&element_key(element)) == Some(&element_hash(element))
+    }
+
+    /// Returns the number of recorded elements missing from the current elements
+    pub fn removed_count(&self, current: &GeneratedElements) -> usize {
+        self.elements
+            .keys()
+            .filter(|key: &&String| !current.elements.contains_key(*key))
+            .count()
+    }
+}
+
+/// Reads the record of generated elements of a world, if there is one
+pub fn read_generated_elements(world_path: &str) -> Option<GeneratedElements> {
+    let file: File = File::open(Path::new(world_path).join(ELEMENTS_FILE_NAME)).ok()?;
+    serde_json::from_reader(BufReader::new(file)).ok()
+}
+
+/// Writes the record of generated elements into the world directory.
+pub fn write_generated_elements(
+    world_path: &str,
+    generated: &GeneratedElements,
+) -> Result<(), String> {
+    let file: File = File::create(Path::new(world_path).join(ELEMENTS_FILE_NAME))
+        .map_err(|e: std::io::Error| format!("无法创建元素记录文件：{}", e))?;
+    serde_json::to_writer(BufWriter::new(file), generated)
+        .map_err(|e: serde_json::Error| format!("无法写入元素记录文件：{}", e))
+}
+
+fn element_key(element: &ProcessedElement) -> String {
+    format!("{}/{}", element.kind(), element.id())
+}
+
+/// Hashes the tags and node positions of an element. FNV is used as its output does not
+/// change between Rust versions, unlike the default hasher.
+fn element_hash(element: &ProcessedElement) -> u64 {
+    let mut hasher: FnvHasher = FnvHasher::default();
+
+    let mut tags: Vec<(&String, &String)> = element.tags().iter().collect();
+    tags.sort();
+    tags.hash(&mut hasher);
+
+    match element {
+        ProcessedElement::Relation(relation) => {
+            for member in &relation.members {
+                matches!(member.role, ProcessedMemberRole::Outer).hash(&mut hasher);
+                hash_nodes(&member.way.nodes, &mut hasher);
+            }
+        }
+        _ => hash_nodes(element.nodes(), &mut hasher),
+    }
+
+    hasher.finish()
+}
+
+fn hash_nodes<'a>(nodes: impl IntoIterator<Item = &'a ProcessedNode>, hasher: &mut FnvHasher) {
+    for node in nodes {
+        (node.id, node.x, node.z).hash(hasher);
+    }
+}
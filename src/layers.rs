@@ -0,0 +1,71 @@
+use crate::osm_parser::ProcessedElement;
+use clap::ValueEnum;
+
+/// Parts of the world that can be generated separately with --only, --include and --exclude
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    /// Ground layer covering the whole area
+    Terrain,
+    /// Buildings including their entrances
+    Buildings,
+    /// Roads, paths, bridges, squares and airport surfaces
+    Roads,
+    /// Railways and subways
+    Railways,
+    /// Rivers, lakes and other water
+    Water,
+    /// Land use areas, leisure areas and natural areas without vegetation
+    Landuse,
+    /// Forests, woods, scrub and single trees
+    Vegetation,
+    /// Amenities, barriers, power lines and other small features
+    Details,
+}
+
+/// Returns the layer an element belongs to, following the order in which elements are
+/// dispatched to the element processors.
+pub fn element_layer(element: &ProcessedElement) -> Layer {
+    let tags = element.tags();
+    let has = |key: &str| -> bool { tags.contains_key(key) };
+    let is = |key: &str, value: &str| -> bool { tags.get(key).map(|s| s.as_str()) == Some(value) };
+
+    if let ProcessedElement::Relation(_) = element {
+        return if has("water") {
+            Layer::Water
+        } else {
+            Layer::Landuse
+        };
+    }
+
+    if has("building") || has("building:part") || has("entrance") || has("door") {
+        Layer::Buildings
+    } else if has("railway") {
+        Layer::Railways
+    } else if has("highway")
+        || has("bridge")
+        || has("aeroway")
+        || is("place", "square")
+        || is("service", "siding")
+    {
+        Layer::Roads
+    } else if has("waterway")
+        || has("water")
+        || matches!(
+            tags.get("natural").map(|s| s.as_str()),
+            Some("water" | "wetland" | "bay" | "spring")
+        )
+    {
+        Layer::Water
+    } else if is("landuse", "forest")
+        || matches!(
+            tags.get("natural").map(|s| s.as_str()),
+            Some("wood" | "tree" | "tree_row" | "scrub")
+        )
+    {
+        Layer::Vegetation
+    } else if has("landuse") || has("leisure") || has("natural") {
+        Layer::Landuse
+    } else {
+        Layer::Details
+    }
+}
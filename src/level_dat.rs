@@ -0,0 +1,135 @@
+use crate::mc_version::McVersion;
+use fastnbt::Value;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Sets the world spawn in the level.dat of the world, so players start on the surface of
+/// the generated area instead of at the spawn of the world template
+pub fn set_spawn_point(world_path: &str, x: i32, y: i32, z: i32) -> Result<(), String> {
+    modify_level_data(world_path, |level_data: &mut HashMap<String, Value>| {
+        level_data.insert("SpawnX".to_string(), Value::Int(x));
+        level_data.insert("SpawnY".to_string(), Value::Int(y));
+        level_data.insert("SpawnZ".to_string(), Value::Int(z));
+        level_data.insert("SpawnAngle".to_string(), Value::Float(0.0));
+    })
+}
+
+/// Sets the world border around the generated area of the given size in blocks, starting at
+/// the given north-west corner. The border is always a square, so it encloses the longer
+/// side of the area exactly.
+pub fn set_world_border(
+    world_path: &str,
+    corner: (i32, i32),
+    size_x: i32,
+    size_z: i32,
+) -> Result<(), String> {
+    let size: f64 = f64::from(size_x.max(size_z));
+    modify_level_data(world_path, |level_data: &mut HashMap<String, Value>| {
+        level_data.insert(
+            "BorderCenterX".to_string(),
+            Value::Double(f64::from(corner.0) + f64::from(size_x) / 2.0),
+        );
+        level_data.insert(
+            "BorderCenterZ".to_string(),
+            Value::Double(f64::from(corner.1) + f64::from(size_z) / 2.0),
+        );
+        level_data.insert("BorderSize".to_string(), Value::Double(size));
+        level_data.insert("BorderSizeLerpTarget".to_string(), Value::Double(size));
+        level_data.insert("BorderSizeLerpTime".to_string(), Value::Long(0));
+    })
+}
+
+/// Marks the world as a world of an older Minecraft version than the world template, so the
+/// version does not refuse to open it or upgrade it on load
+pub fn set_version(world_path: &str, version: McVersion) -> Result<(), String> {
+    modify_level_data(world_path, |level_data: &mut HashMap<String, Value>| {
+        level_data.insert(
+            "DataVersion".to_string(),
+            Value::Int(version.data_version()),
+        );
+        if let Some(Value::Compound(version_data)) = level_data.get_mut("Version") {
+            version_data.insert("Id".to_string(), Value::Int(version.data_version()));
+            version_data.insert(
+                "Name".to_string(),
+                Value::String(version.name().to_string()),
+            );
+        }
+        // The player and the experimental data packs of the template are stored in the
+        // format of its version, older versions start with a new player instead
+        level_data.remove("Player");
+        if let Some(Value::Compound(data_packs)) = level_data.get_mut("DataPacks") {
+            data_packs.insert("Disabled".to_string(), Value::List(vec![]));
+        }
+
+        // Before 1.19, the world generators of the dimensions have their own seed and flat
+        // worlds list their structures in a compound
+        if version >= McVersion::V1_19_4 {
+            return;
+        }
+        let Some(Value::Compound(settings)) = level_data.get_mut("WorldGenSettings") else {
+            return;
+        };
+        let seed: Value = settings.get("seed").cloned().unwrap_or(Value::Long(0));
+        let Some(Value::Compound(dimensions)) = settings.get_mut("dimensions") else {
+            return;
+        };
+        for dimension in dimensions.values_mut() {
+            let Value::Compound(dimension) = dimension else {
+                continue;
+            };
+            let Some(Value::Compound(generator)) = dimension.get_mut("generator") else {
+                continue;
+            };
+            if let Some(Value::Compound(flat_settings)) = generator.get_mut("settings") {
+                flat_settings.remove("structure_overrides");
+                let structures: HashMap<String, Value> =
+                    HashMap::from([("structures".to_string(), Value::Compound(HashMap::new()))]);
+                flat_settings.insert("structures".to_string(), Value::Compound(structures));
+                continue;
+            }
+            generator.insert("seed".to_string(), seed.clone());
+            if let Some(Value::Compound(biome_source)) = generator.get_mut("biome_source") {
+                biome_source.insert("seed".to_string(), seed.clone());
+            }
+        }
+    })
+}
+
+/// Reads the level.dat of the world, changes its Data compound and writes it back
+fn modify_level_data(
+    world_path: &str,
+    modify: impl FnOnce(&mut HashMap<String, Value>),
+) -> Result<(), String> {
+    let level_path: PathBuf = Path::new(world_path).join("level.dat");
+    let compressed: Vec<u8> =
+        fs::read(&level_path).map_err(|e: std::io::Error| format!("无法读取 level.dat：{}", e))?;
+    let mut data: Vec<u8> = Vec::new();
+    GzDecoder::new(compressed.as_slice())
+        .read_to_end(&mut data)
+        .map_err(|e: std::io::Error| format!("无法解压 level.dat：{}", e))?;
+    let mut level: Value = fastnbt::from_bytes(&data)
+        .map_err(|e: fastnbt::error::Error| format!("无法解析 level.dat：{}", e))?;
+
+    let Value::Compound(root) = &mut level else {
+        return Err("level.dat 格式无效".to_string());
+    };
+    let Some(Value::Compound(level_data)) = root.get_mut("Data") else {
+        return Err("level.dat 格式无效".to_string());
+    };
+    modify(level_data);
+
+    let data: Vec<u8> = fastnbt::to_bytes(&level)
+        .map_err(|e: fastnbt::error::Error| format!("无法序列化 level.dat：{}", e))?;
+    let mut encoder: GzEncoder<Vec<u8>> = GzEncoder::new(Vec::new(), Compression::default());
+    let compressed: Vec<u8> = encoder
+        .write_all(&data)
+        .and_then(|_| encoder.finish())
+        .map_err(|e: std::io::Error| format!("无法压缩 level.dat：{}", e))?;
+    fs::write(&level_path, compressed)
+        .map_err(|e: std::io::Error| format!("无法写入 level.dat：{}", e))
+}
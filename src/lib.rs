@@ -0,0 +1,195 @@
+//! Arnis core: generates Minecraft worlds from OpenStreetMap data.
+//!
+//! The `arnis` binary is a thin command line and GUI frontend around this library, so
+//! server plugins and web services can embed the generation without running the binary.
+//! The GUI is the default `gui` feature, with `default-features = false` the library builds
+//! without tauri and its GTK/WebKit dependencies:
+//!
+//! ```no_run
+//! use arnis_core::args::Args;
+//! use arnis_core::context::GenerationContext;
+//! use arnis_core::output::create_backend;
+//! use clap::Parser;
+//!
+//! let options = Args::parse_from(["arnis", "--bbox", "8.54,47.37,8.55,47.38", "--path", "world"]);
+//! let context = GenerationContext::new()
+//!     .with_progress_listener(|event| println!("{:?} {:.0}%", event.stage, event.progress));
+//! arnis_core::create_new_world("world".as_ref(), "Zürich").unwrap();
+//! let mut output = create_backend(&options).unwrap();
+//! let bbox = (8.54, 47.37, 8.55, 47.38);
+//! arnis_core::generate_world(bbox, &options, output.as_mut(), &context).unwrap();
+//! ```
+//!
+//! Everything a generation depends on is passed in, so several generations can run in the
+//! same process, each with its own options and context.
+
+pub mod area_polygon;
+pub mod args;
+pub mod batch;
+pub mod biome_definitions;
+pub mod block_definitions;
+pub mod block_palette;
+pub mod bresenham;
+pub mod building_styles;
+pub mod cancel;
+pub mod checkpoint;
+pub mod colors;
+pub mod context;
+pub mod data_processing;
+pub mod datapack;
+pub mod debug_dump;
+pub mod element_processing;
+pub mod file_lock;
+pub mod floodfill;
+pub mod foliage;
+pub mod gui_settings;
+pub mod heatmap;
+pub mod heightmaps;
+pub mod incremental;
+pub mod layers;
+pub mod level_dat;
+pub mod lighting;
+pub mod locate;
+pub mod manifest;
+pub mod map_preview;
+pub mod mc_version;
+pub mod metadata;
+pub mod night_lights;
+pub mod notification;
+pub mod osm_parser;
+pub mod output;
+pub mod presets;
+pub mod progress;
+pub mod random;
+pub mod retrieve_data;
+pub mod rotation;
+pub mod scheduling;
+pub mod server;
+pub mod suggest;
+pub mod transliteration;
+pub mod tree_templates;
+pub mod version_check;
+pub mod water_validation;
+pub mod world_editor;
+pub mod world_health;
+
+use crate::args::Args;
+use crate::context::GenerationContext;
+use crate::output::OutputBackend;
+use colored::Colorize;
+use fastnbt::Value;
+use flate2::read::GzDecoder;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// Fetches the map data of the area and generates the world into the output.
+///
+/// The bounding box is (min_lng, min_lat, max_lng, max_lat) as given with --bbox, the
+/// options are the command line arguments. The output is usually the backend created for
+/// the options with `output::create_backend`, but can be any `OutputBackend` receiving
+/// the placed blocks. Progress is reported to the listener of the context, and the
+/// generation stops once the cancel token of the context is cancelled.
+pub fn generate_world(
+    bbox: (f64, f64, f64, f64),
+    options: &Args,
+    output: &mut dyn OutputBackend,
+    context: &GenerationContext,
+) -> Result<(), String> {
+    let raw_data: serde_json::Value = retrieve_data::fetch_data(
+        bbox,
+        options.polygon.as_ref(),
+        options.file.as_deref(),
+        options.debug,
+        &options.downloader,
+        options.cache_mode(),
+        context,
+    )
+    .map_err(|e| format!("无法获取数据：{}", e))?;
+
+    let (parsed_elements, scale_factor_x, scale_factor_z) =
+        osm_parser::parse_osm_data(&raw_data, bbox, options, context);
+
+    // Write the parsed OSM data to files for inspection with `arnis debug-query`
+    if options.debug {
+        let dump_dir: &Path = Path::new(debug_dump::DEBUG_DUMP_DIR);
+        if let Err(e) = debug_dump::write_debug_dump(&parsed_elements, dump_dir) {
+            eprintln!("{}", format!("警告：{}", e).yellow());
+        }
+    }
+
+    data_processing::generate_world(
+        parsed_elements,
+        options,
+        bbox,
+        scale_factor_x,
+        scale_factor_z,
+        output,
+        context,
+    )
+}
+
+/// Creates an empty Minecraft world with the given name to generate into
+pub fn create_new_world(world_path: &Path, world_name: &str) -> Result<(), String> {
+    // Create the new world directory structure
+    fs::create_dir_all(world_path.join("region"))
+        .map_err(|e: std::io::Error| format!("无法创建世界目录：{}", e))?;
+
+    // Region files are created when the world is saved
+
+    // Add the level.dat file
+    const LEVEL_TEMPLATE: &[u8] = include_bytes!("../mcassets/level.dat");
+
+    // Decompress the gzipped level.template
+    let mut decoder: GzDecoder<&[u8]> = GzDecoder::new(LEVEL_TEMPLATE);
+    let mut decompressed_data: Vec<u8> = Vec::new();
+    decoder
+        .read_to_end(&mut decompressed_data)
+        .map_err(|e: std::io::Error| format!("无法解压 level.template: {}", e))?;
+
+    // Parse the decompressed NBT data
+    let mut level_data: Value = fastnbt::from_bytes(&decompressed_data)
+        .map_err(|e: fastnbt::error::Error| format!("无法解析 level.dat 模板：{}", e))?;
+
+    // Modify the LevelName and LastPlayed fields
+    if let Value::Compound(ref mut root) = level_data {
+        if let Some(Value::Compound(ref mut data)) = root.get_mut("Data") {
+            // Update LevelName
+            data.insert(
+                "LevelName".to_string(),
+                Value::String(world_name.to_string()),
+            );
+
+            // Update LastPlayed to the current Unix time in milliseconds
+            let current_time: std::time::Duration = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_err(|e: std::time::SystemTimeError| format!("无法获取当前时间：{}", e))?;
+            let current_time_millis: i64 = current_time.as_millis() as i64;
+            data.insert("LastPlayed".to_string(), Value::Long(current_time_millis));
+        }
+    }
+
+    // Serialize the updated NBT data back to bytes
+    let serialized_level_data: Vec<u8> = fastnbt::to_bytes(&level_data)
+        .map_err(|e: fastnbt::error::Error| format!("无法序列化更新的 level.dat：{}", e))?;
+
+    // Compress the serialized data back to gzip
+    let mut encoder: flate2::write::GzEncoder<Vec<u8>> =
+        flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder
+        .write_all(&serialized_level_data)
+        .map_err(|e: std::io::Error| format!("无法压缩更新的 level.dat：{}", e))?;
+    let compressed_level_data: Vec<u8> = encoder
+        .finish()
+        .map_err(|e: std::io::Error| format!("无法完成 level.dat 的压缩：{}", e))?;
+
+    fs::write(world_path.join("level.dat"), compressed_level_data)
+        .map_err(|e: std::io::Error| format!("无法创建 level.dat 文件：{}", e))?;
+
+    // Add the icon.png file
+    const ICON_TEMPLATE: &[u8] = include_bytes!("../mcassets/icon.png");
+    fs::write(world_path.join("icon.png"), ICON_TEMPLATE)
+        .map_err(|e: std::io::Error| format!("无法创建 icon.png 文件：{}", e))?;
+
+    Ok(())
+}
@@ -0,0 +1,449 @@
+use crate::block_definitions::{Block, AIR, COBBLESTONE};
+use crate::world_editor::{Chunk, WorldEditor, MIN_SECTION_Y, SECTION_COUNT};
+use fnv::FnvHashMap;
+use std::collections::VecDeque;
+
+/// Chunks along each side of a tile, which is lit in one pass
+pub const TILE_CHUNKS: i32 = 8;
+/// Chunks around a tile whose blocks are included, since light spreads up to 15 blocks
+pub const PADDING_CHUNKS: i32 = 1;
+/// Blocks along each side of the lit volume
+const VOLUME_SIZE: usize = ((TILE_CHUNKS + 2 * PADDING_CHUNKS) * 16) as usize;
+/// Light level of full daylight and of the brightest light sources
+const MAX_LIGHT: u8 = 15;
+
+/// How a block lets light through and how much light it emits
+#[derive(Clone, Copy)]
+struct LightProperties {
+    /// Light lost when passing through the block, 15 for full blocks
+    opacity: u8,
+    emission: u8,
+    /// Bottom slabs and stairs are lit from above but keep the light from passing downwards
+    solid_bottom: bool,
+}
+
+impl LightProperties {
+    fn of(block: Block) -> Self {
+        Self::of_name(block.name())
+    }
+
+    /// Properties of a block given by its name without the namespace, also for blocks of
+    /// existing worlds that Arnis does not place. Unknown blocks are treated as full blocks.
+    fn of_name(name: &str) -> Self {
+        let emission: u8 = match name {
+            "glowstone" | "lantern" | "light" | "sea_lantern" | "jack_o_lantern"
+            | "shroomlight" | "campfire" | "lava" | "beacon" => 15,
+            "torch" | "wall_torch" | "end_rod" => 14,
+            "soul_lantern" | "soul_torch" | "soul_wall_torch" | "soul_campfire" => 10,
+            _ => 0,
+        };
+        let solid_bottom: bool = name.ends_with("_slab")
+            || name.ends_with("_stairs")
+            || matches!(name, "farmland" | "daylight_detector");
+        let transparent: bool = solid_bottom
+            || matches!(
+                name,
+                "air"
+                    | "cave_air"
+                    | "void_air"
+                    | "light"
+                    | "glass"
+                    | "iron_bars"
+                    | "ladder"
+                    | "rail"
+                    | "tall_grass"
+                    | "short_grass"
+                    | "fern"
+                    | "poppy"
+                    | "dandelion"
+                    | "azure_bluet"
+                    | "blue_orchid"
+                    | "carrots"
+                    | "potatoes"
+                    | "wheat"
+                    | "beetroots"
+                    | "snow"
+                    | "lantern"
+                    | "scaffolding"
+                    | "cauldron"
+                    | "torch"
+                    | "end_rod"
+                    | "chest"
+                    | "lectern"
+                    | "flower_pot"
+            )
+            || name.starts_with("potted_")
+            || [
+                "_fence",
+                "_wall",
+                "_pane",
+                "_glass",
+                "_sign",
+                "_door",
+                "_trapdoor",
+                "_carpet",
+                "_torch",
+                "_button",
+                "_pressure_plate",
+                "_rail",
+                "_lantern",
+                "_banner",
+            ]
+            .iter()
+            .any(|suffix: &&str| name.ends_with(suffix));
+        let opacity: u8 = if name == "water" || name.ends_with("_leaves") {
+            1
+        } else if transparent {
+            0
+        } else {
+            MAX_LIGHT
+        };
+
+        Self {
+            opacity,
+            emission,
+            solid_bottom,
+        }
+    }
+}
+
+/// Sky and block light of a section as nibble arrays in the layout of the chunk format
+pub struct SectionLight {
+    pub y: i8,
+    pub sky: Vec<i8>,
+    pub block: Vec<i8>,
+}
+
+/// Light of the sections of a chunk, from the bottom of the world up to the first section
+/// above the placed blocks. Higher sections receive full daylight.
+pub struct ChunkLight {
+    pub sections: Vec<SectionLight>,
+}
+
+/// Light of a square of chunks, computed together with the blocks of the surrounding chunks
+/// so light crossing chunk borders is consistent
+pub struct TileLight {
+    /// Coordinates of the first chunk of the tile
+    origin: (i32, i32),
+    chunks: Vec<ChunkLight>,
+}
+
+impl TileLight {
+    /// Computes the light of the tile starting at the given chunk. Existing chunks of a world
+    /// that is merged into, keyed by their chunk coordinates, are lit with all their blocks.
+    /// Other chunks without placed blocks are lit as the empty chunks written for them, with
+    /// only a floor at the bottom.
+    pub fn compute(
+        editor: &WorldEditor,
+        existing: &FnvHashMap<(i32, i32), Chunk>,
+        origin_x: i32,
+        origin_z: i32,
+    ) -> Self {
+        let mut volume: LightVolume = LightVolume::new(editor, existing, origin_x, origin_z);
+        volume.light_sky();
+        volume.spread_block_light();
+
+        let mut chunks: Vec<ChunkLight> = vec![];
+        for chunk_x in 0..TILE_CHUNKS {
+            for chunk_z in 0..TILE_CHUNKS {
+                chunks.push(volume.chunk_light(chunk_x + PADDING_CHUNKS, chunk_z + PADDING_CHUNKS));
+            }
+        }
+
+        Self {
+            origin: (origin_x, origin_z),
+            chunks,
+        }
+    }
+
+    /// Returns the light of a chunk of the tile, given in world chunk coordinates
+    pub fn chunk(&self, chunk_x: i32, chunk_z: i32) -> &ChunkLight {
+        let local_x: i32 = chunk_x - self.origin.0;
+        let local_z: i32 = chunk_z - self.origin.1;
+        &self.chunks[(local_x * TILE_CHUNKS + local_z) as usize]
+    }
+}
+
+/// Blocks of a tile and its padding with their light, indexed by y, z, x
+struct LightVolume {
+    height: usize,
+    properties: Vec<LightProperties>,
+    sky: Vec<u8>,
+    block: Vec<u8>,
+    /// Light sources, spread once the volume is filled
+    sources: VecDeque<usize>,
+}
+
+impl LightVolume {
+    fn new(
+        editor: &WorldEditor,
+        existing: &FnvHashMap<(i32, i32), Chunk>,
+        origin_x: i32,
+        origin_z: i32,
+    ) -> Self {
+        let padded_chunks: i32 = TILE_CHUNKS + 2 * PADDING_CHUNKS;
+        let chunk_at = |chunk_x: i32, chunk_z: i32| {
+            editor.chunk(
+                origin_x - PADDING_CHUNKS + chunk_x,
+                origin_z - PADDING_CHUNKS + chunk_z,
+            )
+        };
+        let existing_at = |chunk_x: i32, chunk_z: i32| {
+            existing.get(&(
+                origin_x - PADDING_CHUNKS + chunk_x,
+                origin_z - PADDING_CHUNKS + chunk_z,
+            ))
+        };
+
+        // One more section above the highest block takes the light spreading upwards from
+        // light sources on roofs
+        let highest_section: i8 = (0..padded_chunks)
+            .flat_map(|chunk_x: i32| (0..padded_chunks).map(move |chunk_z: i32| (chunk_x, chunk_z)))
+            .filter_map(|(chunk_x, chunk_z)| match existing_at(chunk_x, chunk_z) {
+                Some(chunk) => chunk.highest_section(),
+                None => chunk_at(chunk_x, chunk_z)?.highest_section(),
+            })
+            .max()
+            .unwrap_or(MIN_SECTION_Y);
+        let top_section: i8 = (highest_section + 1).min(MIN_SECTION_Y + SECTION_COUNT as i8 - 1);
+        let height: usize = (top_section - MIN_SECTION_Y + 1) as usize * 16;
+
+        let air: LightProperties = LightProperties::of(AIR);
+        let size: usize = VOLUME_SIZE * VOLUME_SIZE * height;
+        let mut volume: LightVolume = Self {
+            height,
+            properties: vec![air; size],
+            sky: vec![0; size],
+            block: vec![0; size],
+            sources: VecDeque::new(),
+        };
+
+        let mut cache: [Option<LightProperties>; 256] = [None; 256];
+        for chunk_x in 0..padded_chunks {
+            for chunk_z in 0..padded_chunks {
+                let base_x: usize = chunk_x as usize * 16;
+                let base_z: usize = chunk_z as usize * 16;
+                if let Some(chunk) = existing_at(chunk_x, chunk_z) {
+                    for section in 0..height / 16 {
+                        let Some((names, indices)) =
+                            chunk.section_palette(MIN_SECTION_Y + section as i8)
+                        else {
+                            continue;
+                        };
+                        let palette: Vec<LightProperties> = names
+                            .iter()
+                            .map(|name: &&str| LightProperties::of_name(name))
+                            .collect();
+                        for (offset, &p) in indices.iter().enumerate() {
+                            volume.place(base_x, base_z, section, offset, palette[p]);
+                        }
+                    }
+                    continue;
+                }
+                let Some(chunk) = chunk_at(chunk_x, chunk_z) else {
+                    let floor: LightProperties = LightProperties::of(COBBLESTONE);
+                    for x in 0..16 {
+                        for z in 0..16 {
+                            let index: usize = volume.index(base_x + x, 0, base_z + z);
+                            volume.properties[index] = floor;
+                        }
+                    }
+                    continue;
+                };
+
+                for section in 0..height / 16 {
+                    let Some(blocks) = chunk.section_blocks(MIN_SECTION_Y + section as i8) else {
+                        continue;
+                    };
+                    for (offset, block) in blocks.iter().enumerate() {
+                        if *block == AIR {
+                            continue;
+                        }
+                        let properties: LightProperties = *cache[usize::from(block.id())]
+                            .get_or_insert_with(|| LightProperties::of(*block));
+                        volume.place(base_x, base_z, section, offset, properties);
+                    }
+                }
+            }
+        }
+
+        volume
+    }
+
+    /// Places a block given by its offset in a section of the chunk at the given base
+    fn place(
+        &mut self,
+        base_x: usize,
+        base_z: usize,
+        section: usize,
+        offset: usize,
+        properties: LightProperties,
+    ) {
+        let index: usize = self.index(
+            base_x + offset % 16,
+            section * 16 + offset / 256,
+            base_z + offset / 16 % 16,
+        );
+        self.properties[index] = properties;
+        if properties.emission > 0 {
+            self.block[index] = properties.emission;
+            self.sources.push_back(index);
+        }
+    }
+
+    fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        (y * VOLUME_SIZE + z) * VOLUME_SIZE + x
+    }
+
+    /// Lights every column from the sky downwards, then spreads the daylight sideways
+    /// under overhangs and into openings
+    fn light_sky(&mut self) {
+        // Lowest y of every column from which on all blocks receive full daylight
+        let mut surface: Vec<usize> = vec![self.height; VOLUME_SIZE * VOLUME_SIZE];
+        // Lowest y of every column still reached by daylight from straight above
+        let mut bottom: Vec<usize> = vec![self.height; VOLUME_SIZE * VOLUME_SIZE];
+
+        for z in 0..VOLUME_SIZE {
+            for x in 0..VOLUME_SIZE {
+                let column: usize = z * VOLUME_SIZE + x;
+                let mut light: u8 = MAX_LIGHT;
+                let mut y: usize = self.height;
+                while y > 0 && light > 0 {
+                    y -= 1;
+                    let index: usize = self.index(x, y, z);
+                    let properties: LightProperties = self.properties[index];
+                    light = light.saturating_sub(properties.opacity);
+                    self.sky[index] = light;
+                    if light == MAX_LIGHT {
+                        surface[column] = y;
+                    }
+                    if light > 0 {
+                        bottom[column] = y;
+                    }
+                    if properties.solid_bottom {
+                        light = 0;
+                    }
+                }
+            }
+        }
+
+        // Only blocks below the surface of a neighboring column can light that column
+        // sideways, above it every neighbor is already fully lit
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        for z in 0..VOLUME_SIZE {
+            for x in 0..VOLUME_SIZE {
+                let neighbors: [(usize, usize); 4] = [
+                    (x.saturating_sub(1), z),
+                    ((x + 1).min(VOLUME_SIZE - 1), z),
+                    (x, z.saturating_sub(1)),
+                    (x, (z + 1).min(VOLUME_SIZE - 1)),
+                ];
+                let highest_neighbor: usize = neighbors
+                    .iter()
+                    .map(|(neighbor_x, neighbor_z)| surface[neighbor_z * VOLUME_SIZE + neighbor_x])
+                    .max()
+                    .unwrap_or(0);
+                for y in bottom[z * VOLUME_SIZE + x]..highest_neighbor.min(self.height) {
+                    let index: usize = self.index(x, y, z);
+                    if self.sky[index] > 1 {
+                        queue.push_back(index);
+                    }
+                }
+            }
+        }
+        Self::spread(&mut self.sky, &self.properties, self.height, queue);
+    }
+
+    fn spread_block_light(&mut self) {
+        let sources: VecDeque<usize> = std::mem::take(&mut self.sources);
+        Self::spread(&mut self.block, &self.properties, self.height, sources);
+    }
+
+    /// Spreads light from the queued blocks to their neighbors, losing at least one level
+    /// per block, until it is blocked or fades out
+    fn spread(
+        light: &mut [u8],
+        properties: &[LightProperties],
+        height: usize,
+        mut queue: VecDeque<usize>,
+    ) {
+        let layer: usize = VOLUME_SIZE * VOLUME_SIZE;
+        while let Some(index) = queue.pop_front() {
+            let level: u8 = light[index];
+            if level <= 1 {
+                continue;
+            }
+            let x: usize = index % VOLUME_SIZE;
+            let z: usize = index / VOLUME_SIZE % VOLUME_SIZE;
+            let y: usize = index / layer;
+
+            let mut neighbors: [Option<usize>; 6] = [None; 6];
+            if x > 0 {
+                neighbors[0] = Some(index - 1);
+            }
+            if x + 1 < VOLUME_SIZE {
+                neighbors[1] = Some(index + 1);
+            }
+            if z > 0 {
+                neighbors[2] = Some(index - VOLUME_SIZE);
+            }
+            if z + 1 < VOLUME_SIZE {
+                neighbors[3] = Some(index + VOLUME_SIZE);
+            }
+            if y > 0 && !properties[index].solid_bottom {
+                neighbors[4] = Some(index - layer);
+            }
+            if y + 1 < height {
+                neighbors[5] = Some(index + layer);
+            }
+
+            for (direction, neighbor) in neighbors.into_iter().enumerate() {
+                let Some(neighbor) = neighbor else {
+                    continue;
+                };
+                let neighbor_properties: LightProperties = properties[neighbor];
+                // Light can not enter the solid bottom of a block from below
+                if neighbor_properties.opacity >= MAX_LIGHT
+                    || (direction == 5 && neighbor_properties.solid_bottom)
+                {
+                    continue;
+                }
+                let neighbor_level: u8 = level.saturating_sub(neighbor_properties.opacity.max(1));
+                if neighbor_level > light[neighbor] {
+                    light[neighbor] = neighbor_level;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+    }
+
+    /// Packs the light of a chunk of the volume into the nibble arrays of its sections
+    fn chunk_light(&self, chunk_x: i32, chunk_z: i32) -> ChunkLight {
+        let base_x: usize = chunk_x as usize * 16;
+        let base_z: usize = chunk_z as usize * 16;
+        let sections: Vec<SectionLight> = (0..self.height / 16)
+            .map(|section: usize| {
+                let mut sky: Vec<i8> = vec![0; 2048];
+                let mut block: Vec<i8> = vec![0; 2048];
+                for offset in 0..4096 {
+                    let index: usize = self.index(
+                        base_x + offset % 16,
+                        section * 16 + offset / 256,
+                        base_z + offset / 16 % 16,
+                    );
+                    // Even blocks are stored in the low nibble, odd blocks in the high nibble
+                    let shift: usize = (offset % 2) * 4;
+                    sky[offset / 2] |= (self.sky[index] << shift) as i8;
+                    block[offset / 2] |= (self.block[index] << shift) as i8;
+                }
+                SectionLight {
+                    y: MIN_SECTION_Y + section as i8,
+                    sky,
+                    block,
+                }
+            })
+            .collect();
+
+        ChunkLight { sections }
+    }
+}
@@ -0,0 +1,31 @@
+use crate::args::LocateArgs;
+use crate::metadata::{read_metadata, WorldMetadata};
+use colored::Colorize;
+use std::process::exit;
+
+/// Runs the `arnis locate` command, converting coordinates with the projection metadata
+/// stored in a generated world.
+pub fn run_locate(args: &LocateArgs) {
+    let metadata: WorldMetadata = match read_metadata(&args.world) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            eprintln!("{}", format!("错误！{}", e).red().bold());
+            exit(1);
+        }
+    };
+
+    if args.geo {
+        let (lat, lon) = (args.first, args.second);
+        let (x, z) = metadata.lat_lon_to_block(lat, lon);
+        println!("纬度 {:.6}，经度 {:.6} -> X {}，Z {}", lat, lon, x, z);
+        if !(metadata.min_lat..=metadata.max_lat).contains(&lat)
+            || !(metadata.min_lon..=metadata.max_lon).contains(&lon)
+        {
+            println!("{}", "警告：该位置不在生成区域内".yellow());
+        }
+    } else {
+        let (x, z) = (args.first, args.second);
+        let (lat, lon) = metadata.block_to_lat_lon(x, z);
+        println!("X {}，Z {} -> 纬度 {:.6}，经度 {:.6}", x, z, lat, lon);
+    }
+}
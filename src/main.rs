@@ -1,32 +1,28 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod args;
-mod block_definitions;
-mod bresenham;
-mod colors;
-mod data_processing;
-mod element_processing;
-mod floodfill;
-mod osm_parser;
-mod progress;
-mod retrieve_data;
-mod version_check;
-mod world_editor;
-
-use args::Args;
+use arnis_core::args::{Args, DebugQueryArgs, LocateArgs, ServeArgs, SuggestArgs};
+use arnis_core::cancel::CancelToken;
+use arnis_core::context::GenerationContext;
+use arnis_core::{
+    batch, block_palette, building_styles, cancel, colors, create_new_world, debug_dump, file_lock,
+    floodfill, gui_settings, locate, map_preview, mc_version, notification, output, presets,
+    progress, retrieve_data, server, suggest, transliteration, tree_templates, version_check,
+    world_health,
+};
 use clap::Parser;
 use colored::*;
-use fastnbt::Value;
-use flate2::read::GzDecoder;
 use fs2::FileExt;
 use rfd::FileDialog;
 use std::{
     env,
-    fs::{self, File},
-    io::{Read, Write},
+    fs::File,
     path::{Path, PathBuf},
+    sync::Mutex,
 };
 
+/// Token cancelling the generation started last in the GUI
+static GUI_CANCEL_TOKEN: Mutex<Option<CancelToken>> = Mutex::new(None);
+
 fn print_banner() {
     let version: &str = env!("CARGO_PKG_VERSION");
     let repository: &str = env!("CARGO_PKG_REPOSITORY");
@@ -54,6 +50,35 @@ fn main() {
     // Parse arguments to decide whether to launch the UI or CLI
     let raw_args: Vec<String> = std::env::args().collect();
 
+    // Coordinate conversion for previously generated worlds
+    if raw_args.get(1).map(String::as_str) == Some("locate") {
+        let locate_args: LocateArgs = LocateArgs::parse_from(raw_args.iter().skip(1));
+        locate::run_locate(&locate_args);
+        return;
+    }
+
+    // Scale and ground level suggestion for a bounding box
+    if raw_args.get(1).map(String::as_str) == Some("suggest") {
+        let suggest_args: SuggestArgs = SuggestArgs::parse_from(raw_args.iter().skip(1));
+        suggest::run_suggest(&suggest_args);
+        return;
+    }
+
+    // Search in the debug dump of a previous run with --debug
+    if raw_args.get(1).map(String::as_str) == Some("debug-query") {
+        let query_args: DebugQueryArgs = DebugQueryArgs::parse_from(raw_args.iter().skip(1));
+        debug_dump::run_debug_query(&query_args);
+        return;
+    }
+
+    // HTTP server generating worlds for web frontends and bots
+    if raw_args.iter().any(|arg: &String| arg == "--serve") {
+        print_banner();
+        let serve_args: ServeArgs = ServeArgs::parse_from(raw_args.iter());
+        server::run_server(&serve_args);
+        return;
+    }
+
     // Check if either `--help` or `--path` is present to run command-line mode
     let is_help: bool = raw_args.iter().any(|arg: &String| arg == "--help");
     let is_path_provided: bool = raw_args
@@ -73,60 +98,88 @@ fn main() {
         }
 
         // Parse input arguments
-        let args: Args = Args::parse();
+        // Options of the --preset are added, unless they are given on the command line
+        let command_line: Vec<String> = match presets::apply_preset(&raw_args) {
+            Ok(command_line) => command_line,
+            Err(e) => {
+                eprintln!("{}", format!("错误！{}", e).red().bold());
+                std::process::exit(1);
+            }
+        };
+        let args: Args = Args::parse_from(command_line);
         args.run();
+        let cancel_token: CancelToken = CancelToken::new();
+        cancel::install_ctrl_c_handler(cancel_token.clone());
+        let context: GenerationContext =
+            GenerationContext::new().with_cancel_token(cancel_token.clone());
+
+        // Held until the world is saved, so no other process writes the same world
+        let _world_lock: Option<file_lock::WorldLock> =
+            if args.format == output::OutputFormat::Anvil {
+                match file_lock::lock_world(&args.path) {
+                    Ok(lock) => Some(lock),
+                    Err(e) => {
+                        eprintln!("{}", format!("错误！{}", e).red().bold());
+                        std::process::exit(1);
+                    }
+                }
+            } else {
+                None
+            };
 
-        let bbox: Vec<f64> = args
-            .bbox
-            .as_ref()
-            .expect("需要边界框")
-            .split(',')
-            .map(|s: &str| s.parse::<f64>().expect("边界框坐标无效"))
-            .collect::<Vec<f64>>();
-
-        let bbox_tuple: (f64, f64, f64, f64) = (bbox[0], bbox[1], bbox[2], bbox[3]);
-
-        // Fetch data
-        let raw_data: serde_json::Value =
-            retrieve_data::fetch_data(bbox_tuple, args.file.as_deref(), args.debug, "requests")
-                .expect("无法获取数据");
-
-        // Parse raw data
-        let (mut parsed_elements, scale_factor_x, scale_factor_z) =
-            osm_parser::parse_osm_data(&raw_data, bbox_tuple, &args);
-        parsed_elements.sort_by_key(|element: &osm_parser::ProcessedElement| {
-            osm_parser::get_priority(element)
-        });
+        if let Some(block_config) = &args.block_config {
+            if let Err(e) = block_palette::load_block_palette(block_config) {
+                eprintln!("{}", format!("错误！{}", e).red().bold());
+                std::process::exit(1);
+            }
+        }
 
-        // Write the parsed OSM data to a file for inspection
-        if args.debug {
-            let mut output_file: File =
-                File::create("parsed_osm_data.txt").expect("无法创建输出文件");
-            for element in &parsed_elements {
-                writeln!(
-                    output_file,
-                    "元素 ID：{}，类型：{}，标签：{:?}",
-                    element.id(),
-                    element.kind(),
-                    element.tags(),
-                )
-                .expect("无法写入输出文件");
+        if let Some(tree_templates) = &args.tree_templates {
+            match tree_templates::load_tree_templates(tree_templates) {
+                Ok(count) => println!("已加载 {} 个树木模板", count),
+                Err(e) => {
+                    eprintln!("{}", format!("错误！{}", e).red().bold());
+                    std::process::exit(1);
+                }
             }
         }
 
-        // Generate world
-        let _ =
-            data_processing::generate_world(parsed_elements, &args, scale_factor_x, scale_factor_z);
+        // Validated in args.run()
+        let bboxes: Vec<(f64, f64, f64, f64)> = args.bboxes().expect("需要边界框");
+
+        // Fetch the data and generate the world, several areas one after another
+        let result: Result<(), String> = if let [bbox_tuple] = bboxes[..] {
+            output::create_backend(&args).and_then(|mut output: Box<dyn output::OutputBackend>| {
+                arnis_core::generate_world(bbox_tuple, &args, output.as_mut(), &context)
+            })
+        } else {
+            batch::generate_batch(&bboxes, &args, &context)
+        };
+        if let Err(e) = result {
+            eprintln!("{}", format!("错误！{}", e).red().bold());
+            std::process::exit(if cancel_token.is_cancelled() { 130 } else { 1 });
+        }
     } else {
         // Launch the UI
         println!("正在启动 UI...");
         tauri::Builder::default()
             .invoke_handler(tauri::generate_handler![
                 gui_select_world,
+                gui_check_world,
                 gui_start_generation,
+                gui_cancel_generation,
                 gui_get_version,
-                gui_check_for_updates
+                gui_check_for_updates,
+                gui_get_cache_coverage,
+                gui_suggest_scale,
+                gui_preview_data,
+                gui_list_presets,
+                gui_save_preset,
+                gui_load_settings,
+                gui_save_settings,
+                gui_open_world_folder
             ])
+            .plugin(tauri_plugin_notification::init())
             .setup(|app| {
                 let app_handle = app.handle();
                 let main_window = tauri::Manager::get_webview_window(app_handle, "main")
@@ -238,76 +291,10 @@ fn gui_select_world(generate_new: bool) -> Result<String, String> {
     }
 }
 
-fn create_new_world(world_path: &Path, world_name: &str) -> Result<(), String> {
-    // Create the new world directory structure
-    fs::create_dir_all(world_path.join("region"))
-        .map_err(|e: std::io::Error| format!("无法创建世界目录：{}", e))?;
-
-    // Copy the region template file
-    const REGION_TEMPLATE: &[u8] = include_bytes!("../mcassets/region.template");
-    let region_path = world_path.join("region").join("r.0.0.mca");
-    fs::write(&region_path, REGION_TEMPLATE)
-        .map_err(|e: std::io::Error| format!("无法创建区域文件：{}", e))?;
-
-    // Add the level.dat file
-    const LEVEL_TEMPLATE: &[u8] = include_bytes!("../mcassets/level.dat");
-
-    // Decompress the gzipped level.template
-    let mut decoder: GzDecoder<&[u8]> = GzDecoder::new(LEVEL_TEMPLATE);
-    let mut decompressed_data: Vec<u8> = Vec::new();
-    decoder
-        .read_to_end(&mut decompressed_data)
-        .map_err(|e: std::io::Error| format!("无法解压 level.template: {}", e))?;
-
-    // Parse the decompressed NBT data
-    let mut level_data: Value = fastnbt::from_bytes(&decompressed_data)
-        .map_err(|e: fastnbt::error::Error| format!("无法解析 level.dat 模板：{}", e))?;
-
-    // Modify the LevelName and LastPlayed fields
-    if let Value::Compound(ref mut root) = level_data {
-        if let Some(Value::Compound(ref mut data)) = root.get_mut("Data") {
-            // Update LevelName
-            data.insert(
-                "LevelName".to_string(),
-                Value::String(world_name.to_string()),
-            );
-
-            // Update LastPlayed to the current Unix time in milliseconds
-            let current_time: std::time::Duration = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map_err(|e: std::time::SystemTimeError| {
-                    format!("无法获取当前时间：{}", e)
-                })?;
-            let current_time_millis: i64 = current_time.as_millis() as i64;
-            data.insert("LastPlayed".to_string(), Value::Long(current_time_millis));
-        }
-    }
-
-    // Serialize the updated NBT data back to bytes
-    let serialized_level_data: Vec<u8> =
-        fastnbt::to_bytes(&level_data).map_err(|e: fastnbt::error::Error| {
-            format!("无法序列化更新的 level.dat：{}", e)
-        })?;
-
-    // Compress the serialized data back to gzip
-    let mut encoder: flate2::write::GzEncoder<Vec<u8>> =
-        flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
-    encoder
-        .write_all(&serialized_level_data)
-        .map_err(|e: std::io::Error| format!("无法压缩更新的 level.dat：{}", e))?;
-    let compressed_level_data: Vec<u8> = encoder.finish().map_err(|e: std::io::Error| {
-        format!("无法完成 level.dat 的压缩：{}", e)
-    })?;
-
-    fs::write(world_path.join("level.dat"), compressed_level_data)
-        .map_err(|e: std::io::Error| format!("无法创建 level.dat 文件：{}", e))?;
-
-    // Add the icon.png file
-    const ICON_TEMPLATE: &[u8] = include_bytes!("../mcassets/icon.png");
-    fs::write(world_path.join("icon.png"), ICON_TEMPLATE)
-        .map_err(|e: std::io::Error| format!("无法创建 icon.png 文件：{}", e))?;
-
-    Ok(())
+/// Reports how a selected existing world was generated and whether it can be updated in place
+#[tauri::command]
+fn gui_check_world(world_path: String) -> world_health::WorldHealth {
+    world_health::check_world(Path::new(&world_path))
 }
 
 #[tauri::command]
@@ -324,16 +311,123 @@ fn gui_check_for_updates() -> Result<bool, String> {
 }
 
 #[tauri::command]
+fn gui_open_world_folder(world_path: String) -> Result<(), String> {
+    notification::open_folder(&world_path)
+}
+
+#[tauri::command]
+fn gui_get_cache_coverage(bbox_text: String) -> Result<retrieve_data::CacheCoverage, String> {
+    let bbox: Vec<f64> = bbox_text
+        .split_whitespace()
+        .map(|s| s.parse::<f64>().map_err(|_| "边界框坐标无效".to_string()))
+        .collect::<Result<Vec<f64>, String>>()?;
+
+    if bbox.len() != 4 {
+        return Err("边界框格式无效".to_string());
+    }
+
+    Ok(retrieve_data::cache_coverage((
+        bbox[1], bbox[0], bbox[3], bbox[2],
+    )))
+}
+
+#[tauri::command]
+fn gui_suggest_scale(bbox_text: String) -> Result<suggest::ScaleSuggestion, String> {
+    let bbox: Vec<f64> = bbox_text
+        .split_whitespace()
+        .map(|s| s.parse::<f64>().map_err(|_| "边界框坐标无效".to_string()))
+        .collect::<Result<Vec<f64>, String>>()?;
+
+    if bbox.len() != 4 {
+        return Err("边界框格式无效".to_string());
+    }
+
+    Ok(suggest::suggest_scale(
+        (bbox[1], bbox[0], bbox[3], bbox[2]),
+        None,
+        None,
+    ))
+}
+
+/// Fetches and parses the data of the selected area and returns the outlines of its roads,
+/// buildings and water, drawn by the UI before the generation is started
+#[tauri::command]
+async fn gui_preview_data(
+    bbox_text: String,
+    world_scale: f64,
+) -> Result<map_preview::MapPreview, String> {
+    let bbox: Vec<f64> = bbox_text
+        .split_whitespace()
+        .map(|s| s.parse::<f64>().map_err(|_| "边界框坐标无效".to_string()))
+        .collect::<Result<Vec<f64>, String>>()?;
+
+    if bbox.len() != 4 {
+        return Err("边界框格式无效".to_string());
+    }
+
+    let bbox_tuple: (f64, f64, f64, f64) = (bbox[1], bbox[0], bbox[3], bbox[2]);
+    // Options of a generation of the area, the world path is not used by the preview
+    let args: Args = Args::parse_from([
+        "arnis".to_string(),
+        "--bbox".to_string(),
+        format!(
+            "{},{},{},{}",
+            bbox_tuple.0, bbox_tuple.1, bbox_tuple.2, bbox_tuple.3
+        ),
+        "--path".to_string(),
+        ".".to_string(),
+        "--scale".to_string(),
+        world_scale.to_string(),
+    ]);
+
+    tokio::task::spawn_blocking(move || map_preview::preview_data(bbox_tuple, &args))
+        .await
+        .map_err(|e: tokio::task::JoinError| e.to_string())?
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
 fn gui_start_generation(
     bbox_text: String,
     selected_world: String,
     world_scale: f64,
     ground_level: i32,
+    blocks_per_level: i32,
     winter_mode: bool,
     floodfill_timeout: u64,
+    heatmap: bool,
+    color_palette: String,
+    preset: Option<String>,
 ) -> Result<(), String> {
+    let palette: colors::ColorPalette = clap::ValueEnum::from_str(&color_palette, true)
+        .map_err(|_| format!("无效的调色板：{}", color_palette))?;
+    if blocks_per_level < 2 {
+        return Err(format!("每层方块数至少为 2：{}", blocks_per_level));
+    }
+
+    // Held by the generation task until the world is saved
+    let world_lock: file_lock::WorldLock =
+        file_lock::lock_world(&selected_world).inspect_err(|e: &String| {
+            progress::emit_gui_error(e);
+        })?;
+
+    let cancel_token: CancelToken = CancelToken::new();
+    if let Ok(mut gui_cancel_token) = GUI_CANCEL_TOKEN.lock() {
+        *gui_cancel_token = Some(cancel_token.clone());
+    }
+    let world_path: String = selected_world.clone();
+    let context: GenerationContext = GenerationContext::new()
+        .with_cancel_token(cancel_token.clone())
+        .with_progress_listener(move |event: &progress::ProgressEvent| {
+            progress::emit_gui_progress_update(event);
+            if let Some(summary) = &event.summary {
+                progress::emit_gui_completion(summary, &world_path);
+            }
+        });
     tauri::async_runtime::spawn(async move {
         if let Err(e) = tokio::task::spawn_blocking(move || {
+            let _world_lock: file_lock::WorldLock = world_lock;
+
             // Utility function to reorder bounding box coordinates
             fn reorder_bbox(bbox: &[f64]) -> (f64, f64, f64, f64) {
                 (bbox[1], bbox[0], bbox[3], bbox[2])
@@ -351,39 +445,83 @@ fn gui_start_generation(
 
             // Create an Args instance with the chosen bounding box and world directory path
             let args: Args = Args {
-                bbox: Some(bbox_text),
+                bbox: vec![bbox_text],
+                bbox_file: None,
+                polygon: None,
                 file: None,
                 path: selected_world,
+                preset: None,
                 downloader: "requests".to_string(),
                 scale: world_scale,
                 ground_level,
+                blocks_per_level,
+                style: building_styles::BuildingStyle::Default,
+                interior: false,
+                villagers: false,
+                mobs: false,
+                coordinate_hud: false,
+                details: false,
+                foliage: false,
+                night: false,
+                heatmap,
+                palette,
+                no_cache: false,
+                refresh: false,
+                focus: None,
+                block_config: None,
+                tree_templates: None,
+                sign_script: transliteration::SignScript::Original,
                 winter: winter_mode,
                 debug: false,
+                max_fill_area: 4_000_000,
+                fill_method: floodfill::FillMethod::default(),
+                only: vec![],
+                include: vec![],
+                exclude: vec![],
+                seed: None,
+                world_border: false,
+                x_offset: 0,
+                z_offset: 0,
+                rotation: 0.0,
+                mc_version: mc_version::McVersion::default(),
+                no_lighting: false,
+                incremental: false,
+                overwrite: false,
+                resume: false,
+                lod_radius: None,
+                forest_density: 1.0,
                 timeout: Some(std::time::Duration::from_secs(floodfill_timeout)),
+                format: output::OutputFormat::Anvil,
+                rcon_address: "127.0.0.1:25575".to_string(),
+                rcon_password: None,
             };
 
             // Reorder bounding box coordinates for further processing
             let reordered_bbox: (f64, f64, f64, f64) = reorder_bbox(&bbox);
 
+            let args: Args = match preset.as_deref() {
+                Some(preset) => match apply_gui_preset(args, preset, reordered_bbox) {
+                    Ok(args) => args,
+                    Err(e) => {
+                        progress::emit_gui_error(&e);
+                        return Err(e);
+                    }
+                },
+                None => args,
+            };
+
             // Run data fetch and world generation
-            match retrieve_data::fetch_data(reordered_bbox, None, args.debug, "requests") {
-                Ok(raw_data) => {
-                    let (mut parsed_elements, scale_factor_x, scale_factor_z) =
-                        osm_parser::parse_osm_data(&raw_data, reordered_bbox, &args);
-                    parsed_elements.sort_by_key(|element: &osm_parser::ProcessedElement| {
-                        osm_parser::get_priority(element)
-                    });
-
-                    let _ = data_processing::generate_world(
-                        parsed_elements,
-                        &args,
-                        scale_factor_x,
-                        scale_factor_z,
-                    );
-                    Ok(())
-                }
-                Err(e) => Err(format!("无法开始生成：{}", e)),
+            let result: Result<(), String> = output::create_backend(&args).and_then(
+                |mut output: Box<dyn output::OutputBackend>| {
+                    arnis_core::generate_world(reordered_bbox, &args, output.as_mut(), &context)
+                },
+            );
+            if cancel_token.is_cancelled() {
+                progress::emit_gui_cancelled();
+            } else if let Err(e) = &result {
+                progress::emit_gui_error(e);
             }
+            result
         })
         .await
         {
@@ -393,3 +531,65 @@ fn gui_start_generation(
 
     Ok(())
 }
+
+/// Adds the options of a preset that the settings dialog does not show. The settings of the
+/// dialog are kept, the dialog takes them from the preset when it is selected.
+fn apply_gui_preset(args: Args, preset: &str, bbox: (f64, f64, f64, f64)) -> Result<Args, String> {
+    let command_line: Vec<String> = vec![
+        "arnis".to_string(),
+        "--preset".to_string(),
+        preset.to_string(),
+        "--bbox".to_string(),
+        format!("{},{},{},{}", bbox.0, bbox.1, bbox.2, bbox.3),
+        "--path".to_string(),
+        args.path.clone(),
+    ];
+    let mut preset_args: Args = Args::try_parse_from(presets::apply_preset(&command_line)?)
+        .map_err(|e: clap::Error| e.to_string())?;
+    preset_args.bbox = args.bbox;
+    preset_args.scale = args.scale;
+    preset_args.ground_level = args.ground_level;
+    preset_args.blocks_per_level = args.blocks_per_level;
+    preset_args.winter = args.winter;
+    preset_args.heatmap = args.heatmap;
+    preset_args.palette = args.palette;
+    preset_args.timeout = args.timeout;
+    Ok(preset_args)
+}
+
+/// Returns the built-in presets and those saved by the user
+#[tauri::command]
+fn gui_list_presets() -> Vec<presets::Preset> {
+    presets::list_presets()
+}
+
+/// Saves the current settings as a user preset
+#[tauri::command]
+fn gui_save_preset(
+    name: String,
+    description: String,
+    options: serde_json::Map<String, serde_json::Value>,
+) -> Result<(), String> {
+    presets::save_preset(&name, &description, options)
+}
+
+/// Returns the settings of the last session
+#[tauri::command]
+fn gui_load_settings() -> gui_settings::GuiSettings {
+    gui_settings::load_settings()
+}
+
+/// Saves the current settings for the next session
+#[tauri::command]
+fn gui_save_settings(settings: gui_settings::GuiSettings) -> Result<(), String> {
+    gui_settings::save_settings(&settings)
+}
+
+/// Stops the running generation at the next check, leaving the world as it was before
+/// the region being written
+#[tauri::command]
+fn gui_cancel_generation() {
+    if let Ok(Some(cancel_token)) = GUI_CANCEL_TOKEN.lock().as_deref() {
+        cancel_token.cancel();
+    }
+}
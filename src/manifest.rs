@@ -0,0 +1,125 @@
+use crate::args::Args;
+use fnv::FnvHasher;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File name of the manifest of written files stored in the world directory
+pub const MANIFEST_FILE_NAME: &str = "arnis_manifest.json";
+
+/// Lists every file written by a generation run, so tools can tell which parts of the
+/// world changed without comparing the files themselves.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RunManifest {
+    /// Unix time in seconds at which the world was saved
+    pub saved_at: u64,
+    /// Settings of the run, missing in manifests written by older versions
+    #[serde(default)]
+    pub settings: Option<GenerationSettings>,
+    pub files: Vec<ManifestEntry>,
+}
+
+/// Settings a world was generated with, shown when the world is opened again
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GenerationSettings {
+    pub bbox: Option<String>,
+    pub scale: f64,
+    pub ground_level: i32,
+    pub style: String,
+    pub interior: bool,
+    pub winter: bool,
+    pub night: bool,
+    /// Seed of the random choices, set once the world is saved
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+impl GenerationSettings {
+    pub fn from_args(args: &Args) -> Self {
+        Self {
+            bbox: args.bbox.first().cloned(),
+            scale: args.scale,
+            ground_level: args.ground_level,
+            style: format!("{:?}", args.style).to_lowercase(),
+            interior: args.interior,
+            winter: args.winter,
+            night: args.night,
+            seed: args.seed,
+        }
+    }
+}
+
+/// A region file written during the run
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ManifestEntry {
+    /// Path relative to the world directory, using forward slashes
+    pub path: String,
+    /// Number of chunks stored in the file
+    pub chunks: u32,
+    /// Number of those chunks containing generated blocks or entities
+    pub modified_chunks: u32,
+    /// File size in bytes
+    pub size: u64,
+    /// FNV-1a hash of the file contents as hexadecimal
+    pub checksum: String,
+}
+
+impl RunManifest {
+    pub fn new() -> Self {
+        Self {
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0),
+            settings: None,
+            files: vec![],
+        }
+    }
+
+    /// Adds a written file, reading it back for its size and checksum
+    pub fn add_file(
+        &mut self,
+        world_path: &Path,
+        relative_path: &str,
+        chunks: u32,
+        modified_chunks: u32,
+    ) -> Result<(), String> {
+        let content: Vec<u8> = fs::read(world_path.join(relative_path))
+            .map_err(|e: std::io::Error| format!("无法读取 {}：{}", relative_path, e))?;
+
+        self.files.push(ManifestEntry {
+            path: relative_path.to_string(),
+            chunks,
+            modified_chunks,
+            size: content.len() as u64,
+            checksum: checksum(&content),
+        });
+        Ok(())
+    }
+}
+
+/// Returns the FNV-1a hash of file contents as hexadecimal, as stored in the manifest
+pub fn checksum(content: &[u8]) -> String {
+    let mut hasher: FnvHasher = FnvHasher::default();
+    hasher.write(content);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Reads the manifest of the last run that wrote into the world
+pub fn read_manifest(world_path: &Path) -> Result<RunManifest, String> {
+    let file: File = File::open(world_path.join(MANIFEST_FILE_NAME))
+        .map_err(|e: std::io::Error| format!("无法读取清单文件：{}", e))?;
+    serde_json::from_reader(BufReader::new(file))
+        .map_err(|e: serde_json::Error| format!("清单文件无效：{}", e))
+}
+
+/// Writes the manifest into the world directory.
+pub fn write_manifest(world_path: &Path, manifest: &RunManifest) -> Result<(), String> {
+    let file: File = File::create(world_path.join(MANIFEST_FILE_NAME))
+        .map_err(|e: std::io::Error| format!("无法创建清单文件：{}", e))?;
+    serde_json::to_writer_pretty(BufWriter::new(file), manifest)
+        .map_err(|e: serde_json::Error| format!("无法写入清单文件：{}", e))
+}
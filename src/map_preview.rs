@@ -0,0 +1,97 @@
+use crate::args::Args;
+use crate::context::GenerationContext;
+use crate::layers::{element_layer, Layer};
+use crate::osm_parser::{
+    self, ProcessedElement, ProcessedMember, ProcessedMemberRole, ProcessedNode, ProcessedWay,
+};
+use crate::retrieve_data;
+use serde::Serialize;
+
+/// Layers drawn in the preview, in the order they are drawn
+const PREVIEW_LAYERS: [Layer; 4] = [
+    Layer::Water,
+    Layer::Roads,
+    Layer::Railways,
+    Layer::Buildings,
+];
+
+/// Outlines of the parsed elements in block coordinates, drawn by the GUI before the world
+/// is generated to check the area and scale
+#[derive(Serialize)]
+pub struct MapPreview {
+    /// Size of the area in blocks
+    pub width: i32,
+    pub length: i32,
+    pub shapes: Vec<PreviewShape>,
+}
+
+/// A way drawn as a line, or as a filled area if it is closed
+#[derive(Serialize)]
+pub struct PreviewShape {
+    /// Name of the layer of the element, e.g. roads or buildings
+    pub layer: String,
+    pub closed: bool,
+    pub points: Vec<(i32, i32)>,
+}
+
+/// Fetches and parses the data of the area like a generation with these options, and
+/// returns the outlines of its roads, railways, buildings and water
+pub fn preview_data(bbox: (f64, f64, f64, f64), options: &Args) -> Result<MapPreview, String> {
+    // The preview is quick and reports no progress
+    let context: GenerationContext = GenerationContext::new();
+    let raw_data: serde_json::Value = retrieve_data::fetch_data(
+        bbox,
+        options.polygon.as_ref(),
+        options.file.as_deref(),
+        options.debug,
+        &options.downloader,
+        options.cache_mode(),
+        &context,
+    )
+    .map_err(|e| format!("无法获取数据：{}", e))?;
+
+    let (elements, scale_factor_x, scale_factor_z) =
+        osm_parser::parse_osm_data(&raw_data, bbox, options, &context);
+
+    let mut shapes: Vec<PreviewShape> = vec![];
+    for layer in PREVIEW_LAYERS {
+        for element in &elements {
+            if element_layer(element) != layer {
+                continue;
+            }
+            match element {
+                ProcessedElement::Way(way) => shapes.push(preview_shape(way, layer)),
+                // Inner members are holes, the preview only fills the outer ones
+                ProcessedElement::Relation(relation) => shapes.extend(
+                    relation
+                        .members
+                        .iter()
+                        .filter(|member: &&ProcessedMember| {
+                            matches!(member.role, ProcessedMemberRole::Outer)
+                        })
+                        .map(|member: &ProcessedMember| preview_shape(&member.way, layer)),
+                ),
+                ProcessedElement::Node(_) => {}
+            }
+        }
+    }
+
+    Ok(MapPreview {
+        width: scale_factor_x as i32 + 1,
+        length: scale_factor_z as i32 + 1,
+        shapes,
+    })
+}
+
+fn preview_shape(way: &ProcessedWay, layer: Layer) -> PreviewShape {
+    let closed: bool = way.nodes.len() > 2 && way.nodes[0].id == way.nodes[way.nodes.len() - 1].id;
+    PreviewShape {
+        layer: format!("{:?}", layer).to_lowercase(),
+        closed,
+        points: way
+            .nodes
+            .iter()
+            .map(|node: &ProcessedNode| (node.x, node.z))
+            .collect(),
+    }
+}
@@ -0,0 +1,428 @@
+use clap::ValueEnum;
+use fastnbt::{IntArray, LongArray, Value};
+use std::collections::HashMap;
+
+/// Blocks the world is moved up in versions before 1.18, whose world starts at y = 0
+/// instead of y = -64
+const LEGACY_HEIGHT_OFFSET: i32 = 64;
+/// Sections of the worlds before 1.18, from y = 0 to 255
+const LEGACY_SECTION_COUNT: i8 = 16;
+
+/// Blocks missing in older versions, with the version adding them and the block used
+/// instead
+const NEWER_BLOCKS: [(&str, McVersion, &str); 6] = [
+    ("light", McVersion::V1_17_1, "air"),
+    ("oxidized_copper", McVersion::V1_17_1, "prismarine"),
+    ("moss_block", McVersion::V1_17_1, "green_terracotta"),
+    (
+        "deepslate_bricks",
+        McVersion::V1_17_1,
+        "polished_blackstone_bricks",
+    ),
+    (
+        "polished_deepslate",
+        McVersion::V1_17_1,
+        "polished_blackstone",
+    ),
+    ("mud_bricks", McVersion::V1_19_4, "terracotta"),
+];
+
+/// Numeric biome IDs of the versions before 1.18, which store the biomes of a chunk as IDs.
+/// Biomes added later fall back to plains.
+const LEGACY_BIOME_IDS: [(&str, i32); 10] = [
+    ("plains", 1),
+    ("desert", 2),
+    ("forest", 4),
+    ("swamp", 6),
+    ("river", 7),
+    ("frozen_river", 11),
+    ("snowy_plains", 12),
+    ("beach", 16),
+    ("snowy_beach", 26),
+    ("snowy_taiga", 30),
+];
+
+/// Minecraft Java Edition versions the world can be written for with --mc-version
+#[allow(non_camel_case_types)]
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum McVersion {
+    #[value(name = "1.16.5")]
+    V1_16_5,
+    #[value(name = "1.17.1")]
+    V1_17_1,
+    #[value(name = "1.18.2")]
+    V1_18_2,
+    #[value(name = "1.19.4")]
+    V1_19_4,
+    #[value(name = "1.20.4")]
+    V1_20_4,
+    #[value(name = "1.21.1")]
+    V1_21_1,
+    /// Version of the world template and the chunks written by the world editor
+    #[default]
+    #[value(name = "1.21.4")]
+    V1_21_4,
+}
+
+impl McVersion {
+    pub fn name(&self) -> &'static str {
+        match self {
+            McVersion::V1_16_5 => "1.16.5",
+            McVersion::V1_17_1 => "1.17.1",
+            McVersion::V1_18_2 => "1.18.2",
+            McVersion::V1_19_4 => "1.19.4",
+            McVersion::V1_20_4 => "1.20.4",
+            McVersion::V1_21_1 => "1.21.1",
+            McVersion::V1_21_4 => "1.21.4",
+        }
+    }
+
+    /// Data version stored in the chunks and the level.dat, which Minecraft uses to upgrade
+    /// worlds of older versions
+    pub fn data_version(&self) -> i32 {
+        match self {
+            McVersion::V1_16_5 => 2586,
+            McVersion::V1_17_1 => 2730,
+            McVersion::V1_18_2 => 2975,
+            McVersion::V1_19_4 => 3337,
+            McVersion::V1_20_4 => 3700,
+            McVersion::V1_21_1 => 3955,
+            McVersion::V1_21_4 => 4189,
+        }
+    }
+
+    /// Data pack format of the version
+    pub fn pack_format(&self) -> i32 {
+        match self {
+            McVersion::V1_16_5 => 6,
+            McVersion::V1_17_1 => 7,
+            McVersion::V1_18_2 => 9,
+            McVersion::V1_19_4 => 12,
+            McVersion::V1_20_4 => 26,
+            McVersion::V1_21_1 => 48,
+            McVersion::V1_21_4 => 61,
+        }
+    }
+
+    /// Before 1.18, chunks keep their data in a Level compound and the world only reaches
+    /// from y = 0 to 255
+    pub fn has_legacy_chunks(&self) -> bool {
+        *self < McVersion::V1_18_2
+    }
+
+    /// Before 1.17, entities are stored in the chunks instead of the entities folder
+    pub fn stores_entities_in_chunks(&self) -> bool {
+        *self < McVersion::V1_17_1
+    }
+
+    /// Function macros, which the coordinate display relies on, were added in 1.20.2
+    pub fn supports_function_macros(&self) -> bool {
+        *self >= McVersion::V1_20_4
+    }
+
+    /// Blocks the generated world is moved up when it is written for this version
+    pub fn height_offset(&self) -> i32 {
+        if self.has_legacy_chunks() {
+            LEGACY_HEIGHT_OFFSET
+        } else {
+            0
+        }
+    }
+
+    /// Returns the name of a block in this version, replacing blocks added later by a
+    /// similar block
+    pub fn block_name<'a>(&self, name: &'a str) -> &'a str {
+        let short_name: &str = name.strip_prefix("minecraft:").unwrap_or(name);
+        NEWER_BLOCKS
+            .iter()
+            .find(|(newer, added_in, _)| *newer == short_name && self < added_in)
+            .map_or(name, |(_, _, fallback)| *fallback)
+    }
+
+    /// Converts a chunk written by the world editor into the format of this version
+    pub fn convert_chunk(&self, chunk: Value) -> Value {
+        let Value::Compound(mut chunk) = chunk else {
+            return chunk;
+        };
+        chunk.insert("DataVersion".to_string(), Value::Int(self.data_version()));
+
+        if let Some(Value::List(sections)) = chunk.get_mut("sections") {
+            for section in sections {
+                if let Some(Value::List(palette)) = get_path(section, &["block_states", "palette"])
+                {
+                    for item in palette {
+                        self.convert_palette_item(item);
+                    }
+                }
+            }
+        }
+        if let Some(Value::List(block_entities)) = chunk.get_mut("block_entities") {
+            for block_entity in block_entities {
+                self.convert_block_entity(block_entity);
+            }
+        }
+
+        if self.has_legacy_chunks() {
+            Value::Compound(self.legacy_chunk(chunk))
+        } else {
+            Value::Compound(chunk)
+        }
+    }
+
+    fn convert_palette_item(&self, item: &mut Value) {
+        let Value::Compound(item) = item else {
+            return;
+        };
+        let Some(Value::String(name)) = item.get("Name") else {
+            return;
+        };
+        let converted: &str = self.block_name(name);
+        if converted != name {
+            // The properties belong to the replaced block
+            let converted: String = converted.to_string();
+            *item = HashMap::from([("Name".to_string(), Value::String(converted))]);
+        }
+    }
+
+    /// Moves block entities up with the world, writes item stacks in the format before 1.20.5
+    /// and sign text in the format before 1.20, which had a single text side
+    fn convert_block_entity(&self, block_entity: &mut Value) {
+        let Value::Compound(block_entity) = block_entity else {
+            return;
+        };
+        if let Some(Value::Int(y)) = block_entity.get_mut("y") {
+            *y += self.height_offset();
+        }
+
+        if *self < McVersion::V1_21_1 {
+            if let Some(Value::List(items)) = block_entity.get_mut("Items") {
+                items.iter_mut().for_each(legacy_item_stack);
+            }
+            if let Some(book) = block_entity.get_mut("Book") {
+                legacy_item_stack(book);
+            }
+        }
+
+        if *self >= McVersion::V1_20_4 {
+            return;
+        }
+        let Some(Value::Compound(mut front_text)) = block_entity.remove("front_text") else {
+            return;
+        };
+        block_entity.remove("is_waxed");
+        if let Some(Value::List(messages)) = front_text.remove("messages") {
+            for (index, message) in messages.into_iter().enumerate() {
+                block_entity.insert(format!("Text{}", index + 1), message);
+            }
+        }
+        if let Some(color) = front_text.remove("color") {
+            block_entity.insert("Color".to_string(), color);
+        }
+        if let Some(glowing) = front_text.remove("has_glowing_text") {
+            block_entity.insert("GlowingText".to_string(), glowing);
+        }
+    }
+
+    /// Moves an entity up with the world and replaces entity IDs introduced later
+    pub fn convert_entity(&self, entity: &mut Value) {
+        if let Some(Value::List(position)) = get_path(entity, &["Pos"]) {
+            if let Some(Value::Double(y)) = position.get_mut(1) {
+                *y += f64::from(self.height_offset());
+            }
+        }
+
+        let Value::Compound(entity) = entity else {
+            return;
+        };
+        let Some(Value::String(id)) = entity.get("id") else {
+            return;
+        };
+        if *self < McVersion::V1_21_4 {
+            // Boats share one ID with the wood as a tag before 1.21.2
+            if let Some(wood) = id
+                .strip_prefix("minecraft:")
+                .and_then(|id: &str| id.strip_suffix("_boat"))
+            {
+                let wood: String = wood.to_string();
+                entity.insert(
+                    "id".to_string(),
+                    Value::String("minecraft:boat".to_string()),
+                );
+                entity.insert("Type".to_string(), Value::String(wood));
+                return;
+            }
+        }
+        if *self < McVersion::V1_17_1 && id == "minecraft:goat" {
+            entity.insert(
+                "id".to_string(),
+                Value::String("minecraft:sheep".to_string()),
+            );
+        }
+    }
+
+    /// Rebuilds a chunk in the format before 1.18. The sections are moved up by the height
+    /// offset and the sections above the top of the older world are left out.
+    fn legacy_chunk(&self, mut chunk: HashMap<String, Value>) -> HashMap<String, Value> {
+        let section_offset: i8 = (LEGACY_HEIGHT_OFFSET / 16) as i8;
+        let mut level: HashMap<String, Value> = HashMap::new();
+        for key in [
+            "xPos",
+            "zPos",
+            "LastUpdate",
+            "InhabitedTime",
+            "isLightOn",
+            "Heightmaps",
+        ] {
+            if let Some(value) = chunk.remove(key) {
+                level.insert(key.to_string(), value);
+            }
+        }
+        level.insert("Status".to_string(), Value::String("full".to_string()));
+
+        let mut sections: Vec<Value> = vec![];
+        let mut biomes: Vec<i32> = vec![1; 1024];
+        if let Some(Value::List(modern_sections)) = chunk.remove("sections") {
+            for section in modern_sections {
+                let Value::Compound(mut section) = section else {
+                    continue;
+                };
+                let Some(Value::Byte(y)) = section.get("Y") else {
+                    continue;
+                };
+                let y: i8 = y + section_offset;
+                if !(0..LEGACY_SECTION_COUNT).contains(&y) {
+                    continue;
+                }
+                if y == 0 {
+                    if let Some(section_biomes) = section.get("biomes") {
+                        biomes = legacy_biomes(section_biomes);
+                    }
+                }
+
+                let mut legacy_section: HashMap<String, Value> = HashMap::new();
+                legacy_section.insert("Y".to_string(), Value::Byte(y));
+                for key in ["SkyLight", "BlockLight"] {
+                    if let Some(light) = section.remove(key) {
+                        legacy_section.insert(key.to_string(), light);
+                    }
+                }
+                if let Some(Value::Compound(mut block_states)) = section.remove("block_states") {
+                    if let Some(Value::List(palette)) = block_states.remove("palette") {
+                        // Older versions always need the block data, which for uniform
+                        // sections is 4096 indices of 4 bits pointing to the only block
+                        let data: Value = block_states
+                            .remove("data")
+                            .unwrap_or_else(|| Value::LongArray(LongArray::new(vec![0; 256])));
+                        legacy_section.insert("Palette".to_string(), Value::List(palette));
+                        legacy_section.insert("BlockStates".to_string(), data);
+                    }
+                }
+                sections.push(Value::Compound(legacy_section));
+            }
+        }
+        level.insert("Sections".to_string(), Value::List(sections));
+        level.insert("Biomes".to_string(), Value::IntArray(IntArray::new(biomes)));
+
+        let mut block_entities: Value = chunk
+            .remove("block_entities")
+            .unwrap_or(Value::List(vec![]));
+        if let Value::List(block_entities) = &mut block_entities {
+            block_entities.retain(|block_entity: &Value| {
+                matches!(get_path_ref(block_entity, &["y"]), Some(Value::Int(y)) if (0..256).contains(y))
+            });
+        }
+        level.insert("TileEntities".to_string(), block_entities);
+
+        let mut entities: Vec<Value> = match chunk.remove("Entities") {
+            Some(Value::List(entities)) => entities,
+            _ => vec![],
+        };
+        for entity in &mut entities {
+            self.convert_entity(entity);
+        }
+        level.insert("Entities".to_string(), Value::List(entities));
+
+        level.insert("TileTicks".to_string(), Value::List(vec![]));
+        level.insert("LiquidTicks".to_string(), Value::List(vec![]));
+        level.insert(
+            "PostProcessing".to_string(),
+            Value::List(vec![Value::List(vec![]); LEGACY_SECTION_COUNT as usize]),
+        );
+        let mut structures: HashMap<String, Value> = HashMap::new();
+        structures.insert("References".to_string(), Value::Compound(HashMap::new()));
+        structures.insert("Starts".to_string(), Value::Compound(HashMap::new()));
+        level.insert("Structures".to_string(), Value::Compound(structures));
+
+        let mut root: HashMap<String, Value> = HashMap::new();
+        root.insert("DataVersion".to_string(), Value::Int(self.data_version()));
+        root.insert("Level".to_string(), Value::Compound(level));
+        root
+    }
+}
+
+/// Converts the biomes of a section into the 1024 biome IDs of a chunk before 1.18, with
+/// 4x4x4 cells ordered by y, z and x. The world editor gives every column one biome.
+fn legacy_biomes(section_biomes: &Value) -> Vec<i32> {
+    let ids: Vec<i32> = match get_path_ref(section_biomes, &["palette"]) {
+        Some(Value::List(palette)) => palette
+            .iter()
+            .map(|biome: &Value| {
+                let Value::String(name) = biome else {
+                    return 1;
+                };
+                let name: &str = name.strip_prefix("minecraft:").unwrap_or(name);
+                LEGACY_BIOME_IDS
+                    .iter()
+                    .find(|(legacy_name, _)| *legacy_name == name)
+                    .map_or(1, |(_, id)| *id)
+            })
+            .collect(),
+        _ => vec![1],
+    };
+
+    let mut bits_per_biome: usize = 1;
+    while (1 << bits_per_biome) < ids.len() {
+        bits_per_biome += 1;
+    }
+    let per_long: usize = 64 / bits_per_biome;
+    let mask: u64 = (1 << bits_per_biome) - 1;
+    let column_ids: Vec<i32> = (0..16)
+        .map(|cell: usize| {
+            let index: usize = match get_path_ref(section_biomes, &["data"]) {
+                Some(Value::LongArray(data)) => {
+                    let long: u64 = data.get(cell / per_long).copied().unwrap_or(0) as u64;
+                    ((long >> ((cell % per_long) * bits_per_biome)) & mask) as usize
+                }
+                _ => 0,
+            };
+            ids.get(index).copied().unwrap_or(1)
+        })
+        .collect();
+
+    (0..1024).map(|cell: usize| column_ids[cell % 16]).collect()
+}
+
+/// Item stacks before 1.20.5 store their count as a byte named Count
+fn legacy_item_stack(item: &mut Value) {
+    if let Value::Compound(item) = item {
+        if let Some(Value::Int(count)) = item.remove("count") {
+            item.insert("Count".to_string(), Value::Byte(count as i8));
+        }
+    }
+}
+
+fn get_path<'a>(value: &'a mut Value, path: &[&str]) -> Option<&'a mut Value> {
+    path.iter()
+        .try_fold(value, |value: &mut Value, key: &&str| match value {
+            Value::Compound(compound) => compound.get_mut(*key),
+            _ => None,
+        })
+}
+
+fn get_path_ref<'a>(value: &'a Value, path: &[&str]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(value, |value: &Value, key: &&str| match value {
+            Value::Compound(compound) => compound.get(*key),
+            _ => None,
+        })
+}
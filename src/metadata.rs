@@ -0,0 +1,111 @@
+use crate::rotation::Rotation;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+/// File name of the projection metadata stored in the world directory
+pub const METADATA_FILE_NAME: &str = "arnis_metadata.json";
+
+/// Describes how the generated world maps to real-world coordinates.
+/// Block coordinates relate to geographic coordinates linearly:
+/// lon = lon_origin + x * lon_per_block + z * lon_per_block_z,
+/// lat = lat_origin + x * lat_per_block_x + z * lat_per_block.
+/// The cross terms are zero unless the area was rotated with --rotation.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WorldMetadata {
+    pub min_lon: f64,
+    pub min_lat: f64,
+    pub max_lon: f64,
+    pub max_lat: f64,
+    pub scale_factor_x: f64,
+    pub scale_factor_z: f64,
+    /// Clockwise rotation of the area in degrees
+    #[serde(default)]
+    pub rotation: f64,
+    pub lon_origin: f64,
+    pub lon_per_block: f64,
+    #[serde(default)]
+    pub lon_per_block_z: f64,
+    pub lat_origin: f64,
+    #[serde(default)]
+    pub lat_per_block_x: f64,
+    pub lat_per_block: f64,
+}
+
+impl WorldMetadata {
+    /// Describes an area of the given size in blocks before rotation, rotated clockwise by
+    /// the given degrees and generated with its north-west corner at the given block offset
+    pub fn new(
+        bbox: (f64, f64, f64, f64),
+        area_size: (f64, f64),
+        rotation: f64,
+        offset: (i32, i32),
+    ) -> Self {
+        let (min_lon, min_lat, max_lon, max_lat) = bbox;
+        let (area_x, area_z) = area_size;
+        let turn: Rotation = Rotation::new(rotation);
+        let (scale_factor_x, scale_factor_z) = turn.rotated_size(area_x, area_z);
+        let (sin, cos) = (turn.sin(), turn.cos());
+
+        // Degrees per block of the unrotated area
+        let lon_step: f64 = (max_lon - min_lon) / area_x;
+        let lat_step: f64 = -(max_lat - min_lat) / area_z;
+        // World position of the center of the area, which the rotation turns around
+        let center_x: f64 = f64::from(offset.0) + scale_factor_x / 2.0;
+        let center_z: f64 = f64::from(offset.1) + scale_factor_z / 2.0;
+
+        Self {
+            min_lon,
+            min_lat,
+            max_lon,
+            max_lat,
+            scale_factor_x,
+            scale_factor_z,
+            rotation,
+            lon_origin: min_lon + lon_step * (area_x / 2.0 - center_x * cos - center_z * sin),
+            lon_per_block: lon_step * cos,
+            lon_per_block_z: lon_step * sin,
+            lat_origin: max_lat + lat_step * (area_z / 2.0 + center_x * sin - center_z * cos),
+            lat_per_block_x: -lat_step * sin,
+            lat_per_block: lat_step * cos,
+        }
+    }
+
+    /// Converts Minecraft block coordinates into (latitude, longitude)
+    pub fn block_to_lat_lon(&self, x: f64, z: f64) -> (f64, f64) {
+        (
+            self.lat_origin + x * self.lat_per_block_x + z * self.lat_per_block,
+            self.lon_origin + x * self.lon_per_block + z * self.lon_per_block_z,
+        )
+    }
+
+    /// Converts geographic coordinates into Minecraft block coordinates (x, z)
+    pub fn lat_lon_to_block(&self, lat: f64, lon: f64) -> (i32, i32) {
+        let (d_lon, d_lat) = (lon - self.lon_origin, lat - self.lat_origin);
+        let determinant: f64 =
+            self.lon_per_block * self.lat_per_block - self.lon_per_block_z * self.lat_per_block_x;
+        (
+            ((d_lon * self.lat_per_block - d_lat * self.lon_per_block_z) / determinant).round()
+                as i32,
+            ((d_lat * self.lon_per_block - d_lon * self.lat_per_block_x) / determinant).round()
+                as i32,
+        )
+    }
+}
+
+/// Reads the projection metadata of a world generated by Arnis
+pub fn read_metadata(world_path: &str) -> Result<WorldMetadata, String> {
+    let file: File = File::open(Path::new(world_path).join(METADATA_FILE_NAME))
+        .map_err(|e: std::io::Error| format!("无法读取元数据文件：{}", e))?;
+    serde_json::from_reader(BufReader::new(file))
+        .map_err(|e: serde_json::Error| format!("元数据文件无效：{}", e))
+}
+
+/// Writes the projection metadata into the world directory.
+pub fn write_metadata(world_path: &str, metadata: &WorldMetadata) -> Result<(), String> {
+    let file: File = File::create(Path::new(world_path).join(METADATA_FILE_NAME))
+        .map_err(|e: std::io::Error| format!("无法创建元数据文件：{}", e))?;
+    serde_json::to_writer_pretty(BufWriter::new(file), metadata)
+        .map_err(|e: serde_json::Error| format!("无法写入元数据文件：{}", e))
+}
@@ -0,0 +1,155 @@
+use crate::block_definitions::*;
+use crate::bresenham::bresenham_line;
+use crate::element_processing::highways::find_roadside;
+use crate::osm_parser::{ProcessedElement, ProcessedWay};
+use crate::random::seeded_rng;
+use crate::world_editor::WorldEditor;
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::collections::HashSet;
+
+/// Share of the windows that are lit from the inside
+const WINDOW_LIGHT_CHANCE: f64 = 0.35;
+/// Distance between the lamps along a lit road
+const LAMP_SPACING: usize = 20;
+/// Windows up to this height above the ground belong to the shop front
+const SHOP_FRONT_HEIGHT: i32 = 4;
+
+/// Number of lights placed by the night pass
+#[derive(Debug, Default)]
+pub struct NightLights {
+    /// Windows lit from the inside
+    pub windows: usize,
+    /// Windows of shop fronts, which are all lit
+    pub shop_fronts: usize,
+    /// Lamps placed along roads tagged as lit
+    pub street_lamps: usize,
+}
+
+impl NightLights {
+    pub fn total(&self) -> usize {
+        self.windows + self.shop_fronts + self.street_lamps
+    }
+}
+
+/// Places hidden light blocks behind the windows of shops and a share of all other windows,
+/// and adds lamps along roads tagged as lit. The lights keep streets and buildings bright
+/// at night, which also keeps mobs from spawning there.
+pub fn add_night_lights(
+    editor: &mut WorldEditor,
+    elements: &[ProcessedElement],
+    ground_level: i32,
+) -> NightLights {
+    let mut lights: NightLights = NightLights::default();
+    let mut rng: StdRng = seeded_rng(editor.seed(), "night_lights");
+    let windows: Vec<(i32, i32, i32)> = editor.find_blocks(WHITE_STAINED_GLASS);
+    let window_set: HashSet<(i32, i32, i32)> = windows.iter().copied().collect();
+
+    // Shop fronts are lit completely on the ground floor
+    let mut shop_windows: HashSet<(i32, i32, i32)> = HashSet::new();
+    for element in elements {
+        let ProcessedElement::Way(way) = element else {
+            continue;
+        };
+        let is_shop: bool = way.tags.contains_key("building")
+            && (way.tags.contains_key("shop") || way.tags.contains_key("amenity"));
+        if !is_shop {
+            continue;
+        }
+        for (x, _, z) in outline(way, ground_level) {
+            for y in (ground_level + 1)..=(ground_level + SHOP_FRONT_HEIGHT) {
+                if window_set.contains(&(x, y, z)) {
+                    shop_windows.insert((x, y, z));
+                }
+            }
+        }
+    }
+
+    for (x, y, z) in windows {
+        let is_shop_front: bool = shop_windows.contains(&(x, y, z));
+        if !is_shop_front && !rng.gen_bool(WINDOW_LIGHT_CHANCE) {
+            continue;
+        }
+        if light_behind_window(editor, x, y, z) {
+            if is_shop_front {
+                lights.shop_fronts += 1;
+            } else {
+                lights.windows += 1;
+            }
+        }
+    }
+
+    for element in elements {
+        let ProcessedElement::Way(way) = element else {
+            continue;
+        };
+        let is_lit: bool = way.tags.contains_key("highway")
+            && way.tags.get("lit").is_some_and(|lit: &String| lit != "no");
+        if is_lit {
+            lights.street_lamps += add_street_lamps(editor, way, ground_level);
+        }
+    }
+
+    lights
+}
+
+/// Places an invisible light block inside the building next to a window. The inside is the
+/// side with a roof above it. Returns whether a light was placed.
+fn light_behind_window(editor: &mut WorldEditor, x: i32, y: i32, z: i32) -> bool {
+    for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+        let (ix, iz) = (x + dx, z + dz);
+        let is_inside: bool = editor.get_block(ix, y, iz).is_none()
+            && editor
+                .get_highest_block_y(ix, iz)
+                .is_some_and(|roof_y: i32| roof_y > y);
+        if is_inside {
+            editor.set_block(LIGHT, ix, y, iz, None, None);
+            return true;
+        }
+    }
+    false
+}
+
+/// Places lamp posts at regular distances beside a lit road. Returns the number of lamps.
+fn add_street_lamps(editor: &mut WorldEditor, way: &ProcessedWay, ground_level: i32) -> usize {
+    let mut placed: usize = 0;
+    for (x, _, z) in outline(way, ground_level).into_iter().step_by(LAMP_SPACING) {
+        let (lamp_x, lamp_z) = find_roadside(editor, x, z, ground_level);
+        let is_free: bool = (lamp_x, lamp_z) != (x, z)
+            && !matches!(editor.get_highest_block_y(lamp_x, lamp_z), Some(y) if y > ground_level);
+        if !is_free {
+            continue;
+        }
+
+        for y in 1..=4 {
+            editor.set_block(
+                COBBLESTONE_WALL,
+                lamp_x,
+                ground_level + y,
+                lamp_z,
+                None,
+                None,
+            );
+        }
+        editor.set_block(LANTERN, lamp_x, ground_level + 5, lamp_z, None, None);
+        placed += 1;
+    }
+    placed
+}
+
+/// Returns the line along the nodes of a way
+fn outline(way: &ProcessedWay, ground_level: i32) -> Vec<(i32, i32, i32)> {
+    way.nodes
+        .windows(2)
+        .flat_map(|span| {
+            bresenham_line(
+                span[0].x,
+                ground_level,
+                span[0].z,
+                span[1].x,
+                ground_level,
+                span[1].z,
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,37 @@
+use std::process::Command;
+#[cfg(feature = "gui")]
+use tauri::WebviewWindow;
+#[cfg(feature = "gui")]
+use tauri_plugin_notification::NotificationExt;
+
+/// Shows an OS notification from the GUI window, so users who switched to another
+/// window notice when a long generation has finished.
+#[cfg(feature = "gui")]
+pub fn show_notification(window: &WebviewWindow, title: &str, body: &str) {
+    if let Err(e) = window
+        .notification()
+        .builder()
+        .title(title)
+        .body(body)
+        .show()
+    {
+        eprintln!("无法显示系统通知：{}", e);
+    }
+}
+
+/// Opens a folder in the file manager of the operating system
+pub fn open_folder(path: &str) -> Result<(), String> {
+    let program: &str = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+
+    Command::new(program)
+        .arg(path)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e: std::io::Error| format!("无法打开文件夹：{}", e))
+}
@@ -1,4 +1,7 @@
-use crate::{args::Args, progress::emit_gui_progress_update};
+use crate::args::Args;
+use crate::context::GenerationContext;
+use crate::progress::{ProgressEvent, Stage};
+use crate::rotation::Rotation;
 use colored::Colorize;
 use serde::Deserialize;
 use serde_json::Value;
@@ -118,6 +121,7 @@ fn lat_lon_to_minecraft_coords(
     bbox: (f64, f64, f64, f64), // (min_lon, min_lat, max_lon, max_lat)
     scale_factor_z: f64,
     scale_factor_x: f64,
+    rotation: Rotation,
 ) -> (i32, i32) {
     let (min_lon, min_lat, max_lon, max_lat) = bbox;
 
@@ -125,29 +129,42 @@ fn lat_lon_to_minecraft_coords(
     let rel_x: f64 = (lon - min_lon) / (max_lon - min_lon);
     let rel_z: f64 = 1.0 - (lat - min_lat) / (max_lat - min_lat);
 
-    // Apply scaling factors for each dimension and convert to Minecraft coordinates
-    let x: i32 = (rel_x * scale_factor_x) as i32;
-    let z: i32 = (rel_z * scale_factor_z) as i32;
+    // Apply scaling factors for each dimension and rotate the area with --rotation
+    let (x, z) = rotation.rotate(
+        rel_x * scale_factor_x,
+        rel_z * scale_factor_z,
+        scale_factor_x,
+        scale_factor_z,
+    );
 
-    (x, z)
+    // Convert to Minecraft coordinates
+    (x as i32, z as i32)
+}
+
+/// Returns the size of the area in blocks (x, z) before it is rotated with --rotation
+pub fn area_size(bbox: (f64, f64, f64, f64), scale: f64) -> (f64, f64) {
+    let (length, width) = geo_distance(bbox.1, bbox.3, bbox.0, bbox.2);
+    (width.floor() * scale, length.floor() * scale)
 }
 
 pub fn parse_osm_data(
     json_data: &Value,
     bbox: (f64, f64, f64, f64),
     args: &Args,
+    context: &GenerationContext,
 ) -> (Vec<ProcessedElement>, f64, f64) {
     println!("{} 正在解析数据...", "[2/5]".bold());
-    emit_gui_progress_update(5.0, "正在解析数据...");
+    context.emit_progress(ProgressEvent::new(Stage::Parsing, 5.0));
 
     // Deserialize the JSON data into the OSMData structure
     let data: OsmData =
         serde_json::from_value(json_data.clone()).expect("无法解析 OSM 数据");
 
-    // Determine which dimension is larger and assign scale factors accordingly
-    let (scale_factor_z, scale_factor_x) = geo_distance(bbox.1, bbox.3, bbox.0, bbox.2);
-    let scale_factor_z: f64 = scale_factor_z.floor() * args.scale;
-    let scale_factor_x: f64 = scale_factor_x.floor() * args.scale;
+    // Determine which dimension is larger and assign scale factors accordingly. Rotated
+    // areas span the bounding box of the turned area.
+    let (area_x, area_z) = area_size(bbox, args.scale);
+    let rotation: Rotation = Rotation::new(args.rotation);
+    let (scale_factor_x, scale_factor_z) = rotation.rotated_size(area_x, area_z);
 
     if args.debug {
         println!("比例系数 X：{}", scale_factor_x);
@@ -163,8 +180,7 @@ pub fn parse_osm_data(
     for element in &data.elements {
         if element.r#type == "node" {
             if let (Some(lat), Some(lon)) = (element.lat, element.lon) {
-                let (x, z) =
-                    lat_lon_to_minecraft_coords(lat, lon, bbox, scale_factor_z, scale_factor_x);
+                let (x, z) = lat_lon_to_minecraft_coords(lat, lon, bbox, area_z, area_x, rotation);
 
                 let processed: ProcessedNode = ProcessedNode {
                     id: element.id,
@@ -264,7 +280,7 @@ pub fn parse_osm_data(
         }));
     }
 
-    emit_gui_progress_update(10.0, "");
+    context.emit_progress(ProgressEvent::new(Stage::Parsing, 10.0));
 
     (processed_elements, scale_factor_x, scale_factor_z)
 }
@@ -286,7 +302,7 @@ pub fn get_priority(element: &ProcessedElement) -> usize {
 }
 
 // (lat meters, lon meters)
-fn geo_distance(lat1: f64, lat2: f64, lon1: f64, lon2: f64) -> (f64, f64) {
+pub fn geo_distance(lat1: f64, lat2: f64, lon1: f64, lon2: f64) -> (f64, f64) {
     let z: f64 = lat_distance(lat1, lat2);
 
     // distance between two lons depends on their latitude. In this case we'll just average them
@@ -0,0 +1,428 @@
+use crate::args::Args;
+use crate::biome_definitions::{Biome, PLAINS};
+use crate::heightmaps::chunk_heightmaps;
+use crate::lighting::{TileLight, PADDING_CHUNKS, TILE_CHUNKS};
+use crate::manifest::{write_manifest, GenerationSettings, RunManifest};
+use crate::mc_version::McVersion;
+use crate::output::OutputBackend;
+use crate::progress::{stage_progress_bar, ProgressEvent, Stage};
+use crate::world_editor::{Chunk, ChunkToModify, RegionToModify, WorldEditor, ENTITY_TAG};
+use colored::Colorize;
+use fastanvil::Region;
+use fastnbt::{IntArray, Value};
+use fnv::FnvHashMap;
+use indicatif::ProgressBar;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/// Writes the world into the region files of a Minecraft Java Edition world
+pub struct AnvilBackend {
+    region_dir: String,
+    /// Existing region files are kept and the blocks merged into their chunks, unless
+    /// --overwrite is given
+    merge: bool,
+    default_biome: Biome,
+    /// Writes new chunks with precomputed light, unless --no-lighting is given
+    lighting: bool,
+    /// Minecraft version the chunks are converted to, given with --mc-version
+    version: McVersion,
+    settings: GenerationSettings,
+}
+
+impl AnvilBackend {
+    pub fn new(args: &Args) -> Result<Self, String> {
+        // Existing chunks are read in the format of the current version only
+        if args.merges_into_world() && args.mc_version.has_legacy_chunks() {
+            return Err(format!(
+                "Minecraft {} 的世界不支持 --only 和 --incremental",
+                args.mc_version.name()
+            ));
+        }
+
+        Ok(Self {
+            region_dir: format!("{}/region", args.path),
+            // Worlds of versions with legacy chunks are replaced, their chunks can not be read
+            merge: args.merges_into_world()
+                || (!args.overwrite && !args.mc_version.has_legacy_chunks()),
+            default_biome: if args.winter {
+                PLAINS.winter_variant()
+            } else {
+                PLAINS
+            },
+            lighting: !args.no_lighting,
+            version: args.mc_version,
+            settings: GenerationSettings::from_args(args),
+        })
+    }
+
+    /// Opens the region file for the given region coordinates. Existing files are kept when
+    /// writing into the existing world, otherwise the region starts out empty.
+    fn open_region(&self, region_x: i32, region_z: i32) -> Region<File> {
+        let out_path: String = format!("{}/r.{}.{}.mca", self.region_dir, region_x, region_z);
+        open_region_file(Path::new(&out_path), self.merge)
+    }
+
+    /// Writes the manifest of the region files saved in this run. A missing manifest does
+    /// not affect the world, so failures only produce a warning.
+    fn write_run_manifest(&self, seed: u64, regions: &[(String, u32)], entities: &[(String, u32)]) {
+        let Some(world_path) = Path::new(&self.region_dir).parent() else {
+            return;
+        };
+
+        let mut manifest: RunManifest = RunManifest::new();
+        manifest.settings = Some(GenerationSettings {
+            seed: Some(seed),
+            ..self.settings.clone()
+        });
+        let result: Result<(), String> = regions
+            .iter()
+            .map(|(path, modified_chunks)| (path, 1024, *modified_chunks))
+            .chain(
+                entities
+                    .iter()
+                    .map(|(path, chunks)| (path, *chunks, *chunks)),
+            )
+            .try_for_each(|(path, chunks, modified_chunks)| {
+                manifest.add_file(world_path, path, chunks, modified_chunks)
+            })
+            .and_then(|_| write_manifest(world_path, &manifest));
+
+        if let Err(e) = result {
+            eprintln!("{}", format!("警告：无法写入区域文件清单：{}", e).yellow());
+        }
+    }
+
+    /// Writes the entities into the region files of the world's entities folder, where
+    /// Minecraft stores them separately from the blocks since 1.17. Returns the written
+    /// files relative to the world directory with their number of chunks.
+    fn save_entities(&self, editor: &WorldEditor) -> Vec<(String, u32)> {
+        let entities_dir: PathBuf = Path::new(&self.region_dir).with_file_name("entities");
+        let mut written: Vec<(String, u32)> = vec![];
+
+        for ((region_x, region_z), region_to_modify) in editor.regions() {
+            let chunks: Vec<(&(i32, i32), &ChunkToModify)> = region_to_modify
+                .chunks
+                .iter()
+                .filter(|(_, chunk)| !chunk.entities.is_empty())
+                .collect();
+            if chunks.is_empty() {
+                continue;
+            }
+
+            fs::create_dir_all(&entities_dir).expect("无法创建实体目录");
+            let mut region: Region<File> = open_region_file(
+                &entities_dir.join(format!("r.{}.{}.mca", region_x, region_z)),
+                self.merge,
+            );
+            written.push((
+                format!("entities/r.{}.{}.mca", region_x, region_z),
+                chunks.len() as u32,
+            ));
+
+            for ((chunk_x, chunk_z), chunk_to_modify) in chunks {
+                let mut entity_chunk: HashMap<String, Value> = HashMap::new();
+                entity_chunk.insert(
+                    "DataVersion".to_string(),
+                    Value::Int(self.version.data_version()),
+                );
+                entity_chunk.insert(
+                    "Position".to_string(),
+                    Value::IntArray(IntArray::new(vec![
+                        chunk_x + region_x * 32,
+                        chunk_z + region_z * 32,
+                    ])),
+                );
+                let mut entities: Vec<Value> = chunk_to_modify.entities.clone();
+                for entity in &mut entities {
+                    self.version.convert_entity(entity);
+                }
+                if self.merge {
+                    entities.extend(existing_entities(&mut region, *chunk_x, *chunk_z));
+                }
+                entity_chunk.insert("Entities".to_string(), Value::List(entities));
+
+                let ser: Vec<u8> = fastnbt::to_bytes(&Value::Compound(entity_chunk)).unwrap();
+                region
+                    .write_chunk(*chunk_x as usize, *chunk_z as usize, &ser)
+                    .unwrap();
+            }
+        }
+
+        written
+    }
+}
+
+impl OutputBackend for AnvilBackend {
+    /// Writes the modified chunks into the region files of the world. Regions closest to the
+    /// focus block are written first.
+    fn write(&mut self, editor: &WorldEditor, focus: (i32, i32)) -> Result<(), String> {
+        fs::create_dir_all(&self.region_dir)
+            .map_err(|e: std::io::Error| format!("无法创建区域目录：{}", e))?;
+        let default_biome: Biome = self.default_biome;
+        let merge: bool = self.merge;
+        let total_regions: u64 = editor.regions().len() as u64;
+
+        let save_pb: ProgressBar = stage_progress_bar(Stage::Saving, total_regions, "区域");
+
+        let total_steps: f64 = 9.0;
+        let progress_increment_save: f64 = total_steps / total_regions as f64;
+        let mut current_progress_save: f64 = 90.0;
+        let mut last_emitted_progress: f64 = current_progress_save;
+
+        let mut regions: Vec<(&(i32, i32), &RegionToModify)> = editor.regions().iter().collect();
+        regions.sort_by_key(|((region_x, region_z), _)| {
+            let dx: i64 = i64::from(region_x * 512 + 256 - focus.0);
+            let dz: i64 = i64::from(region_z * 512 + 256 - focus.1);
+            dx * dx + dz * dz
+        });
+
+        let mut written_regions: Vec<(String, u32)> = vec![];
+        for ((region_x, region_z), region_to_modify) in regions {
+            // Regions already written stay complete, the others keep their previous content
+            editor.context().check_cancelled()?;
+            let mut region: Region<File> = self.open_region(*region_x, *region_z);
+            written_regions.push((
+                format!("region/r.{}.{}.mca", region_x, region_z),
+                region_to_modify.chunks.len() as u32,
+            ));
+
+            // Light is computed for tiles of chunks together, so only the tiles of the
+            // current column of tiles are kept
+            let mut tile_lights: FnvHashMap<(i32, i32), TileLight> = FnvHashMap::default();
+            for chunk_x in 0..32 {
+                if chunk_x % TILE_CHUNKS == 0 {
+                    tile_lights.clear();
+                }
+                for chunk_z in 0..32 {
+                    let chunk_to_modify: Option<&ChunkToModify> =
+                        region_to_modify.get_chunk(chunk_x, chunk_z);
+                    let existing: Option<Chunk> = if merge {
+                        read_chunk(&mut region, chunk_x, chunk_z)
+                    } else {
+                        None
+                    };
+
+                    let mut chunk: Chunk = match existing {
+                        // Existing chunks without changes are left as they are
+                        Some(mut chunk) => {
+                            let Some(chunk_to_modify) = chunk_to_modify else {
+                                continue;
+                            };
+                            chunk.merge(chunk_to_modify, default_biome);
+                            chunk
+                        }
+                        None => {
+                            let mut chunk: Chunk = Chunk::empty(
+                                chunk_x + region_x * 32,
+                                chunk_z + region_z * 32,
+                                default_biome,
+                            );
+                            if let Some(chunk_to_modify) = chunk_to_modify {
+                                chunk.sections = chunk_to_modify.sections(default_biome).collect();
+                                chunk.other.extend(chunk_to_modify.other.clone());
+                                chunk.other.insert(
+                                    "Heightmaps".to_string(),
+                                    chunk_heightmaps(chunk_to_modify),
+                                );
+                                if self.version.stores_entities_in_chunks() {
+                                    chunk.other.insert(
+                                        "Entities".to_string(),
+                                        Value::List(chunk_to_modify.entities.clone()),
+                                    );
+                                }
+                            }
+                            chunk
+                        }
+                    };
+
+                    if self.lighting {
+                        let tile: (i32, i32) = (
+                            chunk_x - chunk_x % TILE_CHUNKS,
+                            chunk_z - chunk_z % TILE_CHUNKS,
+                        );
+                        let light: &TileLight = tile_lights.entry(tile).or_insert_with(|| {
+                            // Merged chunks are lit together with the terrain of the world
+                            let existing: FnvHashMap<(i32, i32), Chunk> = if merge {
+                                merged_tile_chunks(
+                                    &mut region,
+                                    region_to_modify,
+                                    (*region_x, *region_z),
+                                    tile,
+                                    default_biome,
+                                )
+                            } else {
+                                FnvHashMap::default()
+                            };
+                            TileLight::compute(
+                                editor,
+                                &existing,
+                                region_x * 32 + tile.0,
+                                region_z * 32 + tile.1,
+                            )
+                        });
+                        let biomes: [Biome; 16] = chunk_to_modify
+                            .map_or([default_biome; 16], |chunk_to_modify: &ChunkToModify| {
+                                chunk_to_modify.biomes(default_biome)
+                            });
+                        chunk.set_light(
+                            light.chunk(region_x * 32 + chunk_x, region_z * 32 + chunk_z),
+                            &biomes,
+                        );
+                    }
+
+                    let ser: Vec<u8> = chunk_bytes(&chunk, self.version);
+
+                    // Write chunk data back to the correct location, ensuring correct chunk coordinates
+                    let expected_chunk_location: (usize, usize) =
+                        ((chunk_x as usize) & 31, (chunk_z as usize) & 31);
+                    region
+                        .write_chunk(expected_chunk_location.0, expected_chunk_location.1, &ser)
+                        .unwrap();
+                }
+            }
+
+            save_pb.inc(1);
+
+            current_progress_save += progress_increment_save;
+            if (current_progress_save - last_emitted_progress).abs() > 0.25 {
+                editor.context().emit_progress(
+                    ProgressEvent::new(Stage::Saving, current_progress_save)
+                        .counts(save_pb.position(), total_regions),
+                );
+                last_emitted_progress = current_progress_save;
+            }
+        }
+
+        save_pb.finish();
+
+        let written_entities: Vec<(String, u32)> = if self.version.stores_entities_in_chunks() {
+            vec![]
+        } else {
+            self.save_entities(editor)
+        };
+        self.write_run_manifest(editor.seed(), &written_regions, &written_entities);
+        Ok(())
+    }
+}
+
+/// Serializes a chunk, converted to the chunk format of the given version
+fn chunk_bytes(chunk: &Chunk, version: McVersion) -> Vec<u8> {
+    if version == McVersion::default() {
+        fastnbt::to_bytes(chunk).unwrap()
+    } else {
+        let chunk: Value = fastnbt::to_value(chunk).unwrap();
+        fastnbt::to_bytes(&version.convert_chunk(chunk)).unwrap()
+    }
+}
+
+/// Opens a region file. When merging into an existing world, an existing file is kept,
+/// otherwise the file is replaced by an empty region. Existing files that can not be read
+/// are replaced as well.
+/// Reads an existing chunk of a region, given in chunk coordinates within the region
+fn read_chunk(region: &mut Region<File>, chunk_x: i32, chunk_z: i32) -> Option<Chunk> {
+    region
+        .read_chunk(chunk_x as usize, chunk_z as usize)
+        .ok()
+        .flatten()
+        .and_then(|data: Vec<u8>| fastnbt::from_bytes(&data).ok())
+}
+
+/// Reads the existing chunks of a tile and of the chunks around it within the region, with
+/// the placed blocks merged into them, keyed by their world chunk coordinates
+fn merged_tile_chunks(
+    region: &mut Region<File>,
+    region_to_modify: &RegionToModify,
+    (region_x, region_z): (i32, i32),
+    (tile_x, tile_z): (i32, i32),
+    default_biome: Biome,
+) -> FnvHashMap<(i32, i32), Chunk> {
+    let mut chunks: FnvHashMap<(i32, i32), Chunk> = FnvHashMap::default();
+    let range = |start: i32| {
+        (start - PADDING_CHUNKS).max(0)..(start + TILE_CHUNKS + PADDING_CHUNKS).min(32)
+    };
+    for chunk_x in range(tile_x) {
+        for chunk_z in range(tile_z) {
+            let Some(mut chunk) = read_chunk(region, chunk_x, chunk_z) else {
+                continue;
+            };
+            if let Some(chunk_to_modify) = region_to_modify.get_chunk(chunk_x, chunk_z) {
+                chunk.merge(chunk_to_modify, default_biome);
+            }
+            chunks.insert((region_x * 32 + chunk_x, region_z * 32 + chunk_z), chunk);
+        }
+    }
+    chunks
+}
+
+fn open_region_file(path: &Path, merge: bool) -> Region<File> {
+    if merge && path.metadata().is_ok_and(|metadata| metadata.len() > 0) {
+        let region_file: File = File::options()
+            .read(true)
+            .write(true)
+            .open(path)
+            .expect("无法打开区域文件");
+        match Region::from_stream(region_file) {
+            Ok(region) => return region,
+            Err(e) => eprintln!(
+                "{}",
+                format!("警告：无法读取区域文件 {}，将替换它：{}", path.display(), e).yellow()
+            ),
+        }
+    }
+
+    let region_file: File = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+        .expect("无法打开区域文件");
+    Region::new(region_file).expect("创建区域失败")
+}
+
+/// Returns the entities already stored in a chunk of an entities region file, except for
+/// those placed by an earlier run, which are replaced by the entities of this run.
+fn existing_entities(region: &mut Region<File>, chunk_x: i32, chunk_z: i32) -> Vec<Value> {
+    let Ok(Some(data)) = region.read_chunk(chunk_x as usize, chunk_z as usize) else {
+        return vec![];
+    };
+    match fastnbt::from_bytes::<HashMap<String, Value>>(&data) {
+        Ok(mut chunk) => match chunk.remove("Entities") {
+            Some(Value::List(entities)) => entities
+                .into_iter()
+                .filter(|entity: &Value| !is_placed_entity(entity))
+                .collect(),
+            _ => vec![],
+        },
+        Err(_) => vec![],
+    }
+}
+
+/// Returns whether an entity carries the tag of the entities placed by Arnis
+fn is_placed_entity(entity: &Value) -> bool {
+    let Value::Compound(entity) = entity else {
+        return false;
+    };
+    matches!(entity.get("Tags"), Some(Value::List(tags))
+        if tags.contains(&Value::String(ENTITY_TAG.to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::ValueEnum;
+
+    #[test]
+    fn saves_chunk_for_every_version() {
+        for version in McVersion::value_variants() {
+            let chunk: Chunk = Chunk::empty(0, 0, PLAINS);
+            let ser: Vec<u8> = chunk_bytes(&chunk, *version);
+            assert!(
+                fastnbt::from_bytes::<Value>(&ser).is_ok(),
+                "chunk of Minecraft {} can not be read",
+                version.name()
+            );
+        }
+    }
+}
@@ -0,0 +1,649 @@
+use crate::args::Args;
+use crate::biome_definitions::{Biome, PLAINS};
+use crate::block_definitions::{Block, AIR};
+use crate::output::leveldb::LevelDbWriter;
+use crate::output::OutputBackend;
+use crate::progress::{stage_progress_bar, ProgressEvent, Stage};
+use crate::world_editor::{ChunkToModify, WorldEditor};
+use colored::Colorize;
+use fastnbt::Value;
+use indicatif::ProgressBar;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Bedrock Edition the worlds are written for, as major, minor and patch version
+const GAME_VERSION: [i32; 3] = [1, 21, 40];
+/// Version of the block states in the palettes, the game upgrades older states on load
+const BLOCK_STATE_VERSION: i32 =
+    (GAME_VERSION[0] << 24) | (GAME_VERSION[1] << 16) | (GAME_VERSION[2] << 8);
+/// Protocol version of the game version, stored in the level.dat
+const NETWORK_VERSION: i32 = 748;
+/// Format version of the chunks
+const CHUNK_VERSION: u8 = 40;
+/// Version of the level.dat format
+const STORAGE_VERSION: i32 = 10;
+
+/// Lowest and highest sub-chunk of the overworld, from y -64 to 319
+const MIN_SUBCHUNK: i8 = -4;
+const MAX_SUBCHUNK: i8 = 19;
+
+/// Keys of the records of a chunk, following its coordinates
+const TAG_DATA_3D: u8 = 43;
+const TAG_VERSION: u8 = 44;
+const TAG_SUBCHUNK: u8 = 47;
+const TAG_FINALIZED_STATE: u8 = 54;
+
+/// Writes the world as a Minecraft Bedrock Edition world into the directory given with
+/// --path, with the chunks in the LevelDB database in db/ and the settings in level.dat.
+/// Block entities and entities are not converted, so signs, banners and chests are placed
+/// without their content.
+pub struct BedrockBackend {
+    world_dir: PathBuf,
+    default_biome: Biome,
+    ground_level: i32,
+}
+
+impl BedrockBackend {
+    pub fn new(args: &Args) -> Result<Self, String> {
+        let world_dir: PathBuf = PathBuf::from(&args.path);
+        // Existing databases can not be merged into, only replaced
+        if world_dir.join("db").exists() && !args.overwrite {
+            return Err("该目录中已有基岩版世界，使用 --overwrite 替换".to_string());
+        }
+
+        Ok(Self {
+            world_dir,
+            default_biome: if args.winter {
+                PLAINS.winter_variant()
+            } else {
+                PLAINS
+            },
+            ground_level: args.ground_level,
+        })
+    }
+
+    /// Writes the level.dat and levelname.txt, with the spawn at the given position
+    fn write_level_data(&self, seed: u64, spawn: (i32, i32, i32)) -> Result<(), String> {
+        let level_name: String = self
+            .world_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Arnis".to_string());
+        let last_played: i64 = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or(0);
+        let version: Value = Value::List(
+            GAME_VERSION
+                .iter()
+                .chain(&[0, 0])
+                .map(|part: &i32| Value::Int(*part))
+                .collect(),
+        );
+
+        let mut level_data: HashMap<String, Value> = HashMap::new();
+        level_data.insert("StorageVersion".to_string(), Value::Int(STORAGE_VERSION));
+        level_data.insert("NetworkVersion".to_string(), Value::Int(NETWORK_VERSION));
+        level_data.insert("lastOpenedWithVersion".to_string(), version.clone());
+        level_data.insert("MinimumCompatibleClientVersion".to_string(), version);
+        level_data.insert("LevelName".to_string(), Value::String(level_name.clone()));
+        level_data.insert("RandomSeed".to_string(), Value::Long(seed as i64));
+        level_data.insert("LastPlayed".to_string(), Value::Long(last_played));
+        // Flat generator, so the terrain around the area stays level like the area itself
+        level_data.insert("Generator".to_string(), Value::Int(2));
+        level_data.insert("GameType".to_string(), Value::Int(1));
+        level_data.insert("commandsEnabled".to_string(), Value::Byte(1));
+        level_data.insert("SpawnX".to_string(), Value::Int(spawn.0));
+        level_data.insert("SpawnY".to_string(), Value::Int(spawn.1));
+        level_data.insert("SpawnZ".to_string(), Value::Int(spawn.2));
+
+        let mut nbt: Vec<u8> = vec![];
+        write_nbt(&mut nbt, "", &Value::Compound(level_data));
+        let mut data: Vec<u8> = Vec::with_capacity(8 + nbt.len());
+        data.extend_from_slice(&STORAGE_VERSION.to_le_bytes());
+        data.extend_from_slice(&(nbt.len() as i32).to_le_bytes());
+        data.extend_from_slice(&nbt);
+
+        fs::write(self.world_dir.join("level.dat"), data)
+            .and_then(|_| fs::write(self.world_dir.join("levelname.txt"), level_name))
+            .map_err(|e: std::io::Error| format!("无法写入 level.dat：{}", e))
+    }
+}
+
+impl OutputBackend for BedrockBackend {
+    fn write(&mut self, editor: &WorldEditor, focus: (i32, i32)) -> Result<(), String> {
+        let db_dir: PathBuf = self.world_dir.join("db");
+        if db_dir.exists() {
+            fs::remove_dir_all(&db_dir)
+                .map_err(|e: std::io::Error| format!("无法删除旧的基岩版世界：{}", e))?;
+        }
+        let mut db: LevelDbWriter = LevelDbWriter::create(&db_dir)
+            .map_err(|e: std::io::Error| format!("无法创建基岩版世界数据库：{}", e))?;
+
+        let chunk_positions: Vec<(i32, i32)> = editor.chunk_positions(focus);
+        let total_chunks: u64 = chunk_positions.len() as u64;
+        let save_pb: ProgressBar = stage_progress_bar(Stage::Saving, total_chunks, "区块");
+        let mut last_emitted_progress: f64 = 90.0;
+
+        // Palette entries are the same in every sub-chunk, so they are serialized once
+        let mut palette_entries: HashMap<Block, Vec<u8>> = HashMap::new();
+        for (chunk_x, chunk_z) in chunk_positions {
+            // The database is only readable once finished, a cancelled world stays empty
+            editor.context().check_cancelled()?;
+            let Some(chunk) = editor.chunk(chunk_x, chunk_z) else {
+                continue;
+            };
+
+            db.put(&chunk_key(chunk_x, chunk_z, TAG_VERSION), &[CHUNK_VERSION]);
+            db.put(
+                &chunk_key(chunk_x, chunk_z, TAG_FINALIZED_STATE),
+                &2i32.to_le_bytes(),
+            );
+            db.put(
+                &chunk_key(chunk_x, chunk_z, TAG_DATA_3D),
+                &data_3d(chunk, self.default_biome),
+            );
+            for y in MIN_SUBCHUNK..=MAX_SUBCHUNK {
+                let Some(blocks) = chunk.section_blocks(y) else {
+                    continue;
+                };
+                let mut key: Vec<u8> = chunk_key(chunk_x, chunk_z, TAG_SUBCHUNK);
+                key.push(y as u8);
+                db.put(&key, &subchunk(y, &blocks, &mut palette_entries));
+            }
+            db.flush()
+                .map_err(|e: std::io::Error| format!("无法写入基岩版世界：{}", e))?;
+
+            save_pb.inc(1);
+            let progress: f64 = 90.0 + 9.0 * save_pb.position() as f64 / total_chunks as f64;
+            if progress - last_emitted_progress > 0.25 {
+                editor.context().emit_progress(
+                    ProgressEvent::new(Stage::Saving, progress)
+                        .counts(save_pb.position(), total_chunks),
+                );
+                last_emitted_progress = progress;
+            }
+        }
+        save_pb.finish();
+
+        db.finish()
+            .map_err(|e: std::io::Error| format!("无法写入基岩版世界：{}", e))?;
+
+        let spawn_y: i32 = editor
+            .chunk_blocks(focus.0 >> 4, focus.1 >> 4)
+            .into_iter()
+            .filter(|((x, _, z), _)| (*x, *z) == focus)
+            .map(|((_, y, _), _)| y + 1)
+            .max()
+            .unwrap_or(self.ground_level + 1);
+        self.write_level_data(editor.seed(), (focus.0, spawn_y, focus.1))?;
+
+        println!(
+            "{}",
+            format!("基岩版世界已保存到 {}", self.world_dir.display()).green()
+        );
+        Ok(())
+    }
+}
+
+/// Returns the database key of a record of an overworld chunk
+fn chunk_key(chunk_x: i32, chunk_z: i32, tag: u8) -> Vec<u8> {
+    let mut key: Vec<u8> = Vec::with_capacity(10);
+    key.extend_from_slice(&chunk_x.to_le_bytes());
+    key.extend_from_slice(&chunk_z.to_le_bytes());
+    key.push(tag);
+    key
+}
+
+/// Serializes the blocks of a sub-chunk, given indexed by y, z, x, into a single block
+/// storage. Bedrock stores the blocks in x, z, y order.
+fn subchunk(
+    y: i8,
+    blocks: &[Block; 4096],
+    palette_entries: &mut HashMap<Block, Vec<u8>>,
+) -> Vec<u8> {
+    let mut palette: Vec<Block> = vec![];
+    let mut indices: Vec<usize> = Vec::with_capacity(4096);
+    for x in 0..16 {
+        for z in 0..16 {
+            for block_y in 0..16 {
+                let block: Block = blocks[block_y * 256 + z * 16 + x];
+                let index: usize = match palette.iter().position(|entry: &Block| *entry == block) {
+                    Some(index) => index,
+                    None => {
+                        palette.push(block);
+                        palette.len() - 1
+                    }
+                };
+                indices.push(index);
+            }
+        }
+    }
+
+    let mut data: Vec<u8> = vec![9, 1, y as u8];
+    write_storage(&mut data, &indices, palette.len(), false);
+    data.extend_from_slice(&(palette.len() as i32).to_le_bytes());
+    for block in palette {
+        let entry: &Vec<u8> = palette_entries.entry(block).or_insert_with(|| {
+            let (name, states): (String, HashMap<String, Value>) = bedrock_block(&block);
+            let mut entry: HashMap<String, Value> = HashMap::new();
+            entry.insert("name".to_string(), Value::String(name));
+            entry.insert("states".to_string(), Value::Compound(states));
+            entry.insert("version".to_string(), Value::Int(BLOCK_STATE_VERSION));
+            let mut bytes: Vec<u8> = vec![];
+            write_nbt(&mut bytes, "", &Value::Compound(entry));
+            bytes
+        });
+        data.extend_from_slice(entry);
+    }
+    data
+}
+
+/// Writes the header and the packed palette indices of a storage. Runtime storages, used for
+/// biomes, keep numeric IDs in their palette instead of block states.
+fn write_storage(data: &mut Vec<u8>, indices: &[usize], palette_len: usize, runtime: bool) {
+    let bits: usize = [1, 2, 3, 4, 5, 6, 8, 16]
+        .into_iter()
+        .find(|bits: &usize| (1 << bits) >= palette_len)
+        .unwrap_or(16);
+    data.push(((bits << 1) | usize::from(runtime)) as u8);
+
+    let per_word: usize = 32 / bits;
+    for word_indices in indices.chunks(per_word) {
+        let word: u32 = word_indices.iter().enumerate().fold(
+            0,
+            |word: u32, (position, index): (usize, &usize)| {
+                word | ((*index as u32) << (position * bits))
+            },
+        );
+        data.extend_from_slice(&word.to_le_bytes());
+    }
+}
+
+/// Serializes the height map and the biomes of a chunk. The biomes are stored for the lowest
+/// sub-chunk, every sub-chunk above repeats the one below it.
+fn data_3d(chunk: &ChunkToModify, default_biome: Biome) -> Vec<u8> {
+    // Height above the bottom of the world of the lowest air block above all blocks
+    let mut heights: [i16; 256] = [0; 256];
+    for y in MIN_SUBCHUNK..=MAX_SUBCHUNK {
+        let Some(blocks) = chunk.section_blocks(y) else {
+            continue;
+        };
+        for (index, block) in blocks.iter().enumerate() {
+            if *block != AIR {
+                let height: i16 = (i16::from(y - MIN_SUBCHUNK) * 16) + (index / 256) as i16 + 1;
+                let column: &mut i16 = &mut heights[index % 256];
+                *column = (*column).max(height);
+            }
+        }
+    }
+
+    let mut data: Vec<u8> = vec![];
+    for height in heights {
+        data.extend_from_slice(&height.to_le_bytes());
+    }
+
+    let biomes: [Biome; 16] = chunk.biomes(default_biome);
+    let mut palette: Vec<i32> = vec![];
+    let mut indices: Vec<usize> = Vec::with_capacity(4096);
+    for x in 0..16 {
+        for z in 0..16 {
+            let id: i32 = bedrock_biome_id(biomes[z / 4 * 4 + x / 4]);
+            let index: usize = match palette.iter().position(|entry: &i32| *entry == id) {
+                Some(index) => index,
+                None => {
+                    palette.push(id);
+                    palette.len() - 1
+                }
+            };
+            indices.extend([index; 16]);
+        }
+    }
+    write_storage(&mut data, &indices, palette.len(), true);
+    data.extend_from_slice(&(palette.len() as i32).to_le_bytes());
+    for id in palette {
+        data.extend_from_slice(&id.to_le_bytes());
+    }
+    data.extend([0xff; (MAX_SUBCHUNK - MIN_SUBCHUNK) as usize]);
+    data
+}
+
+/// Returns the numeric Bedrock Edition ID of a biome
+fn bedrock_biome_id(biome: Biome) -> i32 {
+    match biome.name() {
+        "desert" => 2,
+        "forest" => 4,
+        "swamp" => 6,
+        "river" => 7,
+        "frozen_river" => 11,
+        "snowy_plains" => 12,
+        "beach" => 16,
+        "snowy_beach" => 26,
+        "snowy_taiga" => 30,
+        "meadow" => 186,
+        _ => 1,
+    }
+}
+
+/// Returns the Bedrock Edition name and block states of a block. Most blocks share their
+/// name with Java Edition, the others and all block states are converted here.
+fn bedrock_block(block: &Block) -> (String, HashMap<String, Value>) {
+    let java_name: &str = block.name();
+    let java_name: &str = java_name.strip_prefix("minecraft:").unwrap_or(java_name);
+    let properties: HashMap<String, String> = match block.properties() {
+        Some(Value::Compound(properties)) => properties
+            .into_iter()
+            .filter_map(|(key, value)| match value {
+                Value::String(value) => Some((key, value)),
+                _ => None,
+            })
+            .collect(),
+        _ => HashMap::new(),
+    };
+    let property = |key: &str| -> Option<&str> { properties.get(key).map(String::as_str) };
+    let is_set = |key: &str| -> Value { Value::Byte(i8::from(property(key) == Some("true"))) };
+    let number = |key: &str, default: i32| -> i32 {
+        property(key)
+            .and_then(|value: &str| value.parse().ok())
+            .unwrap_or(default)
+    };
+    let facing: &str = property("facing").unwrap_or("north");
+
+    let mut states: HashMap<String, Value> = HashMap::new();
+    let mut state = |key: &str, value: Value| {
+        states.insert(key.to_string(), value);
+    };
+    let name: String = match java_name {
+        "cave_air" => "air".to_string(),
+        "bricks" => "brick_block".to_string(),
+        "nether_bricks" => "nether_brick".to_string(),
+        "red_nether_bricks" => "red_nether_brick".to_string(),
+        "end_stone_bricks" => "end_bricks".to_string(),
+        "terracotta" => "hardened_clay".to_string(),
+        "melon" => "melon_block".to_string(),
+        "snow_block" => "snow".to_string(),
+        "grass" => "short_grass".to_string(),
+        "snow" => {
+            state("height", Value::Int(number("layers", 1) - 1));
+            state("covered_bit", Value::Byte(0));
+            "snow_layer".to_string()
+        }
+        "bedrock" => {
+            state("infiniburn_bit", Value::Byte(0));
+            java_name.to_string()
+        }
+        "oak_log" | "spruce_log" | "birch_log" | "jungle_log" | "polished_basalt"
+        | "purpur_pillar" | "smooth_quartz" | "hay_block" => {
+            state(
+                "pillar_axis",
+                Value::String(property("axis").unwrap_or("y").to_string()),
+            );
+            if java_name == "hay_block" {
+                state("deprecated", Value::Int(0));
+            }
+            java_name.to_string()
+        }
+        "oak_leaves" | "spruce_leaves" | "birch_leaves" | "jungle_leaves" => {
+            state("persistent_bit", Value::Byte(1));
+            state("update_bit", Value::Byte(0));
+            java_name.to_string()
+        }
+        name if name.ends_with("_slab") => {
+            let material: &str = match name.trim_end_matches("_slab") {
+                "stone" => "normal_stone",
+                material => material,
+            };
+            let half: &str = if property("type") == Some("top") {
+                "top"
+            } else {
+                "bottom"
+            };
+            state("minecraft:vertical_half", Value::String(half.to_string()));
+            if property("type") == Some("double") {
+                format!("{}_double_slab", material)
+            } else {
+                format!("{}_slab", material)
+            }
+        }
+        name if name.ends_with("_stairs") => {
+            let direction: i32 = match facing {
+                "east" => 0,
+                "west" => 1,
+                "south" => 2,
+                _ => 3,
+            };
+            state("weirdo_direction", Value::Int(direction));
+            state(
+                "upside_down_bit",
+                Value::Byte(i8::from(property("half") == Some("top"))),
+            );
+            name.to_string()
+        }
+        name if name.ends_with("_door") => {
+            let direction: i32 = match facing {
+                "east" => 0,
+                "south" => 1,
+                "west" => 2,
+                _ => 3,
+            };
+            state("direction", Value::Int(direction));
+            state(
+                "door_hinge_bit",
+                Value::Byte(i8::from(property("hinge") == Some("right"))),
+            );
+            state("open_bit", is_set("open"));
+            state(
+                "upper_block_bit",
+                Value::Byte(i8::from(property("half") == Some("upper"))),
+            );
+            name.to_string()
+        }
+        "tall_grass" => {
+            state(
+                "upper_block_bit",
+                Value::Byte(i8::from(property("half") == Some("upper"))),
+            );
+            java_name.to_string()
+        }
+        "wheat" | "carrots" | "potatoes" => {
+            state("growth", Value::Int(number("age", 0)));
+            java_name.to_string()
+        }
+        "beetroots" => {
+            // Beetroots grow in 8 stages instead of 4
+            state("growth", Value::Int((number("age", 0) * 2 + 1).min(7)));
+            "beetroot".to_string()
+        }
+        "farmland" => {
+            state("moisturized_amount", Value::Int(number("moisture", 0)));
+            java_name.to_string()
+        }
+        "water" => {
+            state("liquid_depth", Value::Int(number("level", 0)));
+            java_name.to_string()
+        }
+        "oak_sign" | "white_banner" => {
+            state("ground_sign_direction", Value::Int(number("rotation", 0)));
+            if java_name == "oak_sign" {
+                "standing_sign".to_string()
+            } else {
+                "standing_banner".to_string()
+            }
+        }
+        "rail" => {
+            let direction: i32 = match property("shape").unwrap_or("north_south") {
+                "east_west" => 1,
+                "ascending_east" => 2,
+                "ascending_west" => 3,
+                "ascending_north" => 4,
+                "ascending_south" => 5,
+                "south_east" => 6,
+                "south_west" => 7,
+                "north_west" => 8,
+                "north_east" => 9,
+                _ => 0,
+            };
+            state("rail_direction", Value::Int(direction));
+            java_name.to_string()
+        }
+        "chest" | "furnace" | "pumpkin" => {
+            let facing: &str = property("facing").unwrap_or("south");
+            state(
+                "minecraft:cardinal_direction",
+                Value::String(facing.to_string()),
+            );
+            if java_name == "furnace" && property("lit") == Some("true") {
+                "lit_furnace".to_string()
+            } else {
+                java_name.to_string()
+            }
+        }
+        "lectern" => {
+            let direction: i32 = match facing {
+                "south" => 0,
+                "west" => 1,
+                "east" => 3,
+                _ => 2,
+            };
+            state("direction", Value::Int(direction));
+            state("powered_bit", is_set("powered"));
+            java_name.to_string()
+        }
+        "ladder" => {
+            let direction: i32 = match facing {
+                "south" => 3,
+                "west" => 4,
+                "east" => 5,
+                _ => 2,
+            };
+            state("facing_direction", Value::Int(direction));
+            java_name.to_string()
+        }
+        "lantern" => {
+            state("hanging", is_set("hanging"));
+            java_name.to_string()
+        }
+        "daylight_detector" => {
+            state("redstone_signal", Value::Int(number("power", 0)));
+            if property("inverted") == Some("true") {
+                "daylight_detector_inverted".to_string()
+            } else {
+                java_name.to_string()
+            }
+        }
+        "scaffolding" => {
+            state("stability", Value::Int(number("distance", 0)));
+            state("stability_check", Value::Byte(0));
+            java_name.to_string()
+        }
+        "cauldron" => {
+            state("cauldron_liquid", Value::String("water".to_string()));
+            state("fill_level", Value::Int(0));
+            java_name.to_string()
+        }
+        // Potted plants are stored in a block entity, which is not converted
+        "flower_pot" | "potted_poppy" => {
+            state("update_bit", Value::Byte(0));
+            "flower_pot".to_string()
+        }
+        name if name.ends_with("_wall") => {
+            for side in ["east", "north", "south", "west"] {
+                let connection: &str = match property(side) {
+                    Some("low") => "short",
+                    Some("tall") => "tall",
+                    _ => "none",
+                };
+                state(
+                    &format!("wall_connection_type_{}", side),
+                    Value::String(connection.to_string()),
+                );
+            }
+            state(
+                "wall_post_bit",
+                Value::Byte(i8::from(property("up") != Some("false"))),
+            );
+            name.to_string()
+        }
+        "light" => format!("light_block_{}", number("level", 15)),
+        name => name.to_string(),
+    };
+
+    let name: String = if name.contains(':') {
+        name
+    } else {
+        format!("minecraft:{}", name)
+    };
+    (name, states)
+}
+
+/// Writes a named tag in the little-endian NBT format of Bedrock Edition
+fn write_nbt(data: &mut Vec<u8>, name: &str, value: &Value) {
+    data.push(nbt_tag(value));
+    write_nbt_string(data, name);
+    write_nbt_payload(data, value);
+}
+
+fn nbt_tag(value: &Value) -> u8 {
+    match value {
+        Value::Byte(_) => 1,
+        Value::Short(_) => 2,
+        Value::Int(_) => 3,
+        Value::Long(_) => 4,
+        Value::Float(_) => 5,
+        Value::Double(_) => 6,
+        Value::ByteArray(_) => 7,
+        Value::String(_) => 8,
+        Value::List(_) => 9,
+        Value::Compound(_) => 10,
+        Value::IntArray(_) => 11,
+        Value::LongArray(_) => 12,
+    }
+}
+
+fn write_nbt_string(data: &mut Vec<u8>, value: &str) {
+    data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    data.extend_from_slice(value.as_bytes());
+}
+
+fn write_nbt_payload(data: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Byte(value) => data.push(*value as u8),
+        Value::Short(value) => data.extend_from_slice(&value.to_le_bytes()),
+        Value::Int(value) => data.extend_from_slice(&value.to_le_bytes()),
+        Value::Long(value) => data.extend_from_slice(&value.to_le_bytes()),
+        Value::Float(value) => data.extend_from_slice(&value.to_le_bytes()),
+        Value::Double(value) => data.extend_from_slice(&value.to_le_bytes()),
+        Value::ByteArray(values) => {
+            data.extend_from_slice(&(values.len() as i32).to_le_bytes());
+            data.extend(values.iter().map(|value: &i8| *value as u8));
+        }
+        Value::String(value) => write_nbt_string(data, value),
+        Value::List(values) => {
+            data.push(values.first().map_or(0, nbt_tag));
+            data.extend_from_slice(&(values.len() as i32).to_le_bytes());
+            for value in values {
+                write_nbt_payload(data, value);
+            }
+        }
+        Value::Compound(entries) => {
+            for (name, value) in entries {
+                write_nbt(data, name, value);
+            }
+            data.push(0);
+        }
+        Value::IntArray(values) => {
+            data.extend_from_slice(&(values.len() as i32).to_le_bytes());
+            for value in values.iter() {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        Value::LongArray(values) => {
+            data.extend_from_slice(&(values.len() as i32).to_le_bytes());
+            for value in values.iter() {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+}
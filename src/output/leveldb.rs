@@ -0,0 +1,202 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Size of the blocks the records of a log file are split into
+const LOG_BLOCK_SIZE: usize = 32768;
+/// Checksum, length and type in front of every fragment of a record
+const LOG_HEADER_SIZE: usize = 7;
+
+const FULL_RECORD: u8 = 1;
+const FIRST_RECORD: u8 = 2;
+const MIDDLE_RECORD: u8 = 3;
+const LAST_RECORD: u8 = 4;
+
+/// Number of the descriptor file listing the files of the database
+const MANIFEST_NUMBER: u64 = 1;
+/// Number of the log file holding all written entries
+const LOG_NUMBER: u64 = 3;
+
+/// Writes a new LevelDB database as used by Bedrock Edition worlds. All entries go into the
+/// write-ahead log, which the game replays into its tables when opening the world, so no
+/// table files have to be written.
+pub struct LevelDbWriter {
+    dir: PathBuf,
+    log: LogWriter<BufWriter<File>>,
+    batch: Vec<u8>,
+    batch_count: u32,
+    last_sequence: u64,
+}
+
+impl LevelDbWriter {
+    /// Creates the database in the given directory, which must not contain a database yet
+    pub fn create(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let log: File = File::create(dir.join(format!("{:06}.log", LOG_NUMBER)))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            log: LogWriter::new(BufWriter::new(log)),
+            batch: vec![],
+            batch_count: 0,
+            last_sequence: 0,
+        })
+    }
+
+    /// Adds an entry to the current batch, which is written with the next call to `flush`
+    pub fn put(&mut self, key: &[u8], value: &[u8]) {
+        // Value entry: type, length-prefixed key and length-prefixed value
+        self.batch.push(1);
+        write_varint(&mut self.batch, key.len() as u64);
+        self.batch.extend_from_slice(key);
+        write_varint(&mut self.batch, value.len() as u64);
+        self.batch.extend_from_slice(value);
+        self.batch_count += 1;
+    }
+
+    /// Writes the entries added since the last flush as one batch to the log
+    pub fn flush(&mut self) -> io::Result<()> {
+        if self.batch_count == 0 {
+            return Ok(());
+        }
+
+        let mut record: Vec<u8> = Vec::with_capacity(12 + self.batch.len());
+        record.extend_from_slice(&(self.last_sequence + 1).to_le_bytes());
+        record.extend_from_slice(&self.batch_count.to_le_bytes());
+        record.extend_from_slice(&self.batch);
+        self.log.add_record(&record)?;
+
+        self.last_sequence += u64::from(self.batch_count);
+        self.batch.clear();
+        self.batch_count = 0;
+        Ok(())
+    }
+
+    /// Writes the remaining entries and the descriptor, which makes the database readable
+    pub fn finish(mut self) -> io::Result<()> {
+        self.flush()?;
+        self.log.into_inner().flush()?;
+
+        let mut edit: Vec<u8> = vec![];
+        let comparator: &[u8] = b"leveldb.BytewiseComparator";
+        write_varint(&mut edit, 1);
+        write_varint(&mut edit, comparator.len() as u64);
+        edit.extend_from_slice(comparator);
+        for (tag, value) in [
+            (2, LOG_NUMBER),
+            (9, 0),
+            (3, LOG_NUMBER + 1),
+            (4, self.last_sequence),
+        ] {
+            write_varint(&mut edit, tag);
+            write_varint(&mut edit, value);
+        }
+
+        let manifest_name: String = format!("MANIFEST-{:06}", MANIFEST_NUMBER);
+        let mut manifest: LogWriter<File> =
+            LogWriter::new(File::create(self.dir.join(&manifest_name))?);
+        manifest.add_record(&edit)?;
+        manifest.into_inner().sync_all()?;
+
+        fs::write(self.dir.join("CURRENT"), format!("{}\n", manifest_name))
+    }
+}
+
+/// Writes records in the log format of LevelDB, split into blocks of fixed size
+struct LogWriter<W: Write> {
+    writer: W,
+    block_offset: usize,
+}
+
+impl<W: Write> LogWriter<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer,
+            block_offset: 0,
+        }
+    }
+
+    fn add_record(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut remaining: &[u8] = data;
+        let mut is_first: bool = true;
+        loop {
+            // Headers are never split across blocks, the rest of a block is padded instead
+            let block_left: usize = LOG_BLOCK_SIZE - self.block_offset;
+            if block_left < LOG_HEADER_SIZE {
+                self.writer.write_all(&[0; LOG_HEADER_SIZE][..block_left])?;
+                self.block_offset = 0;
+            }
+
+            let available: usize = LOG_BLOCK_SIZE - self.block_offset - LOG_HEADER_SIZE;
+            let length: usize = remaining.len().min(available);
+            let is_last: bool = length == remaining.len();
+            let record_type: u8 = match (is_first, is_last) {
+                (true, true) => FULL_RECORD,
+                (true, false) => FIRST_RECORD,
+                (false, false) => MIDDLE_RECORD,
+                (false, true) => LAST_RECORD,
+            };
+            self.write_fragment(record_type, &remaining[..length])?;
+
+            remaining = &remaining[length..];
+            is_first = false;
+            if is_last {
+                return Ok(());
+            }
+        }
+    }
+
+    fn write_fragment(&mut self, record_type: u8, fragment: &[u8]) -> io::Result<()> {
+        let crc: u32 = crc32c(crc32c(0, &[record_type]), fragment);
+        // Checksums are stored masked, as LevelDB does for checksums of data containing them
+        let masked: u32 = crc.rotate_right(15).wrapping_add(0xa282_ead8);
+        self.writer.write_all(&masked.to_le_bytes())?;
+        self.writer
+            .write_all(&(fragment.len() as u16).to_le_bytes())?;
+        self.writer.write_all(&[record_type])?;
+        self.writer.write_all(fragment)?;
+        self.block_offset += LOG_HEADER_SIZE + fragment.len();
+        Ok(())
+    }
+
+    fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Lookup table of the CRC-32C (Castagnoli) checksum
+const CRC32C_TABLE: [u32; 256] = {
+    let mut table: [u32; 256] = [0; 256];
+    let mut index: usize = 0;
+    while index < 256 {
+        let mut crc: u32 = index as u32;
+        let mut bit: u32 = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0x82f6_3b78
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+};
+
+/// Extends a CRC-32C checksum with the given data
+fn crc32c(crc: u32, data: &[u8]) -> u32 {
+    let mut crc: u32 = !crc;
+    for byte in data {
+        crc = CRC32C_TABLE[((crc ^ u32::from(*byte)) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
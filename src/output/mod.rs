@@ -0,0 +1,82 @@
+mod anvil;
+mod bedrock;
+mod leveldb;
+mod preview;
+mod rcon;
+mod schematic;
+
+use crate::args::Args;
+use crate::block_definitions::Block;
+use crate::mc_version::McVersion;
+use crate::world_editor::WorldEditor;
+use clap::ValueEnum;
+use fastnbt::Value;
+
+/// Targets the generated world can be written to
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Region files of a Minecraft Java Edition world
+    Anvil,
+    /// LevelDB world of Minecraft Bedrock Edition
+    Bedrock,
+    /// Sponge schematic file (.schem) for WorldEdit and similar tools
+    Schematic,
+    /// Blocks placed on a running server through RCON
+    Rcon,
+    /// Top-down PNG preview of the area, without writing a world
+    Preview,
+}
+
+/// Destination of the generated blocks. The element processors only place blocks in the
+/// WorldEditor, which hands them to the backend selected with --format when saving.
+pub trait OutputBackend {
+    /// Writes all placed blocks, starting with the chunks closest to the focus block.
+    /// Backends writing in steps stop between them when the generation is cancelled.
+    fn write(&mut self, editor: &WorldEditor, focus: (i32, i32)) -> Result<(), String>;
+}
+
+/// Creates the backend for the output format selected in the arguments
+pub fn create_backend(args: &Args) -> Result<Box<dyn OutputBackend>, String> {
+    Ok(match args.format {
+        OutputFormat::Anvil => Box::new(anvil::AnvilBackend::new(args)?),
+        OutputFormat::Bedrock => Box::new(bedrock::BedrockBackend::new(args)?),
+        OutputFormat::Schematic => Box::new(schematic::SchematicBackend::new(args)),
+        OutputFormat::Rcon => Box::new(rcon::RconBackend::connect(args)?),
+        OutputFormat::Preview => Box::new(preview::PreviewBackend::new(args)),
+    })
+}
+
+/// Returns the block state string of a block as used in commands and schematic palettes,
+/// e.g. minecraft:oak_stairs[facing=north,half=bottom]. Blocks missing in the Minecraft
+/// version are replaced by a similar block without properties.
+fn block_state(block: &Block, version: McVersion) -> String {
+    let name: &str = version.block_name(block.name());
+    let mut state: String = if name.contains(':') {
+        name.to_string()
+    } else {
+        format!("minecraft:{}", name)
+    };
+
+    if name != block.name() {
+        return state;
+    }
+    if let Some(Value::Compound(properties)) = block.properties() {
+        let mut properties: Vec<(String, String)> = properties
+            .into_iter()
+            .filter_map(|(key, value)| match value {
+                Value::String(value) => Some((key, value)),
+                _ => None,
+            })
+            .collect();
+        if !properties.is_empty() {
+            properties.sort();
+            let properties: Vec<String> = properties
+                .into_iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            state.push_str(&format!("[{}]", properties.join(",")));
+        }
+    }
+
+    state
+}
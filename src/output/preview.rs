@@ -0,0 +1,132 @@
+use crate::args::Args;
+use crate::block_definitions::Block;
+use crate::colors::RGBTuple;
+use crate::output::OutputBackend;
+use crate::world_editor::WorldEditor;
+use colored::Colorize;
+use fnv::FnvHashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+/// Color of columns without any blocks
+const EMPTY_COLOR: RGBTuple = (24, 24, 24);
+/// Change of the brightness per block of height difference to the ground level
+const HEIGHT_SHADING: f64 = 0.015;
+
+/// Renders a top-down preview of the area as a PNG image with one pixel per block column,
+/// without writing a world. Higher blocks are drawn brighter, so buildings stand out.
+pub struct PreviewBackend {
+    path: PathBuf,
+    ground_level: i32,
+}
+
+impl PreviewBackend {
+    pub fn new(args: &Args) -> Self {
+        let path: &Path = Path::new(&args.path);
+        let path: PathBuf = if path.is_dir() {
+            path.join("arnis_preview.png")
+        } else {
+            path.with_extension("png")
+        };
+        Self {
+            path,
+            ground_level: args.ground_level,
+        }
+    }
+}
+
+impl OutputBackend for PreviewBackend {
+    fn write(&mut self, editor: &WorldEditor, focus: (i32, i32)) -> Result<(), String> {
+        // Highest block of every column
+        let mut columns: FnvHashMap<(i32, i32), (i32, Block)> = FnvHashMap::default();
+        for (chunk_x, chunk_z) in editor.chunk_positions(focus) {
+            for ((x, y, z), block) in editor.chunk_blocks(chunk_x, chunk_z) {
+                let column: &mut (i32, Block) = columns.entry((x, z)).or_insert((y, block));
+                if y > column.0 {
+                    *column = (y, block);
+                }
+            }
+        }
+        if columns.is_empty() {
+            return Err("没有可预览的方块".to_string());
+        }
+
+        let min_x: i32 = columns.keys().map(|(x, _)| *x).min().unwrap_or(0);
+        let max_x: i32 = columns.keys().map(|(x, _)| *x).max().unwrap_or(0);
+        let min_z: i32 = columns.keys().map(|(_, z)| *z).min().unwrap_or(0);
+        let max_z: i32 = columns.keys().map(|(_, z)| *z).max().unwrap_or(0);
+        let width: u32 = (max_x - min_x + 1) as u32;
+        let height: u32 = (max_z - min_z + 1) as u32;
+
+        let mut pixels: Vec<u8> = Vec::with_capacity((width * height * 3) as usize);
+        for z in min_z..=max_z {
+            for x in min_x..=max_x {
+                let color: RGBTuple = match columns.get(&(x, z)) {
+                    Some((y, block)) => {
+                        let shade: f64 = 1.0 + f64::from(y - self.ground_level) * HEIGHT_SHADING;
+                        shaded(block_color(block), shade.clamp(0.6, 1.6))
+                    }
+                    None => EMPTY_COLOR,
+                };
+                pixels.extend_from_slice(&[color.0, color.1, color.2]);
+            }
+        }
+
+        let file: File = File::create(&self.path)
+            .map_err(|e: std::io::Error| format!("无法创建预览图文件：{}", e))?;
+        let mut encoder: png::Encoder<BufWriter<File>> =
+            png::Encoder::new(BufWriter::new(file), width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&pixels))
+            .map_err(|e: png::EncodingError| format!("无法写入预览图：{}", e))?;
+
+        println!(
+            "{}",
+            format!("预览图已保存到 {}", self.path.display()).green()
+        );
+        Ok(())
+    }
+}
+
+/// Returns an approximate map color of a block, derived from its name
+fn block_color(block: &Block) -> RGBTuple {
+    let name: &str = block.name();
+    let colors: [(&str, RGBTuple); 22] = [
+        ("water", (64, 96, 220)),
+        ("ice", (150, 180, 255)),
+        ("snow", (240, 250, 250)),
+        ("leaves", (56, 110, 40)),
+        ("grass", (100, 160, 60)),
+        ("moss", (90, 130, 50)),
+        ("sand", (220, 210, 160)),
+        ("dirt", (130, 95, 65)),
+        ("farmland", (110, 75, 50)),
+        ("gravel", (140, 135, 130)),
+        ("black", (30, 30, 35)),
+        ("gray", (110, 110, 115)),
+        ("white", (225, 225, 225)),
+        ("red", (160, 50, 45)),
+        ("orange", (210, 110, 40)),
+        ("yellow", (230, 200, 60)),
+        ("green", (90, 130, 50)),
+        ("blue", (50, 70, 160)),
+        ("brick", (150, 85, 70)),
+        ("oak", (160, 125, 80)),
+        ("spruce", (110, 80, 50)),
+        ("stone", (125, 125, 125)),
+    ];
+    colors
+        .iter()
+        .find(|(keyword, _)| name.contains(keyword))
+        .map_or((150, 150, 150), |(_, color)| *color)
+}
+
+/// Returns the color brightened or darkened by the given factor
+fn shaded(color: RGBTuple, factor: f64) -> RGBTuple {
+    let channel = |value: u8| -> u8 { (f64::from(value) * factor).round().min(255.0) as u8 };
+    (channel(color.0), channel(color.1), channel(color.2))
+}
@@ -0,0 +1,155 @@
+use crate::args::Args;
+use crate::block_definitions::Block;
+use crate::mc_version::McVersion;
+use crate::output::{block_state, OutputBackend};
+use crate::progress::{stage_progress_bar, ProgressEvent, Stage};
+use crate::world_editor::WorldEditor;
+use indicatif::ProgressBar;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// Packet type of the login request
+const PACKET_LOGIN: i32 = 3;
+/// Packet type of a command
+const PACKET_COMMAND: i32 = 2;
+/// Request id returned by the server when the password is wrong
+const AUTH_FAILED: i32 = -1;
+/// Time to wait for a response before giving up on the server
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Places the blocks on a running Minecraft server through its remote console. Every chunk
+/// is force-loaded while its blocks are filled in, rows of the same block are merged into
+/// a single fill command.
+pub struct RconBackend {
+    stream: TcpStream,
+    next_id: i32,
+    /// Minecraft version of the server, given with --mc-version
+    version: McVersion,
+}
+
+impl RconBackend {
+    /// Connects to the server given with --rcon-address and logs in with --rcon-password
+    pub fn connect(args: &Args) -> Result<Self, String> {
+        let stream: TcpStream =
+            TcpStream::connect(&args.rcon_address).map_err(|e: io::Error| {
+                format!("无法连接到 RCON 服务器 {}：{}", args.rcon_address, e)
+            })?;
+        stream
+            .set_read_timeout(Some(READ_TIMEOUT))
+            .map_err(|e: io::Error| format!("无法连接到 RCON 服务器：{}", e))?;
+
+        let mut backend: RconBackend = Self {
+            stream,
+            next_id: 1,
+            version: args.mc_version,
+        };
+        let password: &str = args.rcon_password.as_deref().unwrap_or_default();
+        let response_id: i32 = backend
+            .request(PACKET_LOGIN, password)
+            .map_err(|e: io::Error| format!("RCON 登录失败：{}", e))?;
+        if response_id == AUTH_FAILED {
+            return Err("RCON 密码错误".to_string());
+        }
+        Ok(backend)
+    }
+
+    /// Runs a command on the server
+    fn command(&mut self, command: &str) -> Result<(), String> {
+        self.request(PACKET_COMMAND, command)
+            .map(|_| ())
+            .map_err(|e: io::Error| format!("RCON 命令失败：{}", e))
+    }
+
+    /// Places a row of blocks starting at the given position and ending at end_x
+    fn fill(
+        &mut self,
+        ((x, y, z), end_x, block): ((i32, i32, i32), i32, Block),
+    ) -> Result<(), String> {
+        let state: String = block_state(&block, self.version);
+        let y: i32 = y + self.version.height_offset();
+        if end_x == x {
+            self.command(&format!("setblock {} {} {} {}", x, y, z, state))
+        } else {
+            self.command(&format!(
+                "fill {} {} {} {} {} {} {}",
+                x, y, z, end_x, y, z, state
+            ))
+        }
+    }
+
+    /// Sends a packet and waits for the response, returning the request id of the response
+    fn request(&mut self, packet_type: i32, body: &str) -> io::Result<i32> {
+        let id: i32 = self.next_id;
+        self.next_id += 1;
+
+        let mut packet: Vec<u8> = Vec::with_capacity(body.len() + 14);
+        packet.extend_from_slice(&(body.len() as i32 + 10).to_le_bytes());
+        packet.extend_from_slice(&id.to_le_bytes());
+        packet.extend_from_slice(&packet_type.to_le_bytes());
+        packet.extend_from_slice(body.as_bytes());
+        packet.extend_from_slice(&[0, 0]);
+        self.stream.write_all(&packet)?;
+
+        let mut length: [u8; 4] = [0; 4];
+        self.stream.read_exact(&mut length)?;
+        let mut response: Vec<u8> = vec![0; i32::from_le_bytes(length).max(8) as usize];
+        self.stream.read_exact(&mut response)?;
+        Ok(i32::from_le_bytes([
+            response[0],
+            response[1],
+            response[2],
+            response[3],
+        ]))
+    }
+}
+
+impl OutputBackend for RconBackend {
+    fn write(&mut self, editor: &WorldEditor, focus: (i32, i32)) -> Result<(), String> {
+        let chunks: Vec<(i32, i32)> = editor.chunk_positions(focus);
+        let total_chunks: u64 = chunks.len() as u64;
+
+        let save_pb: ProgressBar = stage_progress_bar(Stage::Saving, total_chunks, "区块");
+
+        let mut last_emitted_progress: f64 = 90.0;
+        for (chunk_x, chunk_z) in chunks {
+            editor.context().check_cancelled()?;
+            let (x, z) = (chunk_x * 16, chunk_z * 16);
+            self.command(&format!("forceload add {} {}", x, z))?;
+
+            let mut blocks: Vec<((i32, i32, i32), Block)> = editor.chunk_blocks(chunk_x, chunk_z);
+            blocks.sort_by_key(|((x, y, z), _)| (*y, *z, *x));
+            let mut run: Option<((i32, i32, i32), i32, Block)> = None;
+            for ((x, y, z), block) in blocks {
+                // Extend the current row while the same block continues along x
+                if let Some((start, end_x, run_block)) = &mut run {
+                    if start.1 == y && start.2 == z && *end_x + 1 == x && *run_block == block {
+                        *end_x = x;
+                        continue;
+                    }
+                }
+                if let Some(finished) = run.replace(((x, y, z), x, block)) {
+                    self.fill(finished)?;
+                }
+            }
+            if let Some(finished) = run {
+                self.fill(finished)?;
+            }
+
+            self.command(&format!("forceload remove {} {}", x, z))?;
+            save_pb.inc(1);
+
+            let progress: f64 = 90.0 + 9.0 * save_pb.position() as f64 / total_chunks as f64;
+            if progress - last_emitted_progress > 0.25 {
+                editor.context().emit_progress(
+                    ProgressEvent::new(Stage::Saving, progress)
+                        .counts(save_pb.position(), total_chunks),
+                );
+                last_emitted_progress = progress;
+            }
+        }
+
+        save_pb.finish();
+        Ok(())
+    }
+}
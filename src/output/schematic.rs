@@ -0,0 +1,149 @@
+use crate::args::Args;
+use crate::block_definitions::Block;
+use crate::mc_version::McVersion;
+use crate::output::{block_state, OutputBackend};
+use crate::world_editor::WorldEditor;
+use colored::Colorize;
+use fastnbt::{ByteArray, IntArray, SerOpts, Value};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Largest edge length of a schematic, limited by the unsigned short dimensions
+const MAX_SCHEMATIC_SIZE: i32 = u16::MAX as i32;
+
+/// Writes the world into a Sponge schematic (version 2), which WorldEdit and similar tools
+/// can paste into any world
+pub struct SchematicBackend {
+    path: PathBuf,
+    version: McVersion,
+}
+
+impl SchematicBackend {
+    pub fn new(args: &Args) -> Self {
+        let path: &Path = Path::new(&args.path);
+        let path: PathBuf = if path.is_dir() {
+            path.join("arnis.schem")
+        } else if path.extension().is_none() {
+            path.with_extension("schem")
+        } else {
+            path.to_path_buf()
+        };
+        Self {
+            path,
+            version: args.mc_version,
+        }
+    }
+}
+
+impl OutputBackend for SchematicBackend {
+    fn write(&mut self, editor: &WorldEditor, focus: (i32, i32)) -> Result<(), String> {
+        let blocks: Vec<((i32, i32, i32), Block)> = editor
+            .chunk_positions(focus)
+            .into_iter()
+            .flat_map(|(chunk_x, chunk_z)| editor.chunk_blocks(chunk_x, chunk_z))
+            .collect();
+        if blocks.is_empty() {
+            return Err("没有可写入原理图的方块".to_string());
+        }
+
+        let min: (i32, i32, i32) = blocks.iter().fold(
+            (i32::MAX, i32::MAX, i32::MAX),
+            |(x, y, z), ((bx, by, bz), _)| (x.min(*bx), y.min(*by), z.min(*bz)),
+        );
+        let max: (i32, i32, i32) = blocks.iter().fold(
+            (i32::MIN, i32::MIN, i32::MIN),
+            |(x, y, z), ((bx, by, bz), _)| (x.max(*bx), y.max(*by), z.max(*bz)),
+        );
+        let width: i32 = max.0 - min.0 + 1;
+        let height: i32 = max.1 - min.1 + 1;
+        let length: i32 = max.2 - min.2 + 1;
+        if width > MAX_SCHEMATIC_SIZE || length > MAX_SCHEMATIC_SIZE {
+            return Err(format!(
+                "区域过大，无法写入原理图（{} x {}，最大 {}）",
+                width, length, MAX_SCHEMATIC_SIZE
+            ));
+        }
+
+        // Palette index 0 is air, so unset positions stay empty
+        let mut palette: HashMap<String, i32> = HashMap::new();
+        palette.insert("minecraft:air".to_string(), 0);
+        let mut block_ids: HashMap<Block, i32> = HashMap::new();
+        let mut indices: Vec<i32> = vec![0; width as usize * height as usize * length as usize];
+        for ((x, y, z), block) in blocks {
+            let next_id: i32 = palette.len() as i32;
+            let id: i32 = *block_ids.entry(block).or_insert_with(|| {
+                *palette
+                    .entry(block_state(&block, self.version))
+                    .or_insert(next_id)
+            });
+            let index: usize =
+                ((x - min.0) + (z - min.2) * width + (y - min.1) * width * length) as usize;
+            indices[index] = id;
+        }
+
+        let mut block_data: Vec<i8> = Vec::with_capacity(indices.len());
+        for id in indices {
+            write_varint(&mut block_data, id as u32);
+        }
+
+        let mut schematic: HashMap<String, Value> = HashMap::new();
+        schematic.insert("Version".to_string(), Value::Int(2));
+        schematic.insert(
+            "DataVersion".to_string(),
+            Value::Int(self.version.data_version()),
+        );
+        schematic.insert("Width".to_string(), Value::Short(width as u16 as i16));
+        schematic.insert("Height".to_string(), Value::Short(height as u16 as i16));
+        schematic.insert("Length".to_string(), Value::Short(length as u16 as i16));
+        schematic.insert(
+            "Offset".to_string(),
+            Value::IntArray(IntArray::new(vec![min.0, min.1, min.2])),
+        );
+        schematic.insert("PaletteMax".to_string(), Value::Int(palette.len() as i32));
+        schematic.insert(
+            "Palette".to_string(),
+            Value::Compound(
+                palette
+                    .into_iter()
+                    .map(|(state, id)| (state, Value::Int(id)))
+                    .collect(),
+            ),
+        );
+        schematic.insert(
+            "BlockData".to_string(),
+            Value::ByteArray(ByteArray::new(block_data)),
+        );
+
+        let data: Vec<u8> = fastnbt::to_bytes_with_opts(
+            &Value::Compound(schematic),
+            SerOpts::new().root_name("Schematic"),
+        )
+        .map_err(|e: fastnbt::error::Error| format!("无法序列化原理图：{}", e))?;
+        let file: File = File::create(&self.path)
+            .map_err(|e: std::io::Error| format!("无法创建原理图文件：{}", e))?;
+        let mut encoder: GzEncoder<File> = GzEncoder::new(file, Compression::default());
+        encoder
+            .write_all(&data)
+            .and_then(|_| encoder.finish().map(|_| ()))
+            .map_err(|e: std::io::Error| format!("无法写入原理图：{}", e))?;
+
+        println!(
+            "{}",
+            format!("原理图已保存到 {}", self.path.display()).green()
+        );
+        Ok(())
+    }
+}
+
+/// Appends a palette index in the variable-length encoding of the schematic block data
+fn write_varint(buffer: &mut Vec<i8>, mut value: u32) {
+    while value >= 0x80 {
+        buffer.push(((value & 0x7F) | 0x80) as u8 as i8);
+        value >>= 7;
+    }
+    buffer.push(value as u8 as i8);
+}
@@ -0,0 +1,188 @@
+use crate::file_lock::write_locked;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::PathBuf;
+
+/// Named set of options selected with --preset or in the GUI. The options are the command
+/// line arguments without the leading dashes, like the options of a server job.
+#[derive(Serialize, Debug, Clone)]
+pub struct Preset {
+    pub name: String,
+    pub description: String,
+    /// Whether the preset comes with Arnis, those can not be replaced by user presets
+    pub builtin: bool,
+    pub options: Map<String, Value>,
+}
+
+fn builtin_presets() -> Vec<Preset> {
+    let preset = |name: &str, description: &str, options: Value| -> Preset {
+        Preset {
+            name: name.to_string(),
+            description: description.to_string(),
+            builtin: true,
+            options: options.as_object().cloned().unwrap_or_default(),
+        }
+    };
+    vec![
+        preset(
+            "fast-draft",
+            "快速草稿：半比例、不计算光照，跳过植被和细节",
+            json!({ "scale": 0.5, "no_lighting": true, "exclude": "vegetation,details" }),
+        ),
+        preset(
+            "detailed-city",
+            "详细城市：建筑内部、街道细节、村民和零散植被",
+            json!({ "interior": true, "details": true, "villagers": true, "foliage": true }),
+        ),
+        preset(
+            "terrain-only",
+            "仅地形：只生成地面、水域、土地利用和植被",
+            json!({ "include": "terrain,water,landuse,vegetation" }),
+        ),
+    ]
+}
+
+/// Returns the file the user presets are stored in, in the config directory
+fn user_presets_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|config_dir: PathBuf| config_dir.join("arnis").join("presets.json"))
+}
+
+/// Reads the user presets, keyed by name. A missing or unreadable file has no presets.
+fn read_user_presets() -> Map<String, Value> {
+    user_presets_path()
+        .and_then(|path: PathBuf| fs::read_to_string(path).ok())
+        .and_then(|content: String| serde_json::from_str::<Map<String, Value>>(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Returns the built-in presets followed by the presets saved by the user
+pub fn list_presets() -> Vec<Preset> {
+    let mut presets: Vec<Preset> = builtin_presets();
+    for (name, preset) in read_user_presets() {
+        presets.push(Preset {
+            name,
+            description: preset["description"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            builtin: false,
+            options: preset["options"].as_object().cloned().unwrap_or_default(),
+        });
+    }
+    presets
+}
+
+/// Saves a user preset, replacing an earlier one with the same name
+pub fn save_preset(
+    name: &str,
+    description: &str,
+    options: Map<String, Value>,
+) -> Result<(), String> {
+    let name: &str = name.trim();
+    if name.is_empty() {
+        return Err("预设名称不能为空".to_string());
+    }
+    if builtin_presets()
+        .iter()
+        .any(|preset: &Preset| preset.name == name)
+    {
+        return Err(format!("不能覆盖内置预设：{}", name));
+    }
+
+    let path: PathBuf = user_presets_path().ok_or_else(|| "找不到配置目录".to_string())?;
+    let mut presets: Map<String, Value> = read_user_presets();
+    presets.insert(
+        name.to_string(),
+        json!({ "description": description, "options": options }),
+    );
+    let content: String = serde_json::to_string_pretty(&presets)
+        .map_err(|e: serde_json::Error| format!("无法保存预设：{}", e))?;
+    write_locked(&path, &content).map_err(|e: std::io::Error| format!("无法保存预设：{}", e))
+}
+
+/// Converts options into command line arguments. Flags are given as true, options given
+/// several times as arrays.
+pub fn option_arguments<'a>(
+    options: impl IntoIterator<Item = (&'a String, &'a Value)>,
+) -> Result<Vec<String>, String> {
+    let mut arguments: Vec<String> = vec![];
+    for (name, value) in options {
+        let flag: String = format!("--{}", name.replace('_', "-"));
+        match value {
+            Value::Bool(true) => arguments.push(flag),
+            Value::Bool(false) | Value::Null => {}
+            Value::Array(values) => {
+                for value in values {
+                    arguments.push(flag.clone());
+                    arguments.push(option_value(value)?);
+                }
+            }
+            value => {
+                arguments.push(flag);
+                arguments.push(option_value(value)?);
+            }
+        }
+    }
+    Ok(arguments)
+}
+
+fn option_value(value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(value) => Ok(value.clone()),
+        Value::Number(value) => Ok(value.to_string()),
+        _ => Err(format!("选项值无效：{}", value)),
+    }
+}
+
+/// Adds the options of the preset given with --preset to the command line. Options given
+/// on the command line take precedence over those of the preset.
+pub fn apply_preset(command_line: &[String]) -> Result<Vec<String>, String> {
+    let Some(name) = preset_name(command_line) else {
+        return Ok(command_line.to_vec());
+    };
+
+    let presets: Vec<Preset> = list_presets();
+    let preset: &Preset = presets
+        .iter()
+        .find(|preset: &&Preset| preset.name == name)
+        .ok_or_else(|| {
+            let names: Vec<&str> = presets
+                .iter()
+                .map(|preset: &Preset| preset.name.as_str())
+                .collect();
+            format!("未知的预设：{}（可用：{}）", name, names.join("、"))
+        })?;
+
+    let is_given = |name: &String| -> bool {
+        let flag: String = format!("--{}", name.replace('_', "-"));
+        command_line
+            .iter()
+            .any(|arg: &String| *arg == flag || arg.starts_with(&format!("{}=", flag)))
+    };
+    let preset_arguments: Vec<String> = option_arguments(
+        preset
+            .options
+            .iter()
+            .filter(|(name, _): &(&String, &Value)| !is_given(name)),
+    )?;
+
+    let mut arguments: Vec<String> = command_line.to_vec();
+    let position: usize = arguments.len().min(1);
+    arguments.splice(position..position, preset_arguments);
+    Ok(arguments)
+}
+
+/// Returns the name given with --preset on the command line
+fn preset_name(command_line: &[String]) -> Option<&str> {
+    command_line
+        .iter()
+        .enumerate()
+        .find_map(|(index, arg): (usize, &String)| {
+            if arg == "--preset" {
+                command_line.get(index + 1).map(String::as_str)
+            } else {
+                arg.strip_prefix("--preset=")
+            }
+        })
+}
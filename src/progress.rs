@@ -1,13 +1,26 @@
+#[cfg(feature = "gui")]
+use crate::notification::show_notification;
+use indicatif::{ProgressBar, ProgressStyle};
+#[cfg(feature = "gui")]
 use once_cell::sync::OnceCell;
+use serde::Serialize;
+#[cfg(feature = "gui")]
 use serde_json::json;
+use std::sync::Mutex;
+use std::time::Instant;
+#[cfg(feature = "gui")]
 use tauri::{Emitter, WebviewWindow};
 
+/// Window of the GUI, only available when built with the gui feature
+#[cfg(feature = "gui")]
 pub static MAIN_WINDOW: OnceCell<WebviewWindow> = OnceCell::new();
 
+#[cfg(feature = "gui")]
 pub fn set_main_window(window: WebviewWindow) {
     MAIN_WINDOW.set(window).ok();
 }
 
+#[cfg(feature = "gui")]
 pub fn get_main_window() -> Option<&'static WebviewWindow> {
     MAIN_WINDOW.get()
 }
@@ -15,32 +28,193 @@ pub fn get_main_window() -> Option<&'static WebviewWindow> {
 /// This function checks if the program is running with a GUI window.
 /// Returns `true` if a GUI window is initialized, `false` otherwise.
 pub fn is_running_with_gui() -> bool {
-    get_main_window().is_some()
+    #[cfg(feature = "gui")]
+    let running: bool = get_main_window().is_some();
+    #[cfg(not(feature = "gui"))]
+    let running: bool = false;
+    running
+}
+
+/// Stage of the generation a progress event belongs to. The GUI shows a localized label
+/// for every stage, so the identifiers must stay in sync with the frontend.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Fetching,
+    /// Download failed and is tried again, counting the attempts
+    Retrying,
+    Parsing,
+    /// Elements are processed, counting the elements
+    Processing,
+    /// Ground layer is generated, counting the blocks
+    Ground,
+    /// Region files are written, counting the regions
+    Saving,
+    Complete,
+    /// Generation was stopped by the user
+    Cancelled,
+    Error,
+}
+
+impl Stage {
+    /// Short name shown in front of the command line progress bars
+    pub fn label(&self) -> &'static str {
+        match self {
+            Stage::Fetching | Stage::Retrying => "下载",
+            Stage::Parsing => "解析",
+            Stage::Processing => "处理元素",
+            Stage::Ground => "地面层",
+            Stage::Saving => "保存",
+            Stage::Complete => "完成",
+            Stage::Cancelled => "已取消",
+            Stage::Error => "错误",
+        }
+    }
+}
+
+/// Progress update sent to the GUI as the payload of a "progress-update" event
+#[derive(Serialize, Debug, Clone)]
+pub struct ProgressEvent {
+    pub stage: Stage,
+    /// Overall progress in percent, or -1 to keep the current progress
+    pub progress: f64,
+    /// Items done within the stage, if the stage counts items
+    pub current: Option<u64>,
+    /// Items of the stage in total
+    pub total: Option<u64>,
+    /// Seconds since the stage started
+    pub elapsed: Option<f64>,
+    /// Estimated seconds until the stage is done, from the rate of the items done so far
+    pub remaining: Option<f64>,
+    /// Problems that did not stop the generation, e.g. elements rendered as outlines only
+    pub warnings: Vec<String>,
+    /// Reason of a failed generation, only set for the error stage
+    pub error: Option<String>,
+    /// Summary of a finished generation, only set for the complete stage
+    pub summary: Option<String>,
+}
+
+impl ProgressEvent {
+    pub fn new(stage: Stage, progress: f64) -> Self {
+        Self {
+            stage,
+            progress,
+            current: None,
+            total: None,
+            elapsed: None,
+            remaining: None,
+            warnings: vec![],
+            error: None,
+            summary: None,
+        }
+    }
+
+    pub fn counts(mut self, current: u64, total: u64) -> Self {
+        self.current = Some(current);
+        self.total = Some(total);
+        self
+    }
+
+    pub fn warnings(mut self, warnings: Vec<String>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// Fills in the elapsed and remaining time of the stage. The stage clock restarts
+    /// whenever an event of another stage is emitted.
+    pub fn timed(mut self, clock: &StageClock) -> Self {
+        let Ok(mut stage_start) = clock.0.lock() else {
+            return self;
+        };
+        let start: Instant = match *stage_start {
+            Some((stage, start)) if stage == self.stage => start,
+            _ => {
+                let now: Instant = Instant::now();
+                *stage_start = Some((self.stage, now));
+                now
+            }
+        };
+
+        let elapsed: f64 = start.elapsed().as_secs_f64();
+        self.elapsed = Some(elapsed);
+        if let (Some(current), Some(total)) = (self.current, self.total) {
+            if current > 0 && current <= total {
+                self.remaining = Some(elapsed * (total - current) as f64 / current as f64);
+            }
+        }
+        self
+    }
+}
+
+/// Stage of the last progress event of a generation and when it started, for the timing of
+/// the events
+#[derive(Default)]
+pub struct StageClock(Mutex<Option<(Stage, Instant)>>);
+
+/// Creates the command line progress bar of a stage counting items, showing the stage
+/// name, the elapsed time and the estimated remaining time
+pub fn stage_progress_bar(stage: Stage, total: u64, unit: &str) -> ProgressBar {
+    let progress_bar: ProgressBar = ProgressBar::new(total);
+    progress_bar.set_style(
+        ProgressStyle::default_bar()
+            .template(&format!(
+                "{{prefix:.bold}} {{spinner:.green}} [{{elapsed_precise}}] [{{bar:45}}] {{pos}}/{{len}} {} (剩余 {{eta}}) {{msg}}",
+                unit
+            ))
+            .unwrap()
+            .progress_chars("█▓░"),
+    );
+    progress_bar.set_prefix(stage.label());
+    progress_bar
 }
 
 /// This code manages a multi-step process with a progress bar indicating the overall completion.
-/// The progress updates are mapped to specific steps in the pipeline:
+/// The progress updates are mapped to specific stages in the pipeline:
 ///
-/// [1/5] Fetching data... - Starts at: 0% / Completes at: 5%
-/// [2/5] Parsing data... - Starts at: 5% / Completes at: 10%
-/// [3/5] Processing data... - Starts at: 10% / Completes at: 60%
-/// [4/5] Generating ground layer... - Starts at: 60% / Completes at: 90%
-/// [5/5] Saving world... - Starts at: 90% / Completes at: 100%
+/// [1/5] Stage::Fetching - Starts at: 0% / Completes at: 5%
+/// [2/5] Stage::Parsing - Starts at: 5% / Completes at: 10%
+/// [3/5] Stage::Processing - Starts at: 10% / Completes at: 60%
+/// [4/5] Stage::Ground - Starts at: 60% / Completes at: 90%
+/// [5/5] Stage::Saving - Starts at: 90% / Completes at: 100%
 ///
-/// The function `emit_gui_progress_update` is used to send real-time progress updates to the UI.
-pub fn emit_gui_progress_update(progress: f64, message: &str) {
+/// A generation reports its progress to the listener of its `GenerationContext`. The GUI
+/// passes the events on to its window with `emit_gui_progress_update`.
+#[cfg_attr(not(feature = "gui"), allow(unused_variables))]
+pub fn emit_gui_progress_update(event: &ProgressEvent) {
+    #[cfg(feature = "gui")]
+    if let Some(window) = get_main_window() {
+        if let Err(e) = window.emit("progress-update", event) {
+            eprintln!("无法发出进度事件：{}", e);
+        }
+    }
+}
+
+/// Reports the end of a generation with a summary, both in the window and as an OS
+/// notification, as the window is often in the background by then.
+#[cfg_attr(not(feature = "gui"), allow(unused_variables))]
+pub fn emit_gui_completion(summary: &str, world_path: &str) {
+    #[cfg(feature = "gui")]
     if let Some(window) = get_main_window() {
         let payload = json!({
-            "progress": progress,
-            "message": message
+            "summary": summary,
+            "worldPath": world_path
         });
 
-        if let Err(e) = window.emit("progress-update", payload) {
-            eprintln!("无法发出进度事件：{}", e);
+        if let Err(e) = window.emit("generation-complete", payload) {
+            eprintln!("无法发出完成事件：{}", e);
         }
+
+        show_notification(window, "Arnis - 世界生成完成", summary);
     }
 }
 
+/// Reports a generation stopped with the cancel button
+pub fn emit_gui_cancelled() {
+    emit_gui_progress_update(&ProgressEvent::new(Stage::Cancelled, -1.0));
+}
+
 pub fn emit_gui_error(message: &str) {
-    emit_gui_progress_update(0.0, &format!("错误！ {}", message));
+    let mut event: ProgressEvent = ProgressEvent::new(Stage::Error, 0.0);
+    event.error = Some(message.to_string());
+    emit_gui_progress_update(&event);
 }
@@ -0,0 +1,16 @@
+use fnv::FnvHasher;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::hash::{Hash, Hasher};
+
+/// Returns a random number generator derived from the world seed and a key, usually the
+/// element ID or a block position. As every element draws from its own generator, it
+/// looks the same no matter in which order or together with which elements it is generated.
+/// The seed is held by the `WorldEditor` of the generation, so generations running side by
+/// side do not share it.
+pub fn seeded_rng(seed: u64, key: impl Hash) -> StdRng {
+    let mut hasher: FnvHasher = FnvHasher::default();
+    seed.hash(&mut hasher);
+    key.hash(&mut hasher);
+    StdRng::seed_from_u64(hasher.finish())
+}
@@ -1,91 +1,356 @@
-use crate::progress::{emit_gui_error, emit_gui_progress_update, is_running_with_gui};
+use crate::area_polygon::AreaPolygon;
+use crate::context::GenerationContext;
+use crate::file_lock::{lock_file, write_locked};
+use crate::progress::{ProgressEvent, Stage};
 use colored::Colorize;
+use fnv::FnvHasher;
+use osmpbf::{Element, ElementReader, RelMember, RelMemberType};
 use rand::seq::SliceRandom;
 use reqwest::blocking::Client;
 use reqwest::blocking::ClientBuilder;
-use serde_json::Value;
-use std::fs::File;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::hash::Hasher;
 use std::io::{self, BufReader, Write};
+use std::path::PathBuf;
 use std::process::Command;
 use std::time::Duration;
 
+/// Error of a single download attempt. Retryable errors make the download switch to
+/// the next Overpass server after a delay.
+enum DownloadError {
+    Retryable(String),
+    Fatal(String),
+}
+
+/// Maximum number of download attempts across all Overpass servers
+const MAX_DOWNLOAD_ATTEMPTS: u32 = 6;
+
+/// How downloaded Overpass responses are cached on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Use a cached response if present and store new downloads
+    Enabled,
+    /// Ignore cached responses but store the new download
+    Refresh,
+    /// Neither read nor write the cache
+    Disabled,
+}
+
+/// Returns the cache file for a query. The query contains the bounding box, so the
+/// hash identifies both the area and the requested features.
+fn cache_file_path(query: &str) -> Option<PathBuf> {
+    let mut hasher: FnvHasher = FnvHasher::default();
+    hasher.write(query.as_bytes());
+    dirs::cache_dir().map(|cache_dir: PathBuf| {
+        cache_dir
+            .join("arnis")
+            .join("overpass")
+            .join(format!("{:016x}.json", hasher.finish()))
+    })
+}
+
+/// Reads a previously cached Overpass response
+fn read_cache(query: &str) -> Option<(Value, String)> {
+    let path: PathBuf = cache_file_path(query)?;
+    if !path.exists() {
+        return None;
+    }
+    // Another process may be writing the same entry
+    let _lock: File = lock_file(&path, false).ok()?;
+    let response: String = fs::read_to_string(path).ok()?;
+    let data: Value = serde_json::from_str(&response).ok()?;
+    Some((data, response))
+}
+
+/// Stores an Overpass response in the cache, together with the bounding box it covers.
+/// Failures only produce a warning, as the cache is not required for generating the world.
+fn write_cache(query: &str, response: &str, bbox: (f64, f64, f64, f64)) {
+    let Some(path) = cache_file_path(query) else {
+        return;
+    };
+
+    let result: io::Result<()> = write_locked(&path, response).and_then(|_| {
+        let bbox: [f64; 4] = [bbox.0, bbox.1, bbox.2, bbox.3];
+        write_locked(&path.with_extension("bbox.json"), &json!(bbox).to_string())
+    });
+    if let Err(e) = result {
+        eprintln!("{}", format!("警告：无法写入缓存：{}", e).yellow());
+    }
+}
+
+/// Cached data available for a bounding box, given as (min_lng, min_lat, max_lng, max_lat)
+#[derive(Serialize, Debug)]
+pub struct CacheCoverage {
+    /// Whether a run for exactly this bounding box can use the cache without downloading
+    pub exact: bool,
+    /// Share of the bounding box covered by any cached area, between 0 and 1
+    pub covered_fraction: f64,
+    /// Cached areas overlapping the bounding box
+    pub cached_areas: Vec<[f64; 4]>,
+    /// Expected download size in bytes, estimated from the density of the cached data
+    pub estimated_download_bytes: Option<u64>,
+}
+
+/// Reports which parts of a bounding box are covered by the download cache. Only a cached
+/// response for the exact bounding box avoids the download.
+pub fn cache_coverage(bbox: (f64, f64, f64, f64)) -> CacheCoverage {
+    let exact: bool =
+        cache_file_path(&overpass_query(bbox, None)).is_some_and(|path| path.exists());
+
+    // Every cached response has the bounding box it covers stored next to it
+    let mut entries: Vec<([f64; 4], u64)> = vec![];
+    let cache_dir: Option<PathBuf> =
+        cache_file_path("").and_then(|path: PathBuf| path.parent().map(PathBuf::from));
+    if let Some(dir_entries) = cache_dir.and_then(|dir: PathBuf| fs::read_dir(dir).ok()) {
+        for path in dir_entries
+            .flatten()
+            .map(|entry: fs::DirEntry| entry.path())
+        {
+            let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let Some(hash) = file_name.strip_suffix(".bbox.json") else {
+                continue;
+            };
+            let area: Option<[f64; 4]> = fs::read_to_string(&path)
+                .ok()
+                .and_then(|content: String| serde_json::from_str(&content).ok());
+            let size: Option<u64> = fs::metadata(path.with_file_name(format!("{}.json", hash)))
+                .ok()
+                .map(|metadata: fs::Metadata| metadata.len());
+            if let (Some(area), Some(size)) = (area, size) {
+                entries.push((area, size));
+            }
+        }
+    }
+
+    let overlaps = |area: &[f64; 4]| -> bool {
+        area[0] < bbox.2 && area[2] > bbox.0 && area[1] < bbox.3 && area[3] > bbox.1
+    };
+    let cached_areas: Vec<[f64; 4]> = entries
+        .iter()
+        .map(|(area, _)| *area)
+        .filter(|area: &[f64; 4]| overlaps(area))
+        .collect();
+
+    // Sample the bounding box on a grid to estimate the covered share
+    const SAMPLES: u32 = 32;
+    let covered_samples: u32 = (0..SAMPLES * SAMPLES)
+        .filter(|sample: &u32| {
+            let lng: f64 = bbox.0
+                + (bbox.2 - bbox.0) * (f64::from(sample % SAMPLES) + 0.5) / f64::from(SAMPLES);
+            let lat: f64 = bbox.1
+                + (bbox.3 - bbox.1) * (f64::from(sample / SAMPLES) + 0.5) / f64::from(SAMPLES);
+            cached_areas.iter().any(|area: &[f64; 4]| {
+                (area[0]..=area[2]).contains(&lng) && (area[1]..=area[3]).contains(&lat)
+            })
+        })
+        .count() as u32;
+    let covered_fraction: f64 = f64::from(covered_samples) / f64::from(SAMPLES * SAMPLES);
+
+    // Bytes per square degree of all cached data, scaled to the requested area
+    let degrees = |area: &[f64; 4]| -> f64 { (area[2] - area[0]) * (area[3] - area[1]) };
+    let cached_degrees: f64 = entries.iter().map(|(area, _)| degrees(area)).sum();
+    let cached_bytes: u64 = entries.iter().map(|(_, size)| size).sum();
+    let estimated_download_bytes: Option<u64> = if exact {
+        Some(0)
+    } else if cached_degrees > 0.0 {
+        let density: f64 = cached_bytes as f64 / cached_degrees;
+        Some((density * degrees(&[bbox.0, bbox.1, bbox.2, bbox.3])) as u64)
+    } else {
+        None
+    };
+
+    CacheCoverage {
+        exact,
+        covered_fraction,
+        cached_areas,
+        estimated_download_bytes,
+    }
+}
+
 /// Function to download data using reqwest
-fn download_with_reqwest(url: &str, query: &str) -> Result<String, Box<dyn std::error::Error>> {
+fn download_with_reqwest(url: &str, query: &str) -> Result<String, DownloadError> {
     let client: Client = ClientBuilder::new()
         .timeout(Duration::from_secs(1800))
-        .build()?;
+        .build()
+        .map_err(|e: reqwest::Error| DownloadError::Fatal(e.to_string()))?;
 
     let response: Result<reqwest::blocking::Response, reqwest::Error> =
         client.get(url).query(&[("data", query)]).send();
 
     match response {
         Ok(resp) => {
-            if resp.status().is_success() {
-                Ok(resp.text()?)
+            let status: reqwest::StatusCode = resp.status();
+            if status.is_success() {
+                resp.text()
+                    .map_err(|e: reqwest::Error| DownloadError::Retryable(e.to_string()))
+            } else if matches!(status.as_u16(), 429 | 502 | 503 | 504) {
+                // Rate limited or overloaded server, another mirror may answer
+                Err(DownloadError::Retryable(format!(
+                    "收到的响应代码：{}",
+                    status
+                )))
             } else {
-                Err(format!("错误！收到的响应代码：{}", resp.status()).into())
+                Err(DownloadError::Fatal(format!("收到的响应代码：{}", status)))
             }
         }
         Err(e) => {
             if e.is_timeout() {
-                eprintln!(
-                    "{}",
-                    "错误！请求超时。请尝试选择较小的区域。"
-                        .red()
-                        .bold()
-                );
-                emit_gui_error("请求超时。请尝试选择较小的区域。");
-            } else {
-                eprintln!("{}", format!("错误！ {}", e).red().bold());
-                emit_gui_error(&e.to_string());
-            }
-
-            if !is_running_with_gui() {
-                std::process::exit(1);
+                Err(DownloadError::Retryable(
+                    "请求超时。请尝试选择较小的区域。".to_string(),
+                ))
             } else {
-                Ok("".to_string())
+                Err(DownloadError::Retryable(e.to_string()))
             }
         }
     }
 }
 
 /// Function to download data using `curl`
-fn download_with_curl(url: &str, query: &str) -> io::Result<String> {
+fn download_with_curl(url: &str, query: &str) -> Result<String, DownloadError> {
     let output: std::process::Output = Command::new("curl")
         .arg("-s") // Add silent mode to suppress output
         .arg(format!("{}?data={}", url, query))
-        .output()?;
+        .output()
+        .map_err(|e: io::Error| DownloadError::Fatal(e.to_string()))?;
 
     if !output.status.success() {
-        Err(io::Error::new(io::ErrorKind::Other, "Curl 命令失败"))
+        Err(DownloadError::Retryable("Curl 命令失败".to_string()))
     } else {
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
 }
 
 /// Function to download data using `wget`
-fn download_with_wget(url: &str, query: &str) -> io::Result<String> {
+fn download_with_wget(url: &str, query: &str) -> Result<String, DownloadError> {
     let output: std::process::Output = Command::new("wget")
         .arg("-qO-") // Use `-qO-` to output the result directly to stdout
         .arg(format!("{}?data={}", url, query))
-        .output()?;
+        .output()
+        .map_err(|e: io::Error| DownloadError::Fatal(e.to_string()))?;
 
     if !output.status.success() {
-        Err(io::Error::new(io::ErrorKind::Other, "Wget 命令失败"))
+        Err(DownloadError::Retryable("Wget 命令失败".to_string()))
     } else {
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
 }
 
+/// Downloads the query result, rotating through the Overpass servers on failures and
+/// waiting exponentially longer between attempts. Returns the parsed and the raw response.
+fn download_with_retries(
+    api_servers: &[&str],
+    query: &str,
+    download_method: &str,
+    context: &GenerationContext,
+) -> Result<(Value, String), String> {
+    let mut last_error: String = String::new();
+
+    for attempt in 0..MAX_DOWNLOAD_ATTEMPTS {
+        let url: &str = api_servers[attempt as usize % api_servers.len()];
+
+        if attempt > 0 {
+            let delay: Duration = Duration::from_secs((5u64 << (attempt - 1)).min(120));
+            println!(
+                "{}",
+                format!(
+                    "{} 秒后使用 {} 重试（第 {}/{} 次）...",
+                    delay.as_secs(),
+                    url,
+                    attempt,
+                    MAX_DOWNLOAD_ATTEMPTS - 1
+                )
+                .yellow()
+            );
+            context.emit_progress(
+                ProgressEvent::new(Stage::Retrying, 1.0)
+                    .counts(u64::from(attempt), u64::from(MAX_DOWNLOAD_ATTEMPTS - 1)),
+            );
+            context.sleep_unless_cancelled(delay);
+            context.check_cancelled()?;
+        }
+
+        let result: Result<String, DownloadError> = match download_method {
+            "requests" => download_with_reqwest(url, query),
+            "curl" => download_with_curl(url, query),
+            "wget" => download_with_wget(url, query),
+            _ => download_with_reqwest(url, query), // Default to requests
+        };
+
+        match result {
+            Ok(response) => match serde_json::from_str::<Value>(&response) {
+                Ok(data) => return Ok((data, response)),
+                // Overloaded servers may answer with an HTML error page
+                Err(e) => last_error = format!("无效的 API 响应：{}", e),
+            },
+            Err(DownloadError::Retryable(message)) => last_error = message,
+            Err(DownloadError::Fatal(message)) => return Err(message),
+        }
+
+        eprintln!("{}", format!("警告：{}（{}）", last_error, url).yellow());
+    }
+
+    Err(last_error)
+}
+
+/// Builds the Overpass API query for all features within the bounding box, and within the
+/// polygon of the area if one is given
+fn overpass_query(bbox: (f64, f64, f64, f64), polygon: Option<&AreaPolygon>) -> String {
+    let area: String = polygon.map_or(String::new(), AreaPolygon::overpass_filter);
+    format!(
+        r#"[out:json][timeout:1800][bbox:{},{},{},{}];
+    (
+        nwr["building"]{area};
+        nwr["highway"]{area};
+        nwr["landuse"]{area};
+        nwr["natural"]{area};
+        nwr["leisure"]{area};
+        nwr["water"]{area};
+        nwr["waterway"]{area};
+        nwr["amenity"]{area};
+        nwr["tourism"]{area};
+        nwr["bridge"]{area};
+        nwr["railway"]{area};
+        nwr["barrier"]{area};
+        nwr["entrance"]{area};
+        nwr["door"]{area};
+        nwr["aeroway"]{area};
+        nwr["emergency"]{area};
+        nwr["man_made"]{area};
+        nwr["power"]{area};
+        nwr["place"="square"]{area};
+        way{area};
+    )->.relsinbbox;
+    (
+        way(r.relsinbbox);
+    )->.waysinbbox;
+    (
+        node(w.waysinbbox);
+        node(w.relsinbbox);
+    )->.nodesinbbox;
+    .relsinbbox out body;
+    .waysinbbox out body;
+    .nodesinbbox out skel qt;"#,
+        bbox.1, bbox.0, bbox.3, bbox.2
+    )
+}
+
 /// Main function to fetch data
 pub fn fetch_data(
     bbox: (f64, f64, f64, f64),
+    polygon: Option<&AreaPolygon>,
     file: Option<&str>,
     debug: bool,
     download_method: &str,
+    cache_mode: CacheMode,
+    context: &GenerationContext,
 ) -> Result<Value, Box<dyn std::error::Error>> {
     println!("{} 正在获取数据...", "[1/5]".bold());
-    emit_gui_progress_update(1.0, "正在获取数据...");
+    context.emit_progress(ProgressEvent::new(Stage::Fetching, 1.0));
 
     // List of Overpass API servers
     let api_servers: Vec<&str> = vec![
@@ -95,57 +360,47 @@ pub fn fetch_data(
         "https://overpass.kumi.systems/api/interpreter",
         "https://overpass.private.coffee/api/interpreter",
     ];
-    let url: &&str = api_servers.choose(&mut rand::thread_rng()).unwrap();
+    let mut api_servers: Vec<&str> = api_servers;
+    api_servers.shuffle(&mut rand::thread_rng());
 
     // Generate Overpass API query for bounding box
-    let query: String = format!(
-        r#"[out:json][timeout:1800][bbox:{},{},{},{}];
-    (
-        nwr["building"];
-        nwr["highway"];
-        nwr["landuse"];
-        nwr["natural"];
-        nwr["leisure"];
-        nwr["water"];
-        nwr["waterway"];
-        nwr["amenity"];
-        nwr["tourism"];
-        nwr["bridge"];
-        nwr["railway"];
-        nwr["barrier"];
-        nwr["entrance"];
-        nwr["door"];
-        way;
-    )->.relsinbbox;
-    (
-        way(r.relsinbbox);
-    )->.waysinbbox;
-    (
-        node(w.waysinbbox);
-        node(w.relsinbbox);
-    )->.nodesinbbox;
-    .relsinbbox out body;
-    .waysinbbox out body;
-    .nodesinbbox out skel qt;"#,
-        bbox.1, bbox.0, bbox.3, bbox.2
-    );
+    let query: String = overpass_query(bbox, polygon);
 
-    if let Some(file) = file {
+    if let Some(file) = file.filter(|file: &&str| file.ends_with(".pbf")) {
+        // Extract the bounding box from a local PBF file
+        load_pbf_file(file, bbox, context)
+    } else if let Some(file) = file {
         // Load data from file
         let file: File = File::open(file)?;
         let reader: BufReader<File> = BufReader::new(file);
         let data: Value = serde_json::from_reader(reader)?;
         Ok(data)
     } else {
-        // Fetch data from Overpass API
-        let response: String = match download_method {
-            "requests" => download_with_reqwest(url, &query)?,
-            "curl" => download_with_curl(url, &query)?,
-            "wget" => download_with_wget(url, &query)?,
-            _ => download_with_reqwest(url, &query)?, // Default to requests
+        // Use the cached response of a previous run for the same area if available
+        let cached: Option<(Value, String)> = if cache_mode == CacheMode::Enabled {
+            read_cache(&query)
+        } else {
+            None
         };
+        let from_cache: bool = cached.is_some();
+        if from_cache {
+            println!("{}", "使用缓存的数据（使用 --refresh 重新下载）".yellow());
+        }
 
-        let data: Value = serde_json::from_str(&response)?;
+        // Fetch data from Overpass API
+        let (data, response): (Value, String) = match cached {
+            Some(result) => result,
+            None => match download_with_retries(&api_servers, &query, download_method, context) {
+                Ok(result) => result,
+                Err(message) if context.is_cancelled() => return Err(message.into()),
+                Err(message) => {
+                    eprintln!("{}", format!("错误！{}", message).red().bold());
+                    context.emit_error(&message);
+                    // The caller decides how to end, an embedding process keeps running
+                    return Err("数据获取失败".into());
+                }
+            },
+        };
 
         if data["elements"]
             .as_array()
@@ -156,14 +411,14 @@ pub fn fetch_data(
                 // Check if the remark mentions memory or other runtime errors
                 if remark.contains("runtime error") && remark.contains("out of memory") {
                     eprintln!("{}", "错误！查询在 Overpass API 服务器上耗尽了内存。请尝试使用较小的区域。".red().bold());
-                    emit_gui_error("尝试使用较小的区域。");
+                    context.emit_error("尝试使用较小的区域。");
                 } else {
                     // Handle other Overpass API errors if present in the remark field
                     eprintln!(
                         "{}",
                         format!("错误！API 返回：{}", remark).red().bold()
                     );
-                    emit_gui_error(&format!("API 返回：{}", remark));
+                    context.emit_error(&format!("API 返回：{}", remark));
                 }
             } else {
                 // General case for when there are no elements and no specific remark
@@ -171,18 +426,18 @@ pub fn fetch_data(
                     "{}",
                     "错误！此区域无可用数据。".red().bold()
                 );
-                emit_gui_error("此区域无可用数据。");
+                context.emit_error("此区域无可用数据。");
             }
 
             if debug {
                 println!("附加调试信息：{}", data);
             }
 
-            if !is_running_with_gui() {
-                std::process::exit(1);
-            } else {
-                return Err("数据获取失败".into());
-            }
+            return Err("数据获取失败".into());
+        }
+
+        if !from_cache && cache_mode != CacheMode::Disabled {
+            write_cache(&query, &response, bbox);
         }
 
         // If debug is enabled, write data to file
@@ -191,8 +446,126 @@ pub fn fetch_data(
             file.write_all(response.as_bytes())?;
         }
 
-        emit_gui_progress_update(5.0, "");
+        context.emit_progress(ProgressEvent::new(Stage::Fetching, 5.0));
 
         Ok(data)
     }
 }
+
+/// Loads all elements within the bounding box from an .osm.pbf extract and converts them
+/// into the same JSON structure as returned by the Overpass API.
+fn load_pbf_file(
+    path: &str,
+    bbox: (f64, f64, f64, f64),
+    context: &GenerationContext,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    let (min_lon, min_lat, max_lon, max_lat) = bbox;
+    let in_bbox = |lat: f64, lon: f64| -> bool {
+        (min_lat..=max_lat).contains(&lat) && (min_lon..=max_lon).contains(&lon)
+    };
+    let collect_tags = |tags: &mut dyn Iterator<Item = (&str, &str)>| -> Value {
+        Value::Object(
+            tags.map(|(key, value)| (key.to_string(), Value::String(value.to_string())))
+                .collect(),
+        )
+    };
+
+    // First pass: nodes inside the bounding box
+    let mut nodes_in_bbox: HashSet<i64> = HashSet::new();
+    let mut tagged_node_ids: HashSet<i64> = HashSet::new();
+    let mut tagged_nodes: Vec<Value> = vec![];
+    ElementReader::from_path(path)?.for_each(|element: Element| {
+        let (id, lat, lon, tags): (i64, f64, f64, Value) = match element {
+            Element::Node(node) => (
+                node.id(),
+                node.lat(),
+                node.lon(),
+                collect_tags(&mut node.tags()),
+            ),
+            Element::DenseNode(node) => (
+                node.id(),
+                node.lat(),
+                node.lon(),
+                collect_tags(&mut node.tags()),
+            ),
+            _ => return,
+        };
+
+        if in_bbox(lat, lon) {
+            nodes_in_bbox.insert(id);
+            if tags.as_object().is_some_and(|tags| !tags.is_empty()) {
+                tagged_node_ids.insert(id);
+                tagged_nodes.push(json!({
+                    "type": "node", "id": id, "lat": lat, "lon": lon, "tags": tags
+                }));
+            }
+        }
+    })?;
+    context.emit_progress(ProgressEvent::new(Stage::Fetching, 2.0));
+
+    // Second pass: ways touching the bounding box and relations containing them
+    let mut ways: Vec<Value> = vec![];
+    let mut way_ids: HashSet<i64> = HashSet::new();
+    let mut relations: Vec<Value> = vec![];
+    let mut referenced_nodes: HashSet<i64> = HashSet::new();
+    ElementReader::from_path(path)?.for_each(|element: Element| match element {
+        Element::Way(way) => {
+            let refs: Vec<i64> = way.refs().collect();
+            if refs.iter().any(|node_id: &i64| nodes_in_bbox.contains(node_id)) {
+                referenced_nodes.extend(refs.iter().copied());
+                way_ids.insert(way.id());
+                ways.push(json!({
+                    "type": "way", "id": way.id(), "nodes": refs, "tags": collect_tags(&mut way.tags())
+                }));
+            }
+        }
+        Element::Relation(relation) => {
+            let members: Vec<Value> = relation
+                .members()
+                .filter(|member: &RelMember| {
+                    member.member_type == RelMemberType::Way && way_ids.contains(&member.member_id)
+                })
+                .map(|member: RelMember| {
+                    json!({
+                        "type": "way", "ref": member.member_id, "role": member.role().unwrap_or("")
+                    })
+                })
+                .collect();
+            if !members.is_empty() {
+                relations.push(json!({
+                    "type": "relation", "id": relation.id(), "members": members,
+                    "tags": collect_tags(&mut relation.tags())
+                }));
+            }
+        }
+        _ => {}
+    })?;
+    context.emit_progress(ProgressEvent::new(Stage::Fetching, 3.0));
+
+    // Third pass: locations of all nodes referenced by the ways, including those outside the bounding box
+    let mut way_nodes: Vec<Value> = vec![];
+    ElementReader::from_path(path)?.for_each(|element: Element| {
+        let (id, lat, lon): (i64, f64, f64) = match element {
+            Element::Node(node) => (node.id(), node.lat(), node.lon()),
+            Element::DenseNode(node) => (node.id(), node.lat(), node.lon()),
+            _ => return,
+        };
+
+        if referenced_nodes.contains(&id) && !tagged_node_ids.contains(&id) {
+            way_nodes.push(json!({ "type": "node", "id": id, "lat": lat, "lon": lon }));
+        }
+    })?;
+
+    let mut elements: Vec<Value> = relations;
+    elements.extend(ways);
+    elements.extend(tagged_nodes);
+    elements.extend(way_nodes);
+
+    if elements.is_empty() {
+        return Err("PBF 文件中没有边界框内的数据".into());
+    }
+
+    context.emit_progress(ProgressEvent::new(Stage::Fetching, 5.0));
+
+    Ok(json!({ "elements": elements }))
+}
@@ -0,0 +1,52 @@
+/// Rotation of the generated area given with --rotation, turning the map clockwise around
+/// the center of the area, e.g. 90 degrees puts north at the east
+#[derive(Debug, Clone, Copy)]
+pub struct Rotation {
+    sin: f64,
+    cos: f64,
+}
+
+impl Rotation {
+    /// Creates the rotation by the given degrees. Quarter turns are exact, so a rotated area
+    /// keeps its street grid on the block grid.
+    pub fn new(degrees: f64) -> Self {
+        let degrees: f64 = degrees.rem_euclid(360.0);
+        let (sin, cos) = match degrees {
+            0.0 => (0.0, 1.0),
+            90.0 => (1.0, 0.0),
+            180.0 => (0.0, -1.0),
+            270.0 => (-1.0, 0.0),
+            d => d.to_radians().sin_cos(),
+        };
+        Self { sin, cos }
+    }
+
+    pub fn sin(&self) -> f64 {
+        self.sin
+    }
+
+    pub fn cos(&self) -> f64 {
+        self.cos
+    }
+
+    /// Returns the size of the bounding box of an area of the given size once rotated
+    pub fn rotated_size(&self, size_x: f64, size_z: f64) -> (f64, f64) {
+        let (sin, cos) = (self.sin.abs(), self.cos.abs());
+        (size_x * cos + size_z * sin, size_x * sin + size_z * cos)
+    }
+
+    /// Rotates a position within an area of the given size to its position within the
+    /// bounding box of the rotated area
+    pub fn rotate(&self, x: f64, z: f64, size_x: f64, size_z: f64) -> (f64, f64) {
+        // Unrotated positions are kept exactly as they are
+        if self.sin == 0.0 && self.cos == 1.0 {
+            return (x, z);
+        }
+        let (rotated_x, rotated_z) = self.rotated_size(size_x, size_z);
+        let (dx, dz) = (x - size_x / 2.0, z - size_z / 2.0);
+        (
+            rotated_x / 2.0 + dx * self.cos - dz * self.sin,
+            rotated_z / 2.0 + dx * self.sin + dz * self.cos,
+        )
+    }
+}
@@ -0,0 +1,123 @@
+use crate::args::Args;
+use crate::osm_parser::{ProcessedElement, ProcessedMember, ProcessedNode};
+use fnv::FnvHashMap;
+
+/// Distance in blocks around the nodes of an element that its generator may place blocks
+/// in, covering road widths, trees, bridges and building details. Scaled with --scale.
+const FOOTPRINT_MARGIN: f64 = 32.0;
+
+/// Elements processed one after another on one thread. Their blocks lie in the regions of
+/// the group, which no other group of the same stage touches.
+pub struct ElementGroup {
+    /// Region coordinates (chunk coordinates >> 5) the elements may place blocks in
+    pub regions: Vec<(i32, i32)>,
+    /// Indices of the elements, in the order they are processed
+    pub elements: Vec<usize>,
+}
+
+/// Splits the elements into stages of groups that can be processed in parallel. The groups
+/// of a stage touch disjoint regions, and every element is processed after all elements
+/// before it in the list that touch one of its regions, so the world is the same as when
+/// processing the elements one after another.
+///
+/// Elements within one region are collected into a single group, elements spanning several
+/// regions get a group of their own in the next stage those regions are free.
+pub fn schedule_elements(
+    elements: &[ProcessedElement],
+    scale_factor_x: f64,
+    scale_factor_z: f64,
+    args: &Args,
+) -> Vec<Vec<ElementGroup>> {
+    let margin: i32 = (FOOTPRINT_MARGIN * args.scale.max(1.0)).ceil() as i32;
+    let world_size: (i32, i32) = (scale_factor_x as i32, scale_factor_z as i32);
+    let offset: (i32, i32) = (args.x_offset, args.z_offset);
+
+    let mut stages: Vec<Vec<ElementGroup>> = vec![];
+    // Stage each region was last used in, and the group taking further elements of only
+    // this region in that stage
+    let mut region_stages: FnvHashMap<(i32, i32), (usize, Option<usize>)> = FnvHashMap::default();
+    for (index, element) in elements.iter().enumerate() {
+        let regions: Vec<(i32, i32)> = footprint_regions(element, margin, world_size, offset);
+
+        if let [region] = regions[..] {
+            if let Some(&(stage, Some(group))) = region_stages.get(&region) {
+                stages[stage][group].elements.push(index);
+                continue;
+            }
+        }
+
+        let stage: usize = regions
+            .iter()
+            .filter_map(|region: &(i32, i32)| region_stages.get(region))
+            .map(|(stage, _)| stage + 1)
+            .max()
+            .unwrap_or(0);
+        if stage == stages.len() {
+            stages.push(vec![]);
+        }
+        let group: usize = stages[stage].len();
+        let open_group: Option<usize> = (regions.len() == 1).then_some(group);
+        for region in &regions {
+            region_stages.insert(*region, (stage, open_group));
+        }
+        stages[stage].push(ElementGroup {
+            regions,
+            elements: vec![index],
+        });
+    }
+    stages
+}
+
+/// Returns the regions an element may place blocks in: those overlapping the bounding box of
+/// its nodes grown by the margin, limited to the area and shifted by its offset in the world
+fn footprint_regions(
+    element: &ProcessedElement,
+    margin: i32,
+    world_size: (i32, i32),
+    offset: (i32, i32),
+) -> Vec<(i32, i32)> {
+    let nodes: Vec<&ProcessedNode> = match element {
+        ProcessedElement::Relation(relation) => relation
+            .members
+            .iter()
+            .flat_map(|member: &ProcessedMember| member.way.nodes.iter())
+            .collect(),
+        _ => element.nodes().collect(),
+    };
+
+    // Elements without nodes may touch the whole world
+    let (min_x, min_z, max_x, max_z) = if nodes.is_empty() {
+        (0, 0, world_size.0, world_size.1)
+    } else {
+        nodes.iter().fold(
+            (i32::MAX, i32::MAX, i32::MIN, i32::MIN),
+            |(min_x, min_z, max_x, max_z), node: &&ProcessedNode| {
+                (
+                    min_x.min(node.x),
+                    min_z.min(node.z),
+                    max_x.max(node.x),
+                    max_z.max(node.z),
+                )
+            },
+        )
+    };
+
+    let region_of =
+        |block: i32, size: i32, offset: i32| -> i32 { (block.clamp(0, size) + offset) >> 9 };
+    let (min_region_x, max_region_x) = (
+        region_of(min_x.saturating_sub(margin), world_size.0, offset.0),
+        region_of(max_x.saturating_add(margin), world_size.0, offset.0),
+    );
+    let (min_region_z, max_region_z) = (
+        region_of(min_z.saturating_sub(margin), world_size.1, offset.1),
+        region_of(max_z.saturating_add(margin), world_size.1, offset.1),
+    );
+
+    let mut regions: Vec<(i32, i32)> = vec![];
+    for region_x in min_region_x..=max_region_x {
+        for region_z in min_region_z..=max_region_z {
+            regions.push((region_x, region_z));
+        }
+    }
+    regions
+}
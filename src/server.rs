@@ -0,0 +1,465 @@
+use crate::args::{validate_bounding_box, Args, ServeArgs};
+use crate::cancel::CancelToken;
+use crate::context::GenerationContext;
+use crate::output::{create_backend, OutputBackend};
+use crate::presets::{apply_preset, option_arguments};
+use crate::progress::{ProgressEvent, Stage};
+use clap::Parser;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use tiny_http::{Header, Method, Request, Response, ResponseBox, Server};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// File name of the zipped world in the directory of a job
+const WORLD_ZIP_FILE_NAME: &str = "world.zip";
+/// Options a job may set. Options reading or writing files outside of the job, like --file
+/// or --block-config, or changing the output to something other than a world are left out.
+const JOB_OPTIONS: [&str; 35] = [
+    "preset",
+    "downloader",
+    "scale",
+    "ground-level",
+    "x-offset",
+    "z-offset",
+    "rotation",
+    "blocks-per-level",
+    "style",
+    "interior",
+    "villagers",
+    "mobs",
+    "coordinate-hud",
+    "details",
+    "foliage",
+    "night",
+    "heatmap",
+    "palette",
+    "no-cache",
+    "refresh",
+    "focus",
+    "sign-script",
+    "winter",
+    "max-fill-area",
+    "fill-method",
+    "only",
+    "include",
+    "exclude",
+    "world-border",
+    "mc-version",
+    "no-lighting",
+    "seed",
+    "lod-radius",
+    "forest-density",
+    "timeout",
+];
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum JobStatus {
+    Queued,
+    Running,
+    Complete,
+    Failed,
+    Cancelled,
+}
+
+/// A world generation requested through the API
+#[derive(Serialize, Debug, Clone)]
+struct Job {
+    id: u64,
+    status: JobStatus,
+    bbox: String,
+    /// Stage of the running generation
+    stage: Option<Stage>,
+    /// Overall progress in percent
+    progress: f64,
+    /// Estimated seconds until the current stage is done
+    remaining: Option<f64>,
+    error: Option<String>,
+}
+
+/// Body of a POST /jobs request. The options are the command line arguments without the
+/// leading dashes, e.g. {"scale": 2.0, "winter": true}.
+#[derive(Deserialize, Debug)]
+struct JobRequest {
+    bbox: String,
+    #[serde(default)]
+    options: Map<String, Value>,
+}
+
+#[derive(Default)]
+struct ServerState {
+    jobs: BTreeMap<u64, Job>,
+    next_id: u64,
+    /// Job currently generated by the worker, with the token cancelling its generation
+    running: Option<(u64, CancelToken)>,
+}
+
+type SharedState = Arc<Mutex<ServerState>>;
+/// Job ID with the arguments of its generation, sent to the worker
+type QueuedJob = (u64, Args);
+
+/// Runs `arnis --serve`, an HTTP server generating worlds on request:
+///
+/// POST /jobs submits a job with a bbox and options, GET /jobs lists all jobs,
+/// GET /jobs/{id} returns the status of a job, GET /jobs/{id}/world downloads the
+/// finished world as a zip file and DELETE /jobs/{id} cancels a job.
+///
+/// Jobs are generated one after another by a single worker thread, each with its own
+/// context receiving its progress and cancelling it, so a server does not run out of memory
+/// with several large areas at once.
+pub fn run_server(args: &ServeArgs) {
+    let jobs_dir: PathBuf = PathBuf::from(&args.jobs_dir);
+    if let Err(e) = fs::create_dir_all(&jobs_dir) {
+        eprintln!("{}", format!("错误！无法创建任务目录：{}", e).red().bold());
+        exit(1);
+    }
+    let server: Server = match Server::http(&args.listen) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!(
+                "{}",
+                format!("错误！无法监听 {}：{}", args.listen, e)
+                    .red()
+                    .bold()
+            );
+            exit(1);
+        }
+    };
+
+    let state: SharedState = Arc::new(Mutex::new(ServerState {
+        next_id: first_free_id(&jobs_dir),
+        ..Default::default()
+    }));
+    let (queue, jobs): (Sender<QueuedJob>, Receiver<QueuedJob>) = mpsc::channel();
+    let worker_state: SharedState = Arc::clone(&state);
+    let worker_jobs_dir: PathBuf = jobs_dir.clone();
+    thread::spawn(move || run_worker(&worker_state, jobs, &worker_jobs_dir));
+
+    println!(
+        "{}",
+        format!("HTTP 服务器正在监听 http://{}", args.listen)
+            .green()
+            .bold()
+    );
+    for request in server.incoming_requests() {
+        let state: SharedState = Arc::clone(&state);
+        let queue: Sender<QueuedJob> = queue.clone();
+        let jobs_dir: PathBuf = jobs_dir.clone();
+        thread::spawn(move || handle_request(request, &state, &queue, &jobs_dir));
+    }
+}
+
+fn lock(state: &SharedState) -> MutexGuard<'_, ServerState> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Returns the first job ID not used by a job directory of a previous server run
+fn first_free_id(jobs_dir: &Path) -> u64 {
+    fs::read_dir(jobs_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.file_name().to_string_lossy().parse::<u64>().ok())
+                .max()
+                .map_or(1, |id: u64| id + 1)
+        })
+        .unwrap_or(1)
+}
+
+/// Generates the queued jobs one at a time
+fn run_worker(state: &SharedState, jobs: Receiver<QueuedJob>, jobs_dir: &Path) {
+    for (id, args) in jobs {
+        let cancel: CancelToken = CancelToken::new();
+        {
+            let mut state: MutexGuard<ServerState> = lock(state);
+            match state.jobs.get_mut(&id) {
+                // Jobs cancelled while queued are skipped
+                Some(job) if job.status == JobStatus::Queued => job.status = JobStatus::Running,
+                _ => continue,
+            }
+            state.running = Some((id, cancel.clone()));
+        }
+
+        let listener_state: SharedState = Arc::clone(state);
+        let context: GenerationContext = GenerationContext::new()
+            .with_cancel_token(cancel.clone())
+            .with_progress_listener(move |event: &ProgressEvent| {
+                let mut state: MutexGuard<ServerState> = lock(&listener_state);
+                if let Some(job) = state.jobs.get_mut(&id) {
+                    job.stage = Some(event.stage);
+                    if event.progress >= 0.0 {
+                        job.progress = event.progress;
+                    }
+                    job.remaining = event.remaining;
+                }
+            });
+        // A panic fails the job instead of stopping the worker, which would leave the job
+        // running and the queue closed
+        let result: Result<(), String> = panic::catch_unwind(AssertUnwindSafe(|| {
+            run_job(&jobs_dir.join(id.to_string()), &args, id, &context)
+        }))
+        .unwrap_or_else(|payload: Box<dyn Any + Send>| Err(panic_message(payload.as_ref())));
+
+        let mut state: MutexGuard<ServerState> = lock(state);
+        state.running = None;
+        if let Some(job) = state.jobs.get_mut(&id) {
+            job.remaining = None;
+            match result {
+                Ok(()) => {
+                    job.status = JobStatus::Complete;
+                    job.progress = 100.0;
+                }
+                Err(_) if cancel.is_cancelled() => job.status = JobStatus::Cancelled,
+                Err(e) => {
+                    eprintln!("{}", format!("任务 {} 失败：{}", id, e).red());
+                    job.status = JobStatus::Failed;
+                    job.error = Some(e);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the message of a panic caught while generating a job
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    let message: &str = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("未知错误");
+    format!("生成时出现内部错误：{}", message)
+}
+
+/// Generates the world of a job into a new world in its directory and zips it for download
+fn run_job(
+    job_dir: &Path,
+    args: &Args,
+    id: u64,
+    context: &GenerationContext,
+) -> Result<(), String> {
+    let world_dir: PathBuf = job_dir.join("world");
+    crate::create_new_world(&world_dir, &format!("Arnis 任务 {}", id))?;
+
+    let bbox_tuple: (f64, f64, f64, f64) = args.bboxes()?[0];
+
+    let mut output: Box<dyn OutputBackend> = create_backend(args)?;
+    crate::generate_world(bbox_tuple, args, output.as_mut(), context)?;
+
+    zip_world(&world_dir, &job_dir.join(WORLD_ZIP_FILE_NAME))?;
+    // The zip holds everything needed for the download
+    let _ = fs::remove_dir_all(&world_dir);
+    Ok(())
+}
+
+/// Builds the arguments of a job the same way as on the command line, so the options are
+/// validated and defaulted exactly like command line arguments
+fn job_args(request: &JobRequest, world_dir: &Path) -> Result<Args, String> {
+    if !validate_bounding_box(&request.bbox) {
+        return Err("边界框无效".to_string());
+    }
+
+    let mut command_line: Vec<String> = vec![
+        "arnis".to_string(),
+        "--bbox".to_string(),
+        request.bbox.clone(),
+        "--path".to_string(),
+        world_dir.display().to_string(),
+    ];
+    for name in request.options.keys() {
+        let name: String = name.replace('_', "-");
+        if !JOB_OPTIONS.contains(&name.as_str()) {
+            return Err(format!("不允许设置选项：{}", name));
+        }
+    }
+    command_line.extend(option_arguments(&request.options)?);
+
+    Args::try_parse_from(apply_preset(&command_line)?).map_err(|e: clap::Error| e.to_string())
+}
+
+/// Writes the world directory into a zip file, with the world in a "world" folder
+fn zip_world(world_dir: &Path, zip_path: &Path) -> Result<(), String> {
+    let file: File =
+        File::create(zip_path).map_err(|e: io::Error| format!("无法创建压缩文件：{}", e))?;
+    let mut zip: ZipWriter<BufWriter<File>> = ZipWriter::new(BufWriter::new(file));
+    let options: SimpleFileOptions =
+        SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    add_to_zip(&mut zip, world_dir, "world", options)?;
+    zip.finish()
+        .map_err(|e: zip::result::ZipError| format!("无法写入压缩文件：{}", e))?;
+    Ok(())
+}
+
+fn add_to_zip(
+    zip: &mut ZipWriter<BufWriter<File>>,
+    dir: &Path,
+    prefix: &str,
+    options: SimpleFileOptions,
+) -> Result<(), String> {
+    let entries: fs::ReadDir =
+        fs::read_dir(dir).map_err(|e: io::Error| format!("无法读取世界目录：{}", e))?;
+    for entry in entries.flatten() {
+        let name: String = format!("{}/{}", prefix, entry.file_name().to_string_lossy());
+        let path: PathBuf = entry.path();
+        if path.is_dir() {
+            zip.add_directory(name.as_str(), options)
+                .map_err(|e: zip::result::ZipError| format!("无法写入压缩文件：{}", e))?;
+            add_to_zip(zip, &path, &name, options)?;
+        } else {
+            zip.start_file(name.as_str(), options)
+                .map_err(|e: zip::result::ZipError| format!("无法写入压缩文件：{}", e))?;
+            File::open(&path)
+                .and_then(|mut file: File| io::copy(&mut file, zip))
+                .map_err(|e: io::Error| format!("无法写入压缩文件：{}", e))?;
+        }
+    }
+    Ok(())
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("无效的响应头")
+}
+
+fn json_response(status: u16, body: &impl Serialize) -> ResponseBox {
+    Response::from_string(serde_json::to_string(body).unwrap_or_default())
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json; charset=utf-8"))
+        .boxed()
+}
+
+fn error_response(status: u16, message: &str) -> ResponseBox {
+    json_response(status, &json!({ "error": message }))
+}
+
+fn handle_request(
+    mut request: Request,
+    state: &SharedState,
+    queue: &Sender<QueuedJob>,
+    jobs_dir: &Path,
+) {
+    let method: Method = request.method().clone();
+    let url: String = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let segments: Vec<&str> = url.trim_matches('/').split('/').collect();
+    let job_id: Option<u64> = segments.get(1).and_then(|id: &&str| id.parse::<u64>().ok());
+
+    let response: ResponseBox = match (&method, segments.as_slice(), job_id) {
+        // CORS preflight of web frontends on other origins
+        (Method::Options, _, _) => Response::empty(204)
+            .with_header(header("Access-Control-Allow-Methods", "GET, POST, DELETE"))
+            .with_header(header("Access-Control-Allow-Headers", "Content-Type"))
+            .boxed(),
+        (Method::Post, ["jobs"], _) => submit_job(&mut request, state, queue, jobs_dir),
+        (Method::Get, ["jobs"], _) => {
+            let jobs: Vec<Job> = lock(state).jobs.values().cloned().collect();
+            json_response(200, &jobs)
+        }
+        (Method::Get, ["jobs", _], Some(id)) => match lock(state).jobs.get(&id) {
+            Some(job) => json_response(200, job),
+            None => error_response(404, "任务不存在"),
+        },
+        (Method::Get, ["jobs", _, "world"], Some(id)) => download_world(state, jobs_dir, id),
+        (Method::Delete, ["jobs", _], Some(id)) => cancel_job(state, id),
+        _ => error_response(404, "未找到"),
+    };
+
+    let response: ResponseBox = response.with_header(header("Access-Control-Allow-Origin", "*"));
+    if let Err(e) = request.respond(response) {
+        eprintln!("无法发送响应：{}", e);
+    }
+}
+
+fn submit_job(
+    request: &mut Request,
+    state: &SharedState,
+    queue: &Sender<QueuedJob>,
+    jobs_dir: &Path,
+) -> ResponseBox {
+    let mut body: String = String::new();
+    if let Err(e) = request.as_reader().read_to_string(&mut body) {
+        return error_response(400, &format!("无法读取请求：{}", e));
+    }
+    let job_request: JobRequest = match serde_json::from_str(&body) {
+        Ok(job_request) => job_request,
+        Err(e) => return error_response(400, &format!("请求无效：{}", e)),
+    };
+
+    let mut state: MutexGuard<ServerState> = lock(state);
+    let id: u64 = state.next_id;
+    let world_dir: PathBuf = jobs_dir.join(id.to_string()).join("world");
+    let args: Args = match job_args(&job_request, &world_dir) {
+        Ok(args) => args,
+        Err(e) => return error_response(400, &e),
+    };
+
+    let job: Job = Job {
+        id,
+        status: JobStatus::Queued,
+        bbox: job_request.bbox,
+        stage: None,
+        progress: 0.0,
+        remaining: None,
+        error: None,
+    };
+    if queue.send((id, args)).is_err() {
+        return error_response(500, "任务队列已关闭");
+    }
+    state.next_id += 1;
+    state.jobs.insert(id, job.clone());
+    json_response(201, &job)
+}
+
+fn download_world(state: &SharedState, jobs_dir: &Path, id: u64) -> ResponseBox {
+    match lock(state).jobs.get(&id).map(|job: &Job| job.status) {
+        Some(JobStatus::Complete) => {}
+        Some(_) => return error_response(409, "世界尚未生成完成"),
+        None => return error_response(404, "任务不存在"),
+    }
+
+    match File::open(jobs_dir.join(id.to_string()).join(WORLD_ZIP_FILE_NAME)) {
+        Ok(file) => Response::from_file(file)
+            .with_header(header("Content-Type", "application/zip"))
+            .with_header(header(
+                "Content-Disposition",
+                &format!("attachment; filename=\"arnis-{}.zip\"", id),
+            ))
+            .boxed(),
+        Err(e) => error_response(500, &format!("无法读取世界文件：{}", e)),
+    }
+}
+
+/// Cancels a queued job right away and a running job at the next check of the generation
+fn cancel_job(state: &SharedState, id: u64) -> ResponseBox {
+    let mut state: MutexGuard<ServerState> = lock(state);
+    let running: Option<CancelToken> = state
+        .running
+        .as_ref()
+        .filter(|(running_id, _)| *running_id == id)
+        .map(|(_, cancel)| cancel.clone());
+    let Some(job) = state.jobs.get_mut(&id) else {
+        return error_response(404, "任务不存在");
+    };
+
+    match (job.status, running) {
+        (JobStatus::Queued, _) => job.status = JobStatus::Cancelled,
+        (JobStatus::Running, Some(cancel)) => cancel.cancel(),
+        _ => return error_response(409, "任务已结束"),
+    }
+    json_response(200, job)
+}
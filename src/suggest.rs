@@ -0,0 +1,141 @@
+use crate::args::SuggestArgs;
+use crate::osm_parser::geo_distance;
+use crate::world_editor::MAX_Y;
+use colored::Colorize;
+use serde::Serialize;
+use std::process::exit;
+
+/// Longest side in blocks that is generated in reasonable time and memory
+const MAX_SIDE_BLOCKS: f64 = 12_000.0;
+/// Number of region files above which generation takes very long
+const LARGE_REGION_COUNT: u64 = 100;
+/// Scales are suggested in the steps of the GUI slider
+const SCALE_STEP: f64 = 0.25;
+/// Smallest scale that is suggested, below which streets become too thin to be useful
+const MIN_SCALE: f64 = 0.25;
+/// Ground level leaving room for two layers of tunnels above the bedrock
+const SUGGESTED_GROUND_LEVEL: i32 = -52;
+/// Lowest ground level at which tunnels directly below the surface still fit
+const MIN_TUNNEL_GROUND_LEVEL: i32 = -56;
+/// Highest block of the world
+const MAX_BUILD_HEIGHT: i32 = MAX_Y;
+/// Height of the tallest buildings found in most cities, in meters
+const TALL_BUILDING_HEIGHT: i32 = 300;
+
+/// Recommended settings for a bounding box
+#[derive(Serialize)]
+pub struct ScaleSuggestion {
+    /// Size of the area from west to east, in meters
+    pub width_meters: f64,
+    /// Size of the area from south to north, in meters
+    pub length_meters: f64,
+    pub scale: f64,
+    pub ground_level: i32,
+    pub width_blocks: i32,
+    pub length_blocks: i32,
+    pub regions: u64,
+    /// Tallest building that fits above the ground level, in blocks
+    pub max_building_height: i32,
+    pub warnings: Vec<String>,
+}
+
+/// Suggests a scale and ground level for a bounding box given as (min_lng, min_lat, max_lng,
+/// max_lat). Areas are generated at one block per meter unless they would get too large.
+/// A given scale or ground level is checked against the limits instead.
+pub fn suggest_scale(
+    bbox: (f64, f64, f64, f64),
+    scale: Option<f64>,
+    ground_level: Option<i32>,
+) -> ScaleSuggestion {
+    let (length_meters, width_meters) = geo_distance(bbox.1, bbox.3, bbox.0, bbox.2);
+    let longest: f64 = width_meters.max(length_meters).max(1.0);
+
+    let scale: f64 = scale.unwrap_or_else(|| {
+        let fitting: f64 = (MAX_SIDE_BLOCKS / longest).min(1.0);
+        ((fitting / SCALE_STEP).floor() * SCALE_STEP).max(MIN_SCALE)
+    });
+    let ground_level: i32 = ground_level.unwrap_or(SUGGESTED_GROUND_LEVEL);
+
+    let width_blocks: i32 = (width_meters.floor() * scale) as i32 + 1;
+    let length_blocks: i32 = (length_meters.floor() * scale) as i32 + 1;
+    let regions: u64 = u64::from((width_blocks as u32).div_ceil(512))
+        * u64::from((length_blocks as u32).div_ceil(512));
+    // The roof of the tallest building sits two blocks above its height
+    let max_building_height: i32 = MAX_BUILD_HEIGHT - ground_level - 2;
+
+    let mut warnings: Vec<String> = vec![];
+    if f64::from(width_blocks.max(length_blocks)) > MAX_SIDE_BLOCKS {
+        warnings.push(format!(
+            "区域边长 {} 方块，超过建议的 {} 方块，生成需要大量时间和内存",
+            width_blocks.max(length_blocks),
+            MAX_SIDE_BLOCKS
+        ));
+    }
+    if regions > LARGE_REGION_COUNT {
+        warnings.push(format!(
+            "将写入 {} 个区域文件，生成可能需要很长时间",
+            regions
+        ));
+    }
+    if max_building_height < TALL_BUILDING_HEIGHT {
+        warnings.push(format!(
+            "地面高度 {} 只留出 {} 格高度，更高的建筑将被截断",
+            ground_level, max_building_height
+        ));
+    }
+    if ground_level < MIN_TUNNEL_GROUND_LEVEL {
+        warnings.push(format!(
+            "地面高度低于 {}，隧道和地铁将因空间不足而被跳过",
+            MIN_TUNNEL_GROUND_LEVEL
+        ));
+    }
+
+    ScaleSuggestion {
+        width_meters,
+        length_meters,
+        scale,
+        ground_level,
+        width_blocks,
+        length_blocks,
+        regions,
+        max_building_height,
+        warnings,
+    }
+}
+
+/// Runs the `arnis suggest` command, printing the recommended settings for a bounding box
+pub fn run_suggest(args: &SuggestArgs) {
+    let bbox: Vec<f64> = args
+        .bbox
+        .split(',')
+        .filter_map(|s: &str| s.trim().parse::<f64>().ok())
+        .collect();
+    let [min_lng, min_lat, max_lng, max_lat] = bbox[..] else {
+        eprintln!("{}", "错误！输入的边界框无效".red().bold());
+        exit(1);
+    };
+
+    let suggestion: ScaleSuggestion = suggest_scale(
+        (min_lng, min_lat, max_lng, max_lat),
+        args.scale,
+        args.ground_level,
+    );
+    println!(
+        "区域大小：{:.0} × {:.0} 米",
+        suggestion.width_meters, suggestion.length_meters
+    );
+    println!(
+        "建议设置：--scale {} --ground-level {}",
+        suggestion.scale, suggestion.ground_level
+    );
+    println!(
+        "世界大小：{} × {} 方块，{} 个区域文件，建筑最高 {} 格",
+        suggestion.width_blocks,
+        suggestion.length_blocks,
+        suggestion.regions,
+        suggestion.max_building_height
+    );
+    for warning in &suggestion.warnings {
+        println!("{}", format!("警告：{}", warning).yellow());
+    }
+}
@@ -0,0 +1,39 @@
+use clap::ValueEnum;
+use deunicode::deunicode;
+
+/// Script of the street and shop names on signs and name tags
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignScript {
+    /// Names as they are mapped
+    Original,
+    /// Names transliterated into Latin letters
+    Latin,
+    /// Mapped names with their transliteration below
+    Both,
+}
+
+/// Transliterates a name into Latin letters, e.g. "Москва" into "Moskva"
+pub fn transliterate(name: &str) -> String {
+    deunicode(name)
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Returns the text of a name in the chosen script, and its transliteration as a second line
+/// when both scripts are shown. Names that are already written in Latin letters keep a
+/// single line.
+pub fn name_lines(name: &str, script: SignScript) -> (String, Option<String>) {
+    match script {
+        SignScript::Original => (name.to_string(), None),
+        SignScript::Latin => (transliterate(name), None),
+        SignScript::Both => {
+            let latin: String = transliterate(name);
+            if latin == name.trim() {
+                (name.to_string(), None)
+            } else {
+                (name.to_string(), Some(latin))
+            }
+        }
+    }
+}
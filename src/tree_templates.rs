@@ -0,0 +1,161 @@
+use crate::block_definitions::{register_custom_block, Block};
+use crate::world_editor::WorldEditor;
+use colored::Colorize;
+use flate2::read::GzDecoder;
+use once_cell::sync::OnceCell;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// Tree structures provided by the user, keyed by lowercase species, genus or tree type
+static TREE_TEMPLATES: OnceCell<HashMap<String, Vec<TreeTemplate>>> = OnceCell::new();
+
+/// Blocks of a tree structure, relative to the bottom center of the structure
+#[derive(Debug)]
+pub struct TreeTemplate {
+    blocks: Vec<(i32, i32, i32, Block)>,
+}
+
+/// Structure file as saved by structure blocks
+#[derive(Deserialize)]
+struct StructureFile {
+    size: Vec<i32>,
+    palette: Vec<PaletteEntry>,
+    blocks: Vec<StructureBlock>,
+}
+
+#[derive(Deserialize)]
+struct PaletteEntry {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Properties")]
+    properties: Option<HashMap<String, String>>,
+}
+
+#[derive(Deserialize)]
+struct StructureBlock {
+    pos: Vec<i32>,
+    state: usize,
+}
+
+/// Loads all tree structures from a folder with one subfolder per species, genus or tree
+/// type (oak, spruce, birch, jungle), each containing structure NBT files.
+/// Returns the number of loaded templates.
+pub fn load_tree_templates(path: &str) -> Result<usize, String> {
+    let entries: fs::ReadDir =
+        fs::read_dir(path).map_err(|e: std::io::Error| format!("无法读取树木模板目录：{}", e))?;
+
+    let mut templates: HashMap<String, Vec<TreeTemplate>> = HashMap::new();
+    let mut count: usize = 0;
+    for key_dir in entries.flatten().map(|entry: fs::DirEntry| entry.path()) {
+        let Some(key) = key_dir.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let Ok(files) = fs::read_dir(&key_dir) else {
+            continue;
+        };
+        let key: String = key.to_lowercase();
+
+        for file in files.flatten().map(|entry: fs::DirEntry| entry.path()) {
+            if file.extension().is_none_or(|extension| extension != "nbt") {
+                continue;
+            }
+            match read_template(&file) {
+                Ok(template) => {
+                    templates.entry(key.clone()).or_default().push(template);
+                    count += 1;
+                }
+                Err(e) => eprintln!(
+                    "{}",
+                    format!("警告：跳过树木模板 {}：{}", file.display(), e).yellow()
+                ),
+            }
+        }
+    }
+
+    TREE_TEMPLATES
+        .set(templates)
+        .map_err(|_| "树木模板已加载".to_string())?;
+    Ok(count)
+}
+
+/// Reads a gzip-compressed structure file and registers its blocks
+fn read_template(path: &Path) -> Result<TreeTemplate, String> {
+    let mut data: Vec<u8> = vec![];
+    GzDecoder::new(fs::File::open(path).map_err(|e: std::io::Error| e.to_string())?)
+        .read_to_end(&mut data)
+        .map_err(|e: std::io::Error| e.to_string())?;
+    let structure: StructureFile =
+        fastnbt::from_bytes(&data).map_err(|e: fastnbt::error::Error| e.to_string())?;
+
+    // Air and structure voids are left out, so templates do not carve into their surroundings
+    let palette: Vec<Option<Block>> = structure
+        .palette
+        .iter()
+        .map(|entry: &PaletteEntry| {
+            let name: &str = entry.name.trim_start_matches("minecraft:");
+            if matches!(name, "air" | "cave_air" | "structure_void") {
+                return Ok(None);
+            }
+            register_custom_block(&block_id(name, entry.properties.as_ref())).map(Some)
+        })
+        .collect::<Result<Vec<Option<Block>>, String>>()?;
+
+    let [size_x, _, size_z] = structure.size[..] else {
+        return Err("结构尺寸无效".to_string());
+    };
+    let (center_x, center_z) = (size_x / 2, size_z / 2);
+    let blocks: Vec<(i32, i32, i32, Block)> = structure
+        .blocks
+        .iter()
+        .filter_map(|block: &StructureBlock| {
+            let placed: Block = (*palette.get(block.state)?)?;
+            let [x, y, z] = block.pos[..] else {
+                return None;
+            };
+            Some((x - center_x, y, z - center_z, placed))
+        })
+        .collect();
+
+    Ok(TreeTemplate { blocks })
+}
+
+/// Builds a block ID with block states. Leaves keep only their persistence, so they do not
+/// decay and their many distance states do not use up the custom block slots.
+fn block_id(name: &str, properties: Option<&HashMap<String, String>>) -> String {
+    if name.ends_with("_leaves") {
+        return format!("{}[persistent=true]", name);
+    }
+
+    let mut states: Vec<String> = properties
+        .into_iter()
+        .flatten()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect();
+    if states.is_empty() {
+        return name.to_string();
+    }
+    states.sort();
+    format!("{}[{}]", name, states.join(","))
+}
+
+/// Picks a random template for the first key with templates, e.g. species, genus, tree type
+pub fn tree_template(keys: &[String], rng: &mut impl Rng) -> Option<&'static TreeTemplate> {
+    let templates: &HashMap<String, Vec<TreeTemplate>> = TREE_TEMPLATES.get()?;
+    keys.iter()
+        .find_map(|key: &String| templates.get(key))
+        .and_then(|variants: &Vec<TreeTemplate>| variants.choose(rng))
+}
+
+impl TreeTemplate {
+    /// Places the structure with its bottom center at the given position
+    pub fn place(&self, editor: &mut WorldEditor, x: i32, y: i32, z: i32) {
+        for &(dx, dy, dz, block) in &self.blocks {
+            editor.set_block(block, x + dx, y + dy, z + dz, None, None);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use crate::block_definitions::*;
+use crate::block_palette::{palette_block, BlockCategory};
+use crate::world_editor::WorldEditor;
+use std::collections::HashSet;
+
+/// Upper limit of passes, as patching one block can expose water next to it
+const MAX_PASSES: usize = 4;
+
+/// Number of water blocks patched by the validation pass
+#[derive(Debug, Default)]
+pub struct WaterFixes {
+    /// Blocks placed below water that would otherwise fall down
+    pub supported: usize,
+    /// Blocks placed next to water that would otherwise flow out
+    pub contained: usize,
+    /// Single water columns standing above the ground that were removed
+    pub removed: usize,
+}
+
+impl WaterFixes {
+    pub fn total(&self) -> usize {
+        self.supported + self.contained + self.removed
+    }
+}
+
+/// Finds water that would start flowing once the world is loaded and patches it:
+/// single-block water columns above the ground are removed, and air below or beside
+/// the remaining water is filled, so water stays where it was placed.
+/// Only positions within the generated area (0..=max_x, 0..=max_z) are patched.
+pub fn fix_water(
+    editor: &mut WorldEditor,
+    ground_level: i32,
+    max_x: i32,
+    max_z: i32,
+) -> WaterFixes {
+    let mut fixes: WaterFixes = WaterFixes::default();
+    let water_blocks: Vec<Block> = vec![WATER, palette_block(BlockCategory::Water, WATER)];
+
+    for _ in 0..MAX_PASSES {
+        let water: HashSet<(i32, i32, i32)> = water_blocks
+            .iter()
+            .collect::<HashSet<&Block>>()
+            .into_iter()
+            .flat_map(|block: &Block| editor.find_blocks(*block))
+            .filter(|&(x, _, z)| (0..=max_x).contains(&x) && (0..=max_z).contains(&z))
+            .collect();
+        let fixes_before: usize = fixes.total();
+
+        let is_open = |editor: &WorldEditor, x: i32, y: i32, z: i32| -> bool {
+            if !(0..=max_x).contains(&x) || !(0..=max_z).contains(&z) {
+                return false;
+            }
+            // Modified chunks only contain the placed blocks, so unset blocks are air
+            editor
+                .get_block(x, y, z)
+                .is_none_or(|block: Block| block == CAVE_AIR)
+        };
+
+        for &(x, y, z) in &water {
+            let neighbors: [(i32, i32); 4] = [(x + 1, z), (x - 1, z), (x, z + 1), (x, z - 1)];
+
+            // Single-block water towers above the ground
+            if y > ground_level
+                && neighbors
+                    .iter()
+                    .all(|&(nx, nz)| !water.contains(&(nx, y, nz)))
+            {
+                editor.set_block(AIR, x, y, z, Some(&water_blocks), None);
+                fixes.removed += 1;
+                continue;
+            }
+
+            // Blocks above the ground keep the look of a built basin, below it the soil
+            let patch_block: Block = if y > ground_level {
+                STONE_BRICKS
+            } else {
+                STONE
+            };
+
+            if is_open(editor, x, y - 1, z) {
+                editor.set_block(patch_block, x, y - 1, z, Some(&[CAVE_AIR]), None);
+                fixes.supported += 1;
+            }
+
+            for (nx, nz) in neighbors {
+                if !water.contains(&(nx, y, nz)) && is_open(editor, nx, y, nz) {
+                    editor.set_block(patch_block, nx, y, nz, Some(&[CAVE_AIR]), None);
+                    fixes.contained += 1;
+                }
+            }
+        }
+
+        if fixes.total() == fixes_before {
+            break;
+        }
+    }
+
+    fixes
+}
@@ -1,30 +1,223 @@
+use crate::area_polygon::AreaMask;
 use crate::args::Args;
+use crate::biome_definitions::Biome;
 use crate::block_definitions::*;
-use crate::progress::emit_gui_progress_update;
+use crate::context::GenerationContext;
+use crate::heightmaps::flat_heightmaps;
+use crate::lighting::ChunkLight;
+use crate::output::OutputBackend;
+use crate::progress::{ProgressEvent, Stage};
+use crate::random::seeded_rng;
 use colored::Colorize;
-use fastanvil::Region;
-use fastnbt::{LongArray, Value};
+use fastnbt::{ByteArray, IntArray, LongArray, Value};
 use fnv::FnvHashMap;
-use indicatif::{ProgressBar, ProgressStyle};
+use once_cell::sync::Lazy;
+use rand::rngs::StdRng;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::Write;
+use std::sync::Arc;
+
+/// Data version of the generated chunks (Minecraft 1.21.4), matching the level.dat template
+pub const DATA_VERSION: i32 = 4189;
+/// Lowest section of the world, covering the blocks from y = -64 to -49
+pub const MIN_SECTION_Y: i8 = -4;
+/// Number of sections from the bottom to the top of the world
+pub const SECTION_COUNT: usize = 24;
+/// Lowest y at which blocks can be placed
+pub const MIN_Y: i32 = MIN_SECTION_Y as i32 * 16;
+/// Highest y at which blocks can be placed
+pub const MAX_Y: i32 = MIN_Y + SECTION_COUNT as i32 * 16 - 1;
+/// Scoreboard tag of the entities placed by Arnis
+pub const ENTITY_TAG: &str = "arnis";
+
+/// Palettes of the most common sections, whose palette items are built only once instead
+/// of for every section
+static COMMON_PALETTES: Lazy<Vec<(Vec<Block>, Vec<PaletteItem>)>> = Lazy::new(|| {
+    [
+        vec![AIR],
+        vec![STONE],
+        vec![WATER],
+        vec![AIR, COBBLESTONE],
+        vec![AIR, WATER],
+        vec![AIR, DIRT, GRASS_BLOCK],
+        vec![AIR, DIRT, SNOW_BLOCK],
+        vec![DIRT, GRASS_BLOCK, STONE],
+    ]
+    .into_iter()
+    .map(|mut blocks: Vec<Block>| {
+        blocks.sort();
+        let items: Vec<PaletteItem> = blocks.iter().map(PaletteItem::from_block).collect();
+        (blocks, items)
+    })
+    .collect()
+});
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct Chunk {
-    sections: Vec<Section>,
+pub struct Chunk {
+    pub sections: Vec<Section>,
     x_pos: i32,
     z_pos: i32,
     #[serde(default)]
     is_light_on: u8,
     #[serde(flatten)]
-    other: FnvHashMap<String, Value>,
+    pub other: FnvHashMap<String, Value>,
+}
+
+impl Chunk {
+    /// Creates a fully generated chunk without blocks other than a cobblestone floor at the
+    /// bottom of the world, which chunks without modifications keep.
+    pub fn empty(x_pos: i32, z_pos: i32, default_biome: Biome) -> Self {
+        let mut floor: SectionToModify = SectionToModify::default();
+        for x in 0..16 {
+            for z in 0..16 {
+                floor.set_block(x, 0, z, COBBLESTONE);
+            }
+        }
+
+        let mut structures: HashMap<String, Value> = HashMap::new();
+        structures.insert("References".to_string(), Value::Compound(HashMap::new()));
+        structures.insert("starts".to_string(), Value::Compound(HashMap::new()));
+
+        let mut other: FnvHashMap<String, Value> = FnvHashMap::default();
+        other.insert("DataVersion".to_string(), Value::Int(DATA_VERSION));
+        other.insert(
+            "Status".to_string(),
+            Value::String("minecraft:full".to_string()),
+        );
+        other.insert("yPos".to_string(), Value::Int(i32::from(MIN_SECTION_Y)));
+        other.insert("LastUpdate".to_string(), Value::Long(0));
+        other.insert("InhabitedTime".to_string(), Value::Long(0));
+        other.insert("structures".to_string(), Value::Compound(structures));
+        other.insert(
+            "PostProcessing".to_string(),
+            Value::List(vec![Value::List(vec![]); SECTION_COUNT]),
+        );
+        // Only the floor at the bottom of the world counts
+        other.insert("Heightmaps".to_string(), flat_heightmaps(1));
+        for key in ["block_entities", "block_ticks", "fluid_ticks"] {
+            other.insert(key.to_string(), Value::List(vec![]));
+        }
+
+        Self {
+            sections: vec![floor.to_section(MIN_SECTION_Y, &[default_biome; 16])],
+            x_pos,
+            z_pos,
+            // Light is left to Minecraft to compute unless it is set with set_light
+            is_light_on: 0,
+            other,
+        }
+    }
+}
+
+impl Chunk {
+    /// Merges the placed blocks and block entities into an existing chunk. Existing blocks
+    /// are kept wherever nothing was placed, and heightmaps are recomputed by Minecraft when
+    /// the chunk is loaded, as is light unless it is set again with set_light.
+    pub fn merge(&mut self, chunk_to_modify: &ChunkToModify, default_biome: Biome) {
+        for placed in chunk_to_modify.sections(default_biome) {
+            match self
+                .sections
+                .iter_mut()
+                .find(|section| section.y == placed.y)
+            {
+                Some(section) => section.merge(&placed),
+                None => self.sections.push(placed),
+            }
+        }
+
+        for (key, value) in &chunk_to_modify.other {
+            match (self.other.get_mut(key), value) {
+                (Some(Value::List(existing)), Value::List(added)) => {
+                    existing.extend(added.iter().cloned())
+                }
+                _ => {
+                    self.other.insert(key.clone(), value.clone());
+                }
+            }
+        }
+
+        self.is_light_on = 0;
+        self.other.remove("Heightmaps");
+    }
+
+    /// Stores precomputed light in the sections of the chunk, so Minecraft does not have to
+    /// light it when it is loaded. Sections without blocks are added to hold their light.
+    pub fn set_light(&mut self, light: &ChunkLight, biomes: &[Biome; 16]) {
+        for section_light in &light.sections {
+            let index: usize = match self
+                .sections
+                .iter()
+                .position(|section: &Section| section.y == section_light.y)
+            {
+                Some(index) => index,
+                None => {
+                    self.sections
+                        .push(SectionToModify::default().to_section(section_light.y, biomes));
+                    self.sections.len() - 1
+                }
+            };
+            let section: &mut Section = &mut self.sections[index];
+            section.other.insert(
+                "SkyLight".to_string(),
+                Value::ByteArray(ByteArray::new(section_light.sky.clone())),
+            );
+            // Sections without block light may leave it out
+            if section_light.block.iter().any(|nibbles: &i8| *nibbles != 0) {
+                section.other.insert(
+                    "BlockLight".to_string(),
+                    Value::ByteArray(ByteArray::new(section_light.block.clone())),
+                );
+            }
+        }
+        self.is_light_on = 1;
+    }
+
+    /// Returns the y of the highest section holding other blocks than air
+    pub fn highest_section(&self) -> Option<i8> {
+        self.sections
+            .iter()
+            .filter(|section: &&Section| {
+                section
+                    .block_states
+                    .palette
+                    .iter()
+                    .any(|item: &PaletteItem| !is_air_name(&item.name))
+            })
+            .map(|section: &Section| section.y)
+            .max()
+    }
+
+    /// Returns the block names of a section without their namespace, and the palette indices
+    /// of its 4096 blocks indexed by y, z, x, if the chunk has the section
+    pub fn section_palette(&self, y: i8) -> Option<(Vec<&str>, Vec<usize>)> {
+        let section: &Section = self.sections.iter().find(|section| section.y == y)?;
+        if section.block_states.palette.is_empty() {
+            return None;
+        }
+        let names: Vec<&str> = section
+            .block_states
+            .palette
+            .iter()
+            .map(|item: &PaletteItem| strip_namespace(&item.name))
+            .collect();
+        Some((names, section.block_states.indices()))
+    }
+}
+
+fn strip_namespace(name: &str) -> &str {
+    name.strip_prefix("minecraft:").unwrap_or(name)
+}
+
+fn is_air_name(name: &str) -> bool {
+    matches!(strip_namespace(name), "air" | "cave_air" | "void_air")
 }
 
 #[derive(Serialize, Deserialize)]
-struct Section {
+pub struct Section {
+    // Sections above and below the world in existing chunks only hold light data
+    #[serde(default)]
     block_states: Blockstates,
     #[serde(rename = "Y")]
     y: i8,
@@ -32,29 +225,166 @@ struct Section {
     other: FnvHashMap<String, Value>,
 }
 
+impl Section {
+    /// Overwrites the blocks of this section with the non-air blocks of a placed section.
+    fn merge(&mut self, placed: &Section) {
+        let mut palette: Vec<PaletteItem> = self.block_states.palette.clone();
+        if palette.is_empty() {
+            palette = Blockstates::default().palette;
+        }
+        let mut indices: Vec<usize> = self.block_states.indices();
+
+        let placed_indices: Vec<usize> = placed.block_states.indices();
+        for (index, &p) in placed_indices.iter().enumerate() {
+            let item: &PaletteItem = &placed.block_states.palette[p];
+            if item.name == AIR.name() {
+                continue;
+            }
+            indices[index] = match palette.iter().position(|existing| existing == item) {
+                Some(position) => position,
+                None => {
+                    palette.push(item.clone());
+                    palette.len() - 1
+                }
+            };
+        }
+
+        // Drop palette entries that were completely overwritten
+        let mut used: Vec<usize> = indices.clone();
+        used.sort_unstable();
+        used.dedup();
+        let palette: Vec<PaletteItem> = used.iter().map(|&p| palette[p].clone()).collect();
+        let indices: Vec<usize> = indices
+            .iter()
+            .map(|p| used.binary_search(p).unwrap())
+            .collect();
+
+        self.block_states = Blockstates::from_indices(palette, &indices);
+        self.other.remove("BlockLight");
+        self.other.remove("SkyLight");
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 struct Blockstates {
     palette: Vec<PaletteItem>,
+    // Left out of sections holding a single block. None can not be converted to an NBT value
+    // for the chunks of other versions, so it is skipped instead.
+    #[serde(skip_serializing_if = "Option::is_none")]
     data: Option<LongArray>,
     #[serde(flatten)]
     other: FnvHashMap<String, Value>,
 }
 
-#[derive(Serialize, Deserialize)]
+impl Default for Blockstates {
+    fn default() -> Self {
+        Self {
+            palette: vec![PaletteItem {
+                name: AIR.name().to_string(),
+                properties: None,
+            }],
+            data: None,
+            other: FnvHashMap::default(),
+        }
+    }
+}
+
+impl Blockstates {
+    /// Packs the palette indices of the 4096 blocks of a section, without spanning longs.
+    fn from_indices(palette: Vec<PaletteItem>, indices: &[usize]) -> Self {
+        let mut bits_per_block = 4; // minimum allowed
+        while (1 << bits_per_block) < palette.len() {
+            bits_per_block += 1;
+        }
+
+        let mut data = vec![];
+
+        let mut cur: i64 = 0;
+        let mut cur_idx = 0;
+        for &p in indices {
+            if cur_idx + bits_per_block > 64 {
+                data.push(cur);
+                cur = 0;
+                cur_idx = 0;
+            }
+
+            cur |= (p as i64) << cur_idx;
+            cur_idx += bits_per_block;
+        }
+
+        if cur_idx > 0 {
+            data.push(cur);
+        }
+
+        Self {
+            palette,
+            data: Some(LongArray::new(data)),
+            other: FnvHashMap::default(),
+        }
+    }
+
+    /// Unpacks the palette indices of the 4096 blocks of a section.
+    fn indices(&self) -> Vec<usize> {
+        let Some(data) = &self.data else {
+            return vec![0; 4096];
+        };
+
+        let mut bits_per_block: usize = 4;
+        while (1 << bits_per_block) < self.palette.len() {
+            bits_per_block += 1;
+        }
+        let per_long: usize = 64 / bits_per_block;
+        let mask: u64 = (1 << bits_per_block) - 1;
+
+        (0..4096)
+            .map(|i: usize| {
+                let long: u64 = data.get(i / per_long).copied().unwrap_or(0) as u64;
+                ((long >> ((i % per_long) * bits_per_block)) & mask) as usize
+            })
+            .map(|p: usize| if p < self.palette.len() { p } else { 0 })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 struct PaletteItem {
     #[serde(rename = "Name")]
     name: String,
-    #[serde(rename = "Properties")]
+    #[serde(rename = "Properties", skip_serializing_if = "Option::is_none")]
     properties: Option<Value>,
 }
 
+impl PaletteItem {
+    fn from_block(block: &Block) -> Self {
+        Self {
+            name: block.name().to_string(),
+            properties: block.properties(),
+        }
+    }
+}
+
+/// Blocks of a section, stored as indices into a palette of the blocks placed in it. The
+/// indices take as few bits as the palette needs, so sections holding a few different blocks
+/// use a fraction of the memory of one byte per block, and sections holding a single block
+/// store no indices at all.
 struct SectionToModify {
-    blocks: [Block; 4096],
+    palette: Vec<Block>,
+    /// Bits per palette index: 0, 1, 2, 4 or 8, so no index spans two longs
+    bits_per_block: usize,
+    data: Vec<u64>,
 }
 
 impl SectionToModify {
+    fn from_blocks(blocks: &[Block; 4096]) -> Self {
+        let mut section: SectionToModify = SectionToModify::default();
+        for (index, block) in blocks.iter().enumerate() {
+            section.set(index, *block);
+        }
+        section
+    }
+
     fn get_block(&self, x: u8, y: u8, z: u8) -> Option<Block> {
-        let b = self.blocks[Self::index(x, y, z)];
+        let b = self.get(Self::index(x, y, z));
         if b == AIR {
             return None;
         }
@@ -63,82 +393,196 @@ impl SectionToModify {
     }
 
     fn set_block(&mut self, x: u8, y: u8, z: u8, block: Block) {
-        self.blocks[Self::index(x, y, z)] = block;
+        self.set(Self::index(x, y, z), block);
     }
 
     fn index(x: u8, y: u8, z: u8) -> usize {
         usize::from(y) % 16 * 256 + usize::from(z) * 16 + usize::from(x)
     }
 
-    fn to_section(&self, y: i8) -> Section {
-        let mut palette = self.blocks.to_vec();
-        palette.sort();
-        palette.dedup();
+    /// Returns whether the block may have been placed in the section
+    fn may_contain(&self, block: Block) -> bool {
+        self.palette.contains(&block)
+    }
 
-        let palette_lookup: FnvHashMap<_, _> = palette
-            .iter()
-            .enumerate()
-            .map(|(k, v)| (v, i64::try_from(k).unwrap()))
-            .collect();
+    fn get(&self, index: usize) -> Block {
+        self.palette[self.palette_index(index)]
+    }
 
-        let mut bits_per_block = 4; // minimum allowed
-        while (1 << bits_per_block) < palette.len() {
-            bits_per_block += 1;
-        }
+    fn set(&mut self, index: usize, block: Block) {
+        let palette_index: usize = match self.palette.iter().position(|b: &Block| *b == block) {
+            Some(palette_index) => palette_index,
+            None => {
+                self.palette.push(block);
+                if self.palette.len() > 1 << self.bits_per_block {
+                    self.grow();
+                }
+                self.palette.len() - 1
+            }
+        };
+        self.set_palette_index(index, palette_index);
+    }
 
-        let mut data = vec![];
+    /// Returns all blocks of the section, indexed by y, z, x
+    fn blocks(&self) -> [Block; 4096] {
+        let mut blocks: [Block; 4096] = [self.palette[0]; 4096];
+        if self.bits_per_block > 0 {
+            for (index, block) in blocks.iter_mut().enumerate() {
+                *block = self.get(index);
+            }
+        }
+        blocks
+    }
 
-        let mut cur = 0;
-        let mut cur_idx = 0;
-        for block in &self.blocks {
-            let p = palette_lookup[block];
+    fn palette_index(&self, index: usize) -> usize {
+        if self.bits_per_block == 0 {
+            return 0;
+        }
+        let per_long: usize = 64 / self.bits_per_block;
+        let mask: u64 = (1 << self.bits_per_block) - 1;
+        ((self.data[index / per_long] >> (index % per_long * self.bits_per_block)) & mask) as usize
+    }
 
-            if cur_idx + bits_per_block > 64 {
-                data.push(cur);
-                cur = 0;
-                cur_idx = 0;
-            }
+    fn set_palette_index(&mut self, index: usize, palette_index: usize) {
+        if self.bits_per_block == 0 {
+            return;
+        }
+        let per_long: usize = 64 / self.bits_per_block;
+        let shift: usize = index % per_long * self.bits_per_block;
+        let mask: u64 = ((1 << self.bits_per_block) - 1) << shift;
+        let long: &mut u64 = &mut self.data[index / per_long];
+        *long = (*long & !mask) | ((palette_index as u64) << shift);
+    }
 
-            cur |= p << cur_idx;
-            cur_idx += bits_per_block;
+    /// Repacks the indices with twice the bits once the palette outgrows them. Blocks have
+    /// at most 256 ids, so the indices never need more than 8 bits.
+    fn grow(&mut self) {
+        let indices: Vec<usize> = (0..4096).map(|i: usize| self.palette_index(i)).collect();
+        self.bits_per_block = (self.bits_per_block * 2).max(1);
+        self.data = vec![0; 4096 * self.bits_per_block / 64];
+        for (index, palette_index) in indices.into_iter().enumerate() {
+            self.set_palette_index(index, palette_index);
         }
+    }
 
-        if cur_idx > 0 {
-            data.push(cur);
+    fn to_section(&self, y: i8, biomes: &[Biome; 16]) -> Section {
+        // Sections hold only a few different blocks, so the palette is collected in a single
+        // pass and sorted afterwards
+        let blocks: [Block; 4096] = self.blocks();
+        let mut seen: [bool; 256] = [false; 256];
+        let mut palette: Vec<Block> = vec![];
+        for block in &blocks {
+            if !seen[usize::from(block.id())] {
+                seen[usize::from(block.id())] = true;
+                palette.push(*block);
+            }
         }
+        palette.sort();
 
-        let palette = palette
+        let items: Vec<PaletteItem> = COMMON_PALETTES
             .iter()
-            .map(|x| PaletteItem {
-                name: x.name().to_string(),
-                properties: x.properties(),
-            })
-            .collect();
+            .find(|(blocks, _)| *blocks == palette)
+            .map(|(_, items)| items.clone())
+            .unwrap_or_else(|| palette.iter().map(PaletteItem::from_block).collect());
+
+        // Uniform sections need no data array
+        let block_states: Blockstates = if palette.len() == 1 {
+            Blockstates {
+                palette: items,
+                data: None,
+                other: FnvHashMap::default(),
+            }
+        } else {
+            let mut palette_lookup: [usize; 256] = [0; 256];
+            for (index, block) in palette.iter().enumerate() {
+                palette_lookup[usize::from(block.id())] = index;
+            }
+            let indices: Vec<usize> = blocks
+                .iter()
+                .map(|block| palette_lookup[usize::from(block.id())])
+                .collect();
+            Blockstates::from_indices(items, &indices)
+        };
+
+        let mut other: FnvHashMap<String, Value> = FnvHashMap::default();
+        other.insert("biomes".to_string(), Self::biomes_to_value(biomes));
 
         Section {
-            block_states: Blockstates {
-                palette,
-                data: Some(LongArray::new(data)),
-                other: FnvHashMap::default(),
-            },
+            block_states,
             y,
-            other: FnvHashMap::default(),
+            other,
         }
     }
+
+    /// Serializes the biomes of the 4x4 columns of a chunk into the 4x4x4 biome cells of a section.
+    fn biomes_to_value(biomes: &[Biome; 16]) -> Value {
+        let mut palette: Vec<Biome> = biomes.to_vec();
+        palette.sort();
+        palette.dedup();
+
+        let mut biome_data: HashMap<String, Value> = HashMap::new();
+        biome_data.insert(
+            "palette".to_string(),
+            Value::List(
+                palette
+                    .iter()
+                    .map(|biome: &Biome| Value::String(biome.name().to_string()))
+                    .collect(),
+            ),
+        );
+
+        // A single biome needs no data array
+        if palette.len() > 1 {
+            let mut bits_per_biome: usize = 1;
+            while (1 << bits_per_biome) < palette.len() {
+                bits_per_biome += 1;
+            }
+
+            let mut data: Vec<i64> = vec![];
+            let mut cur: i64 = 0;
+            let mut cur_idx: usize = 0;
+            for cell in 0..64 {
+                // Cells are ordered by y, z, x; every vertical cell uses the column biome
+                let column: usize = cell % 16;
+                let p: i64 = palette.binary_search(&biomes[column]).unwrap() as i64;
+
+                if cur_idx + bits_per_biome > 64 {
+                    data.push(cur);
+                    cur = 0;
+                    cur_idx = 0;
+                }
+
+                cur |= p << cur_idx;
+                cur_idx += bits_per_biome;
+            }
+
+            if cur_idx > 0 {
+                data.push(cur);
+            }
+
+            biome_data.insert("data".to_string(), Value::LongArray(LongArray::new(data)));
+        }
+
+        Value::Compound(biome_data)
+    }
 }
 
 impl Default for SectionToModify {
     fn default() -> Self {
         Self {
-            blocks: [AIR; 4096],
+            palette: vec![AIR],
+            bits_per_block: 0,
+            data: vec![],
         }
     }
 }
 
 #[derive(Default)]
-struct ChunkToModify {
+pub struct ChunkToModify {
     sections: FnvHashMap<i8, SectionToModify>,
-    other: FnvHashMap<String, Value>,
+    biomes: [Option<Biome>; 16],
+    pub entities: Vec<Value>,
+    pub other: FnvHashMap<String, Value>,
 }
 
 impl ChunkToModify {
@@ -150,6 +594,22 @@ impl ChunkToModify {
         section.get_block(x, (y & 15).try_into().unwrap(), z)
     }
 
+    fn get_highest_block_y(&self, x: u8, z: u8) -> Option<i32> {
+        let mut section_indices: Vec<&i8> = self.sections.keys().collect();
+        section_indices.sort_unstable_by(|a, b| b.cmp(a));
+
+        for section_idx in section_indices {
+            let section = &self.sections[section_idx];
+            for y in (0..16).rev() {
+                if section.get_block(x, y, z).is_some() {
+                    return Some(i32::from(*section_idx) * 16 + i32::from(y));
+                }
+            }
+        }
+
+        None
+    }
+
     fn set_block(&mut self, x: u8, y: i32, z: u8, block: Block) {
         let section_idx: i8 = (y >> 4).try_into().unwrap();
 
@@ -158,14 +618,85 @@ impl ChunkToModify {
         section.set_block(x, (y & 15).try_into().unwrap(), z, block);
     }
 
-    fn sections(&self) -> impl Iterator<Item = Section> + '_ {
-        self.sections.iter().map(|(y, s)| s.to_section(*y))
+    fn set_biome(&mut self, x: u8, z: u8, biome: Biome) {
+        self.biomes[usize::from(z / 4) * 4 + usize::from(x / 4)] = Some(biome);
+    }
+
+    /// Adds everything placed in another copy of the chunk to this one. Like placing blocks
+    /// one after another, blocks, biomes and block entities placed here first are kept, so
+    /// the result does not depend on the order the copies are combined in.
+    fn overlay(&mut self, other: ChunkToModify) {
+        for (y, section) in other.sections {
+            let target: &mut SectionToModify = self.sections.entry(y).or_default();
+            for (index, placed) in section.blocks().into_iter().enumerate() {
+                if placed != AIR && target.get(index) == AIR {
+                    target.set(index, placed);
+                }
+            }
+        }
+        for (biome, placed) in self.biomes.iter_mut().zip(other.biomes) {
+            if biome.is_none() {
+                *biome = placed;
+            }
+        }
+        self.entities.extend(other.entities);
+
+        for (key, value) in other.other {
+            match (self.other.get_mut(&key), value) {
+                (Some(Value::List(existing)), Value::List(added)) => {
+                    let occupied: Vec<Option<[i32; 3]>> =
+                        existing.iter().map(block_entity_position).collect();
+                    existing.extend(added.into_iter().filter(|entry: &Value| {
+                        block_entity_position(entry)
+                            .is_none_or(|position| !occupied.contains(&Some(position)))
+                    }));
+                }
+                (None, value) => {
+                    self.other.insert(key, value);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the blocks of a section indexed by y, z, x, if any block was placed in it
+    pub fn section_blocks(&self, y: i8) -> Option<[Block; 4096]> {
+        self.sections.get(&y).map(SectionToModify::blocks)
+    }
+
+    /// Returns the y of the highest section with placed blocks
+    pub fn highest_section(&self) -> Option<i8> {
+        self.sections.keys().max().copied()
+    }
+
+    /// Returns the biomes of the 4x4 columns of the chunk
+    pub fn biomes(&self, default_biome: Biome) -> [Biome; 16] {
+        self.biomes
+            .map(|biome: Option<Biome>| biome.unwrap_or(default_biome))
+    }
+
+    pub fn sections(&self, default_biome: Biome) -> impl Iterator<Item = Section> + '_ {
+        let biomes: [Biome; 16] = self.biomes(default_biome);
+        self.sections
+            .iter()
+            .map(move |(y, s)| s.to_section(*y, &biomes))
+    }
+}
+
+/// Returns the position of a block entity, or None for other entries of a chunk list
+fn block_entity_position(entry: &Value) -> Option<[i32; 3]> {
+    let Value::Compound(entry) = entry else {
+        return None;
+    };
+    match (entry.get("x"), entry.get("y"), entry.get("z")) {
+        (Some(Value::Int(x)), Some(Value::Int(y)), Some(Value::Int(z))) => Some([*x, *y, *z]),
+        _ => None,
     }
 }
 
 #[derive(Default)]
-struct RegionToModify {
-    chunks: FnvHashMap<(i32, i32), ChunkToModify>,
+pub struct RegionToModify {
+    pub chunks: FnvHashMap<(i32, i32), ChunkToModify>,
 }
 
 impl RegionToModify {
@@ -173,7 +704,7 @@ impl RegionToModify {
         self.chunks.entry((x, z)).or_default()
     }
 
-    fn get_chunk(&self, x: i32, z: i32) -> Option<&ChunkToModify> {
+    pub fn get_chunk(&self, x: i32, z: i32) -> Option<&ChunkToModify> {
         self.chunks.get(&(x, z))
     }
 }
@@ -193,6 +724,9 @@ impl WorldToModify {
     }
 
     fn get_block(&self, x: i32, y: i32, z: i32) -> Option<Block> {
+        if !(MIN_Y..=MAX_Y).contains(&y) {
+            return None;
+        }
         let chunk_x: i32 = x >> 4;
         let chunk_z: i32 = z >> 4;
         let region_x: i32 = chunk_x >> 5;
@@ -208,6 +742,74 @@ impl WorldToModify {
         )
     }
 
+    fn find_blocks(&self, block: Block) -> Vec<(i32, i32, i32)> {
+        let mut positions: Vec<(i32, i32, i32)> = vec![];
+        for ((region_x, region_z), region) in &self.regions {
+            for ((chunk_x, chunk_z), chunk) in &region.chunks {
+                let base_x: i32 = (region_x * 32 + chunk_x) * 16;
+                let base_z: i32 = (region_z * 32 + chunk_z) * 16;
+                for (section_idx, section) in &chunk.sections {
+                    if !section.may_contain(block) {
+                        continue;
+                    }
+                    for (index, _) in section
+                        .blocks()
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, existing)| **existing == block)
+                    {
+                        positions.push((
+                            base_x + (index % 16) as i32,
+                            i32::from(*section_idx) * 16 + (index / 256) as i32,
+                            base_z + (index / 16 % 16) as i32,
+                        ));
+                    }
+                }
+            }
+        }
+        positions
+    }
+
+    fn get_highest_block_y(&self, x: i32, z: i32) -> Option<i32> {
+        let chunk_x: i32 = x >> 4;
+        let chunk_z: i32 = z >> 4;
+        let region_x: i32 = chunk_x >> 5;
+        let region_z: i32 = chunk_z >> 5;
+
+        let region: &RegionToModify = self.get_region(region_x, region_z)?;
+        let chunk: &ChunkToModify = region.get_chunk(chunk_x & 31, chunk_z & 31)?;
+
+        chunk.get_highest_block_y((x & 15).try_into().unwrap(), (z & 15).try_into().unwrap())
+    }
+
+    fn set_biome(&mut self, x: i32, z: i32, biome: Biome) {
+        let chunk_x: i32 = x >> 4;
+        let chunk_z: i32 = z >> 4;
+        let region_x: i32 = chunk_x >> 5;
+        let region_z: i32 = chunk_z >> 5;
+
+        let region: &mut RegionToModify = self.get_or_create_region(region_x, region_z);
+        let chunk: &mut ChunkToModify = region.get_or_create_chunk(chunk_x & 31, chunk_z & 31);
+
+        chunk.set_biome(
+            (x & 15).try_into().unwrap(),
+            (z & 15).try_into().unwrap(),
+            biome,
+        );
+    }
+
+    fn add_entity(&mut self, x: i32, z: i32, entity: Value) {
+        let chunk_x: i32 = x >> 4;
+        let chunk_z: i32 = z >> 4;
+        let region_x: i32 = chunk_x >> 5;
+        let region_z: i32 = chunk_z >> 5;
+
+        let region: &mut RegionToModify = self.get_or_create_region(region_x, region_z);
+        let chunk: &mut ChunkToModify = region.get_or_create_chunk(chunk_x & 31, chunk_z & 31);
+
+        chunk.entities.push(entity);
+    }
+
     fn set_block(&mut self, x: i32, y: i32, z: i32, block: Block) {
         let chunk_x: i32 = x >> 4;
         let chunk_z: i32 = z >> 4;
@@ -226,49 +828,122 @@ impl WorldToModify {
     }
 }
 
+/// Placed blocks, biomes and entities of a chunk, stored in a checkpoint
+#[derive(Serialize, Deserialize)]
+struct ChunkCheckpoint {
+    x: i32,
+    z: i32,
+    sections: Vec<SectionCheckpoint>,
+    /// Biome IDs of the 4x4 columns, -1 where the default biome is used
+    biomes: ByteArray,
+    entities: Vec<Value>,
+    other: FnvHashMap<String, Value>,
+    placed_blocks: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SectionCheckpoint {
+    y: i8,
+    blocks: ByteArray,
+}
+
+/// Everything placed in the world so far, written to disk so an interrupted run can be
+/// resumed without processing the elements again
+#[derive(Serialize, Deserialize)]
+pub struct WorldCheckpoint {
+    chunks: Vec<ChunkCheckpoint>,
+}
+
 pub struct WorldEditor<'a> {
-    region_dir: String,
     world: WorldToModify,
     scale_factor_x: f64,
     scale_factor_z: f64,
     args: &'a Args,
+    modified_blocks: FnvHashMap<(i32, i32), u32>,
+    /// Entities added so far, keeping the UUIDs of entities at the same position apart
+    entity_count: u64,
+    /// World position of the origin of the area, given with --x-offset and --z-offset.
+    /// Coordinates passed to the editor are relative to the area and shifted when the
+    /// world is read or written.
+    offset: (i32, i32),
+    /// Blocks within the polygon given with --polygon, nothing is placed outside of it
+    area_mask: Option<Arc<AreaMask>>,
+    /// Seed of the world, from which every random choice of the generation derives
+    seed: u64,
+    /// Cancellation and progress listener of the generation
+    context: &'a GenerationContext,
 }
 
 impl<'a> WorldEditor<'a> {
-    /// Initializes the WorldEditor with the region directory and template region path.
-    pub fn new(region_dir: &str, scale_factor_x: f64, scale_factor_z: f64, args: &'a Args) -> Self {
+    /// Initializes the WorldEditor for an area of the given size and the seed of the world.
+    pub fn new(
+        scale_factor_x: f64,
+        scale_factor_z: f64,
+        args: &'a Args,
+        seed: u64,
+        context: &'a GenerationContext,
+    ) -> Self {
         Self {
-            region_dir: region_dir.to_string(),
             world: WorldToModify::default(),
             scale_factor_x,
             scale_factor_z,
             args,
+            modified_blocks: FnvHashMap::default(),
+            entity_count: 0,
+            offset: (args.x_offset, args.z_offset),
+            area_mask: None,
+            seed,
+            context,
         }
     }
 
-    /// Creates a region for the given region coordinates.
-    fn create_region(&self, region_x: i32, region_z: i32) -> Region<File> {
-        let out_path: String = format!("{}/r.{}.{}.mca", self.region_dir, region_x, region_z);
+    /// Returns the seed of the world, to be passed to `random::seeded_rng`
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
 
-        const REGION_TEMPLATE: &[u8] = include_bytes!("../mcassets/region.template");
+    /// Returns the context of the generation, for the output backends to report their
+    /// progress and to stop when the generation is cancelled
+    pub fn context(&self) -> &'a GenerationContext {
+        self.context
+    }
+
+    /// Limits the placed blocks to the polygon of the area
+    pub fn set_area_mask(&mut self, mask: AreaMask) {
+        self.area_mask = Some(Arc::new(mask));
+    }
 
-        let mut region_file: File = File::options()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&out_path)
-            .expect("无法打开区域文件");
+    /// Returns whether a position lies within the area, blocks outside of it are cut off
+    fn is_in_area(&self, x: i32, z: i32) -> bool {
+        x >= 0
+            && x <= self.scale_factor_x as i32
+            && z >= 0
+            && z <= self.scale_factor_z as i32
+            && self
+                .area_mask
+                .as_ref()
+                .is_none_or(|mask: &Arc<AreaMask>| mask.contains(x, z))
+    }
 
-        region_file
-            .write_all(REGION_TEMPLATE)
-            .expect("无法写入区域模板");
+    /// Returns the number of blocks placed so far in every chunk, keyed by the chunk
+    /// coordinates in the world.
+    pub fn modified_blocks(&self) -> &FnvHashMap<(i32, i32), u32> {
+        &self.modified_blocks
+    }
 
-        Region::from_stream(region_file).expect("加载区域失败")
+    /// Returns whether a position lies outside the --lod-radius around the center of the
+    /// area, where elements are generated with less detail
+    pub fn is_low_detail(&self, x: i32, z: i32) -> bool {
+        let Some(radius) = self.args.lod_radius else {
+            return false;
+        };
+        let dx: f64 = f64::from(x) - self.scale_factor_x / 2.0;
+        let dz: f64 = f64::from(z) - self.scale_factor_z / 2.0;
+        dx.hypot(dz) > radius * self.args.scale
     }
 
     pub fn get_max_coords(&self) -> (i32, i32) {
-        (self.scale_factor_x as i32, self.scale_factor_x as i32)
+        (self.scale_factor_x as i32, self.scale_factor_z as i32)
     }
 
     // Unused and not tested
@@ -286,53 +961,82 @@ impl<'a> WorldEditor<'a> {
         x: i32,
         y: i32,
         z: i32,
-        _rotation: i8,
+        rotation: i8,
     ) {
-        let chunk_x = x >> 4;
-        let chunk_z = z >> 4;
-        let region_x = chunk_x >> 5;
-        let region_z = chunk_z >> 5;
-
-        let mut block_entities = HashMap::new();
+        let mut sign_data: HashMap<String, Value> = HashMap::new();
 
-        let messages = vec![
-            Value::String(format!("\"{}\"", line1)),
-            Value::String(format!("\"{}\"", line2)),
-            Value::String(format!("\"{}\"", line3)),
-            Value::String(format!("\"{}\"", line4)),
-        ];
+        // Sign lines are JSON text components, serializing escapes quotes in the text
+        let messages = [line1, line2, line3, line4]
+            .into_iter()
+            .map(|line: String| Value::String(serde_json::Value::String(line).to_string()))
+            .collect::<Vec<Value>>();
 
         let mut text_data = HashMap::new();
         text_data.insert("messages".to_string(), Value::List(messages));
         text_data.insert("color".to_string(), Value::String("black".to_string()));
         text_data.insert("has_glowing_text".to_string(), Value::Byte(0));
 
-        block_entities.insert("front_text".to_string(), Value::Compound(text_data));
-        block_entities.insert(
-            "id".to_string(),
-            Value::String("minecraft:sign".to_string()),
-        );
-        block_entities.insert("is_waxed".to_string(), Value::Byte(0));
-        block_entities.insert("keepPacked".to_string(), Value::Byte(0));
-        block_entities.insert("x".to_string(), Value::Int(x));
-        block_entities.insert("y".to_string(), Value::Int(y));
-        block_entities.insert("z".to_string(), Value::Int(z));
+        sign_data.insert("front_text".to_string(), Value::Compound(text_data));
+        sign_data.insert("is_waxed".to_string(), Value::Byte(0));
+
+        // Signs facing one of the four directions have their own block, others share one
+        let sign_block: Block = match rotation {
+            0 => SIGN_SOUTH,
+            4 => SIGN_WEST,
+            8 => SIGN_NORTH,
+            12 => SIGN_EAST,
+            _ => SIGN,
+        };
+        self.set_block_entity(sign_block, x, y, z, "minecraft:sign", sign_data);
+    }
+
+    /// Places a block together with its block entity, e.g. a chest with its items. The data
+    /// holds the NBT of the block entity besides its id and position.
+    pub fn set_block_entity(
+        &mut self,
+        block: Block,
+        x: i32,
+        y: i32,
+        z: i32,
+        id: &str,
+        data: HashMap<String, Value>,
+    ) {
+        if !self.is_in_area(x, z) || !(MIN_Y..=MAX_Y).contains(&y) {
+            return;
+        }
+        let (world_x, world_z) = (x + self.offset.0, z + self.offset.1);
+        let chunk_x = world_x >> 4;
+        let chunk_z = world_z >> 4;
+        let region_x = chunk_x >> 5;
+        let region_z = chunk_z >> 5;
+
+        let mut block_entity: HashMap<String, Value> = data;
+        block_entity.insert("id".to_string(), Value::String(id.to_string()));
+        block_entity.insert("keepPacked".to_string(), Value::Byte(0));
+        block_entity.insert("x".to_string(), Value::Int(world_x));
+        block_entity.insert("y".to_string(), Value::Int(y));
+        block_entity.insert("z".to_string(), Value::Int(world_z));
 
         let region: &mut RegionToModify = self.world.get_or_create_region(region_x, region_z);
         let chunk: &mut ChunkToModify = region.get_or_create_chunk(chunk_x & 31, chunk_z & 31);
 
-        if let Some(chunk_data) = chunk.other.get_mut("block_entities") {
-            if let Value::List(entities) = chunk_data {
-                entities.push(Value::Compound(block_entities));
-            }
+        if let Some(Value::List(entities)) = chunk.other.get_mut("block_entities") {
+            // A block holds a single block entity, the last one placed replaces the others
+            entities.retain(|entity: &Value| {
+                !matches!(entity, Value::Compound(entity)
+                    if entity.get("x") == Some(&Value::Int(world_x))
+                        && entity.get("y") == Some(&Value::Int(y))
+                        && entity.get("z") == Some(&Value::Int(world_z)))
+            });
+            entities.push(Value::Compound(block_entity));
         } else {
             chunk.other.insert(
                 "block_entities".to_string(),
-                Value::List(vec![Value::Compound(block_entities)]),
+                Value::List(vec![Value::Compound(block_entity)]),
             );
         }
 
-        self.set_block(SIGN, x, y, z, None, None);
+        self.set_block(block, x, y, z, None, None);
     }
 
     /// Sets a block of the specified type at the given coordinates.
@@ -345,11 +1049,12 @@ impl<'a> WorldEditor<'a> {
         override_whitelist: Option<&[Block]>,
         override_blacklist: Option<&[Block]>,
     ) {
-        // Check if coordinates are within bounds
-        if x < 0 || x > self.scale_factor_x as i32 || z < 0 || z > self.scale_factor_z as i32 {
+        // Check if coordinates are within bounds, blocks above and below the world are cut off
+        if !self.is_in_area(x, z) || !(MIN_Y..=MAX_Y).contains(&y) {
             return;
         }
 
+        let (x, z) = (x + self.offset.0, z + self.offset.1);
         let should_insert = if let Some(existing_block) = self.world.get_block(x, y, z) {
             // Check against whitelist and blacklist
             if let Some(whitelist) = override_whitelist {
@@ -369,7 +1074,70 @@ impl<'a> WorldEditor<'a> {
 
         if should_insert {
             self.world.set_block(x, y, z, block);
+            *self.modified_blocks.entry((x >> 4, z >> 4)).or_insert(0) += 1;
+        }
+    }
+
+    /// Adds an entity standing in the center of the given block. The entity compound only
+    /// needs the entity specific tags, the id, position and UUID are added here.
+    pub fn add_entity(
+        &mut self,
+        id: &str,
+        x: i32,
+        y: i32,
+        z: i32,
+        yaw: f32,
+        mut entity: HashMap<String, Value>,
+    ) {
+        // Check if coordinates are within bounds, blocks above and below the world are cut off
+        if !self.is_in_area(x, z) || !(MIN_Y..=MAX_Y).contains(&y) {
+            return;
         }
+
+        let mut rng: StdRng = seeded_rng(self.seed, (x, y, z, self.entity_count));
+        self.entity_count += 1;
+        let (x, z) = (x + self.offset.0, z + self.offset.1);
+        entity.insert("id".to_string(), Value::String(id.to_string()));
+        entity.insert(
+            "Pos".to_string(),
+            Value::List(vec![
+                Value::Double(f64::from(x) + 0.5),
+                Value::Double(f64::from(y)),
+                Value::Double(f64::from(z) + 0.5),
+            ]),
+        );
+        entity.insert(
+            "Rotation".to_string(),
+            Value::List(vec![Value::Float(yaw), Value::Float(0.0)]),
+        );
+        entity.insert(
+            "UUID".to_string(),
+            Value::IntArray(IntArray::new((0..4).map(|_| rng.gen()).collect())),
+        );
+        // Marks the entity as placed by Arnis, so a later run into the world replaces it
+        entity.insert(
+            "Tags".to_string(),
+            Value::List(vec![Value::String(ENTITY_TAG.to_string())]),
+        );
+
+        self.world.add_entity(x, z, Value::Compound(entity));
+    }
+
+    /// Sets the biome of the 4x4 column containing the given coordinates.
+    /// In winter mode the snowy variant of the biome is used.
+    pub fn set_biome(&mut self, biome: Biome, x: i32, z: i32) {
+        // Check if coordinates are within bounds
+        if !self.is_in_area(x, z) {
+            return;
+        }
+
+        let biome: Biome = if self.args.winter {
+            biome.winter_variant()
+        } else {
+            biome
+        };
+        self.world
+            .set_biome(x + self.offset.0, z + self.offset.1, biome);
     }
 
     /// Fills a cuboid area with the specified block between two coordinates.
@@ -409,7 +1177,7 @@ impl<'a> WorldEditor<'a> {
         blacklist: Option<&[Block]>,
     ) -> bool {
         // Retrieve the chunk modification map
-        if let Some(existing_block) = self.world.get_block(x, y, z) {
+        if let Some(existing_block) = self.get_block(x, y, z) {
             // Check against whitelist and blacklist
             if let Some(whitelist) = whitelist {
                 if whitelist
@@ -432,70 +1200,205 @@ impl<'a> WorldEditor<'a> {
         false
     }
 
-    /// Saves all changes made to the world by writing modified chunks to the appropriate region files.
-    pub fn save(&mut self) {
-        println!("{} 保存世界...", "[5/5]".bold());
-        emit_gui_progress_update(90.0, "保存世界...");
-
-        let _debug: bool = self.args.debug;
-        let total_regions: u64 = self.world.regions.len() as u64;
-
-        let save_pb: ProgressBar = ProgressBar::new(total_regions);
-        save_pb.set_style(
-            ProgressStyle::default_bar()
-                .template(
-                    "{spinner:.green} [{elapsed_precise}] [{bar:45}] {pos}/{len} 区域 ({eta})",
-                )
-                .unwrap()
-                .progress_chars("█▓░"),
-        );
+    /// Returns the block placed so far at the given position, or None for air.
+    pub fn get_block(&self, x: i32, y: i32, z: i32) -> Option<Block> {
+        self.world
+            .get_block(x + self.offset.0, y, z + self.offset.1)
+    }
 
-        let total_steps: f64 = 9.0;
-        let progress_increment_save: f64 = total_steps / total_regions as f64;
-        let mut current_progress_save: f64 = 90.0;
-        let mut last_emitted_progress: f64 = current_progress_save;
+    /// Returns the positions of all placed blocks of the given type.
+    pub fn find_blocks(&self, block: Block) -> Vec<(i32, i32, i32)> {
+        self.world
+            .find_blocks(block)
+            .into_iter()
+            .map(|(x, y, z)| (x - self.offset.0, y, z - self.offset.1))
+            .collect()
+    }
 
-        for ((region_x, region_z), region_to_modify) in &self.world.regions {
-            let mut region: Region<File> = self.create_region(*region_x, *region_z);
+    /// Returns the Y coordinate of the highest block placed so far at the given column.
+    pub fn get_highest_block_y(&self, x: i32, z: i32) -> Option<i32> {
+        self.world
+            .get_highest_block_y(x + self.offset.0, z + self.offset.1)
+    }
 
-            for chunk_x in 0..32 {
-                for chunk_z in 0..32 {
-                    let data: Vec<u8> = region
-                        .read_chunk(chunk_x as usize, chunk_z as usize)
-                        .unwrap()
-                        .unwrap();
+    /// Returns a copy of everything placed so far, to be stored in a checkpoint
+    pub fn checkpoint(&self) -> WorldCheckpoint {
+        let mut chunks: Vec<ChunkCheckpoint> = vec![];
+        for ((region_x, region_z), region) in &self.world.regions {
+            for ((chunk_x, chunk_z), chunk) in &region.chunks {
+                let (x, z) = (region_x * 32 + chunk_x, region_z * 32 + chunk_z);
+                chunks.push(ChunkCheckpoint {
+                    x,
+                    z,
+                    sections: chunk
+                        .sections
+                        .iter()
+                        .map(|(y, section)| SectionCheckpoint {
+                            y: *y,
+                            blocks: ByteArray::new(
+                                section.blocks().iter().map(|b| b.id() as i8).collect(),
+                            ),
+                        })
+                        .collect(),
+                    biomes: ByteArray::new(
+                        chunk
+                            .biomes
+                            .iter()
+                            .map(|biome: &Option<Biome>| biome.map_or(-1, |b| b.id() as i8))
+                            .collect(),
+                    ),
+                    entities: chunk.entities.clone(),
+                    other: chunk.other.clone(),
+                    placed_blocks: self.modified_blocks.get(&(x, z)).copied().unwrap_or(0) as i32,
+                });
+            }
+        }
+        WorldCheckpoint { chunks }
+    }
 
-                    let mut chunk: Chunk = fastnbt::from_bytes(&data).unwrap();
+    /// Moves the given regions into a new editor, so elements placing blocks only in these
+    /// regions can be processed on another thread. The editor is merged back with `merge`.
+    pub fn split_off(&mut self, regions: &[(i32, i32)]) -> WorldEditor<'a> {
+        let mut world: WorldToModify = WorldToModify::default();
+        for position in regions {
+            if let Some(region) = self.world.regions.remove(position) {
+                world.regions.insert(*position, region);
+            }
+        }
+        WorldEditor {
+            world,
+            scale_factor_x: self.scale_factor_x,
+            scale_factor_z: self.scale_factor_z,
+            args: self.args,
+            modified_blocks: FnvHashMap::default(),
+            // Editors split off together place entities in different regions, so continuing
+            // from the same count keeps their UUIDs apart
+            entity_count: self.entity_count,
+            offset: self.offset,
+            area_mask: self.area_mask.clone(),
+            seed: self.seed,
+            context: self.context,
+        }
+    }
 
-                    if let Some(chunk_to_modify) = region_to_modify.get_chunk(chunk_x, chunk_z) {
-                        chunk.sections = chunk_to_modify.sections().collect();
-                        chunk.other.extend(chunk_to_modify.other.clone());
+    /// Takes back the regions of an editor created with `split_off`
+    pub fn merge(&mut self, other: WorldEditor) {
+        for (position, region) in other.world.regions {
+            let Some(existing) = self.world.regions.get_mut(&position) else {
+                self.world.regions.insert(position, region);
+                continue;
+            };
+            // Blocks placed outside the regions split off are laid over the existing ones
+            for (chunk_position, chunk) in region.chunks {
+                match existing.chunks.get_mut(&chunk_position) {
+                    Some(existing_chunk) => existing_chunk.overlay(chunk),
+                    None => {
+                        existing.chunks.insert(chunk_position, chunk);
                     }
-
-                    chunk.x_pos = chunk_x + region_x * 32;
-                    chunk.z_pos = chunk_z + region_z * 32;
-                    chunk.is_light_on = 0; // Force minecraft to recompute
-
-                    let ser: Vec<u8> = fastnbt::to_bytes(&chunk).unwrap();
-
-                    // Write chunk data back to the correct location, ensuring correct chunk coordinates
-                    let expected_chunk_location: (usize, usize) =
-                        ((chunk_x as usize) & 31, (chunk_z as usize) & 31);
-                    region
-                        .write_chunk(expected_chunk_location.0, expected_chunk_location.1, &ser)
-                        .unwrap();
                 }
             }
+        }
+        for (chunk, count) in other.modified_blocks {
+            *self.modified_blocks.entry(chunk).or_insert(0) += count;
+        }
+        self.entity_count = self.entity_count.max(other.entity_count);
+    }
 
-            save_pb.inc(1);
+    /// Restores everything placed by the run a checkpoint was taken from
+    pub fn restore(&mut self, checkpoint: WorldCheckpoint) {
+        for chunk in checkpoint.chunks {
+            let region: &mut RegionToModify =
+                self.world.get_or_create_region(chunk.x >> 5, chunk.z >> 5);
+            let chunk_to_modify: &mut ChunkToModify =
+                region.get_or_create_chunk(chunk.x & 31, chunk.z & 31);
+
+            for section in chunk.sections {
+                let mut blocks: [Block; 4096] = [AIR; 4096];
+                for (block, id) in blocks.iter_mut().zip(section.blocks.iter()) {
+                    *block = Block::from_id(*id as u8);
+                }
+                chunk_to_modify
+                    .sections
+                    .insert(section.y, SectionToModify::from_blocks(&blocks));
+            }
+            for (biome, id) in chunk_to_modify.biomes.iter_mut().zip(chunk.biomes.iter()) {
+                *biome = (*id >= 0).then(|| Biome::from_id(*id as u8));
+            }
+            chunk_to_modify.entities = chunk.entities;
+            chunk_to_modify.other = chunk.other;
 
-            current_progress_save += progress_increment_save;
-            if (current_progress_save - last_emitted_progress).abs() > 0.25 {
-                emit_gui_progress_update(current_progress_save, "保存世界...");
-                last_emitted_progress = current_progress_save;
+            if chunk.placed_blocks > 0 {
+                self.modified_blocks
+                    .insert((chunk.x, chunk.z), chunk.placed_blocks as u32);
             }
         }
+    }
 
-        save_pb.finish();
+    /// Writes the generated world to the output, usually the backend selected with --format.
+    /// Chunks closest to the focus block of the area are written first.
+    pub fn save(&self, output: &mut dyn OutputBackend, focus: (i32, i32)) -> Result<(), String> {
+        println!("{} 保存世界...", "[5/5]".bold());
+        self.context
+            .emit_progress(ProgressEvent::new(Stage::Saving, 90.0));
+
+        self.context.check_cancelled()?;
+        output.write(self, (focus.0 + self.offset.0, focus.1 + self.offset.1))
+    }
+
+    /// Returns the modified regions with their chunks, for backends writing region files
+    pub fn regions(&self) -> &FnvHashMap<(i32, i32), RegionToModify> {
+        &self.world.regions
+    }
+
+    /// Returns the positions of all chunks with placed blocks, sorted by the distance of
+    /// their centers to the focus block
+    pub fn chunk_positions(&self, focus: (i32, i32)) -> Vec<(i32, i32)> {
+        let mut positions: Vec<(i32, i32)> = self
+            .world
+            .regions
+            .iter()
+            .flat_map(|((region_x, region_z), region)| {
+                region.chunks.keys().map(move |(chunk_x, chunk_z)| {
+                    (region_x * 32 + chunk_x, region_z * 32 + chunk_z)
+                })
+            })
+            .collect();
+        positions.sort_by_key(|(chunk_x, chunk_z)| {
+            let dx: i64 = i64::from(chunk_x * 16 + 8 - focus.0);
+            let dz: i64 = i64::from(chunk_z * 16 + 8 - focus.1);
+            dx * dx + dz * dz
+        });
+        positions
+    }
+
+    /// Returns the placed blocks, biomes and entities of a chunk, if anything was placed in it
+    pub fn chunk(&self, chunk_x: i32, chunk_z: i32) -> Option<&ChunkToModify> {
+        self.world
+            .get_region(chunk_x >> 5, chunk_z >> 5)
+            .and_then(|region: &RegionToModify| region.get_chunk(chunk_x & 31, chunk_z & 31))
+    }
+
+    /// Returns all placed blocks of a chunk with their world coordinates
+    pub fn chunk_blocks(&self, chunk_x: i32, chunk_z: i32) -> Vec<((i32, i32, i32), Block)> {
+        let Some(chunk) = self.chunk(chunk_x, chunk_z) else {
+            return vec![];
+        };
+
+        let mut blocks: Vec<((i32, i32, i32), Block)> = vec![];
+        for (section_idx, section) in &chunk.sections {
+            for (index, block) in section.blocks().iter().enumerate() {
+                if *block != AIR {
+                    blocks.push((
+                        (
+                            chunk_x * 16 + (index % 16) as i32,
+                            i32::from(*section_idx) * 16 + (index / 256) as i32,
+                            chunk_z * 16 + (index / 16 % 16) as i32,
+                        ),
+                        *block,
+                    ));
+                }
+            }
+        }
+        blocks
     }
 }
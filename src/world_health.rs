@@ -0,0 +1,166 @@
+use crate::checkpoint::CHECKPOINT_FILE_NAME;
+use crate::incremental::ELEMENTS_FILE_NAME;
+use crate::manifest::{checksum, read_manifest, GenerationSettings, ManifestEntry, RunManifest};
+use crate::metadata::{read_metadata, WorldMetadata};
+use fastanvil::Region;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+/// Folders of the world holding region files
+const REGION_DIRS: [&str; 2] = ["region", "entities"];
+
+/// Report on an existing world selected in the GUI, telling whether it was generated by
+/// Arnis and can be updated in place or should rather be generated anew
+#[derive(Serialize, Debug)]
+pub struct WorldHealth {
+    /// Whether the world contains the metadata or manifest written by Arnis
+    pub generated_by_arnis: bool,
+    /// Unix time in seconds of the last run writing into the world
+    pub saved_at: Option<u64>,
+    pub settings: Option<GenerationSettings>,
+    /// Area of the world as (min_lon, min_lat, max_lon, max_lat)
+    pub bbox: Option<(f64, f64, f64, f64)>,
+    /// Files listed in the manifest that no longer exist
+    pub missing_files: Vec<String>,
+    /// Files changed since the last run, e.g. by playing the world
+    pub modified_files: Vec<String>,
+    /// Region files that can not be read
+    pub corrupted_files: Vec<String>,
+    /// The world can be regenerated or extended in place
+    pub can_update_in_place: bool,
+    /// A run with --incremental can update only the changed elements
+    pub supports_incremental: bool,
+    /// A run with --resume can continue an interrupted generation
+    pub can_resume: bool,
+    /// What the user should do with the world
+    pub recommendation: String,
+}
+
+/// Checks an existing world for the files written by Arnis and for damaged region files.
+/// Files whose checksum still matches the manifest are known to be intact, so only region
+/// files changed since the last run or not written by Arnis are read.
+pub fn check_world(world_path: &Path) -> WorldHealth {
+    let metadata: Option<WorldMetadata> = read_metadata(&world_path.to_string_lossy()).ok();
+    let manifest: Option<RunManifest> = read_manifest(world_path).ok();
+    let listed: HashMap<&str, &ManifestEntry> = manifest
+        .iter()
+        .flat_map(|manifest: &RunManifest| manifest.files.iter())
+        .map(|entry: &ManifestEntry| (entry.path.as_str(), entry))
+        .collect();
+
+    let mut missing_files: Vec<String> = listed
+        .keys()
+        .filter(|path: &&&str| !world_path.join(path).exists())
+        .map(|path: &&str| path.to_string())
+        .collect();
+    missing_files.sort();
+
+    let mut modified_files: Vec<String> = vec![];
+    let mut corrupted_files: Vec<String> = vec![];
+    for dir in REGION_DIRS {
+        let Ok(entries) = fs::read_dir(world_path.join(dir)) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name: String = entry.file_name().to_string_lossy().to_string();
+            if !file_name.ends_with(".mca") {
+                continue;
+            }
+            let relative_path: String = format!("{}/{}", dir, file_name);
+            let Ok(content) = fs::read(entry.path()) else {
+                corrupted_files.push(relative_path);
+                continue;
+            };
+
+            let unchanged: bool =
+                listed
+                    .get(relative_path.as_str())
+                    .is_some_and(|listed: &&ManifestEntry| {
+                        listed.size == content.len() as u64 && listed.checksum == checksum(&content)
+                    });
+            if unchanged {
+                continue;
+            }
+            if listed.contains_key(relative_path.as_str()) {
+                modified_files.push(relative_path.clone());
+            }
+            if !is_readable_region(content) {
+                corrupted_files.push(relative_path);
+            }
+        }
+    }
+    modified_files.sort();
+    corrupted_files.sort();
+
+    let generated_by_arnis: bool = metadata.is_some() || manifest.is_some();
+    let can_update_in_place: bool = metadata.is_some() && corrupted_files.is_empty();
+    let supports_incremental: bool =
+        can_update_in_place && world_path.join(ELEMENTS_FILE_NAME).exists();
+    let can_resume: bool = world_path.join(CHECKPOINT_FILE_NAME).exists();
+
+    let recommendation: String = if !corrupted_files.is_empty() {
+        format!(
+            "检测到 {} 个损坏的区域文件，建议生成到新世界。",
+            corrupted_files.len()
+        )
+    } else if !generated_by_arnis {
+        "此世界不是由 Arnis 生成的，生成时将替换所选区域内的现有结构。".to_string()
+    } else if can_resume {
+        "上次生成未完成，可使用相同的区域和设置重新生成。".to_string()
+    } else if metadata.is_none() {
+        "缺少元数据文件，无法确定世界的范围，建议生成到新世界。".to_string()
+    } else if !modified_files.is_empty() || !missing_files.is_empty() {
+        format!(
+            "此世界可以就地更新，但有 {} 个区域文件在生成后被修改或删除，重新生成会覆盖这些改动。",
+            modified_files.len() + missing_files.len()
+        )
+    } else if supports_incremental {
+        "此世界可以就地更新，使用 --incremental 只会生成变化的元素。".to_string()
+    } else {
+        "此世界可以就地更新或扩展。".to_string()
+    };
+
+    WorldHealth {
+        generated_by_arnis,
+        saved_at: manifest
+            .as_ref()
+            .map(|manifest: &RunManifest| manifest.saved_at),
+        settings: manifest.and_then(|manifest: RunManifest| manifest.settings),
+        bbox: metadata.map(|metadata: WorldMetadata| {
+            (
+                metadata.min_lon,
+                metadata.min_lat,
+                metadata.max_lon,
+                metadata.max_lat,
+            )
+        }),
+        missing_files,
+        modified_files,
+        corrupted_files,
+        can_update_in_place,
+        supports_incremental,
+        can_resume,
+        recommendation,
+    }
+}
+
+/// Returns whether a region file has a valid header and all of its chunks can be read
+fn is_readable_region(content: Vec<u8>) -> bool {
+    // Minecraft leaves empty files behind for regions without chunks
+    if content.is_empty() {
+        return true;
+    }
+    // Region files consist of whole 4 KiB sectors, starting with two header sectors
+    if content.len() < 8192 || !content.len().is_multiple_of(4096) {
+        return false;
+    }
+    let Ok(mut region) = Region::from_stream(Cursor::new(content)) else {
+        return false;
+    };
+    (0..32).all(|chunk_x: usize| {
+        (0..32).all(|chunk_z: usize| region.read_chunk(chunk_x, chunk_z).is_ok())
+    })
+}